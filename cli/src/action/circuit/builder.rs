@@ -16,7 +16,7 @@
 use splinter::admin::messages::AuthorizationType;
 use splinter::admin::messages::{
     BuilderError, CreateCircuit, CreateCircuitBuilder, SplinterNode, SplinterNodeBuilder,
-    SplinterServiceBuilder,
+    SplinterServiceBuilder, VotingRule,
 };
 
 use crate::error::CliError;
@@ -34,6 +34,7 @@ pub struct CreateCircuitMessageBuilder {
     authorization_type: Option<AuthorizationType>,
     application_metadata: Vec<u8>,
     comments: Option<String>,
+    voting_rule: Option<VotingRule>,
 }
 
 impl CreateCircuitMessageBuilder {
@@ -47,6 +48,7 @@ impl CreateCircuitMessageBuilder {
             authorization_type: None,
             application_metadata: vec![],
             comments: None,
+            voting_rule: None,
         }
     }
 
@@ -233,6 +235,33 @@ impl CreateCircuitMessageBuilder {
         self.comments = Some(comments.into());
     }
 
+    /// Sets the voting rule of the circuit, using the format `unanimous`, `majority`,
+    /// `threshold=<count>`, or `required=<node_id>[,<node_id>...]`.
+    pub fn set_voting_rule(&mut self, voting_rule: &str) -> Result<(), CliError> {
+        let mut parts = voting_rule.splitn(2, '=');
+        let voting_rule = match (parts.next(), parts.next()) {
+            (Some("unanimous"), None) => VotingRule::Unanimous,
+            (Some("majority"), None) => VotingRule::Majority,
+            (Some("threshold"), Some(threshold)) => {
+                VotingRule::Threshold(threshold.parse().map_err(|_| {
+                    CliError::ActionError(format!("Invalid voting threshold {}", threshold))
+                })?)
+            }
+            (Some("required"), Some(approvers)) if !approvers.is_empty() => {
+                VotingRule::RequiredApprovers(approvers.split(',').map(String::from).collect())
+            }
+            _ => {
+                return Err(CliError::ActionError(format!(
+                    "Invalid voting rule {}",
+                    voting_rule
+                )))
+            }
+        };
+
+        self.voting_rule = Some(voting_rule);
+        Ok(())
+    }
+
     pub fn build(self) -> Result<CreateCircuit, CliError> {
         // if management type is not set, check for environment variable
         let management_type = self
@@ -280,6 +309,11 @@ impl CreateCircuitMessageBuilder {
             .with_application_metadata(&self.application_metadata)
             .with_comments(&self.comments.unwrap_or_default());
 
+        let create_circuit_builder = match self.voting_rule {
+            Some(voting_rule) => create_circuit_builder.with_voting_rule(&voting_rule),
+            None => create_circuit_builder,
+        };
+
         #[cfg(feature = "circuit-auth-type")]
         let create_circuit_builder = match self.authorization_type {
             Some(authorization_type) => {
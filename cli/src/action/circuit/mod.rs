@@ -22,6 +22,7 @@ pub mod template;
 use std::collections::HashMap;
 use std::fs::File;
 use std::io::{Error as IoError, ErrorKind, Read};
+use std::time::{SystemTime, UNIX_EPOCH};
 
 use clap::ArgMatches;
 use serde::Deserialize;
@@ -35,7 +36,7 @@ use super::{Action, DEFAULT_SPLINTER_REST_API_URL, SPLINTER_REST_API_URL_ENV};
 
 use api::{CircuitServiceSlice, CircuitSlice, SplinterRestClient};
 use builder::CreateCircuitMessageBuilder;
-use payload::make_signed_payload;
+use payload::{make_signed_payload, ExpiringAction};
 
 pub struct CircuitProposeAction;
 
@@ -154,8 +155,24 @@ impl Action for CircuitProposeAction {
             builder.set_comments(comments);
         }
 
+        if let Some(voting_rule) = args.value_of("voting_rule") {
+            builder.set_voting_rule(voting_rule)?;
+        }
+
         let create_circuit = builder.build()?;
 
+        let expires_in = args
+            .value_of("expires_in")
+            .map(|expires_in| {
+                expires_in.parse::<u64>().map_err(|_| {
+                    CliError::ActionError(format!(
+                        "Invalid proposal expiration {}: must be a number of seconds",
+                        expires_in
+                    ))
+                })
+            })
+            .transpose()?;
+
         let circuit_slice = CircuitSlice::from(&create_circuit);
 
         if !args.is_present("dry_run") {
@@ -170,8 +187,22 @@ impl Action for CircuitProposeAction {
             let requester_node = client.fetch_node_id()?;
             let private_key_hex = read_private_key(key)?;
 
-            let signed_payload =
-                make_signed_payload(&requester_node, &private_key_hex, create_circuit)?;
+            let signed_payload = match expires_in {
+                Some(expires_in) => {
+                    let now = SystemTime::now()
+                        .duration_since(UNIX_EPOCH)
+                        .map_err(|err| {
+                            CliError::ActionError(format!("Unable to get current time: {}", err))
+                        })?
+                        .as_secs();
+                    let expiring_circuit = ExpiringAction {
+                        action: create_circuit,
+                        expires_at: now + expires_in,
+                    };
+                    make_signed_payload(&requester_node, &private_key_hex, expiring_circuit)?
+                }
+                None => make_signed_payload(&requester_node, &private_key_hex, create_circuit)?,
+            };
             client.submit_admin_payload(signed_payload)?;
 
             info!("The circuit proposal was submited successfully");
@@ -556,6 +587,19 @@ struct CircuitVote {
     vote: Vote,
 }
 
+struct CircuitDestroy {
+    circuit_id: String,
+}
+
+struct CircuitAbandon {
+    circuit_id: String,
+}
+
+struct CircuitWithdraw {
+    circuit_id: String,
+    circuit_hash: String,
+}
+
 pub struct CircuitVoteAction;
 
 impl Action for CircuitVoteAction {
@@ -612,6 +656,121 @@ fn vote_on_circuit_proposal(
     }
 }
 
+pub struct CircuitDestroyAction;
+
+impl Action for CircuitDestroyAction {
+    fn run<'a>(&mut self, arg_matches: Option<&ArgMatches<'a>>) -> Result<(), CliError> {
+        let args = arg_matches.ok_or_else(|| CliError::RequiresArgs)?;
+        let url = args
+            .value_of("url")
+            .map(ToOwned::to_owned)
+            .or_else(|| std::env::var(SPLINTER_REST_API_URL_ENV).ok())
+            .unwrap_or_else(|| DEFAULT_SPLINTER_REST_API_URL.to_string());
+        let key = args.value_of("private_key_file").unwrap_or("splinter");
+        let circuit_id = args
+            .value_of("circuit_id")
+            .ok_or_else(|| CliError::ActionError("'circuit-id' argument is required".into()))?;
+
+        propose_circuit_destroy(&url, key, circuit_id)
+    }
+}
+
+fn propose_circuit_destroy(url: &str, key: &str, circuit_id: &str) -> Result<(), CliError> {
+    let client = SplinterRestClient::new(url);
+    let private_key_hex = read_private_key(key)?;
+
+    let requester_node = client.fetch_node_id()?;
+    if client.fetch_circuit(circuit_id)?.is_none() {
+        return Err(CliError::ActionError(format!(
+            "Circuit '{}' does not exist",
+            circuit_id
+        )));
+    }
+
+    let circuit_destroy = CircuitDestroy {
+        circuit_id: circuit_id.into(),
+    };
+    let signed_payload = make_signed_payload(&requester_node, &private_key_hex, circuit_destroy)?;
+    client.submit_admin_payload(signed_payload)
+}
+
+pub struct CircuitAbandonAction;
+
+impl Action for CircuitAbandonAction {
+    fn run<'a>(&mut self, arg_matches: Option<&ArgMatches<'a>>) -> Result<(), CliError> {
+        let args = arg_matches.ok_or_else(|| CliError::RequiresArgs)?;
+        let url = args
+            .value_of("url")
+            .map(ToOwned::to_owned)
+            .or_else(|| std::env::var(SPLINTER_REST_API_URL_ENV).ok())
+            .unwrap_or_else(|| DEFAULT_SPLINTER_REST_API_URL.to_string());
+        let key = args.value_of("private_key_file").unwrap_or("splinter");
+        let circuit_id = args
+            .value_of("circuit_id")
+            .ok_or_else(|| CliError::ActionError("'circuit-id' argument is required".into()))?;
+
+        abandon_circuit(&url, key, circuit_id)
+    }
+}
+
+fn abandon_circuit(url: &str, key: &str, circuit_id: &str) -> Result<(), CliError> {
+    let client = SplinterRestClient::new(url);
+    let private_key_hex = read_private_key(key)?;
+
+    let requester_node = client.fetch_node_id()?;
+    if client.fetch_circuit(circuit_id)?.is_none() {
+        return Err(CliError::ActionError(format!(
+            "Circuit '{}' does not exist",
+            circuit_id
+        )));
+    }
+
+    let circuit_abandon = CircuitAbandon {
+        circuit_id: circuit_id.into(),
+    };
+    let signed_payload = make_signed_payload(&requester_node, &private_key_hex, circuit_abandon)?;
+    client.submit_admin_payload(signed_payload)
+}
+
+pub struct CircuitWithdrawAction;
+
+impl Action for CircuitWithdrawAction {
+    fn run<'a>(&mut self, arg_matches: Option<&ArgMatches<'a>>) -> Result<(), CliError> {
+        let args = arg_matches.ok_or_else(|| CliError::RequiresArgs)?;
+        let url = args
+            .value_of("url")
+            .map(ToOwned::to_owned)
+            .or_else(|| std::env::var(SPLINTER_REST_API_URL_ENV).ok())
+            .unwrap_or_else(|| DEFAULT_SPLINTER_REST_API_URL.to_string());
+        let key = args.value_of("private_key_file").unwrap_or("splinter");
+        let circuit_id = args
+            .value_of("circuit_id")
+            .ok_or_else(|| CliError::ActionError("'circuit-id' argument is required".into()))?;
+
+        withdraw_circuit_proposal(&url, key, circuit_id)
+    }
+}
+
+fn withdraw_circuit_proposal(url: &str, key: &str, circuit_id: &str) -> Result<(), CliError> {
+    let client = SplinterRestClient::new(url);
+    let private_key_hex = read_private_key(key)?;
+
+    let requester_node = client.fetch_node_id()?;
+    let proposal = client.fetch_proposal(circuit_id)?.ok_or_else(|| {
+        CliError::ActionError(format!(
+            "Proposal for circuit '{}' does not exist",
+            circuit_id
+        ))
+    })?;
+
+    let circuit_withdraw = CircuitWithdraw {
+        circuit_id: circuit_id.into(),
+        circuit_hash: proposal.circuit_hash,
+    };
+    let signed_payload = make_signed_payload(&requester_node, &private_key_hex, circuit_withdraw)?;
+    client.submit_admin_payload(signed_payload)
+}
+
 pub struct CircuitListAction;
 
 impl Action for CircuitListAction {
@@ -17,20 +17,48 @@ use protobuf::Message;
 use sawtooth_sdk::signing::secp256k1;
 use splinter::admin::messages::CreateCircuit;
 use splinter::protos::admin::{
-    CircuitCreateRequest, CircuitManagementPayload, CircuitManagementPayload_Action as Action,
+    CircuitAbandon as CircuitAbandonProto, CircuitCreateRequest, CircuitDestroyRequest,
+    CircuitManagementPayload, CircuitManagementPayload_Action as Action,
     CircuitManagementPayload_Header as Header, CircuitProposalVote, CircuitProposalVote_Vote,
+    CircuitProposalWithdraw,
 };
 use splinter::signing::{sawtooth, Signer};
 
 use crate::error::CliError;
 
-use super::{CircuitVote, Vote};
+use super::{CircuitAbandon, CircuitDestroy, CircuitVote, CircuitWithdraw, Vote};
 
 /// A circuit action that has a type and can be converted into a protobuf-serializable struct.
 pub trait CircuitAction<M: Message> {
     fn into_proto(self) -> Result<M, CliError>;
 
     fn action_type(&self) -> Action;
+
+    /// Returns the time, in seconds since the UNIX epoch, after which a proposal created by this
+    /// action expires, or 0 if the proposal does not expire.
+    fn proposal_expires_at(&self) -> u64 {
+        0
+    }
+}
+
+/// A circuit action whose proposal expires at the given time.
+pub struct ExpiringAction<A> {
+    pub action: A,
+    pub expires_at: u64,
+}
+
+impl<M: Message, A: CircuitAction<M>> CircuitAction<M> for ExpiringAction<A> {
+    fn into_proto(self) -> Result<M, CliError> {
+        self.action.into_proto()
+    }
+
+    fn action_type(&self) -> Action {
+        self.action.action_type()
+    }
+
+    fn proposal_expires_at(&self) -> u64 {
+        self.expires_at
+    }
 }
 
 /// Applies a circuit payload action to the given CircuitManagementPayload.
@@ -49,6 +77,7 @@ where
     A: CircuitAction<M>,
 {
     let action_type = action.action_type();
+    let proposal_expires_at = action.proposal_expires_at();
     let action_proto = action.into_proto()?;
     let serialized_action = action_proto
         .write_to_bytes()
@@ -72,6 +101,7 @@ where
     header.set_payload_sha512(hashed_bytes.to_vec());
     header.set_requester(public_key);
     header.set_requester_node_id(requester_node.into());
+    header.set_proposal_expires_at(proposal_expires_at);
     let header_bytes = header.write_to_bytes().map_err(|err| {
         CliError::ActionError(format!("Failed to serialize payload header: {}", err))
     })?;
@@ -136,3 +166,61 @@ impl ApplyToEnvelope for CircuitProposalVote {
         circuit_management_payload.set_circuit_proposal_vote(self);
     }
 }
+
+impl CircuitAction<CircuitDestroyRequest> for CircuitDestroy {
+    fn action_type(&self) -> Action {
+        Action::CIRCUIT_DESTROY_REQUEST
+    }
+
+    fn into_proto(self) -> Result<CircuitDestroyRequest, CliError> {
+        let mut destroy_request = CircuitDestroyRequest::new();
+        destroy_request.set_circuit_id(self.circuit_id);
+
+        Ok(destroy_request)
+    }
+}
+
+impl ApplyToEnvelope for CircuitDestroyRequest {
+    fn apply(self, circuit_management_payload: &mut CircuitManagementPayload) {
+        circuit_management_payload.set_circuit_destroy_request(self);
+    }
+}
+
+impl CircuitAction<CircuitAbandonProto> for CircuitAbandon {
+    fn action_type(&self) -> Action {
+        Action::CIRCUIT_ABANDON
+    }
+
+    fn into_proto(self) -> Result<CircuitAbandonProto, CliError> {
+        let mut circuit_abandon = CircuitAbandonProto::new();
+        circuit_abandon.set_circuit_id(self.circuit_id);
+
+        Ok(circuit_abandon)
+    }
+}
+
+impl ApplyToEnvelope for CircuitAbandonProto {
+    fn apply(self, circuit_management_payload: &mut CircuitManagementPayload) {
+        circuit_management_payload.set_circuit_abandon(self);
+    }
+}
+
+impl CircuitAction<CircuitProposalWithdraw> for CircuitWithdraw {
+    fn action_type(&self) -> Action {
+        Action::CIRCUIT_PROPOSAL_WITHDRAW
+    }
+
+    fn into_proto(self) -> Result<CircuitProposalWithdraw, CliError> {
+        let mut circuit_withdraw = CircuitProposalWithdraw::new();
+        circuit_withdraw.set_circuit_id(self.circuit_id);
+        circuit_withdraw.set_circuit_hash(self.circuit_hash);
+
+        Ok(circuit_withdraw)
+    }
+}
+
+impl ApplyToEnvelope for CircuitProposalWithdraw {
+    fn apply(self, circuit_management_payload: &mut CircuitManagementPayload) {
+        circuit_management_payload.set_circuit_proposal_withdraw(self);
+    }
+}
@@ -17,6 +17,7 @@ use clap::ArgMatches;
 use super::Action;
 use crate::error::CliError;
 use diesel::{connection::Connection as _, pg::PgConnection};
+use splinter::admin::migrations::run_postgres_migrations as run_admin_postgres_migrations;
 #[cfg(feature = "database-migrate-biome")]
 use splinter::biome::migrations::run_postgres_migrations;
 
@@ -38,6 +39,10 @@ impl Action for MigrateAction {
             ))
         })?;
 
+        run_admin_postgres_migrations(&connection).map_err(|err| {
+            CliError::ActionError(format!("Unable to run admin migrations: {}", err))
+        })?;
+
         #[cfg(feature = "database-migrate-biome")]
         run_postgres_migrations(&connection).map_err(|err| {
             CliError::ActionError(format!("Unable to run Biome migrations: {}", err))
@@ -316,6 +316,23 @@ fn run<I: IntoIterator<Item = T>, T: Into<OsString> + Clone>(args: I) -> Result<
                 .takes_value(true)
                 .help("Add human-readable comments to the proposal"),
         )
+        .arg(
+            Arg::with_name("expires_in")
+                .long("expires-in")
+                .value_name("seconds")
+                .takes_value(true)
+                .help("Number of seconds after which the proposal expires if it is not accepted"),
+        )
+        .arg(
+            Arg::with_name("voting_rule")
+                .long("voting-rule")
+                .takes_value(true)
+                .help(
+                    "Rule used to approve proposals for the circuit: unanimous, majority, \
+                     threshold=<count>, or required=<node_id>[,<node_id>...] \
+                     (default: unanimous)",
+                ),
+        )
         .arg(
             Arg::with_name("dry_run")
                 .long("dry-run")
@@ -399,6 +416,84 @@ fn run<I: IntoIterator<Item = T>, T: Into<OsString> + Clone>(args: I) -> Result<
                         .help("Reject the proposal"),
                 ),
         )
+        .subcommand(
+            SubCommand::with_name("destroy")
+                .about("Propose that an existing circuit be destroyed")
+                .arg(
+                    Arg::with_name("url")
+                        .short("U")
+                        .long("url")
+                        .takes_value(true)
+                        .help("URL of Splinter Daemon"),
+                )
+                .arg(
+                    Arg::with_name("private_key_file")
+                        .value_name("private-key-file")
+                        .short("k")
+                        .long("key")
+                        .takes_value(true)
+                        .help("Path to private key file"),
+                )
+                .arg(
+                    Arg::with_name("circuit_id")
+                        .value_name("circuit-id")
+                        .takes_value(true)
+                        .required(true)
+                        .help("ID of the circuit to destroy"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("abandon")
+                .about("Abandon an existing circuit on this node")
+                .arg(
+                    Arg::with_name("url")
+                        .short("U")
+                        .long("url")
+                        .takes_value(true)
+                        .help("URL of Splinter Daemon"),
+                )
+                .arg(
+                    Arg::with_name("private_key_file")
+                        .value_name("private-key-file")
+                        .short("k")
+                        .long("key")
+                        .takes_value(true)
+                        .help("Path to private key file"),
+                )
+                .arg(
+                    Arg::with_name("circuit_id")
+                        .value_name("circuit-id")
+                        .takes_value(true)
+                        .required(true)
+                        .help("ID of the circuit to abandon"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("withdraw")
+                .about("Withdraw a pending circuit proposal made by this node")
+                .arg(
+                    Arg::with_name("url")
+                        .short("U")
+                        .long("url")
+                        .takes_value(true)
+                        .help("URL of Splinter Daemon"),
+                )
+                .arg(
+                    Arg::with_name("private_key_file")
+                        .value_name("private-key-file")
+                        .short("k")
+                        .long("key")
+                        .takes_value(true)
+                        .help("Path to private key file"),
+                )
+                .arg(
+                    Arg::with_name("circuit_id")
+                        .value_name("circuit-id")
+                        .takes_value(true)
+                        .required(true)
+                        .help("ID of the circuit whose proposal is withdrawn"),
+                ),
+        )
         .subcommand(
             SubCommand::with_name("list")
                 .about("List the circuits")
@@ -610,6 +705,9 @@ fn run<I: IntoIterator<Item = T>, T: Into<OsString> + Clone>(args: I) -> Result<
     let circuit_command = SubcommandActions::new()
         .with_command("propose", circuit::CircuitProposeAction)
         .with_command("vote", circuit::CircuitVoteAction)
+        .with_command("destroy", circuit::CircuitDestroyAction)
+        .with_command("abandon", circuit::CircuitAbandonAction)
+        .with_command("withdraw", circuit::CircuitWithdrawAction)
         .with_command("list", circuit::CircuitListAction)
         .with_command("show", circuit::CircuitShowAction)
         .with_command("proposals", circuit::CircuitProposalsAction);
@@ -165,6 +165,21 @@ pub fn update_gameroom_status(
         .map(|_| ())
 }
 
+pub fn update_gameroom_alias(
+    conn: &PgConnection,
+    circuit_id: &str,
+    updated_time: &SystemTime,
+    alias: &str,
+) -> QueryResult<()> {
+    diesel::update(gameroom::table.find(circuit_id))
+        .set((
+            gameroom::updated_time.eq(updated_time),
+            gameroom::alias.eq(alias),
+        ))
+        .execute(conn)
+        .map(|_| ())
+}
+
 pub fn update_gameroom_member_status(
     conn: &PgConnection,
     circuit_id: &str,
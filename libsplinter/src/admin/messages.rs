@@ -16,6 +16,7 @@ pub use super::service::messages::{
     is_valid_circuit_id, is_valid_service_id, AdminServiceEvent, AuthorizationType,
     CircuitProposal, CircuitProposalVote, CreateCircuit, DurabilityType, PersistenceType,
     ProposalType, RouteType, SplinterNode, SplinterService, Vote, VoteRecord,
+    PROPOSAL_EXPIRED_SIGNER,
 };
 
 pub use super::service::messages::builders::{
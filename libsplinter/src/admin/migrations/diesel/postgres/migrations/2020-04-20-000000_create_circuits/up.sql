@@ -0,0 +1,32 @@
+-- Copyright 2018-2020 Cargill Incorporated
+--
+-- Licensed under the Apache License, Version 2.0 (the "License");
+-- you may not use this file except in compliance with the License.
+-- You may obtain a copy of the License at
+--
+--     http://www.apache.org/licenses/LICENSE-2.0
+--
+-- Unless required by applicable law or agreed to in writing, software
+-- distributed under the License is distributed on an "AS IS" BASIS,
+-- WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+-- See the License for the specific language governing permissions and
+-- limitations under the License.
+-- -----------------------------------------------------------------------------
+
+CREATE TABLE IF NOT EXISTS circuits (
+    circuit_id              TEXT PRIMARY KEY,
+    circuit_management_type TEXT NOT NULL,
+    definition              TEXT NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS circuit_members (
+    circuit_id TEXT NOT NULL,
+    node_id    TEXT NOT NULL,
+    PRIMARY KEY(circuit_id, node_id),
+    FOREIGN KEY (circuit_id) REFERENCES circuits(circuit_id) ON DELETE CASCADE
+);
+
+CREATE TABLE IF NOT EXISTS splinter_nodes (
+    node_id   TEXT PRIMARY KEY,
+    endpoints TEXT NOT NULL
+);
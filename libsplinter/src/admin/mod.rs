@@ -14,6 +14,8 @@
 
 pub mod error;
 pub mod messages;
+#[cfg(feature = "diesel")]
+pub mod migrations;
 #[cfg(feature = "rest-api")]
 pub mod rest_api;
 pub mod service;
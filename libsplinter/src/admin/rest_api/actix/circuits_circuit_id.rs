@@ -159,6 +159,7 @@ mod tests {
             .with_durability(DurabilityType::NoDurability)
             .with_routes(RouteType::Any)
             .with_circuit_management_type("circuit_1_type".into())
+            .with_application_metadata(b"circuit_1_metadata".to_vec())
             .build()
             .expect("Should have built a correct circuit")
     }
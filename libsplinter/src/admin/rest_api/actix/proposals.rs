@@ -170,7 +170,7 @@ mod tests {
     use crate::admin::{
         messages::{
             AuthorizationType, CircuitProposal, CreateCircuit, DurabilityType, PersistenceType,
-            ProposalType, RouteType, SplinterNode,
+            ProposalType, RouteType, SplinterNode, VotingRule,
         },
         service::proposal_store::{ProposalFilter, ProposalIter, ProposalStoreError},
     };
@@ -542,10 +542,12 @@ mod tests {
                 circuit_management_type: "mgmt_type_1".into(),
                 application_metadata: vec![],
                 comments: "mock circuit 1".into(),
+                voting_rule: VotingRule::Unanimous,
             },
             votes: vec![],
             requester: vec![],
             requester_node_id: "node_id".into(),
+            expires_at: None,
         }
     }
 
@@ -565,10 +567,12 @@ mod tests {
                 circuit_management_type: "mgmt_type_2".into(),
                 application_metadata: vec![],
                 comments: "mock circuit 2".into(),
+                voting_rule: VotingRule::Unanimous,
             },
             votes: vec![],
             requester: vec![],
             requester_node_id: "node_id".into(),
+            expires_at: None,
         }
     }
 
@@ -591,10 +595,12 @@ mod tests {
                 circuit_management_type: "mgmt_type_2".into(),
                 application_metadata: vec![],
                 comments: "mock circuit 3".into(),
+                voting_rule: VotingRule::Unanimous,
             },
             votes: vec![],
             requester: vec![],
             requester_node_id: "node_id".into(),
+            expires_at: None,
         }
     }
 
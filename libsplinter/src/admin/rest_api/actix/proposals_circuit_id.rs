@@ -86,7 +86,7 @@ mod tests {
     use crate::admin::{
         messages::{
             AuthorizationType, CircuitProposal, CreateCircuit, DurabilityType, PersistenceType,
-            ProposalType, RouteType,
+            ProposalType, RouteType, VotingRule,
         },
         service::proposal_store::{ProposalFilter, ProposalIter, ProposalStoreError},
     };
@@ -188,10 +188,12 @@ mod tests {
                 circuit_management_type: "mgmt_type".into(),
                 application_metadata: vec![],
                 comments: "mock circuit".into(),
+                voting_rule: VotingRule::Unanimous,
             },
             votes: vec![],
             requester: vec![],
             requester_node_id: "node_id".into(),
+            expires_at: None,
         }
     }
 
@@ -15,6 +15,7 @@
 use std::collections::BTreeMap;
 
 use crate::circuit::{Circuit, Roster, ServiceDefinition};
+use crate::hex::as_hex;
 use crate::rest_api::paging::Paging;
 
 #[derive(Debug, Serialize, Clone, PartialEq)]
@@ -29,6 +30,8 @@ pub(crate) struct CircuitResponse<'a> {
     pub members: Vec<String>,
     pub roster: Vec<ServiceResponse<'a>>,
     pub management_type: &'a str,
+    #[serde(serialize_with = "as_hex")]
+    pub application_metadata: &'a [u8],
 }
 
 impl<'a> From<&'a Circuit> for CircuitResponse<'a> {
@@ -38,6 +41,7 @@ impl<'a> From<&'a Circuit> for CircuitResponse<'a> {
             members: circuit.members().to_vec(),
             roster: circuit.roster().into(),
             management_type: circuit.circuit_management_type(),
+            application_metadata: circuit.application_metadata(),
         }
     }
 }
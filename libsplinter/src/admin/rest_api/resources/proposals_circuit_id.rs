@@ -14,6 +14,7 @@
 
 use crate::admin::messages::{
     CircuitProposal, CreateCircuit, ProposalType, SplinterNode, SplinterService, Vote, VoteRecord,
+    VotingRule,
 };
 use crate::hex::as_hex;
 
@@ -27,6 +28,8 @@ pub(crate) struct ProposalResponse<'a> {
     #[serde(serialize_with = "as_hex")]
     pub requester: &'a [u8],
     pub requester_node_id: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<u64>,
 }
 
 impl<'a> From<&'a CircuitProposal> for ProposalResponse<'a> {
@@ -37,6 +40,7 @@ impl<'a> From<&'a CircuitProposal> for ProposalResponse<'a> {
             ProposalType::AddNode => "AddNode",
             ProposalType::RemoveNode => "RemoveNode",
             ProposalType::Destroy => "Destroy",
+            ProposalType::UpdateApplicationMetadata => "UpdateApplicationMetadata",
         };
 
         Self {
@@ -47,6 +51,7 @@ impl<'a> From<&'a CircuitProposal> for ProposalResponse<'a> {
             votes: proposal.votes.iter().map(VoteResponse::from).collect(),
             requester: &proposal.requester,
             requester_node_id: &proposal.requester_node_id,
+            expires_at: proposal.expires_at,
         }
     }
 }
@@ -83,6 +88,7 @@ pub(crate) struct CircuitResponse<'a> {
     #[serde(serialize_with = "as_hex")]
     pub application_metadata: &'a [u8],
     pub comments: &'a str,
+    pub voting_rule: &'a VotingRule,
 }
 
 impl<'a> From<&'a CreateCircuit> for CircuitResponse<'a> {
@@ -94,6 +100,7 @@ impl<'a> From<&'a CreateCircuit> for CircuitResponse<'a> {
             management_type: &circuit.circuit_management_type,
             application_metadata: &circuit.application_metadata,
             comments: &circuit.comments,
+            voting_rule: &circuit.voting_rule,
         }
     }
 }
@@ -162,7 +162,7 @@ impl ProposalManager for AdminProposalManager {
             .lock()
             .map_err(|_| ServiceError::PoisonedLock("the admin state lock was poisoned".into()))?;
         if let Some(circuit_payload) = shared.pop_pending_circuit_payload() {
-            let (expected_hash, circuit_proposal) = shared
+            let (expected_hash, _) = shared
                 .propose_change(circuit_payload.clone())
                 .map_err(|err| ProposalManagerError::Internal(Box::new(err)))?;
 
@@ -179,12 +179,13 @@ impl ProposalManager for AdminProposalManager {
             proposal.summary = expected_hash.as_bytes().into();
 
             let mut required_verifiers = RequiredVerifiers::new();
-            let mut verifiers = vec![];
-            let members = circuit_proposal.get_circuit_proposal().get_members();
-            for member in members {
-                verifiers.push(admin_service_id(member.get_node_id()).as_bytes().to_vec());
-            }
-            required_verifiers.set_verifiers(RepeatedField::from_vec(verifiers));
+            let verifiers = shared.current_consensus_verifiers().clone();
+            required_verifiers.set_verifiers(RepeatedField::from_vec(
+                verifiers
+                    .iter()
+                    .map(|verifier| verifier.as_bytes().to_vec())
+                    .collect(),
+            ));
             let required_verifiers_bytes = required_verifiers
                 .write_to_bytes()
                 .map_err(|err| ProposalManagerError::Internal(Box::new(err)))?;
@@ -205,11 +206,10 @@ impl ProposalManager for AdminProposalManager {
             msg.set_proposed_circuit(proposed_circuit);
 
             let envelope_bytes = msg.write_to_bytes().unwrap();
-            for member in members {
-                if member.get_node_id() != shared.node_id() {
-                    network_sender
-                        .send(&admin_service_id(member.get_node_id()), &envelope_bytes)
-                        .unwrap();
+            let local_admin_service_id = admin_service_id(shared.node_id());
+            for verifier in verifiers {
+                if verifier != local_admin_service_id {
+                    network_sender.send(&verifier, &envelope_bytes).unwrap();
                 }
             }
 
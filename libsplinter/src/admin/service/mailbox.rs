@@ -332,10 +332,12 @@ mod tests {
                 circuit_management_type: event_type.into(),
                 application_metadata: vec![],
                 comments: "mock circuit".into(),
+                voting_rule: messages::VotingRule::Unanimous,
             },
             votes: vec![],
             requester: vec![],
             requester_node_id: "another-node".into(),
+            expires_at: None,
         })
     }
 }
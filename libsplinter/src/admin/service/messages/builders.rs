@@ -18,7 +18,7 @@ use crate::base62::generate_random_base62_string;
 
 use super::{
     is_valid_circuit_id, is_valid_service_id, AuthorizationType, CreateCircuit, DurabilityType,
-    PersistenceType, RouteType, SplinterNode, SplinterService,
+    PersistenceType, RouteType, SplinterNode, SplinterService, VotingRule,
 };
 
 #[derive(Default, Clone)]
@@ -33,6 +33,7 @@ pub struct CreateCircuitBuilder {
     circuit_management_type: Option<String>,
     application_metadata: Option<Vec<u8>>,
     comments: Option<String>,
+    voting_rule: Option<VotingRule>,
 }
 
 impl CreateCircuitBuilder {
@@ -80,6 +81,10 @@ impl CreateCircuitBuilder {
         self.comments.clone()
     }
 
+    pub fn voting_rule(&self) -> Option<VotingRule> {
+        self.voting_rule.clone()
+    }
+
     pub fn with_circuit_id(mut self, circuit_id: &str) -> CreateCircuitBuilder {
         self.circuit_id = Some(circuit_id.into());
         self
@@ -139,6 +144,11 @@ impl CreateCircuitBuilder {
         self
     }
 
+    pub fn with_voting_rule(mut self, voting_rule: &VotingRule) -> CreateCircuitBuilder {
+        self.voting_rule = Some(voting_rule.clone());
+        self
+    }
+
     pub fn build(self) -> Result<CreateCircuit, BuilderError> {
         let circuit_id = match self.circuit_id {
             Some(circuit_id) if is_valid_circuit_id(&circuit_id) => circuit_id,
@@ -200,6 +210,8 @@ impl CreateCircuitBuilder {
 
         let comments = self.comments.unwrap_or_default();
 
+        let voting_rule = self.voting_rule.unwrap_or_default();
+
         let create_circuit_message = CreateCircuit {
             circuit_id,
             roster,
@@ -211,6 +223,7 @@ impl CreateCircuitBuilder {
             circuit_management_type,
             application_metadata,
             comments,
+            voting_rule,
         };
 
         Ok(create_circuit_message)
@@ -379,6 +392,7 @@ mod tests {
         assert!(builder.circuit_management_type().is_none());
         assert!(builder.application_metadata().is_none());
         assert!(builder.comments().is_none());
+        assert!(builder.voting_rule().is_none());
 
         let service = SplinterServiceBuilder::new()
             .with_service_type("service_type")
@@ -400,7 +414,8 @@ mod tests {
             .with_routes(&RouteType::Any)
             .with_circuit_management_type("mgmt_type")
             .with_application_metadata(b"abcd")
-            .with_comments("new circuit");
+            .with_comments("new circuit")
+            .with_voting_rule(&VotingRule::Threshold(1));
         assert_eq!(builder.circuit_id(), Some("0123a-bcDEF".into()));
         assert_eq!(builder.roster(), Some(vec![service.clone()]));
         assert_eq!(builder.members(), Some(vec![node.clone()]));
@@ -411,6 +426,7 @@ mod tests {
         assert_eq!(builder.circuit_management_type(), Some("mgmt_type".into()));
         assert_eq!(builder.application_metadata(), Some(b"abcd".to_vec()));
         assert_eq!(builder.comments(), Some("new circuit".into()));
+        assert_eq!(builder.voting_rule(), Some(VotingRule::Threshold(1)));
 
         let circuit = builder.build().expect("failed to build circuit");
         assert_eq!(&circuit.circuit_id, "0123a-bcDEF");
@@ -423,11 +439,12 @@ mod tests {
         assert_eq!(&circuit.circuit_management_type, "mgmt_type");
         assert_eq!(&circuit.application_metadata, b"abcd");
         assert_eq!(&circuit.comments, "new circuit");
+        assert_eq!(circuit.voting_rule, VotingRule::Threshold(1));
     }
 
     /// Verify that the `CreateCircuitBuilder` builds a correct `CreateCircuit` when `circuit_id`,
-    /// `authorization_type`, `persistence`, `durability`, `routes`, `application_metadata`, and
-    /// `comments` are unset.
+    /// `authorization_type`, `persistence`, `durability`, `routes`, `application_metadata`,
+    /// `comments`, and `voting_rule` are unset.
     #[test]
     fn circuit_builder_successful_with_defaults() {
         let service = SplinterServiceBuilder::new()
@@ -464,6 +481,7 @@ mod tests {
         assert_eq!(&circuit.circuit_management_type, "mgmt_type");
         assert!(circuit.application_metadata.is_empty());
         assert!(circuit.comments.is_empty());
+        assert_eq!(circuit.voting_rule, VotingRule::Unanimous);
     }
 
     /// Verify that the `CreateCircuitBuilder` fails to build when an invalid `circuit_id` is
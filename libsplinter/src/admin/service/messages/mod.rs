@@ -21,6 +21,11 @@ use crate::protos::admin::{self, CircuitCreateRequest};
 
 use super::error::MarshallingError;
 
+/// The signer of the `ProposalRejected` event that is sent when a proposal expires. An expired
+/// proposal was not rejected by any key, and the signer of any other `ProposalRejected` event is
+/// never empty.
+pub const PROPOSAL_EXPIRED_SIGNER: &[u8] = &[];
+
 #[derive(Clone, Serialize, Deserialize, Debug, PartialEq, Eq)]
 pub struct CreateCircuit {
     pub circuit_id: String,
@@ -39,6 +44,8 @@ pub struct CreateCircuit {
     pub application_metadata: Vec<u8>,
     #[serde(default)]
     pub comments: String,
+    #[serde(default)]
+    pub voting_rule: VotingRule,
 }
 
 impl CreateCircuit {
@@ -96,6 +103,7 @@ impl CreateCircuit {
             circuit_management_type: proto.take_circuit_management_type(),
             application_metadata: proto.take_application_metadata(),
             comments: proto.take_comments(),
+            voting_rule: VotingRule::from_proto(proto.take_voting_rule())?,
         })
     }
 
@@ -119,6 +127,7 @@ impl CreateCircuit {
         circuit.set_circuit_management_type(self.circuit_management_type);
         circuit.set_application_metadata(self.application_metadata);
         circuit.set_comments(self.comments);
+        circuit.set_voting_rule(self.voting_rule.into_proto());
 
         match self.authorization_type {
             AuthorizationType::Trust => {
@@ -194,6 +203,57 @@ impl Default for RouteType {
     }
 }
 
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq, Eq)]
+pub enum VotingRule {
+    Unanimous,
+    Majority,
+    Threshold(u32),
+    RequiredApprovers(Vec<String>),
+}
+
+impl Default for VotingRule {
+    fn default() -> Self {
+        VotingRule::Unanimous
+    }
+}
+
+impl VotingRule {
+    pub fn from_proto(mut proto: admin::VotingRule) -> Result<Self, MarshallingError> {
+        match proto.get_rule_type() {
+            admin::VotingRule_RuleType::UNSET_RULE_TYPE | admin::VotingRule_RuleType::UNANIMOUS => {
+                Ok(VotingRule::Unanimous)
+            }
+            admin::VotingRule_RuleType::MAJORITY => Ok(VotingRule::Majority),
+            admin::VotingRule_RuleType::THRESHOLD => {
+                Ok(VotingRule::Threshold(proto.get_threshold()))
+            }
+            admin::VotingRule_RuleType::REQUIRED_APPROVERS => Ok(VotingRule::RequiredApprovers(
+                proto.take_required_approvers().into(),
+            )),
+        }
+    }
+
+    pub fn into_proto(self) -> admin::VotingRule {
+        let mut proto = admin::VotingRule::new();
+
+        match self {
+            // unanimous is the default rule, and is left unset
+            VotingRule::Unanimous => (),
+            VotingRule::Majority => proto.set_rule_type(admin::VotingRule_RuleType::MAJORITY),
+            VotingRule::Threshold(threshold) => {
+                proto.set_rule_type(admin::VotingRule_RuleType::THRESHOLD);
+                proto.set_threshold(threshold);
+            }
+            VotingRule::RequiredApprovers(approvers) => {
+                proto.set_rule_type(admin::VotingRule_RuleType::REQUIRED_APPROVERS);
+                proto.set_required_approvers(RepeatedField::from_vec(approvers));
+            }
+        }
+
+        proto
+    }
+}
+
 #[derive(Clone, Serialize, Deserialize, Debug, PartialEq, Eq)]
 pub struct SplinterNode {
     pub node_id: String,
@@ -284,6 +344,8 @@ pub struct CircuitProposal {
     #[serde(deserialize_with = "deserialize_hex")]
     pub requester: Vec<u8>,
     pub requester_node_id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<u64>,
 }
 
 impl CircuitProposal {
@@ -294,6 +356,9 @@ impl CircuitProposal {
             admin::CircuitProposal_ProposalType::ADD_NODE => ProposalType::AddNode,
             admin::CircuitProposal_ProposalType::REMOVE_NODE => ProposalType::RemoveNode,
             admin::CircuitProposal_ProposalType::DESTROY => ProposalType::Destroy,
+            admin::CircuitProposal_ProposalType::UPDATE_APPLICATION_METADATA => {
+                ProposalType::UpdateApplicationMetadata
+            }
             admin::CircuitProposal_ProposalType::UNSET_PROPOSAL_TYPE => {
                 return Err(MarshallingError::UnsetField(
                     "Unset proposal type".to_string(),
@@ -315,6 +380,10 @@ impl CircuitProposal {
             votes,
             requester: proto.take_requester(),
             requester_node_id: proto.take_requester_node_id(),
+            expires_at: match proto.get_expires_at() {
+                0 => None,
+                expires_at => Some(expires_at),
+            },
         })
     }
 
@@ -325,6 +394,9 @@ impl CircuitProposal {
             ProposalType::AddNode => admin::CircuitProposal_ProposalType::ADD_NODE,
             ProposalType::RemoveNode => admin::CircuitProposal_ProposalType::REMOVE_NODE,
             ProposalType::Destroy => admin::CircuitProposal_ProposalType::DESTROY,
+            ProposalType::UpdateApplicationMetadata => {
+                admin::CircuitProposal_ProposalType::UPDATE_APPLICATION_METADATA
+            }
         };
 
         let votes = self
@@ -343,6 +415,7 @@ impl CircuitProposal {
         proposal.set_votes(RepeatedField::from_vec(votes));
         proposal.set_requester(self.requester.to_vec());
         proposal.set_requester_node_id(self.requester_node_id.to_string());
+        proposal.set_expires_at(self.expires_at.unwrap_or(0));
 
         Ok(proposal)
     }
@@ -355,6 +428,7 @@ pub enum ProposalType {
     AddNode,
     RemoveNode,
     Destroy,
+    UpdateApplicationMetadata,
 }
 
 #[derive(Clone, Serialize, Deserialize, Debug, PartialEq, Eq)]
@@ -446,8 +520,12 @@ pub enum AdminServiceEvent {
     ProposalSubmitted(CircuitProposal),
     ProposalVote((CircuitProposal, Vec<u8>)),
     ProposalAccepted((CircuitProposal, Vec<u8>)),
+    /// A proposal was rejected by the given signer, withdrawn by its requester (the signer), or
+    /// expired (the signer is `PROPOSAL_EXPIRED_SIGNER`)
     ProposalRejected((CircuitProposal, Vec<u8>)),
     CircuitReady(CircuitProposal),
+    CircuitDestroyed(CircuitProposal),
+    ApplicationMetadataUpdated(CircuitProposal),
 }
 
 impl AdminServiceEvent {
@@ -458,6 +536,8 @@ impl AdminServiceEvent {
             AdminServiceEvent::ProposalAccepted((proposal, _)) => proposal,
             AdminServiceEvent::ProposalRejected((proposal, _)) => proposal,
             AdminServiceEvent::CircuitReady(proposal) => proposal,
+            AdminServiceEvent::CircuitDestroyed(proposal) => proposal,
+            AdminServiceEvent::ApplicationMetadataUpdated(proposal) => proposal,
         }
     }
 }
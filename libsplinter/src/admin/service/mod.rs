@@ -19,11 +19,14 @@ pub(crate) mod messages;
 pub(super) mod open_proposals;
 pub(super) mod proposal_store;
 mod shared;
+mod voting;
 
 use std::any::Any;
 #[cfg(feature = "service-arg-validation")]
 use std::collections::HashMap;
+use std::sync::mpsc::{channel, RecvTimeoutError, Sender};
 use std::sync::{Arc, Mutex};
+use std::thread::{Builder, JoinHandle};
 use std::time::{Duration, SystemTime};
 
 use openssl::hash::{hash, MessageDigest};
@@ -54,8 +57,11 @@ use self::shared::AdminServiceShared;
 
 pub use self::error::AdminServiceError;
 pub use self::error::AdminSubscriberError;
+#[cfg(feature = "diesel")]
+pub use self::proposal_store::diesel::DieselProposalStore;
 
 const DEFAULT_COORDINATOR_TIMEOUT: u64 = 30; // 30 seconds
+const PROPOSAL_EXPIRATION_CHECK_INTERVAL: u64 = 1; // 1 second
 
 pub trait AdminServiceEventSubscriber: Send {
     fn handle_event(
@@ -105,12 +111,65 @@ impl Iterator for Events {
     }
 }
 
+/// Periodically rejects the open proposals that have expired.
+struct ProposalExpirationChecker {
+    shutdown_tx: Sender<()>,
+    thread_handle: JoinHandle<()>,
+}
+
+impl ProposalExpirationChecker {
+    fn new(
+        service_id: &str,
+        shared: Arc<Mutex<AdminServiceShared>>,
+    ) -> Result<Self, std::io::Error> {
+        let (shutdown_tx, shutdown_rx) = channel();
+        let interval = Duration::from_secs(PROPOSAL_EXPIRATION_CHECK_INTERVAL);
+
+        let thread_handle = Builder::new()
+            .name(format!("proposal-expiration-{}", service_id))
+            .spawn(move || loop {
+                match shutdown_rx.recv_timeout(interval) {
+                    Err(RecvTimeoutError::Timeout) => match shared.lock() {
+                        Ok(mut shared) => {
+                            if let Err(err) = shared.expire_proposals() {
+                                error!("Unable to expire proposals: {}", err);
+                            }
+                        }
+                        Err(_) => {
+                            error!("the admin shared lock was poisoned");
+                            break;
+                        }
+                    },
+                    // shutdown was requested or the sender was dropped
+                    _ => break,
+                }
+            })?;
+
+        Ok(ProposalExpirationChecker {
+            shutdown_tx,
+            thread_handle,
+        })
+    }
+
+    /// Consumes self and shuts down the expiration thread.
+    fn shutdown(self) {
+        if self.shutdown_tx.send(()).is_err() {
+            debug!("proposal expiration thread has already exited");
+        }
+
+        self.thread_handle
+            .join()
+            .unwrap_or_else(|err| error!("proposal expiration thread failed: {:?}", err));
+    }
+}
+
 pub struct AdminService {
     service_id: String,
     admin_service_shared: Arc<Mutex<AdminServiceShared>>,
     /// The coordinator timeout for the two-phase commit consensus engine
     coordinator_timeout: Duration,
     consensus: Option<AdminConsensusManager>,
+    proposal_expiration: Option<ProposalExpirationChecker>,
 }
 
 impl AdminService {
@@ -153,6 +212,7 @@ impl AdminService {
             )?)),
             coordinator_timeout,
             consensus: None,
+            proposal_expiration: None,
         };
 
         let auth_callback_shared = Arc::clone(&new_service.admin_service_shared);
@@ -197,6 +257,26 @@ impl AdminService {
     pub fn proposals(&self) -> impl ProposalStore {
         AdminServiceProposals::new(&self.admin_service_shared)
     }
+
+    /// Use the given proposal store to persist open circuit proposals, instead of the storage
+    /// type the service was created with. Open proposals are loaded from the store.
+    #[cfg(feature = "diesel")]
+    pub fn with_proposal_store(
+        self,
+        proposal_store: DieselProposalStore,
+    ) -> Result<Self, ServiceError> {
+        self.admin_service_shared
+            .lock()
+            .map_err(|_| {
+                ServiceError::PoisonedLock(
+                    "The lock was poisoned while setting the proposal store".into(),
+                )
+            })?
+            .set_proposal_store(proposal_store)
+            .map_err(|err| ServiceError::UnableToCreate(Box::new(err)))?;
+
+        Ok(self)
+    }
 }
 
 impl Service for AdminService {
@@ -244,6 +324,11 @@ impl Service for AdminService {
             })?
             .set_proposal_sender(Some(proposal_sender));
 
+        self.proposal_expiration = Some(
+            ProposalExpirationChecker::new(&self.service_id, self.admin_service_shared.clone())
+                .map_err(|err| ServiceStartError::Internal(Box::new(err)))?,
+        );
+
         self.admin_service_shared
             .lock()
             .map_err(|_| {
@@ -276,6 +361,10 @@ impl Service for AdminService {
             .shutdown()
             .map_err(|err| ServiceStopError::Internal(Box::new(err)))?;
 
+        if let Some(proposal_expiration) = self.proposal_expiration.take() {
+            proposal_expiration.shutdown();
+        }
+
         let mut admin_service_shared = self.admin_service_shared.lock().map_err(|_| {
             ServiceStopError::PoisonedLock("the admin shared lock was poisoned".into())
         })?;
@@ -353,6 +442,24 @@ impl Service for AdminService {
                     .add_ready_member(circuit_id, member_node_id.into())
                     .map_err(|err| ServiceError::UnableToHandleMessage(Box::new(err)))
             }
+            AdminMessage_Type::CIRCUIT_JOIN_REQUEST => {
+                let join_request = admin_message.get_circuit_join_request();
+
+                let mut shared = self.admin_service_shared.lock().map_err(|_| {
+                    ServiceError::PoisonedLock("the admin shared lock was poisoned".into())
+                })?;
+
+                shared.handle_join_request(join_request.clone(), &message_context.sender)
+            }
+            AdminMessage_Type::CIRCUIT_ABANDON => {
+                let circuit_abandon = admin_message.get_circuit_abandon();
+
+                let mut shared = self.admin_service_shared.lock().map_err(|_| {
+                    ServiceError::PoisonedLock("the admin shared lock was poisoned".into())
+                })?;
+
+                shared.handle_circuit_abandon(circuit_abandon.clone(), &message_context.sender)
+            }
             AdminMessage_Type::UNSET => Err(ServiceError::InvalidMessageFormat(Box::new(
                 AdminError::MessageTypeUnset,
             ))),
@@ -21,10 +21,16 @@ use crate::storage::get_storage;
 
 use super::error::OpenProposalError;
 use super::messages;
+#[cfg(feature = "diesel")]
+use super::proposal_store::{diesel::DieselProposalStore, ProposalStore};
 
 pub struct OpenProposals {
     storage_location: String,
     proposal_registry: ProposalRegistry,
+    // If set, changes are persisted to this store a proposal at a time, instead of writing all
+    // open proposals to the storage location.
+    #[cfg(feature = "diesel")]
+    proposal_store: Option<DieselProposalStore>,
 }
 
 impl OpenProposals {
@@ -42,6 +48,31 @@ impl OpenProposals {
         Ok(Self {
             storage_location,
             proposal_registry,
+            #[cfg(feature = "diesel")]
+            proposal_store: None,
+        })
+    }
+
+    /// Constructs a new OpenProposals that is loaded from, and persisted to, the given proposal
+    /// store.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `OpenProposalError` if the proposals could not be loaded from the store.
+    #[cfg(feature = "diesel")]
+    pub fn from_proposal_store(
+        proposal_store: DieselProposalStore,
+    ) -> Result<Self, OpenProposalError> {
+        let proposals = proposal_store
+            .proposals(vec![])
+            .map_err(|err| OpenProposalError::WriteError(err.to_string()))?
+            .map(|proposal| (proposal.circuit_id.clone(), proposal))
+            .collect();
+
+        Ok(Self {
+            storage_location: "database".into(),
+            proposal_registry: ProposalRegistry { proposals },
+            proposal_store: Some(proposal_store),
         })
     }
 
@@ -49,8 +80,9 @@ impl OpenProposals {
         &mut self,
         circuit_proposal: CircuitProposal,
     ) -> Result<Option<CircuitProposal>, OpenProposalError> {
+        let circuit_id = circuit_proposal.get_circuit_id().to_string();
         let proposal = self.proposal_registry.add_proposal(circuit_proposal);
-        self.write_open_proposals()?;
+        self.write_open_proposal(&circuit_id)?;
 
         proposal
     }
@@ -60,7 +92,7 @@ impl OpenProposals {
         circuit_id: &str,
     ) -> Result<Option<CircuitProposal>, OpenProposalError> {
         let proposal = self.proposal_registry.remove_proposal(circuit_id);
-        self.write_open_proposals()?;
+        self.write_open_proposal(circuit_id)?;
 
         proposal
     }
@@ -84,6 +116,22 @@ impl OpenProposals {
         &self.storage_location
     }
 
+    fn write_open_proposal(&self, circuit_id: &str) -> Result<(), OpenProposalError> {
+        #[cfg(feature = "diesel")]
+        {
+            if let Some(proposal_store) = &self.proposal_store {
+                let result = match self.proposal_registry.proposals.get(circuit_id) {
+                    Some(proposal) => proposal_store.add_proposal(proposal.clone()),
+                    None => proposal_store.remove_proposal(circuit_id),
+                };
+
+                return result.map_err(|err| OpenProposalError::WriteError(err.to_string()));
+            }
+        }
+
+        self.write_open_proposals()
+    }
+
     fn write_open_proposals(&self) -> Result<(), OpenProposalError> {
         // Replace stored key_registry with the current key registry
         let mut storage = get_storage(self.storage_location(), || self.proposal_registry.clone())
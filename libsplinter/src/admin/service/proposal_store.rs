@@ -12,6 +12,9 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+#[cfg(feature = "diesel")]
+pub mod diesel;
+
 use std::sync::{Arc, Mutex};
 
 use super::messages::CircuitProposal;
@@ -0,0 +1,86 @@
+// Copyright 2018-2020 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! A database-backed proposal store, powered by [`Diesel`](https://crates.io/crates/diesel).
+//!
+//! Each proposal is stored in its own row, so adding or removing a proposal only writes that
+//! proposal, rather than every open proposal.
+//!
+//! Only PostgreSQL is supported; the store's tables are created by
+//! `admin::migrations::run_postgres_migrations`, and there are no SQLite migrations.
+
+mod models;
+mod operations;
+mod schema;
+
+use crate::admin::service::messages::CircuitProposal;
+use crate::admin::service::proposal_store::{
+    ProposalFilter, ProposalIter, ProposalStore, ProposalStoreError,
+};
+use crate::database::ConnectionPool;
+
+use operations::{
+    add_proposal::ProposalStoreAddProposalOperation as _,
+    fetch_proposal::ProposalStoreFetchProposalOperation as _,
+    list_proposals::ProposalStoreListProposalsOperation as _,
+    remove_proposal::ProposalStoreRemoveProposalOperation as _, ProposalStoreOperations,
+};
+
+/// Manages creating, updating and fetching circuit proposals from a PostgreSQL database.
+#[derive(Clone)]
+pub struct DieselProposalStore {
+    connection_pool: ConnectionPool,
+}
+
+impl DieselProposalStore {
+    /// Creates a new DieselProposalStore
+    ///
+    /// # Arguments
+    ///
+    ///  * `connection_pool`: connection pool to the PostgreSQL database
+    ///
+    pub fn new(connection_pool: ConnectionPool) -> Self {
+        DieselProposalStore { connection_pool }
+    }
+
+    /// Adds a proposal to the store, replacing any existing proposal for the same circuit.
+    pub(crate) fn add_proposal(&self, proposal: CircuitProposal) -> Result<(), ProposalStoreError> {
+        ProposalStoreOperations::new(&*self.connection()?).add_proposal(proposal)
+    }
+
+    /// Removes the proposal for the given circuit from the store, if it exists.
+    pub(crate) fn remove_proposal(&self, circuit_id: &str) -> Result<(), ProposalStoreError> {
+        ProposalStoreOperations::new(&*self.connection()?).remove_proposal(circuit_id)
+    }
+
+    fn connection(&self) -> Result<crate::database::Connection, ProposalStoreError> {
+        self.connection_pool
+            .get()
+            .map_err(|err| ProposalStoreError::new(&err.to_string()))
+    }
+}
+
+impl ProposalStore for DieselProposalStore {
+    fn proposals(&self, filters: Vec<ProposalFilter>) -> Result<ProposalIter, ProposalStoreError> {
+        let proposals =
+            ProposalStoreOperations::new(&*self.connection()?).list_proposals(filters)?;
+        let total = proposals.len();
+
+        Ok(ProposalIter::new(Box::new(proposals.into_iter()), total))
+    }
+
+    fn proposal(&self, circuit_id: &str) -> Result<Option<CircuitProposal>, ProposalStoreError> {
+        ProposalStoreOperations::new(&*self.connection()?).fetch_proposal(circuit_id)
+    }
+}
@@ -0,0 +1,34 @@
+// Copyright 2018-2020 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::schema::{circuit_proposal_members, circuit_proposals};
+
+/// A circuit proposal, stored as JSON alongside the columns used for filtering.
+#[derive(Insertable, Queryable, Identifiable, PartialEq, Debug)]
+#[table_name = "circuit_proposals"]
+#[primary_key(circuit_id)]
+pub struct CircuitProposalModel {
+    pub circuit_id: String,
+    pub circuit_management_type: String,
+    pub proposal: String,
+}
+
+/// A member of the circuit proposed by a circuit proposal.
+#[derive(Insertable, Queryable, Identifiable, PartialEq, Debug)]
+#[table_name = "circuit_proposal_members"]
+#[primary_key(circuit_id, node_id)]
+pub struct CircuitProposalMemberModel {
+    pub circuit_id: String,
+    pub node_id: String,
+}
@@ -0,0 +1,87 @@
+// Copyright 2018-2020 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::ProposalStoreOperations;
+use crate::admin::service::messages::CircuitProposal;
+use crate::admin::service::proposal_store::diesel::models::{
+    CircuitProposalMemberModel, CircuitProposalModel,
+};
+use crate::admin::service::proposal_store::diesel::schema::{
+    circuit_proposal_members, circuit_proposals,
+};
+use crate::admin::service::proposal_store::ProposalStoreError;
+
+use diesel::{
+    dsl::{delete, insert_into},
+    prelude::*,
+};
+
+pub(in crate::admin::service::proposal_store) trait ProposalStoreAddProposalOperation {
+    fn add_proposal(&self, proposal: CircuitProposal) -> Result<(), ProposalStoreError>;
+}
+
+impl<'a, C> ProposalStoreAddProposalOperation for ProposalStoreOperations<'a, C>
+where
+    C: diesel::Connection,
+    <C as diesel::Connection>::Backend: diesel::backend::SupportsDefaultKeyword,
+    <C as diesel::Connection>::Backend: 'static,
+    String: diesel::deserialize::FromSql<diesel::sql_types::Text, C::Backend>,
+{
+    fn add_proposal(&self, proposal: CircuitProposal) -> Result<(), ProposalStoreError> {
+        let circuit_id = proposal.circuit_id.clone();
+        let proposal_json = serde_json::to_string(&proposal).map_err(|err| {
+            ProposalStoreError::from_source("Failed to serialize circuit proposal", err)
+        })?;
+
+        let proposal_model = CircuitProposalModel {
+            circuit_id: circuit_id.clone(),
+            circuit_management_type: proposal.circuit.circuit_management_type.clone(),
+            proposal: proposal_json,
+        };
+        let member_models = proposal
+            .circuit
+            .members
+            .iter()
+            .map(|member| CircuitProposalMemberModel {
+                circuit_id: circuit_id.clone(),
+                node_id: member.node_id.clone(),
+            })
+            .collect::<Vec<_>>();
+
+        // Replace any existing proposal for the circuit, so that votes added to a proposal are
+        // stored by adding it again.
+        self.conn
+            .transaction::<_, diesel::result::Error, _>(|| {
+                delete(
+                    circuit_proposal_members::table
+                        .filter(circuit_proposal_members::circuit_id.eq(&circuit_id)),
+                )
+                .execute(self.conn)?;
+                delete(
+                    circuit_proposals::table.filter(circuit_proposals::circuit_id.eq(&circuit_id)),
+                )
+                .execute(self.conn)?;
+
+                insert_into(circuit_proposals::table)
+                    .values(vec![proposal_model])
+                    .execute(self.conn)?;
+                insert_into(circuit_proposal_members::table)
+                    .values(member_models)
+                    .execute(self.conn)?;
+
+                Ok(())
+            })
+            .map_err(|err| ProposalStoreError::from_source("Failed to add circuit proposal", err))
+    }
+}
@@ -0,0 +1,60 @@
+// Copyright 2018-2020 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::ProposalStoreOperations;
+use crate::admin::service::messages::CircuitProposal;
+use crate::admin::service::proposal_store::diesel::models::CircuitProposalModel;
+use crate::admin::service::proposal_store::diesel::schema::circuit_proposals;
+use crate::admin::service::proposal_store::ProposalStoreError;
+
+use diesel::{prelude::*, result::Error::NotFound};
+
+pub(in crate::admin::service::proposal_store) trait ProposalStoreFetchProposalOperation {
+    fn fetch_proposal(
+        &self,
+        circuit_id: &str,
+    ) -> Result<Option<CircuitProposal>, ProposalStoreError>;
+}
+
+impl<'a, C> ProposalStoreFetchProposalOperation for ProposalStoreOperations<'a, C>
+where
+    C: diesel::Connection,
+    <C as diesel::Connection>::Backend: diesel::backend::SupportsDefaultKeyword,
+    <C as diesel::Connection>::Backend: 'static,
+    String: diesel::deserialize::FromSql<diesel::sql_types::Text, C::Backend>,
+{
+    fn fetch_proposal(
+        &self,
+        circuit_id: &str,
+    ) -> Result<Option<CircuitProposal>, ProposalStoreError> {
+        circuit_proposals::table
+            .filter(circuit_proposals::circuit_id.eq(circuit_id))
+            .first::<CircuitProposalModel>(self.conn)
+            .map(Some)
+            .or_else(|err| if err == NotFound { Ok(None) } else { Err(err) })
+            .map_err(|err| {
+                ProposalStoreError::from_source("Failed to fetch circuit proposal", err)
+            })?
+            .map(|proposal_model| proposal_from_model(&proposal_model))
+            .transpose()
+    }
+}
+
+pub(super) fn proposal_from_model(
+    proposal_model: &CircuitProposalModel,
+) -> Result<CircuitProposal, ProposalStoreError> {
+    serde_json::from_str(&proposal_model.proposal).map_err(|err| {
+        ProposalStoreError::from_source("Failed to deserialize circuit proposal", err)
+    })
+}
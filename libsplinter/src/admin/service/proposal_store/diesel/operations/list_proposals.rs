@@ -0,0 +1,83 @@
+// Copyright 2018-2020 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::fetch_proposal::proposal_from_model;
+use super::ProposalStoreOperations;
+use crate::admin::service::messages::CircuitProposal;
+use crate::admin::service::proposal_store::diesel::models::CircuitProposalModel;
+use crate::admin::service::proposal_store::diesel::schema::{
+    circuit_proposal_members, circuit_proposals,
+};
+use crate::admin::service::proposal_store::{ProposalFilter, ProposalStoreError};
+
+use diesel::prelude::*;
+
+pub(in crate::admin::service::proposal_store) trait ProposalStoreListProposalsOperation {
+    fn list_proposals(
+        &self,
+        filters: Vec<ProposalFilter>,
+    ) -> Result<Vec<CircuitProposal>, ProposalStoreError>;
+}
+
+impl<'a, C> ProposalStoreListProposalsOperation for ProposalStoreOperations<'a, C>
+where
+    C: diesel::Connection,
+    <C as diesel::Connection>::Backend: diesel::backend::SupportsDefaultKeyword,
+    <C as diesel::Connection>::Backend: 'static,
+    String: diesel::deserialize::FromSql<diesel::sql_types::Text, C::Backend>,
+{
+    fn list_proposals(
+        &self,
+        filters: Vec<ProposalFilter>,
+    ) -> Result<Vec<CircuitProposal>, ProposalStoreError> {
+        // Narrow the proposals down to the circuit IDs that match every filter, then load them.
+        let mut circuit_ids: Option<Vec<String>> = None;
+        for filter in filters.iter() {
+            let matching_ids = match filter {
+                ProposalFilter::WithManagementType(management_type) => circuit_proposals::table
+                    .filter(circuit_proposals::circuit_management_type.eq(management_type))
+                    .select(circuit_proposals::circuit_id)
+                    .load::<String>(self.conn),
+                ProposalFilter::WithMember(member) => circuit_proposal_members::table
+                    .filter(circuit_proposal_members::node_id.eq(member))
+                    .select(circuit_proposal_members::circuit_id)
+                    .load::<String>(self.conn),
+            }
+            .map_err(|err| {
+                ProposalStoreError::from_source("Failed to filter circuit proposals", err)
+            })?;
+
+            circuit_ids = Some(match circuit_ids {
+                Some(ids) => ids
+                    .into_iter()
+                    .filter(|id| matching_ids.contains(id))
+                    .collect(),
+                None => matching_ids,
+            });
+        }
+
+        let proposal_models = match circuit_ids {
+            Some(circuit_ids) => circuit_proposals::table
+                .filter(circuit_proposals::circuit_id.eq_any(circuit_ids))
+                .order(circuit_proposals::circuit_id)
+                .load::<CircuitProposalModel>(self.conn),
+            None => circuit_proposals::table
+                .order(circuit_proposals::circuit_id)
+                .load::<CircuitProposalModel>(self.conn),
+        }
+        .map_err(|err| ProposalStoreError::from_source("Failed to list circuit proposals", err))?;
+
+        proposal_models.iter().map(proposal_from_model).collect()
+    }
+}
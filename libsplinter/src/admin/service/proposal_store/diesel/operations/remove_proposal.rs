@@ -0,0 +1,53 @@
+// Copyright 2018-2020 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::ProposalStoreOperations;
+use crate::admin::service::proposal_store::diesel::schema::{
+    circuit_proposal_members, circuit_proposals,
+};
+use crate::admin::service::proposal_store::ProposalStoreError;
+
+use diesel::{dsl::delete, prelude::*};
+
+pub(in crate::admin::service::proposal_store) trait ProposalStoreRemoveProposalOperation {
+    fn remove_proposal(&self, circuit_id: &str) -> Result<(), ProposalStoreError>;
+}
+
+impl<'a, C> ProposalStoreRemoveProposalOperation for ProposalStoreOperations<'a, C>
+where
+    C: diesel::Connection,
+    <C as diesel::Connection>::Backend: diesel::backend::SupportsDefaultKeyword,
+    <C as diesel::Connection>::Backend: 'static,
+    String: diesel::deserialize::FromSql<diesel::sql_types::Text, C::Backend>,
+{
+    fn remove_proposal(&self, circuit_id: &str) -> Result<(), ProposalStoreError> {
+        self.conn
+            .transaction::<_, diesel::result::Error, _>(|| {
+                delete(
+                    circuit_proposal_members::table
+                        .filter(circuit_proposal_members::circuit_id.eq(circuit_id)),
+                )
+                .execute(self.conn)?;
+                delete(
+                    circuit_proposals::table.filter(circuit_proposals::circuit_id.eq(circuit_id)),
+                )
+                .execute(self.conn)?;
+
+                Ok(())
+            })
+            .map_err(|err| {
+                ProposalStoreError::from_source("Failed to remove circuit proposal", err)
+            })
+    }
+}
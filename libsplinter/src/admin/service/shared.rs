@@ -26,24 +26,28 @@ use crate::circuit::{
     service::SplinterNode as StateNode,
     service::{Service, ServiceId},
     AuthorizationType, Circuit as StateCircuit, DurabilityType, PersistenceType, RouteType,
-    ServiceDefinition as StateServiceDefinition,
+    ServiceDefinition as StateServiceDefinition, VotingRule as StateVotingRule,
 };
 use crate::consensus::{Proposal, ProposalId, ProposalUpdate};
 use crate::hex::to_hex;
-use crate::keys::{KeyPermissionManager, KeyRegistry};
+use crate::keys::{
+    roles::{PROPOSER_ROLE, VOTER_ROLE},
+    KeyPermissionManager, KeyRegistry,
+};
 use crate::network::{
     auth::{AuthorizationCallbackError, AuthorizationInquisitor, PeerAuthorizationState},
     peer::PeerConnector,
 };
 use crate::orchestrator::{ServiceDefinition, ServiceOrchestrator, ShutdownServiceError};
-#[cfg(feature = "service-arg-validation")]
-use crate::protos::admin::SplinterService;
 use crate::protos::admin::{
-    AdminMessage, AdminMessage_Type, Circuit, CircuitManagementPayload,
-    CircuitManagementPayload_Action, CircuitManagementPayload_Header, CircuitProposal,
-    CircuitProposalVote, CircuitProposalVote_Vote, CircuitProposal_ProposalType,
-    CircuitProposal_VoteRecord, Circuit_AuthorizationType, Circuit_DurabilityType,
-    Circuit_PersistenceType, Circuit_RouteType, MemberReady, SplinterNode,
+    AdminMessage, AdminMessage_Type, Circuit, CircuitAbandon, CircuitJoinRequest,
+    CircuitManagementPayload, CircuitManagementPayload_Action, CircuitManagementPayload_Header,
+    CircuitProposal, CircuitProposalVote, CircuitProposalVote_Vote, CircuitProposalWithdraw,
+    CircuitProposal_ProposalType, CircuitProposal_VoteRecord, CircuitUpdateAddNodeRequest,
+    CircuitUpdateApplicationMetadataRequest, CircuitUpdateRemoveNodeRequest,
+    CircuitUpdateRosterRequest, Circuit_AuthorizationType, Circuit_DurabilityType,
+    Circuit_PersistenceType, Circuit_RouteType, MemberReady, SplinterNode, SplinterService,
+    SplinterService_Argument, VotingRule, VotingRule_RuleType,
 };
 use crate::service::error::ServiceError;
 #[cfg(feature = "service-arg-validation")]
@@ -57,15 +61,25 @@ use super::error::{AdminSharedError, MarshallingError};
 use super::mailbox::Mailbox;
 use super::messages;
 use super::open_proposals::OpenProposals;
+#[cfg(feature = "diesel")]
+use super::proposal_store::diesel::DieselProposalStore;
+use super::voting::{validate_voting_rule, voting_policy, CircuitProposalStatus, VoteTally};
 use super::{admin_service_id, sha256, AdminServiceEventSubscriber, AdminSubscriberError, Events};
 
 const DEFAULT_STATE_DIR: &str = "/var/lib/splinter/";
 const STATE_DIR_ENV: &str = "SPLINTER_STATE_DIR";
-static VOTER_ROLE: &str = "voter";
-static PROPOSER_ROLE: &str = "proposer";
+// the service argument listing the other services a service shares state with
+const PEER_SERVICES_ARG: &str = "peer_services";
 
 const DEFAULT_IN_MEMORY_EVENT_LIMIT: usize = 100;
 
+// the clock skew, in seconds, that is tolerated between the nodes that must agree on a proposal.
+// Every node rejects votes on a proposal once its own clock passes the proposal's expiration
+// time, so a vote can only be committed if no node considers the proposal expired. An expired
+// proposal is only removed once this tolerance has also passed, so that a node whose clock is
+// ahead does not remove a proposal that the other nodes may still commit a vote on.
+const PROPOSAL_EXPIRATION_SKEW_TOLERANCE: u64 = 60;
+
 pub enum PayloadType {
     Circuit(CircuitManagementPayload),
     Consensus(ProposalId, (Proposal, CircuitManagementPayload)),
@@ -77,12 +91,6 @@ pub struct UnpeeredPendingPayload {
     pub message_sender: String,
 }
 
-enum CircuitProposalStatus {
-    Accepted,
-    Rejected,
-    Pending,
-}
-
 struct CircuitProposalContext {
     pub circuit_proposal: CircuitProposal,
     pub action: CircuitManagementPayload_Action,
@@ -151,6 +159,9 @@ pub struct AdminServiceShared {
     // the list of circuit that have been committed to splinter state but whose services haven't
     // been initialized
     uninitialized_circuits: HashMap<String, UninitializedCircuit>,
+    // the requests to join existing circuits that are awaiting acceptance by a requester of this
+    // node, by circuit id
+    pending_join_requests: HashMap<String, CircuitProposal>,
     // orchestrator used to initialize and shutdown services
     orchestrator: ServiceOrchestrator,
     // map of service arg validators, by service type
@@ -232,6 +243,7 @@ impl AdminServiceShared {
             network_sender: None,
             open_proposals,
             uninitialized_circuits: Default::default(),
+            pending_join_requests: Default::default(),
             orchestrator,
             #[cfg(feature = "service-arg-validation")]
             service_arg_validators,
@@ -314,50 +326,49 @@ impl AdminServiceShared {
                     .circuit_management_type
                     .clone();
 
+                if action == CircuitManagementPayload_Action::CIRCUIT_PROPOSAL_WITHDRAW {
+                    return self.commit_withdraw_proposal(
+                        circuit_proposal,
+                        circuit_proposal_context.signer_public_key,
+                    );
+                }
+
                 match self.check_approved(&circuit_proposal) {
                     Ok(CircuitProposalStatus::Accepted) => {
-                        // commit new circuit
-                        let circuit = circuit_proposal.get_circuit_proposal();
-                        self.update_splinter_state(circuit)?;
-                        // remove approved proposal
-                        self.remove_proposal(&circuit_id)?;
-                        // send message about circuit acceptance
-
-                        let circuit_proposal_proto =
-                            messages::CircuitProposal::from_proto(circuit_proposal.clone())
-                                .map_err(AdminSharedError::InvalidMessageFormat)?;
-                        let event = messages::AdminServiceEvent::ProposalAccepted((
-                            circuit_proposal_proto,
-                            circuit_proposal_context.signer_public_key,
-                        ));
-                        self.send_event(&mgmt_type, event);
-
-                        // send MEMBER_READY message to all other members' admin services
-                        if let Some(ref network_sender) = self.network_sender {
-                            let mut member_ready = MemberReady::new();
-                            member_ready.set_circuit_id(circuit.circuit_id.clone());
-                            member_ready.set_member_node_id(self.node_id.clone());
-                            let mut msg = AdminMessage::new();
-                            msg.set_message_type(AdminMessage_Type::MEMBER_READY);
-                            msg.set_member_ready(member_ready);
-
-                            let envelope_bytes =
-                                msg.write_to_bytes().map_err(MarshallingError::from)?;
-                            for member in circuit.members.iter() {
-                                if member.get_node_id() != self.node_id {
-                                    network_sender.send(
-                                        &admin_service_id(member.get_node_id()),
-                                        &envelope_bytes,
-                                    )?;
-                                }
-                            }
+                        match circuit_proposal.get_proposal_type() {
+                            CircuitProposal_ProposalType::CREATE => self.commit_create_circuit(
+                                circuit_proposal,
+                                circuit_proposal_context.signer_public_key,
+                            ),
+                            CircuitProposal_ProposalType::DESTROY => self.commit_destroy_circuit(
+                                circuit_proposal,
+                                circuit_proposal_context.signer_public_key,
+                            ),
+                            CircuitProposal_ProposalType::UPDATE_ROSTER
+                            | CircuitProposal_ProposalType::UPDATE_APPLICATION_METADATA
+                            | CircuitProposal_ProposalType::ADD_NODE
+                            | CircuitProposal_ProposalType::REMOVE_NODE => self
+                                .commit_circuit_update(
+                                    circuit_proposal,
+                                    circuit_proposal_context.signer_public_key,
+                                ),
+                            proposal_type => Err(AdminSharedError::CommitError(format!(
+                                "Unable to commit proposal of type {:?}",
+                                proposal_type
+                            ))),
                         }
-
-                        // add circuit as pending initialization
-                        self.add_uninitialized_circuit(circuit_proposal.clone())
                     }
                     Ok(CircuitProposalStatus::Pending) => {
-                        self.add_proposal(circuit_proposal.clone())?;
+                        // an expired proposal does not block a new proposal for the same circuit,
+                        // which replaces it
+                        if let Some(replaced) = self.add_proposal(circuit_proposal.clone())? {
+                            if is_expired(&replaced) {
+                                self.send_proposal_expired(
+                                    messages::CircuitProposal::from_proto(replaced)
+                                        .map_err(AdminSharedError::InvalidMessageFormat)?,
+                                );
+                            }
+                        }
 
                         match action {
                             CircuitManagementPayload_Action::CIRCUIT_CREATE_REQUEST => {
@@ -373,6 +384,27 @@ impl AdminServiceShared {
                                 Ok(())
                             }
 
+                            CircuitManagementPayload_Action::CIRCUIT_DESTROY_REQUEST
+                            | CircuitManagementPayload_Action::CIRCUIT_UPDATE_ROSTER_REQUEST
+                            | CircuitManagementPayload_Action::CIRCUIT_UPDATE_APPLICATION_METADATA_REQUEST
+                            | CircuitManagementPayload_Action::CIRCUIT_UPDATE_ADD_NODE
+                            | CircuitManagementPayload_Action::CIRCUIT_UPDATE_REMOVE_NODE => {
+                                // notify registered application authorization handlers of the
+                                // committed proposal to change the circuit
+                                let event = messages::AdminServiceEvent::ProposalSubmitted(
+                                    messages::CircuitProposal::from_proto(circuit_proposal.clone())
+                                        .map_err(AdminSharedError::InvalidMessageFormat)?,
+                                );
+                                self.send_event(&mgmt_type, event);
+
+                                info!(
+                                    "committed changes for {:?} proposal for circuit {}",
+                                    circuit_proposal.get_proposal_type(),
+                                    circuit_id
+                                );
+                                Ok(())
+                            }
+
                             CircuitManagementPayload_Action::CIRCUIT_PROPOSAL_VOTE => {
                                 // notify registered application authorization handlers of the
                                 // committed circuit proposal
@@ -417,6 +449,271 @@ impl AdminServiceShared {
         }
     }
 
+    fn commit_withdraw_proposal(
+        &mut self,
+        circuit_proposal: CircuitProposal,
+        signer_public_key: Vec<u8>,
+    ) -> Result<(), AdminSharedError> {
+        let circuit_id = circuit_proposal.get_circuit_id();
+        let mgmt_type = circuit_proposal
+            .get_circuit_proposal()
+            .circuit_management_type
+            .clone();
+
+        self.remove_proposal(circuit_id)?;
+
+        // a withdrawn proposal is reported as rejected by its requester
+        let circuit_proposal_proto =
+            messages::CircuitProposal::from_proto(circuit_proposal.clone())
+                .map_err(AdminSharedError::InvalidMessageFormat)?;
+        let event = messages::AdminServiceEvent::ProposalRejected((
+            circuit_proposal_proto,
+            signer_public_key,
+        ));
+        self.send_event(&mgmt_type, event);
+
+        info!("circuit proposal for {} has been withdrawn", circuit_id);
+        Ok(())
+    }
+
+    /// Reject and remove all open proposals whose expiration time, plus the tolerated clock skew
+    /// between nodes, has passed.
+    ///
+    /// A `ProposalRejected` event whose signer is `PROPOSAL_EXPIRED_SIGNER` is sent for each
+    /// expired proposal.
+    pub fn expire_proposals(&mut self) -> Result<(), AdminSharedError> {
+        let now = current_time_secs();
+        let expired_proposals = self
+            .get_proposals()
+            .into_iter()
+            .filter(|(_, proposal)| {
+                proposal
+                    .expires_at
+                    .map(|expires_at| {
+                        expires_at.saturating_add(PROPOSAL_EXPIRATION_SKEW_TOLERANCE) <= now
+                    })
+                    .unwrap_or(false)
+            })
+            .collect::<Vec<_>>();
+
+        for (circuit_id, proposal) in expired_proposals {
+            self.remove_proposal(&circuit_id)?;
+            self.send_proposal_expired(proposal);
+        }
+
+        Ok(())
+    }
+
+    fn send_proposal_expired(&mut self, proposal: messages::CircuitProposal) {
+        let circuit_id = proposal.circuit_id.clone();
+        let mgmt_type = proposal.circuit.circuit_management_type.clone();
+        let event = messages::AdminServiceEvent::ProposalRejected((
+            proposal,
+            messages::PROPOSAL_EXPIRED_SIGNER.to_vec(),
+        ));
+        self.send_event(&mgmt_type, event);
+
+        info!("circuit proposal for {} has expired", circuit_id);
+    }
+
+    fn commit_create_circuit(
+        &mut self,
+        circuit_proposal: CircuitProposal,
+        signer_public_key: Vec<u8>,
+    ) -> Result<(), AdminSharedError> {
+        let circuit_id = circuit_proposal.get_circuit_id();
+        let mgmt_type = circuit_proposal
+            .get_circuit_proposal()
+            .circuit_management_type
+            .clone();
+
+        // commit new circuit
+        let circuit = circuit_proposal.get_circuit_proposal();
+        let deciding_members =
+            get_deciding_members(circuit, circuit_proposal.get_requester_node_id());
+        self.update_splinter_state(circuit)?;
+        // remove approved proposal
+        self.remove_proposal(circuit_id)?;
+        // send message about circuit acceptance
+
+        let circuit_proposal_proto =
+            messages::CircuitProposal::from_proto(circuit_proposal.clone())
+                .map_err(AdminSharedError::InvalidMessageFormat)?;
+        let event = messages::AdminServiceEvent::ProposalAccepted((
+            circuit_proposal_proto,
+            signer_public_key,
+        ));
+        self.send_event(&mgmt_type, event);
+
+        self.send_member_ready(circuit)?;
+
+        // the members that did not take part in the proposal are sent the circuit by the
+        // requester, and join it once they have accepted it
+        if circuit_proposal.get_requester_node_id() == self.node_id {
+            for member in circuit.get_members() {
+                if !deciding_members.contains(member) {
+                    if let Err(err) = self.send_join_request(circuit, member.get_node_id()) {
+                        warn!(
+                            "Unable to send circuit {} to {}: {}",
+                            circuit_id,
+                            member.get_node_id(),
+                            err
+                        );
+                    }
+                }
+            }
+        }
+
+        // add circuit as pending initialization
+        self.add_uninitialized_circuit(circuit_proposal)
+    }
+
+    /// Send a MEMBER_READY message for the circuit to all other members' admin services
+    fn send_member_ready(&self, circuit: &Circuit) -> Result<(), AdminSharedError> {
+        if let Some(ref network_sender) = self.network_sender {
+            let mut member_ready = MemberReady::new();
+            member_ready.set_circuit_id(circuit.circuit_id.clone());
+            member_ready.set_member_node_id(self.node_id.clone());
+            let mut msg = AdminMessage::new();
+            msg.set_message_type(AdminMessage_Type::MEMBER_READY);
+            msg.set_member_ready(member_ready);
+
+            let envelope_bytes = msg.write_to_bytes().map_err(MarshallingError::from)?;
+            for member in circuit.members.iter() {
+                if member.get_node_id() != self.node_id {
+                    network_sender
+                        .send(&admin_service_id(member.get_node_id()), &envelope_bytes)?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn commit_destroy_circuit(
+        &mut self,
+        circuit_proposal: CircuitProposal,
+        signer_public_key: Vec<u8>,
+    ) -> Result<(), AdminSharedError> {
+        let circuit_id = circuit_proposal.get_circuit_id();
+        let mgmt_type = circuit_proposal
+            .get_circuit_proposal()
+            .circuit_management_type
+            .clone();
+
+        // stop any services this node is running on the circuit, then remove the circuit and its
+        // services from splinter state
+        self.stop_circuit_services(circuit_id)?;
+        self.remove_circuit_from_state(circuit_id)?;
+        self.uninitialized_circuits.remove(circuit_id);
+        // remove approved proposal
+        self.remove_proposal(circuit_id)?;
+
+        let circuit_proposal_proto =
+            messages::CircuitProposal::from_proto(circuit_proposal.clone())
+                .map_err(AdminSharedError::InvalidMessageFormat)?;
+        let event = messages::AdminServiceEvent::ProposalAccepted((
+            circuit_proposal_proto.clone(),
+            signer_public_key,
+        ));
+        self.send_event(&mgmt_type, event);
+
+        // notify registered application authorization handlers that the circuit is gone
+        let event = messages::AdminServiceEvent::CircuitDestroyed(circuit_proposal_proto);
+        self.send_event(&mgmt_type, event);
+
+        info!("circuit {} has been destroyed", circuit_id);
+        Ok(())
+    }
+
+    fn commit_circuit_update(
+        &mut self,
+        circuit_proposal: CircuitProposal,
+        signer_public_key: Vec<u8>,
+    ) -> Result<(), AdminSharedError> {
+        let circuit_id = circuit_proposal.get_circuit_id();
+        let circuit = circuit_proposal.get_circuit_proposal();
+        let mgmt_type = circuit.circuit_management_type.clone();
+
+        let current_members = self
+            .splinter_state
+            .circuit(circuit_id)?
+            .map(|current_circuit| current_circuit.members().to_vec())
+            .unwrap_or_default();
+        let new_members = circuit
+            .get_members()
+            .iter()
+            .filter(|member| !current_members.contains(&member.node_id))
+            .map(|member| member.get_node_id().to_string())
+            .collect::<Vec<_>>();
+
+        let removed = !circuit
+            .get_members()
+            .iter()
+            .any(|member| member.get_node_id() == self.node_id);
+        if !removed {
+            self.update_circuit(circuit)?;
+        } else {
+            // this node has been removed from the circuit
+            self.stop_circuit_services(circuit_id)?;
+            self.remove_circuit_from_state(circuit_id)?;
+        }
+        // remove approved proposal
+        self.remove_proposal(circuit_id)?;
+
+        let circuit_proposal_proto =
+            messages::CircuitProposal::from_proto(circuit_proposal.clone())
+                .map_err(AdminSharedError::InvalidMessageFormat)?;
+        let event = messages::AdminServiceEvent::ProposalAccepted((
+            circuit_proposal_proto.clone(),
+            signer_public_key,
+        ));
+        self.send_event(&mgmt_type, event);
+
+        // notify registered application authorization handlers that the circuit is gone for this
+        // node, since it has been removed
+        if removed {
+            let event =
+                messages::AdminServiceEvent::CircuitDestroyed(circuit_proposal_proto.clone());
+            self.send_event(&mgmt_type, event);
+            info!("this node has been removed from circuit {}", circuit_id);
+        }
+
+        if circuit_proposal.get_proposal_type()
+            == CircuitProposal_ProposalType::UPDATE_APPLICATION_METADATA
+        {
+            let event =
+                messages::AdminServiceEvent::ApplicationMetadataUpdated(circuit_proposal_proto);
+            self.send_event(&mgmt_type, event);
+        }
+
+        // the requester is connected to any nodes being added, so it sends them the updated
+        // circuit definition
+        if circuit_proposal.get_requester_node_id() == self.node_id {
+            for node_id in new_members {
+                self.send_join_request(circuit, &node_id)?;
+            }
+        }
+
+        info!("committed changes to circuit {}", circuit_id);
+        Ok(())
+    }
+
+    fn send_join_request(&self, circuit: &Circuit, node_id: &str) -> Result<(), AdminSharedError> {
+        if let Some(ref network_sender) = self.network_sender {
+            let mut join_request = CircuitJoinRequest::new();
+            join_request.set_circuit(circuit.clone());
+            let mut msg = AdminMessage::new();
+            msg.set_message_type(AdminMessage_Type::CIRCUIT_JOIN_REQUEST);
+            msg.set_circuit_join_request(join_request);
+
+            let envelope_bytes = msg.write_to_bytes().map_err(MarshallingError::from)?;
+            network_sender.send(&admin_service_id(node_id), &envelope_bytes)?;
+        }
+
+        Ok(())
+    }
+
     pub fn rollback(&mut self) -> Result<(), AdminSharedError> {
         match self.pending_changes.take() {
             Some(circuit_proposal_context) => info!(
@@ -445,14 +742,14 @@ impl AdminServiceShared {
             CircuitManagementPayload_Action::CIRCUIT_CREATE_REQUEST => {
                 let mut create_request = circuit_payload.take_circuit_create_request();
                 let proposed_circuit = create_request.take_circuit();
+                let signer_public_key = header.get_requester();
+                let requester_node_id = header.get_requester_node_id();
+
                 let mut verifiers = vec![];
-                for member in proposed_circuit.get_members() {
+                for member in get_deciding_members(&proposed_circuit, requester_node_id) {
                     verifiers.push(admin_service_id(member.get_node_id()));
                 }
 
-                let signer_public_key = header.get_requester();
-                let requester_node_id = header.get_requester_node_id();
-
                 self.validate_create_circuit(
                     &proposed_circuit,
                     signer_public_key,
@@ -467,6 +764,7 @@ impl AdminServiceShared {
                 circuit_proposal.set_circuit_proposal(proposed_circuit);
                 circuit_proposal.set_requester(header.get_requester().to_vec());
                 circuit_proposal.set_requester_node_id(header.get_requester_node_id().to_string());
+                circuit_proposal.set_expires_at(header.get_proposal_expires_at());
 
                 let expected_hash = sha256(&circuit_proposal)?;
                 self.pending_changes = Some(CircuitProposalContext {
@@ -478,6 +776,114 @@ impl AdminServiceShared {
 
                 Ok((expected_hash, circuit_proposal))
             }
+            CircuitManagementPayload_Action::CIRCUIT_DESTROY_REQUEST => {
+                let circuit_id = circuit_payload
+                    .get_circuit_destroy_request()
+                    .get_circuit_id()
+                    .to_string();
+                let signer_public_key = header.get_requester();
+                let requester_node_id = header.get_requester_node_id();
+
+                self.validate_destroy_circuit(&circuit_id, signer_public_key, requester_node_id)?;
+                debug!("proposing destruction of {}", circuit_id);
+
+                // the proposed circuit of a destroy proposal is the circuit as it currently exists
+                let circuit = self.get_circuit_proto(&circuit_id)?;
+                self.propose_circuit_update(
+                    CircuitProposal_ProposalType::DESTROY,
+                    CircuitManagementPayload_Action::CIRCUIT_DESTROY_REQUEST,
+                    circuit,
+                    &header,
+                )
+            }
+            CircuitManagementPayload_Action::CIRCUIT_UPDATE_ROSTER_REQUEST => {
+                let update_roster_request = circuit_payload.get_circuit_update_roster_request();
+                let signer_public_key = header.get_requester();
+                let requester_node_id = header.get_requester_node_id();
+
+                let circuit = self.validate_update_roster(
+                    update_roster_request,
+                    signer_public_key,
+                    requester_node_id,
+                )?;
+                debug!(
+                    "proposing roster update of {}",
+                    update_roster_request.get_circuit_id()
+                );
+
+                self.propose_circuit_update(
+                    CircuitProposal_ProposalType::UPDATE_ROSTER,
+                    CircuitManagementPayload_Action::CIRCUIT_UPDATE_ROSTER_REQUEST,
+                    circuit,
+                    &header,
+                )
+            }
+            CircuitManagementPayload_Action::CIRCUIT_UPDATE_APPLICATION_METADATA_REQUEST => {
+                let update_metadata_request =
+                    circuit_payload.get_circuit_update_application_metadata_request();
+                let signer_public_key = header.get_requester();
+                let requester_node_id = header.get_requester_node_id();
+
+                let circuit = self.validate_update_application_metadata(
+                    update_metadata_request,
+                    signer_public_key,
+                    requester_node_id,
+                )?;
+                debug!(
+                    "proposing application metadata update of {}",
+                    update_metadata_request.get_circuit_id()
+                );
+
+                self.propose_circuit_update(
+                    CircuitProposal_ProposalType::UPDATE_APPLICATION_METADATA,
+                    CircuitManagementPayload_Action::CIRCUIT_UPDATE_APPLICATION_METADATA_REQUEST,
+                    circuit,
+                    &header,
+                )
+            }
+            CircuitManagementPayload_Action::CIRCUIT_UPDATE_ADD_NODE => {
+                let add_node_request = circuit_payload.get_circuit_update_add_node();
+                let signer_public_key = header.get_requester();
+                let requester_node_id = header.get_requester_node_id();
+
+                let circuit =
+                    self.validate_add_node(add_node_request, signer_public_key, requester_node_id)?;
+                debug!(
+                    "proposing addition of node {} to {}",
+                    add_node_request.get_node().get_node_id(),
+                    add_node_request.get_circuit_id()
+                );
+
+                self.propose_circuit_update(
+                    CircuitProposal_ProposalType::ADD_NODE,
+                    CircuitManagementPayload_Action::CIRCUIT_UPDATE_ADD_NODE,
+                    circuit,
+                    &header,
+                )
+            }
+            CircuitManagementPayload_Action::CIRCUIT_UPDATE_REMOVE_NODE => {
+                let remove_node_request = circuit_payload.get_circuit_update_remove_node();
+                let signer_public_key = header.get_requester();
+                let requester_node_id = header.get_requester_node_id();
+
+                let circuit = self.validate_remove_node(
+                    remove_node_request,
+                    signer_public_key,
+                    requester_node_id,
+                )?;
+                debug!(
+                    "proposing removal of node {} from {}",
+                    remove_node_request.get_node_id(),
+                    remove_node_request.get_circuit_id()
+                );
+
+                self.propose_circuit_update(
+                    CircuitProposal_ProposalType::REMOVE_NODE,
+                    CircuitManagementPayload_Action::CIRCUIT_UPDATE_REMOVE_NODE,
+                    circuit,
+                    &header,
+                )
+            }
             CircuitManagementPayload_Action::CIRCUIT_PROPOSAL_VOTE => {
                 let proposal_vote = circuit_payload.get_circuit_proposal_vote();
 
@@ -499,7 +905,7 @@ impl AdminServiceShared {
                     })?;
 
                 let mut verifiers = vec![];
-                for member in circuit_proposal.get_circuit_proposal().get_members() {
+                for member in self.get_consensus_members(&circuit_proposal)? {
                     verifiers.push(admin_service_id(member.get_node_id()));
                 }
                 let signer_public_key = header.get_requester();
@@ -529,6 +935,31 @@ impl AdminServiceShared {
                 self.current_consensus_verifiers = verifiers;
                 Ok((expected_hash, circuit_proposal))
             }
+            CircuitManagementPayload_Action::CIRCUIT_PROPOSAL_WITHDRAW => {
+                let circuit_proposal = self.validate_withdraw_proposal(
+                    circuit_payload.get_circuit_proposal_withdraw(),
+                    header.get_requester(),
+                    header.get_requester_node_id(),
+                )?;
+                debug!(
+                    "proposing withdrawal of proposal for {}",
+                    circuit_proposal.get_circuit_id()
+                );
+
+                let mut verifiers = vec![];
+                for member in self.get_consensus_members(&circuit_proposal)? {
+                    verifiers.push(admin_service_id(member.get_node_id()));
+                }
+
+                let expected_hash = sha256(&circuit_proposal)?;
+                self.pending_changes = Some(CircuitProposalContext {
+                    circuit_proposal: circuit_proposal.clone(),
+                    signer_public_key: header.get_requester().to_vec(),
+                    action: CircuitManagementPayload_Action::CIRCUIT_PROPOSAL_WITHDRAW,
+                });
+                self.current_consensus_verifiers = verifiers;
+                Ok((expected_hash, circuit_proposal))
+            }
             CircuitManagementPayload_Action::ACTION_UNSET => Err(
                 AdminSharedError::ValidationFailed("Action must be set".to_string()),
             ),
@@ -539,14 +970,58 @@ impl AdminServiceShared {
         }
     }
 
+    /// Create a proposal that changes an existing circuit to the given definition and set it as
+    /// the pending change. The current members of the circuit verify the proposal.
+    fn propose_circuit_update(
+        &mut self,
+        proposal_type: CircuitProposal_ProposalType,
+        action: CircuitManagementPayload_Action,
+        circuit: Circuit,
+        header: &CircuitManagementPayload_Header,
+    ) -> Result<(String, CircuitProposal), AdminSharedError> {
+        let mut circuit_proposal = CircuitProposal::new();
+        circuit_proposal.set_proposal_type(proposal_type);
+        circuit_proposal.set_circuit_id(circuit.get_circuit_id().into());
+        circuit_proposal.set_circuit_hash(sha256(&circuit)?);
+        circuit_proposal.set_circuit_proposal(circuit);
+        circuit_proposal.set_requester(header.get_requester().to_vec());
+        circuit_proposal.set_requester_node_id(header.get_requester_node_id().to_string());
+        circuit_proposal.set_expires_at(header.get_proposal_expires_at());
+
+        let mut verifiers = vec![];
+        for member in self.get_consensus_members(&circuit_proposal)? {
+            verifiers.push(admin_service_id(member.get_node_id()));
+        }
+
+        let expected_hash = sha256(&circuit_proposal)?;
+        self.pending_changes = Some(CircuitProposalContext {
+            circuit_proposal: circuit_proposal.clone(),
+            signer_public_key: header.get_requester().to_vec(),
+            action,
+        });
+        self.current_consensus_verifiers = verifiers;
+
+        Ok((expected_hash, circuit_proposal))
+    }
+
     pub fn has_proposal(&self, circuit_id: &str) -> bool {
         self.open_proposals.has_proposal(circuit_id)
     }
 
+    /// Returns whether the circuit has an open proposal that has not expired. Expired proposals
+    /// are only removed by the expiration checker, so that validation has no side effects.
+    fn has_unexpired_proposal(&self, circuit_id: &str) -> Result<bool, AdminSharedError> {
+        Ok(self
+            .get_proposal(circuit_id)?
+            .map(|proposal| !is_expired(&proposal))
+            .unwrap_or(false))
+    }
+
     /// Propose a new circuit
     ///
-    /// This operation will propose a new circuit to all the member nodes of the circuit.  If there
-    /// is no peer connection, a connection to the peer will also be established.
+    /// This operation will propose a new circuit to the member nodes whose votes can decide the
+    /// proposal under the circuit's voting rule.  If there is no peer connection, a connection to
+    /// the peer will also be established.
     pub fn propose_circuit(
         &mut self,
         payload: CircuitManagementPayload,
@@ -560,12 +1035,25 @@ impl AdminServiceShared {
                 .get_circuit_id()
         );
 
-        // get members as vec to payload can be sent to helper function as well
-        let members = payload
-            .get_circuit_create_request()
-            .get_circuit()
-            .get_members()
-            .to_vec();
+        let header =
+            protobuf::parse_from_bytes::<CircuitManagementPayload_Header>(payload.get_header())?;
+        let circuit = payload.get_circuit_create_request().get_circuit();
+        let members = get_deciding_members(circuit, header.get_requester_node_id());
+
+        // the other members are sent the circuit once it has been created, so connect to them
+        // without waiting for the connections to be authorized
+        for node in circuit.get_members() {
+            if self.node_id() != node.get_node_id()
+                && !members.contains(node)
+                && !self.auth_inquisitor.is_authorized(node.get_node_id())
+            {
+                debug!("Connecting to node {:?}", node);
+                self.peer_connector
+                    .connect_peer(node.get_node_id(), node.get_endpoints())
+                    .map_err(|err| ServiceError::UnableToHandleMessage(Box::new(err)))?;
+            }
+        }
+
         self.check_connected_peers_payload(&members, payload, message_sender)
     }
 
@@ -595,39 +1083,172 @@ impl AdminServiceShared {
                 )))
             })?;
 
-        self.check_connected_peers_payload(
-            proposal.get_circuit_proposal().get_members(),
-            payload,
-            message_sender,
-        )
+        let members = self
+            .get_consensus_members(&proposal)
+            .map_err(|err| ServiceError::UnableToHandleMessage(Box::new(err)))?;
+        self.check_connected_peers_payload(&members, payload, message_sender)
     }
 
-    fn check_connected_peers_payload(
+    /// Propose the destruction of an existing circuit
+    ///
+    /// This operation will propose removing the circuit to all the member nodes of the circuit.
+    /// If there is no peer connection, a connection to the peer will also be established.
+    pub fn propose_destroy(
         &mut self,
-        members: &[SplinterNode],
         payload: CircuitManagementPayload,
         message_sender: String,
     ) -> Result<(), ServiceError> {
-        let mut unauthorized_peers = vec![];
-        for node in members {
-            if self.node_id() != node.get_node_id() {
-                if self.auth_inquisitor.is_authorized(node.get_node_id()) {
-                    continue;
-                }
-
-                debug!("Connecting to node {:?}", node);
-                self.peer_connector
-                    .connect_peer(node.get_node_id(), node.get_endpoints())
-                    .map_err(|err| ServiceError::UnableToHandleMessage(Box::new(err)))?;
+        let circuit_id = payload.get_circuit_destroy_request().get_circuit_id();
+        debug!("received destroy proposal for {}", circuit_id);
+
+        let members = self
+            .get_circuit_proto(circuit_id)
+            .map_err(|err| ServiceError::UnableToHandleMessage(Box::new(err)))?
+            .take_members()
+            .into_vec();
+        self.check_connected_peers_payload(&members, payload, message_sender)
+    }
 
-                unauthorized_peers.push(node.get_node_id().into());
-            }
-        }
+    /// Propose updating the roster of an existing circuit
+    ///
+    /// This operation will propose the change to all the current member nodes of the circuit.
+    pub fn propose_update_roster(
+        &mut self,
+        payload: CircuitManagementPayload,
+        message_sender: String,
+    ) -> Result<(), ServiceError> {
+        let circuit_id = payload.get_circuit_update_roster_request().get_circuit_id();
+        debug!("received roster update proposal for {}", circuit_id);
+
+        let members = self
+            .get_circuit_proto(circuit_id)
+            .map_err(|err| ServiceError::UnableToHandleMessage(Box::new(err)))?
+            .take_members()
+            .into_vec();
+        self.check_connected_peers_payload(&members, payload, message_sender)
+    }
 
-        if unauthorized_peers.is_empty() {
-            self.pending_circuit_payloads.push_back(payload);
-        } else {
-            debug!(
+    /// Propose updating the application metadata of an existing circuit
+    ///
+    /// This operation will propose the change to all the current member nodes of the circuit.
+    pub fn propose_update_application_metadata(
+        &mut self,
+        payload: CircuitManagementPayload,
+        message_sender: String,
+    ) -> Result<(), ServiceError> {
+        let circuit_id = payload
+            .get_circuit_update_application_metadata_request()
+            .get_circuit_id();
+        debug!(
+            "received application metadata update proposal for {}",
+            circuit_id
+        );
+
+        let members = self
+            .get_circuit_proto(circuit_id)
+            .map_err(|err| ServiceError::UnableToHandleMessage(Box::new(err)))?
+            .take_members()
+            .into_vec();
+        self.check_connected_peers_payload(&members, payload, message_sender)
+    }
+
+    /// Propose adding a node to an existing circuit
+    ///
+    /// This operation will propose the change to all the current member nodes of the circuit. A
+    /// connection to the new node is also established, so that it may be sent the updated circuit
+    /// definition once the proposal is accepted.
+    pub fn propose_add_node(
+        &mut self,
+        payload: CircuitManagementPayload,
+        message_sender: String,
+    ) -> Result<(), ServiceError> {
+        let add_node_request = payload.get_circuit_update_add_node();
+        debug!(
+            "received proposal to add node {} to {}",
+            add_node_request.get_node().get_node_id(),
+            add_node_request.get_circuit_id()
+        );
+
+        let mut members = self
+            .get_circuit_proto(add_node_request.get_circuit_id())
+            .map_err(|err| ServiceError::UnableToHandleMessage(Box::new(err)))?
+            .take_members()
+            .into_vec();
+        members.push(add_node_request.get_node().clone());
+        self.check_connected_peers_payload(&members, payload, message_sender)
+    }
+
+    /// Propose removing a node from an existing circuit
+    ///
+    /// This operation will propose the change to all the current member nodes of the circuit,
+    /// including the node being removed.
+    pub fn propose_remove_node(
+        &mut self,
+        payload: CircuitManagementPayload,
+        message_sender: String,
+    ) -> Result<(), ServiceError> {
+        let remove_node_request = payload.get_circuit_update_remove_node();
+        debug!(
+            "received proposal to remove node {} from {}",
+            remove_node_request.get_node_id(),
+            remove_node_request.get_circuit_id()
+        );
+
+        let members = self
+            .get_circuit_proto(remove_node_request.get_circuit_id())
+            .map_err(|err| ServiceError::UnableToHandleMessage(Box::new(err)))?
+            .take_members()
+            .into_vec();
+        self.check_connected_peers_payload(&members, payload, message_sender)
+    }
+
+    /// Propose withdrawing a pending proposal
+    ///
+    /// This operation will propose the withdrawal to all the member nodes that must agree on the
+    /// proposal being withdrawn.
+    pub fn propose_withdraw(
+        &mut self,
+        payload: CircuitManagementPayload,
+        circuit_proposal: &CircuitProposal,
+        message_sender: String,
+    ) -> Result<(), ServiceError> {
+        debug!(
+            "received withdrawal of proposal for {}",
+            circuit_proposal.get_circuit_id()
+        );
+
+        let members = self
+            .get_consensus_members(circuit_proposal)
+            .map_err(|err| ServiceError::UnableToHandleMessage(Box::new(err)))?;
+        self.check_connected_peers_payload(&members, payload, message_sender)
+    }
+
+    fn check_connected_peers_payload(
+        &mut self,
+        members: &[SplinterNode],
+        payload: CircuitManagementPayload,
+        message_sender: String,
+    ) -> Result<(), ServiceError> {
+        let mut unauthorized_peers = vec![];
+        for node in members {
+            if self.node_id() != node.get_node_id() {
+                if self.auth_inquisitor.is_authorized(node.get_node_id()) {
+                    continue;
+                }
+
+                debug!("Connecting to node {:?}", node);
+                self.peer_connector
+                    .connect_peer(node.get_node_id(), node.get_endpoints())
+                    .map_err(|err| ServiceError::UnableToHandleMessage(Box::new(err)))?;
+
+                unauthorized_peers.push(node.get_node_id().into());
+            }
+        }
+
+        if unauthorized_peers.is_empty() {
+            self.pending_circuit_payloads.push_back(payload);
+        } else {
+            debug!(
                 "Members {:?} added; awaiting network authorization before proceeding",
                 &unauthorized_peers
             );
@@ -663,6 +1284,66 @@ impl AdminServiceShared {
 
                 self.propose_circuit(payload, "local".to_string())
             }
+            CircuitManagementPayload_Action::CIRCUIT_DESTROY_REQUEST => {
+                let signer_public_key = header.get_requester();
+                let requester_node_id = header.get_requester_node_id();
+                self.validate_destroy_circuit(
+                    payload.get_circuit_destroy_request().get_circuit_id(),
+                    signer_public_key,
+                    requester_node_id,
+                )
+                .map_err(|err| ServiceError::UnableToHandleMessage(Box::new(err)))?;
+
+                self.propose_destroy(payload, "local".to_string())
+            }
+            CircuitManagementPayload_Action::CIRCUIT_UPDATE_ROSTER_REQUEST => {
+                let signer_public_key = header.get_requester();
+                let requester_node_id = header.get_requester_node_id();
+                self.validate_update_roster(
+                    payload.get_circuit_update_roster_request(),
+                    signer_public_key,
+                    requester_node_id,
+                )
+                .map_err(|err| ServiceError::UnableToHandleMessage(Box::new(err)))?;
+
+                self.propose_update_roster(payload, "local".to_string())
+            }
+            CircuitManagementPayload_Action::CIRCUIT_UPDATE_APPLICATION_METADATA_REQUEST => {
+                let signer_public_key = header.get_requester();
+                let requester_node_id = header.get_requester_node_id();
+                self.validate_update_application_metadata(
+                    payload.get_circuit_update_application_metadata_request(),
+                    signer_public_key,
+                    requester_node_id,
+                )
+                .map_err(|err| ServiceError::UnableToHandleMessage(Box::new(err)))?;
+
+                self.propose_update_application_metadata(payload, "local".to_string())
+            }
+            CircuitManagementPayload_Action::CIRCUIT_UPDATE_ADD_NODE => {
+                let signer_public_key = header.get_requester();
+                let requester_node_id = header.get_requester_node_id();
+                self.validate_add_node(
+                    payload.get_circuit_update_add_node(),
+                    signer_public_key,
+                    requester_node_id,
+                )
+                .map_err(|err| ServiceError::UnableToHandleMessage(Box::new(err)))?;
+
+                self.propose_add_node(payload, "local".to_string())
+            }
+            CircuitManagementPayload_Action::CIRCUIT_UPDATE_REMOVE_NODE => {
+                let signer_public_key = header.get_requester();
+                let requester_node_id = header.get_requester_node_id();
+                self.validate_remove_node(
+                    payload.get_circuit_update_remove_node(),
+                    signer_public_key,
+                    requester_node_id,
+                )
+                .map_err(|err| ServiceError::UnableToHandleMessage(Box::new(err)))?;
+
+                self.propose_remove_node(payload, "local".to_string())
+            }
             CircuitManagementPayload_Action::CIRCUIT_PROPOSAL_VOTE => {
                 let proposal_vote = payload.get_circuit_proposal_vote();
 
@@ -698,6 +1379,36 @@ impl AdminServiceShared {
 
                 self.propose_vote(payload, "local".to_string())
             }
+            CircuitManagementPayload_Action::CIRCUIT_ABANDON => {
+                let signer_public_key = header.get_requester();
+                let requester_node_id = header.get_requester_node_id();
+                let circuit_id = payload.get_circuit_abandon().get_circuit_id();
+                self.validate_abandon_circuit(circuit_id, signer_public_key, requester_node_id)
+                    .map_err(|err| ServiceError::UnableToHandleMessage(Box::new(err)))?;
+
+                self.abandon_circuit(circuit_id)
+                    .map_err(|err| ServiceError::UnableToHandleMessage(Box::new(err)))
+            }
+            CircuitManagementPayload_Action::CIRCUIT_JOIN_REQUEST => {
+                let signer_public_key = header.get_requester();
+                let requester_node_id = header.get_requester_node_id();
+                let circuit = payload.get_circuit_join_request().get_circuit();
+                self.validate_join_circuit(circuit, signer_public_key, requester_node_id)
+                    .map_err(|err| ServiceError::UnableToHandleMessage(Box::new(err)))?;
+
+                self.join_circuit(circuit.get_circuit_id())
+            }
+            CircuitManagementPayload_Action::CIRCUIT_PROPOSAL_WITHDRAW => {
+                let circuit_proposal = self
+                    .validate_withdraw_proposal(
+                        payload.get_circuit_proposal_withdraw(),
+                        header.get_requester(),
+                        header.get_requester_node_id(),
+                    )
+                    .map_err(|err| ServiceError::UnableToHandleMessage(Box::new(err)))?;
+
+                self.propose_withdraw(payload, &circuit_proposal, "local".to_string())
+            }
             CircuitManagementPayload_Action::ACTION_UNSET => {
                 Err(ServiceError::UnableToHandleMessage(Box::new(
                     AdminSharedError::ValidationFailed(String::from("No action specified")),
@@ -767,6 +1478,231 @@ impl AdminServiceShared {
         }
     }
 
+    /// Handle a request to join an existing circuit
+    ///
+    /// This operation is performed by a node that has been added to a circuit. The request must
+    /// have been sent by a member of the circuit. As this node has not taken part in the proposal
+    /// that added it, the request is held until a requester of this node accepts it by submitting
+    /// a request to join the same circuit definition; see `join_circuit`.
+    pub fn handle_join_request(
+        &mut self,
+        join_request: CircuitJoinRequest,
+        message_sender: &str,
+    ) -> Result<(), ServiceError> {
+        let circuit = join_request.get_circuit();
+        let circuit_id = circuit.get_circuit_id();
+        debug!("received request to join circuit {}", circuit_id);
+
+        let requester_node_id = circuit
+            .get_members()
+            .iter()
+            .map(|member| member.get_node_id())
+            .find(|node_id| admin_service_id(node_id) == message_sender)
+            .ok_or_else(|| {
+                ServiceError::UnableToHandleMessage(Box::new(AdminSharedError::ValidationFailed(
+                    format!(
+                        "Received request to join circuit {} from {}, which is not a member",
+                        circuit_id, message_sender
+                    ),
+                )))
+            })?;
+
+        if self
+            .splinter_state
+            .has_circuit(circuit_id)
+            .map_err(|err| ServiceError::UnableToHandleMessage(Box::new(err)))?
+        {
+            debug!("Ignoring request to join existing circuit {}", circuit_id);
+            return Ok(());
+        }
+
+        self.validate_circuit(circuit)
+            .map_err(|err| ServiceError::UnableToHandleMessage(Box::new(err)))?;
+
+        if !circuit
+            .get_members()
+            .iter()
+            .any(|member| member.get_node_id() == self.node_id)
+        {
+            return Err(ServiceError::UnableToHandleMessage(Box::new(
+                AdminSharedError::ValidationFailed(format!(
+                    "Received request to join circuit {}, which does not include node {}",
+                    circuit_id, self.node_id
+                )),
+            )));
+        }
+
+        let mut circuit_proposal = CircuitProposal::new();
+        circuit_proposal.set_proposal_type(CircuitProposal_ProposalType::ADD_NODE);
+        circuit_proposal.set_circuit_id(circuit_id.into());
+        circuit_proposal.set_circuit_hash(
+            sha256(circuit).map_err(|err| ServiceError::UnableToHandleMessage(Box::new(err)))?,
+        );
+        circuit_proposal.set_circuit_proposal(circuit.clone());
+        circuit_proposal.set_requester_node_id(requester_node_id.into());
+
+        // notify registered application authorization handlers that the request is awaiting
+        // acceptance
+        let mgmt_type = circuit.get_circuit_management_type().to_string();
+        let event = messages::AdminServiceEvent::ProposalSubmitted(
+            messages::CircuitProposal::from_proto(circuit_proposal.clone())
+                .map_err(|err| ServiceError::UnableToHandleMessage(Box::new(err)))?,
+        );
+        self.send_event(&mgmt_type, event);
+
+        self.pending_join_requests
+            .insert(circuit_id.to_string(), circuit_proposal);
+
+        info!(
+            "received request to join circuit {} from {}; awaiting acceptance",
+            circuit_id, requester_node_id
+        );
+        Ok(())
+    }
+
+    /// Join an existing circuit, accepting the request to join it that was received from one of
+    /// its members
+    ///
+    /// The circuit is committed to splinter state and the services this node runs on the circuit
+    /// are started.
+    fn join_circuit(&mut self, circuit_id: &str) -> Result<(), ServiceError> {
+        let circuit_proposal = self
+            .pending_join_requests
+            .remove(circuit_id)
+            .ok_or_else(|| {
+                ServiceError::UnableToHandleMessage(Box::new(AdminSharedError::ValidationFailed(
+                    format!(
+                        "No request to join circuit {} has been received",
+                        circuit_id
+                    ),
+                )))
+            })?;
+        let circuit = circuit_proposal.get_circuit_proposal();
+
+        for node in circuit.get_members() {
+            if self.node_id() != node.get_node_id()
+                && !self.auth_inquisitor.is_authorized(node.get_node_id())
+            {
+                debug!("Connecting to node {:?}", node);
+                self.peer_connector
+                    .connect_peer(node.get_node_id(), node.get_endpoints())
+                    .map_err(|err| ServiceError::UnableToHandleMessage(Box::new(err)))?;
+            }
+        }
+
+        self.update_splinter_state(circuit)
+            .map_err(|err| ServiceError::UnableToHandleMessage(Box::new(err)))?;
+        self.uninitialized_circuits.remove(circuit_id);
+        self.initialize_services(circuit)
+            .map_err(|err| ServiceError::UnableToHandleMessage(Box::new(err)))?;
+
+        // the members of a new circuit wait for every member to be ready before starting their
+        // services
+        self.send_member_ready(circuit)
+            .map_err(|err| ServiceError::UnableToHandleMessage(Box::new(err)))?;
+
+        // notify registered application authorization handlers that the circuit is ready on
+        // this node
+        let mgmt_type = circuit.get_circuit_management_type().to_string();
+        let event = messages::AdminServiceEvent::CircuitReady(
+            messages::CircuitProposal::from_proto(circuit_proposal.clone())
+                .map_err(|err| ServiceError::UnableToHandleMessage(Box::new(err)))?,
+        );
+        self.send_event(&mgmt_type, event);
+
+        info!("joined circuit {}", circuit_id);
+        Ok(())
+    }
+
+    /// Abandon a circuit on this node, without the agreement of the other members
+    ///
+    /// This operation will stop all services this node runs on the circuit and mark the circuit as
+    /// abandoned by this node. The other members are notified, so that they no longer route
+    /// messages to this node's services.
+    pub fn abandon_circuit(&mut self, circuit_id: &str) -> Result<(), AdminSharedError> {
+        let circuit = self.get_circuit_proto(circuit_id)?;
+
+        self.stop_circuit_services(circuit_id)?;
+        self.splinter_state
+            .add_abandoned_member(circuit_id, &self.node_id)?;
+        if self.has_proposal(circuit_id) {
+            self.remove_proposal(circuit_id)?;
+        }
+
+        if let Some(ref network_sender) = self.network_sender {
+            let mut circuit_abandon = CircuitAbandon::new();
+            circuit_abandon.set_circuit_id(circuit_id.into());
+            let mut msg = AdminMessage::new();
+            msg.set_message_type(AdminMessage_Type::CIRCUIT_ABANDON);
+            msg.set_circuit_abandon(circuit_abandon);
+
+            let envelope_bytes = msg.write_to_bytes().map_err(MarshallingError::from)?;
+            for member in circuit.get_members() {
+                if member.get_node_id() == self.node_id {
+                    continue;
+                }
+
+                // the circuit is abandoned regardless of whether every member is reachable
+                if let Err(err) =
+                    network_sender.send(&admin_service_id(member.get_node_id()), &envelope_bytes)
+                {
+                    warn!(
+                        "Unable to notify {} that circuit {} was abandoned: {}",
+                        member.get_node_id(),
+                        circuit_id,
+                        err
+                    );
+                }
+            }
+        }
+
+        info!("abandoned circuit {}", circuit_id);
+        Ok(())
+    }
+
+    /// Handle a notification that another member has abandoned a circuit
+    ///
+    /// The member is marked as having abandoned the circuit, so that messages are no longer routed
+    /// to its services.
+    pub fn handle_circuit_abandon(
+        &mut self,
+        circuit_abandon: CircuitAbandon,
+        message_sender: &str,
+    ) -> Result<(), ServiceError> {
+        let circuit_id = circuit_abandon.get_circuit_id();
+        let circuit = match self
+            .splinter_state
+            .circuit(circuit_id)
+            .map_err(|err| ServiceError::UnableToHandleMessage(Box::new(err)))?
+        {
+            Some(circuit) => circuit,
+            None => {
+                debug!("Ignoring abandonment of unknown circuit {}", circuit_id);
+                return Ok(());
+            }
+        };
+
+        let node_id = circuit
+            .members()
+            .into_iter()
+            .find(|node_id| admin_service_id(node_id) == message_sender)
+            .ok_or_else(|| {
+                ServiceError::UnableToHandleMessage(Box::new(AdminSharedError::ValidationFailed(
+                    format!(
+                        "Received abandonment of circuit {} from {}, which is not a member",
+                        circuit_id, message_sender
+                    ),
+                )))
+            })?;
+
+        self.splinter_state
+            .add_abandoned_member(circuit_id, node_id)
+            .map_err(|err| ServiceError::UnableToHandleMessage(Box::new(err)))?;
+
+        info!("node {} has abandoned circuit {}", node_id, circuit_id);
+        Ok(())
+    }
+
     pub fn get_events_since(
         &self,
         since_timestamp: &SystemTime,
@@ -899,6 +1835,17 @@ impl AdminServiceShared {
         self.open_proposals.get_proposals()
     }
 
+    /// Replace the open proposals with those loaded from the given proposal store; further
+    /// changes to the open proposals are persisted to the store.
+    #[cfg(feature = "diesel")]
+    pub fn set_proposal_store(
+        &mut self,
+        proposal_store: DieselProposalStore,
+    ) -> Result<(), super::error::OpenProposalError> {
+        self.open_proposals = OpenProposals::from_proposal_store(proposal_store)?;
+        Ok(())
+    }
+
     pub fn remove_proposal(
         &mut self,
         circuit_id: &str,
@@ -953,6 +1900,14 @@ impl AdminServiceShared {
         circuit_id: &str,
         member_node_id: String,
     ) -> Result<(), AdminSharedError> {
+        // A member that joins a circuit after its services have been initialized has nothing to
+        // wait for
+        if !self.uninitialized_circuits.contains_key(circuit_id)
+            && self.splinter_state.has_circuit(circuit_id)?
+        {
+            return Ok(());
+        }
+
         // If uninitialized circuit does not already exist, create it
         if self.uninitialized_circuits.get(circuit_id).is_none() {
             self.uninitialized_circuits.insert(
@@ -1026,20 +1981,384 @@ impl AdminServiceShared {
         signer_public_key: &[u8],
         requester_node_id: &str,
     ) -> Result<(), AdminSharedError> {
-        if requester_node_id.is_empty() {
-            return Err(AdminSharedError::ValidationFailed(
-                "requester_node_id is empty".to_string(),
-            ));
+        self.validate_requester(signer_public_key, requester_node_id)?;
+
+        if self.has_unexpired_proposal(circuit.get_circuit_id())? {
+            return Err(AdminSharedError::ValidationFailed(format!(
+                "Ignoring duplicate create proposal of circuit {}",
+                circuit.get_circuit_id()
+            )));
         }
 
-        self.validate_key(signer_public_key)?;
+        if self.splinter_state.has_circuit(circuit.get_circuit_id())? {
+            return Err(AdminSharedError::ValidationFailed(format!(
+                "Circuit with circuit id {} already exists",
+                circuit.get_circuit_id()
+            )));
+        }
 
-        let key_info = self
-            .key_registry
-            .get_key(signer_public_key)
-            .map_err(|err| AdminSharedError::ValidationFailed(err.to_string()))?
-            .ok_or_else(|| {
-                AdminSharedError::ValidationFailed(format!(
+        self.validate_circuit(circuit)?;
+        Ok(())
+    }
+
+    fn validate_destroy_circuit(
+        &self,
+        circuit_id: &str,
+        signer_public_key: &[u8],
+        requester_node_id: &str,
+    ) -> Result<(), AdminSharedError> {
+        self.validate_circuit_change(circuit_id, signer_public_key, requester_node_id)?;
+        Ok(())
+    }
+
+    /// Validate a request to update the roster of a circuit, returning the updated circuit
+    /// definition. Removed services are also removed from the peer services of the remaining
+    /// services, and added services are added to the peer services of the existing services of the
+    /// same type. The updated circuit must be valid, in the same way as a new circuit.
+    fn validate_update_roster(
+        &self,
+        update_roster_request: &CircuitUpdateRosterRequest,
+        signer_public_key: &[u8],
+        requester_node_id: &str,
+    ) -> Result<Circuit, AdminSharedError> {
+        let circuit_id = update_roster_request.get_circuit_id();
+        self.validate_circuit_change(circuit_id, signer_public_key, requester_node_id)?;
+
+        let add_services = update_roster_request.get_add_services();
+        let remove_services = update_roster_request.get_remove_services();
+        if add_services.is_empty() && remove_services.is_empty() {
+            return Err(AdminSharedError::ValidationFailed(format!(
+                "Roster update of circuit {} does not add or remove any services",
+                circuit_id
+            )));
+        }
+
+        let mut circuit = self.get_circuit_proto(circuit_id)?;
+        let removed_services = remove_services
+            .iter()
+            .map(|service| service.get_service_id().to_string())
+            .collect::<Vec<_>>();
+        for service_id in removed_services.iter() {
+            if !circuit
+                .get_roster()
+                .iter()
+                .any(|service| service.get_service_id() == service_id)
+            {
+                return Err(AdminSharedError::ValidationFailed(format!(
+                    "Service {} is not on the roster of circuit {}",
+                    service_id, circuit_id
+                )));
+            }
+        }
+
+        for service in add_services {
+            if !removed_services.contains(&service.service_id)
+                && circuit
+                    .get_roster()
+                    .iter()
+                    .any(|existing| existing.get_service_id() == service.get_service_id())
+            {
+                return Err(AdminSharedError::ValidationFailed(format!(
+                    "Service {} is already on the roster of circuit {}",
+                    service.get_service_id(),
+                    circuit_id
+                )));
+            }
+        }
+
+        let mut roster = circuit
+            .take_roster()
+            .into_iter()
+            .filter(|service| !removed_services.contains(&service.service_id))
+            .collect::<Vec<_>>();
+        for service in roster.iter_mut() {
+            remove_peer_services(service, &removed_services)?;
+
+            let added_services = add_services
+                .iter()
+                .filter(|added| added.get_service_type() == service.get_service_type())
+                .map(|added| added.get_service_id().to_string())
+                .collect::<Vec<_>>();
+            add_peer_services(service, &added_services)?;
+        }
+        roster.extend(add_services.iter().cloned());
+        circuit.set_roster(RepeatedField::from_vec(roster));
+
+        self.validate_circuit(&circuit)?;
+
+        Ok(circuit)
+    }
+
+    /// Validate a request to abandon a circuit. A circuit may only be abandoned by a requester of
+    /// this node.
+    fn validate_abandon_circuit(
+        &self,
+        circuit_id: &str,
+        signer_public_key: &[u8],
+        requester_node_id: &str,
+    ) -> Result<(), AdminSharedError> {
+        self.validate_requester(signer_public_key, requester_node_id)?;
+
+        if requester_node_id != self.node_id {
+            return Err(AdminSharedError::ValidationFailed(format!(
+                "Requester node {} cannot abandon circuit {} for node {}",
+                requester_node_id, circuit_id, self.node_id
+            )));
+        }
+
+        let circuit = self.splinter_state.circuit(circuit_id)?.ok_or_else(|| {
+            AdminSharedError::ValidationFailed(format!(
+                "Circuit with circuit id {} does not exist",
+                circuit_id
+            ))
+        })?;
+
+        if !circuit.members().contains(&self.node_id) {
+            return Err(AdminSharedError::ValidationFailed(format!(
+                "Node {} is not a member of circuit {}",
+                self.node_id, circuit_id
+            )));
+        }
+
+        if circuit.is_abandoned_by(&self.node_id) {
+            return Err(AdminSharedError::ValidationFailed(format!(
+                "Circuit {} has already been abandoned",
+                circuit_id
+            )));
+        }
+
+        Ok(())
+    }
+
+    /// Validate a request to join a circuit. A circuit may only be joined by a requester of this
+    /// node, and only if one of its members has requested that this node join the same circuit
+    /// definition.
+    fn validate_join_circuit(
+        &self,
+        circuit: &Circuit,
+        signer_public_key: &[u8],
+        requester_node_id: &str,
+    ) -> Result<(), AdminSharedError> {
+        let circuit_id = circuit.get_circuit_id();
+        self.validate_requester(signer_public_key, requester_node_id)?;
+
+        if requester_node_id != self.node_id {
+            return Err(AdminSharedError::ValidationFailed(format!(
+                "Requester node {} cannot join circuit {} for node {}",
+                requester_node_id, circuit_id, self.node_id
+            )));
+        }
+
+        let join_request = self.pending_join_requests.get(circuit_id).ok_or_else(|| {
+            AdminSharedError::ValidationFailed(format!(
+                "No request to join circuit {} has been received",
+                circuit_id
+            ))
+        })?;
+
+        if join_request.get_circuit_hash() != sha256(circuit)? {
+            return Err(AdminSharedError::ValidationFailed(format!(
+                "Circuit does not match the request to join circuit {}",
+                circuit_id
+            )));
+        }
+
+        Ok(())
+    }
+
+    /// Validate a request to update the application metadata of a circuit, returning the updated
+    /// circuit definition.
+    fn validate_update_application_metadata(
+        &self,
+        update_metadata_request: &CircuitUpdateApplicationMetadataRequest,
+        signer_public_key: &[u8],
+        requester_node_id: &str,
+    ) -> Result<Circuit, AdminSharedError> {
+        let circuit_id = update_metadata_request.get_circuit_id();
+        self.validate_circuit_change(circuit_id, signer_public_key, requester_node_id)?;
+
+        let mut circuit = self.get_circuit_proto(circuit_id)?;
+        let application_metadata = update_metadata_request.get_application_metedata();
+        if circuit.get_application_metadata() == application_metadata {
+            return Err(AdminSharedError::ValidationFailed(format!(
+                "Application metadata of circuit {} is unchanged",
+                circuit_id
+            )));
+        }
+
+        circuit.set_application_metadata(application_metadata.to_vec());
+
+        Ok(circuit)
+    }
+
+    /// Validate a request to add a node to a circuit, returning the updated circuit definition.
+    fn validate_add_node(
+        &self,
+        add_node_request: &CircuitUpdateAddNodeRequest,
+        signer_public_key: &[u8],
+        requester_node_id: &str,
+    ) -> Result<Circuit, AdminSharedError> {
+        let circuit_id = add_node_request.get_circuit_id();
+        self.validate_circuit_change(circuit_id, signer_public_key, requester_node_id)?;
+
+        let node = add_node_request.get_node();
+        let mut circuit = self.get_circuit_proto(circuit_id)?;
+        if circuit
+            .get_members()
+            .iter()
+            .any(|member| member.get_node_id() == node.get_node_id())
+        {
+            return Err(AdminSharedError::ValidationFailed(format!(
+                "Node {} is already a member of circuit {}",
+                node.get_node_id(),
+                circuit_id
+            )));
+        }
+
+        circuit.mut_members().push(node.clone());
+        self.validate_circuit(&circuit)?;
+
+        Ok(circuit)
+    }
+
+    /// Validate a request to remove a node from a circuit, returning the updated circuit
+    /// definition. Any services that run on the removed node are removed from the roster, and from
+    /// the peer services of the remaining services. The updated circuit must be valid; for
+    /// instance, its voting rule may not require the approval of the removed node, or a threshold
+    /// of votes that the remaining members cannot reach.
+    fn validate_remove_node(
+        &self,
+        remove_node_request: &CircuitUpdateRemoveNodeRequest,
+        signer_public_key: &[u8],
+        requester_node_id: &str,
+    ) -> Result<Circuit, AdminSharedError> {
+        let circuit_id = remove_node_request.get_circuit_id();
+        let node_id = remove_node_request.get_node_id();
+        let current_circuit =
+            self.validate_circuit_change(circuit_id, signer_public_key, requester_node_id)?;
+
+        if !current_circuit.members().contains(node_id) {
+            return Err(AdminSharedError::ValidationFailed(format!(
+                "Node {} is not a member of circuit {}",
+                node_id, circuit_id
+            )));
+        }
+
+        let mut circuit = self.get_circuit_proto(circuit_id)?;
+        let removed_services = circuit
+            .get_roster()
+            .iter()
+            .filter(|service| {
+                service
+                    .get_allowed_nodes()
+                    .iter()
+                    .any(|node| node == node_id)
+            })
+            .map(|service| service.get_service_id().to_string())
+            .collect::<Vec<_>>();
+
+        let members = circuit
+            .take_members()
+            .into_iter()
+            .filter(|member| member.get_node_id() != node_id)
+            .collect::<Vec<_>>();
+        if members.is_empty() {
+            return Err(AdminSharedError::ValidationFailed(format!(
+                "Unable to remove node {}: it is the only member of circuit {}",
+                node_id, circuit_id
+            )));
+        }
+        circuit.set_members(RepeatedField::from_vec(members));
+
+        let mut roster = circuit
+            .take_roster()
+            .into_iter()
+            .filter(|service| !removed_services.contains(&service.service_id))
+            .collect::<Vec<_>>();
+        for service in roster.iter_mut() {
+            remove_peer_services(service, &removed_services)?;
+        }
+        circuit.set_roster(RepeatedField::from_vec(roster));
+
+        // this node may be the node that is removed, so it is not required to be a member
+        self.validate_circuit_definition(&circuit)
+            .map_err(|err| match err {
+                AdminSharedError::ValidationFailed(msg) => {
+                    AdminSharedError::ValidationFailed(format!(
+                        "Unable to remove node {} from {}: {}",
+                        node_id, circuit_id, msg
+                    ))
+                }
+                err => err,
+            })?;
+
+        Ok(circuit)
+    }
+
+    /// Validate that the requester may propose a change to an existing circuit, returning the
+    /// circuit as it currently exists.
+    fn validate_circuit_change(
+        &self,
+        circuit_id: &str,
+        signer_public_key: &[u8],
+        requester_node_id: &str,
+    ) -> Result<StateCircuit, AdminSharedError> {
+        self.validate_requester(signer_public_key, requester_node_id)?;
+
+        if self.has_unexpired_proposal(circuit_id)? {
+            return Err(AdminSharedError::ValidationFailed(format!(
+                "Ignoring proposal for circuit {}: circuit already has a pending proposal",
+                circuit_id
+            )));
+        }
+
+        let circuit = self.splinter_state.circuit(circuit_id)?.ok_or_else(|| {
+            AdminSharedError::ValidationFailed(format!(
+                "Circuit with circuit id {} does not exist",
+                circuit_id
+            ))
+        })?;
+
+        if !circuit.members().contains(requester_node_id) {
+            return Err(AdminSharedError::ValidationFailed(format!(
+                "Requester node {} is not a member of circuit {}",
+                requester_node_id, circuit_id
+            )));
+        }
+
+        for node_id in &[self.node_id.as_str(), requester_node_id] {
+            if circuit.is_abandoned_by(node_id) {
+                return Err(AdminSharedError::ValidationFailed(format!(
+                    "Circuit {} has been abandoned by node {}",
+                    circuit_id, node_id
+                )));
+            }
+        }
+
+        Ok(circuit)
+    }
+
+    /// Validate that the requester's key is registered to the requester node and that it is
+    /// permitted to submit proposals.
+    fn validate_requester(
+        &self,
+        signer_public_key: &[u8],
+        requester_node_id: &str,
+    ) -> Result<(), AdminSharedError> {
+        if requester_node_id.is_empty() {
+            return Err(AdminSharedError::ValidationFailed(
+                "requester_node_id is empty".to_string(),
+            ));
+        }
+
+        self.validate_key(signer_public_key)?;
+
+        let key_info = self
+            .key_registry
+            .get_key(signer_public_key)
+            .map_err(|err| AdminSharedError::ValidationFailed(err.to_string()))?
+            .ok_or_else(|| {
+                AdminSharedError::ValidationFailed(format!(
                     "{} is not registered for a node",
                     to_hex(signer_public_key)
                 ))
@@ -1052,31 +2371,26 @@ impl AdminServiceShared {
             )));
         };
 
-        self.key_permission_manager
+        let permitted = self
+            .key_permission_manager
             .is_permitted(signer_public_key, PROPOSER_ROLE)
-            .map_err(|_| {
+            .map_err(|err| {
                 AdminSharedError::ValidationFailed(format!(
-                    "{} is not permitted to vote for node {}",
+                    "unable to check if {} is permitted to propose for node {}: {}",
                     to_hex(signer_public_key),
-                    key_info.associated_node_id()
+                    key_info.associated_node_id(),
+                    err
                 ))
             })?;
 
-        if self.has_proposal(circuit.get_circuit_id()) {
-            return Err(AdminSharedError::ValidationFailed(format!(
-                "Ignoring duplicate create proposal of circuit {}",
-                circuit.get_circuit_id()
-            )));
-        }
-
-        if self.splinter_state.has_circuit(circuit.get_circuit_id())? {
+        if !permitted {
             return Err(AdminSharedError::ValidationFailed(format!(
-                "Circuit with circuit id {} already exists",
-                circuit.get_circuit_id()
+                "{} is not permitted to propose for node {}",
+                to_hex(signer_public_key),
+                key_info.associated_node_id()
             )));
         }
 
-        self.validate_circuit(circuit)?;
         Ok(())
     }
 
@@ -1092,6 +2406,25 @@ impl AdminServiceShared {
     }
 
     fn validate_circuit(&self, circuit: &Circuit) -> Result<(), AdminSharedError> {
+        self.validate_circuit_definition(circuit)?;
+
+        // check this node is in members
+        if !circuit
+            .get_members()
+            .iter()
+            .any(|member| member.get_node_id() == self.node_id)
+        {
+            return Err(AdminSharedError::ValidationFailed(format!(
+                "Circuit does not contain this node: {}",
+                self.node_id
+            )));
+        }
+
+        Ok(())
+    }
+
+    /// Validate a circuit definition, without requiring that this node is one of its members.
+    fn validate_circuit_definition(&self, circuit: &Circuit) -> Result<(), AdminSharedError> {
         if circuit.get_authorization_type() == Circuit_AuthorizationType::UNSET_AUTHORIZATION_TYPE {
             return Err(AdminSharedError::ValidationFailed(
                 "authorization_type cannot be unset".to_string(),
@@ -1178,13 +2511,8 @@ impl AdminServiceShared {
             ));
         }
 
-        // check this node is in members
-        if !members.contains(&self.node_id) {
-            return Err(AdminSharedError::ValidationFailed(format!(
-                "Circuit does not contain this node: {}",
-                self.node_id
-            )));
-        }
+        validate_voting_rule(circuit.get_voting_rule(), &members)
+            .map_err(AdminSharedError::ValidationFailed)?;
 
         if circuit.get_roster().is_empty() {
             return Err(AdminSharedError::ValidationFailed(
@@ -1305,6 +2633,16 @@ impl AdminServiceShared {
             )));
         }
 
+        if !self
+            .get_voting_members(circuit_proposal)?
+            .contains(&signer_node)
+        {
+            return Err(AdminSharedError::ValidationFailed(format!(
+                "Node {} is not permitted to vote on the proposal for {}",
+                signer_node, proposal_vote.circuit_id
+            )));
+        }
+
         let voted_nodes: Vec<String> = circuit_proposal
             .get_votes()
             .iter()
@@ -1318,16 +2656,26 @@ impl AdminServiceShared {
             )));
         }
 
-        self.key_permission_manager
+        let permitted = self
+            .key_permission_manager
             .is_permitted(signer_public_key, VOTER_ROLE)
-            .map_err(|_| {
+            .map_err(|err| {
                 AdminSharedError::ValidationFailed(format!(
-                    "{} is not permitted to vote for node {}",
+                    "unable to check if {} is permitted to vote for node {}: {}",
                     to_hex(signer_public_key),
-                    signer_node
+                    signer_node,
+                    err
                 ))
             })?;
 
+        if !permitted {
+            return Err(AdminSharedError::ValidationFailed(format!(
+                "{} is not permitted to vote for node {}",
+                to_hex(signer_public_key),
+                signer_node
+            )));
+        }
+
         // validate hash of circuit
         if circuit_proposal.get_circuit_hash() != circuit_hash {
             return Err(AdminSharedError::ValidationFailed(format!(
@@ -1336,9 +2684,53 @@ impl AdminServiceShared {
             )));
         }
 
+        if is_expired(circuit_proposal) {
+            return Err(AdminSharedError::ValidationFailed(format!(
+                "Received vote for an expired proposal: {}",
+                proposal_vote.circuit_id
+            )));
+        }
+
         Ok(())
     }
 
+    /// Validates that the withdrawn proposal exists and that the requester of the withdrawal is
+    /// the requester of the proposal, returning the proposal to withdraw.
+    fn validate_withdraw_proposal(
+        &self,
+        withdraw: &CircuitProposalWithdraw,
+        signer_public_key: &[u8],
+        requester_node_id: &str,
+    ) -> Result<CircuitProposal, AdminSharedError> {
+        self.validate_requester(signer_public_key, requester_node_id)?;
+
+        let circuit_id = withdraw.get_circuit_id();
+        let circuit_proposal = self.get_proposal(circuit_id)?.ok_or_else(|| {
+            AdminSharedError::ValidationFailed(format!(
+                "Received withdrawal of a proposal that does not exist: circuit id {}",
+                circuit_id
+            ))
+        })?;
+
+        if circuit_proposal.get_requester() != signer_public_key
+            || circuit_proposal.get_requester_node_id() != requester_node_id
+        {
+            return Err(AdminSharedError::ValidationFailed(format!(
+                "Only the requester of the proposal for {} may withdraw it",
+                circuit_id
+            )));
+        }
+
+        if circuit_proposal.get_circuit_hash() != withdraw.get_circuit_hash() {
+            return Err(AdminSharedError::ValidationFailed(format!(
+                "Hash of circuit does not match circuit proposal: {}",
+                circuit_id
+            )));
+        }
+
+        Ok(circuit_proposal)
+    }
+
     fn validate_circuit_management_payload(
         &self,
         payload: &CircuitManagementPayload,
@@ -1374,35 +2766,107 @@ impl AdminServiceShared {
             ));
         };
 
+        // Validate the header, the proposal expiration is unset or in the future
+        let expires_at = header.get_proposal_expires_at();
+        if expires_at != 0 && expires_at <= current_time_secs() {
+            return Err(AdminSharedError::ValidationFailed(
+                "CircuitManagementPayload proposal expiration has already passed".to_string(),
+            ));
+        };
+
         Ok(())
     }
 
+    /// Determines the status of a proposal by evaluating its votes against the circuit's voting
+    /// rule. The requester is only counted as accepting the proposal if the rule's policy says
+    /// so; otherwise, the proposal is decided by the votes of the other voting members.
     fn check_approved(
         &self,
         proposal: &CircuitProposal,
     ) -> Result<CircuitProposalStatus, AdminSharedError> {
-        let mut received_votes = HashSet::new();
+        let policy = voting_policy(&self.get_voting_rule(proposal)?);
+
+        let mut voters = self.get_voting_members(proposal)?;
+        if !policy.counts_requester() {
+            voters.remove(proposal.get_requester_node_id());
+        }
+
+        let mut tally = VoteTally::new(voters);
+        tally.accept(proposal.get_requester_node_id());
         for vote in proposal.get_votes() {
-            if vote.get_vote() == CircuitProposalVote_Vote::REJECT {
-                return Ok(CircuitProposalStatus::Rejected);
+            match vote.get_vote() {
+                CircuitProposalVote_Vote::ACCEPT => tally.accept(vote.get_voter_node_id()),
+                CircuitProposalVote_Vote::REJECT => tally.reject(vote.get_voter_node_id()),
+                CircuitProposalVote_Vote::UNSET_VOTE => (),
             }
-            received_votes.insert(vote.get_voter_node_id().to_string());
         }
 
-        let mut required_votes = proposal
-            .get_circuit_proposal()
+        Ok(policy.evaluate(&tally))
+    }
+
+    /// Returns the voting rule that decides a proposal. Changes to an existing circuit are
+    /// decided by the rule of the existing circuit, not the rule being proposed.
+    fn get_voting_rule(&self, proposal: &CircuitProposal) -> Result<VotingRule, AdminSharedError> {
+        if self
+            .splinter_state
+            .circuit(proposal.get_circuit_id())?
+            .is_some()
+        {
+            Ok(self
+                .get_circuit_proto(proposal.get_circuit_id())?
+                .take_voting_rule())
+        } else {
+            Ok(proposal.get_circuit_proposal().get_voting_rule().clone())
+        }
+    }
+
+    /// Returns the nodes that vote on a proposal: the nodes that are members of the circuit both
+    /// before and after the proposed change, and that have not abandoned it. For a new circuit,
+    /// these are all of its proposed members.
+    fn get_voting_members(
+        &self,
+        proposal: &CircuitProposal,
+    ) -> Result<HashSet<String>, AdminSharedError> {
+        let proposed_members = proposal
+            .get_circuit_proposal()
             .get_members()
-            .to_vec()
             .iter()
             .map(|member| member.get_node_id().to_string())
             .collect::<HashSet<String>>();
 
-        required_votes.remove(proposal.get_requester_node_id());
+        match self.splinter_state.circuit(proposal.get_circuit_id())? {
+            Some(circuit) => Ok(circuit
+                .members()
+                .into_iter()
+                .filter(|member| {
+                    proposed_members.contains(*member) && !circuit.is_abandoned_by(member)
+                })
+                .cloned()
+                .collect()),
+            None => Ok(proposed_members),
+        }
+    }
 
-        if required_votes == received_votes {
-            Ok(CircuitProposalStatus::Accepted)
-        } else {
-            Ok(CircuitProposalStatus::Pending)
+    /// Returns the nodes whose admin services must agree on a proposal. Every current member of
+    /// an existing circuit holds the definition being changed, so all of the members that have
+    /// not abandoned it must agree. If the circuit does not exist yet, only the requester and the
+    /// proposed members whose votes can decide the proposal must agree; the other members are
+    /// sent the circuit once it has been created.
+    fn get_consensus_members(
+        &self,
+        proposal: &CircuitProposal,
+    ) -> Result<Vec<SplinterNode>, AdminSharedError> {
+        match self.splinter_state.circuit(proposal.get_circuit_id())? {
+            Some(circuit) => Ok(self
+                .get_circuit_proto(proposal.get_circuit_id())?
+                .take_members()
+                .into_iter()
+                .filter(|member| !circuit.is_abandoned_by(member.get_node_id()))
+                .collect()),
+            None => Ok(get_deciding_members(
+                proposal.get_circuit_proposal(),
+                proposal.get_requester_node_id(),
+            )),
         }
     }
 
@@ -1431,6 +2895,10 @@ impl AdminServiceShared {
                 service_type: service.service_type.clone(),
             };
 
+            if self.running_services.contains(&service_definition) {
+                continue;
+            }
+
             let service_arguments = service
                 .arguments
                 .iter()
@@ -1477,6 +2945,33 @@ impl AdminServiceShared {
         }
     }
 
+    /// Stops all running services on the given circuit
+    fn stop_circuit_services(&mut self, circuit_id: &str) -> Result<(), AdminSharedError> {
+        let (circuit_services, other_services): (HashSet<_>, HashSet<_>) = self
+            .running_services
+            .drain()
+            .partition(|service| service.circuit == circuit_id);
+        self.running_services = other_services;
+
+        let shutdown_errors = circuit_services
+            .iter()
+            .map(|service| {
+                debug!(
+                    "Stopping service {} in circuit {}",
+                    service.service_id, service.circuit
+                );
+                self.orchestrator.shutdown_service(&service)
+            })
+            .filter_map(Result::err)
+            .collect::<Vec<ShutdownServiceError>>();
+
+        if shutdown_errors.is_empty() {
+            Ok(())
+        } else {
+            Err(AdminSharedError::ServiceShutdownFailed(shutdown_errors))
+        }
+    }
+
     /// On restart of a splinter node, all services that this node should run on the existing
     /// circuits should be intialized using the service orchestrator. This may not include all
     /// services if they are not supported locally. It is expected that some services will be
@@ -1486,6 +2981,10 @@ impl AdminServiceShared {
 
         // start all services of the supported types
         for (circuit_name, circuit) in circuits.iter() {
+            if circuit.is_abandoned_by(&self.node_id) {
+                continue;
+            }
+
             // Get all services this node is allowed to run and the orchestrator has a factory for
             let services = circuit
                 .roster()
@@ -1603,6 +3102,33 @@ impl AdminServiceShared {
             }
         };
 
+        let voting_rule = match circuit.get_voting_rule().get_rule_type() {
+            VotingRule_RuleType::UNSET_RULE_TYPE | VotingRule_RuleType::UNANIMOUS => {
+                StateVotingRule::Unanimous
+            }
+            VotingRule_RuleType::MAJORITY => StateVotingRule::Majority,
+            VotingRule_RuleType::THRESHOLD => {
+                StateVotingRule::Threshold(circuit.get_voting_rule().get_threshold())
+            }
+            VotingRule_RuleType::REQUIRED_APPROVERS => StateVotingRule::RequiredApprovers(
+                circuit.get_voting_rule().get_required_approvers().to_vec(),
+            ),
+        };
+
+        // members that abandoned the circuit and are still members remain abandoned
+        let abandoned_members = self
+            .splinter_state
+            .circuit(circuit.get_circuit_id())?
+            .map(|current_circuit| {
+                current_circuit
+                    .abandoned_members()
+                    .iter()
+                    .filter(|member| members.iter().any(|node| node.id() == member.as_str()))
+                    .cloned()
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default();
+
         let new_circuit = StateCircuit::builder()
             .with_id(circuit.get_circuit_id().to_string())
             .with_members(
@@ -1617,6 +3143,9 @@ impl AdminServiceShared {
             .with_durability(durability)
             .with_routes(routes)
             .with_circuit_management_type(circuit.get_circuit_management_type().to_string())
+            .with_application_metadata(circuit.get_application_metadata().to_vec())
+            .with_abandoned_members(abandoned_members)
+            .with_voting_rule(voting_rule)
             .build()
             .map_err(|err| {
                 AdminSharedError::CommitError(format!("Unable build new circuit: {}", err))
@@ -1656,6 +3185,172 @@ impl AdminServiceShared {
         Ok(())
     }
 
+    /// Replace a circuit in splinter state with its updated definition. Services that are no
+    /// longer on the roster are removed from the service directory, and any services this node
+    /// runs on the circuit are restarted if their definition has changed.
+    fn update_circuit(&mut self, circuit: &Circuit) -> Result<(), AdminSharedError> {
+        let circuit_id = circuit.get_circuit_id();
+        let previous_roster = self
+            .splinter_state
+            .circuit(circuit_id)?
+            .map(|previous_circuit| previous_circuit.roster().to_vec())
+            .unwrap_or_default();
+
+        self.update_splinter_state(circuit)?;
+
+        let roster = self
+            .splinter_state
+            .circuit(circuit_id)?
+            .map(|updated_circuit| updated_circuit.roster().to_vec())
+            .unwrap_or_default();
+
+        for service in previous_roster.iter() {
+            if !roster
+                .iter()
+                .any(|updated| updated.service_id() == service.service_id())
+            {
+                let unique_id =
+                    ServiceId::new(circuit_id.to_string(), service.service_id().to_string());
+                self.splinter_state.remove_service(&unique_id)?;
+            }
+        }
+
+        // stop any running services that were removed or changed
+        let changed_services = self
+            .running_services
+            .iter()
+            .filter(|running| {
+                running.circuit == circuit_id && {
+                    let previous = previous_roster
+                        .iter()
+                        .find(|service| service.service_id() == running.service_id);
+                    let updated = roster
+                        .iter()
+                        .find(|service| service.service_id() == running.service_id);
+                    updated.is_none() || previous != updated
+                }
+            })
+            .cloned()
+            .collect::<Vec<_>>();
+
+        for service in changed_services {
+            debug!(
+                "Stopping service {} in circuit {}",
+                service.service_id, service.circuit
+            );
+            self.running_services.remove(&service);
+            self.orchestrator
+                .shutdown_service(&service)
+                .map_err(|err| AdminSharedError::ServiceShutdownFailed(vec![err]))?;
+        }
+
+        // start any services that are not running, including the changed services
+        self.initialize_services(circuit)
+    }
+
+    /// Remove a circuit, and the services on its roster, from splinter state.
+    fn remove_circuit_from_state(&mut self, circuit_id: &str) -> Result<(), AdminSharedError> {
+        if let Some(circuit) = self.splinter_state.circuit(circuit_id)? {
+            for service in circuit.roster().iter() {
+                let unique_id =
+                    ServiceId::new(circuit_id.to_string(), service.service_id().to_string());
+                self.splinter_state.remove_service(&unique_id)?;
+            }
+        }
+
+        self.splinter_state.remove_circuit(circuit_id)?;
+
+        Ok(())
+    }
+
+    /// Build the protobuf representation of a circuit that exists in splinter state, including
+    /// the endpoints of each of its members.
+    fn get_circuit_proto(&self, circuit_id: &str) -> Result<Circuit, AdminSharedError> {
+        let circuit = self.splinter_state.circuit(circuit_id)?.ok_or_else(|| {
+            AdminSharedError::ValidationFailed(format!(
+                "Circuit with circuit id {} does not exist",
+                circuit_id
+            ))
+        })?;
+
+        let mut members = vec![];
+        for member_id in circuit.members() {
+            let node = self.splinter_state.node(member_id)?.ok_or_else(|| {
+                AdminSharedError::ValidationFailed(format!(
+                    "Unable to find member {} of circuit {}",
+                    member_id, circuit_id
+                ))
+            })?;
+
+            let mut member = SplinterNode::new();
+            member.set_node_id(member_id.to_string());
+            member.set_endpoints(RepeatedField::from_vec(node.endpoints().to_vec()));
+            members.push(member);
+        }
+
+        let roster = circuit
+            .roster()
+            .iter()
+            .map(|service| {
+                let mut proto_service = SplinterService::new();
+                proto_service.set_service_id(service.service_id().to_string());
+                proto_service.set_service_type(service.service_type().to_string());
+                proto_service
+                    .set_allowed_nodes(RepeatedField::from_vec(service.allowed_nodes().to_vec()));
+                proto_service.set_arguments(RepeatedField::from_vec(
+                    service
+                        .arguments()
+                        .iter()
+                        .map(|(key, value)| {
+                            let mut argument = SplinterService_Argument::new();
+                            argument.set_key(key.to_string());
+                            argument.set_value(value.to_string());
+                            argument
+                        })
+                        .collect(),
+                ));
+                proto_service
+            })
+            .collect::<Vec<SplinterService>>();
+
+        let mut proto_circuit = Circuit::new();
+        proto_circuit.set_circuit_id(circuit.id().to_string());
+        proto_circuit.set_members(RepeatedField::from_vec(members));
+        proto_circuit.set_roster(RepeatedField::from_vec(roster));
+        proto_circuit.set_authorization_type(match circuit.auth() {
+            AuthorizationType::Trust => Circuit_AuthorizationType::TRUST_AUTHORIZATION,
+        });
+        proto_circuit.set_persistence(match circuit.persistence() {
+            PersistenceType::Any => Circuit_PersistenceType::ANY_PERSISTENCE,
+        });
+        proto_circuit.set_durability(match circuit.durability() {
+            DurabilityType::NoDurability => Circuit_DurabilityType::NO_DURABILITY,
+        });
+        proto_circuit.set_routes(match circuit.routes() {
+            RouteType::Any => Circuit_RouteType::ANY_ROUTE,
+        });
+        proto_circuit.set_circuit_management_type(circuit.circuit_management_type().to_string());
+        proto_circuit.set_application_metadata(circuit.application_metadata().to_vec());
+
+        // a unanimous rule is left unset, so that circuits that predate voting rules hash the same
+        let mut voting_rule = VotingRule::new();
+        match circuit.voting_rule() {
+            StateVotingRule::Unanimous => (),
+            StateVotingRule::Majority => voting_rule.set_rule_type(VotingRule_RuleType::MAJORITY),
+            StateVotingRule::Threshold(threshold) => {
+                voting_rule.set_rule_type(VotingRule_RuleType::THRESHOLD);
+                voting_rule.set_threshold(*threshold);
+            }
+            StateVotingRule::RequiredApprovers(approvers) => {
+                voting_rule.set_rule_type(VotingRule_RuleType::REQUIRED_APPROVERS);
+                voting_rule.set_required_approvers(RepeatedField::from_vec(approvers.clone()));
+            }
+        }
+        proto_circuit.set_voting_rule(voting_rule);
+
+        Ok(proto_circuit)
+    }
+
     pub fn add_services_to_directory(&mut self) -> Result<(), AdminSharedError> {
         let circuits = self.splinter_state.circuits()?;
         for (id, circuit) in circuits {
@@ -1698,6 +3393,122 @@ impl AdminServiceShared {
     }
 }
 
+/// Remove the given services from the peer services argument of a service, if it has one.
+fn remove_peer_services(
+    service: &mut SplinterService,
+    removed_services: &[String],
+) -> Result<(), AdminSharedError> {
+    let service_id = service.get_service_id().to_string();
+    for argument in service.mut_arguments().iter_mut() {
+        if argument.get_key() != PEER_SERVICES_ARG {
+            continue;
+        }
+
+        let peer_services =
+            serde_json::from_str::<Vec<String>>(argument.get_value()).map_err(|err| {
+                AdminSharedError::ValidationFailed(format!(
+                    "Unable to parse peer services of service {}: {}",
+                    service_id, err
+                ))
+            })?;
+
+        if peer_services
+            .iter()
+            .any(|peer_service| removed_services.contains(peer_service))
+        {
+            let peer_services = peer_services
+                .into_iter()
+                .filter(|peer_service| !removed_services.contains(peer_service))
+                .collect::<Vec<_>>();
+            argument.set_value(serde_json::to_string(&peer_services).map_err(|err| {
+                AdminSharedError::ValidationFailed(format!(
+                    "Unable to serialize peer services of service {}: {}",
+                    service_id, err
+                ))
+            })?);
+        }
+    }
+
+    Ok(())
+}
+
+/// Add the given services to the peer services argument of a service, if it has one.
+fn add_peer_services(
+    service: &mut SplinterService,
+    added_services: &[String],
+) -> Result<(), AdminSharedError> {
+    let service_id = service.get_service_id().to_string();
+    for argument in service.mut_arguments().iter_mut() {
+        if argument.get_key() != PEER_SERVICES_ARG {
+            continue;
+        }
+
+        let mut peer_services =
+            serde_json::from_str::<Vec<String>>(argument.get_value()).map_err(|err| {
+                AdminSharedError::ValidationFailed(format!(
+                    "Unable to parse peer services of service {}: {}",
+                    service_id, err
+                ))
+            })?;
+
+        let new_peer_services = added_services
+            .iter()
+            .filter(|added| **added != service_id && !peer_services.contains(*added))
+            .cloned()
+            .collect::<Vec<_>>();
+
+        if !new_peer_services.is_empty() {
+            peer_services.extend(new_peer_services);
+            argument.set_value(serde_json::to_string(&peer_services).map_err(|err| {
+                AdminSharedError::ValidationFailed(format!(
+                    "Unable to serialize peer services of service {}: {}",
+                    service_id, err
+                ))
+            })?);
+        }
+    }
+
+    Ok(())
+}
+
+/// Returns the members of a proposed circuit that must agree on the proposal to create it: the
+/// requester, and the members whose votes can decide the proposal under the circuit's voting rule.
+fn get_deciding_members(circuit: &Circuit, requester_node_id: &str) -> Vec<SplinterNode> {
+    let policy = voting_policy(circuit.get_voting_rule());
+    let voters = circuit
+        .get_members()
+        .iter()
+        .map(|member| member.get_node_id().to_string())
+        .filter(|node_id| policy.counts_requester() || node_id != requester_node_id)
+        .collect::<HashSet<_>>();
+    let deciding_voters = policy.deciding_voters(&voters);
+
+    circuit
+        .get_members()
+        .iter()
+        .filter(|member| {
+            member.get_node_id() == requester_node_id
+                || deciding_voters.contains(member.get_node_id())
+        })
+        .cloned()
+        .collect()
+}
+
+/// Returns the current time in seconds since the UNIX epoch.
+fn current_time_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or(0)
+}
+
+/// Returns true if the proposal has an expiration time that has passed, according to this node's
+/// clock. Votes on an expired proposal are rejected.
+fn is_expired(circuit_proposal: &CircuitProposal) -> bool {
+    let expires_at = circuit_proposal.get_expires_at();
+    expires_at != 0 && expires_at <= current_time_secs()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1706,7 +3517,8 @@ mod tests {
 
     use crate::circuit::directory::CircuitDirectory;
     use crate::keys::{
-        insecure::AllowAllKeyPermissionManager, storage::StorageKeyRegistry, KeyInfo,
+        insecure::AllowAllKeyPermissionManager, roles::RoleBasedKeyPermissionManager,
+        storage::StorageKeyRegistry, KeyInfo,
     };
     use crate::mesh::Mesh;
     use crate::network::{
@@ -1923,6 +3735,52 @@ mod tests {
         }
     }
 
+    #[test]
+    // test that if a circuit is proposed by a signer key that has not been granted the proposer
+    // role the proposal is invalid
+    fn test_validate_circuit_signer_not_permitted() {
+        let state = setup_splinter_state();
+        let peer_connector = setup_peer_connector();
+        let orchestrator = setup_orchestrator();
+        // set up key registry
+        let pub_key = (0u8..33).collect::<Vec<_>>();
+        let mut key_registry = StorageKeyRegistry::new("memory".to_string()).unwrap();
+        let key_info = KeyInfo::builder(pub_key.clone(), "node_a".to_string()).build();
+        key_registry.save_key(key_info).unwrap();
+
+        // only grant the voter role
+        let permission_manager = RoleBasedKeyPermissionManager::new("memory".to_string()).unwrap();
+        permission_manager.grant_role(&pub_key, VOTER_ROLE).unwrap();
+
+        let admin_shared = AdminServiceShared::new(
+            "node_a".into(),
+            orchestrator,
+            #[cfg(feature = "service-arg-validation")]
+            HashMap::new(),
+            peer_connector,
+            Box::new(MockAuthInquisitor),
+            state,
+            Box::new(HashVerifier),
+            Box::new(key_registry),
+            Box::new(permission_manager.clone()),
+            "memory",
+        )
+        .unwrap();
+        let circuit = setup_test_circuit();
+
+        if let Ok(_) = admin_shared.validate_create_circuit(&circuit, &pub_key, "node_a") {
+            panic!("Should have been invalid due to signer not having the proposer role");
+        }
+
+        permission_manager
+            .grant_role(&pub_key, PROPOSER_ROLE)
+            .unwrap();
+
+        if let Err(err) = admin_shared.validate_create_circuit(&circuit, &pub_key, "node_a") {
+            panic!("Should have been valid: {}", err);
+        }
+    }
+
     #[test]
     // test that if a circuit is proposed by a signer key that is not registered the proposal is
     // invalid
@@ -2813,6 +4671,48 @@ mod tests {
         }
     }
 
+    #[test]
+    // test that if the signer of the vote has not been granted the voter role the vote is invalid
+    fn test_validate_proposal_vote_not_permitted() {
+        let state = setup_splinter_state();
+        let peer_connector = setup_peer_connector();
+        let orchestrator = setup_orchestrator();
+
+        // set up key registry
+        let pub_key = (0u8..33).collect::<Vec<_>>();
+        let mut key_registry = StorageKeyRegistry::new("memory".to_string()).unwrap();
+        let key_info = KeyInfo::builder(pub_key.clone(), "node_a".to_string()).build();
+        key_registry.save_key(key_info).unwrap();
+
+        // only grant the proposer role
+        let permission_manager = RoleBasedKeyPermissionManager::new("memory".to_string()).unwrap();
+        permission_manager
+            .grant_role(&pub_key, PROPOSER_ROLE)
+            .unwrap();
+
+        let admin_shared = AdminServiceShared::new(
+            "node_a".into(),
+            orchestrator,
+            #[cfg(feature = "service-arg-validation")]
+            HashMap::new(),
+            peer_connector,
+            Box::new(MockAuthInquisitor),
+            state,
+            Box::new(HashVerifier),
+            Box::new(key_registry),
+            Box::new(permission_manager),
+            "memory",
+        )
+        .unwrap();
+        let circuit = setup_test_circuit();
+        let vote = setup_test_vote(&circuit);
+        let proposal = setup_test_proposal(&circuit);
+
+        if let Ok(_) = admin_shared.validate_circuit_vote(&vote, &pub_key, &proposal, "node_a") {
+            panic!("Should have been invalid because signer does not have the voter role");
+        }
+    }
+
     #[test]
     // test that if the signer of the vote is not registered to a node the vote is invalid
     fn test_validate_proposal_vote_node_not_registered() {
@@ -3190,51 +5090,1080 @@ mod tests {
         }
     }
 
-    pub fn setup_test_circuit() -> Circuit {
-        let mut service_a = SplinterService::new();
-        service_a.set_service_id("0123".to_string());
-        service_a.set_service_type("type_a".to_string());
-        service_a.set_allowed_nodes(RepeatedField::from_vec(vec!["node_a".to_string()]));
-
-        let mut service_b = SplinterService::new();
-        service_b.set_service_id("ABCD".to_string());
-        service_b.set_service_type("type_a".to_string());
-        service_b.set_allowed_nodes(RepeatedField::from_vec(vec!["node_b".to_string()]));
-
-        let mut node_a = SplinterNode::new();
-        node_a.set_node_id("node_a".to_string());
-        node_a.set_endpoints(vec!["test://endpoint_a:0".to_string()].into());
+    #[test]
+    // test that a destroy proposal for an existing circuit, made by a member node, is valid
+    fn test_validate_destroy_circuit_valid() {
+        let pub_key = (0u8..33).collect::<Vec<_>>();
+        let mut shared = setup_admin_shared_with_key(&pub_key, "node_a");
+        shared
+            .update_splinter_state(&setup_test_circuit())
+            .expect("Unable to commit circuit");
 
-        let mut node_b = SplinterNode::new();
-        node_b.set_node_id("node_b".to_string());
-        node_b.set_endpoints(vec!["test://endpoint_b:0".to_string()].into());
+        if let Err(err) = shared.validate_destroy_circuit("01234-ABCDE", &pub_key, "node_a") {
+            panic!("Should have been valid: {}", err);
+        }
+    }
 
-        let mut circuit = Circuit::new();
-        circuit.set_circuit_id("01234-ABCDE".to_string());
-        circuit.set_members(RepeatedField::from_vec(vec![node_a, node_b]));
-        circuit.set_roster(RepeatedField::from_vec(vec![service_b, service_a]));
-        circuit.set_authorization_type(Circuit_AuthorizationType::TRUST_AUTHORIZATION);
-        circuit.set_persistence(Circuit_PersistenceType::ANY_PERSISTENCE);
-        circuit.set_durability(Circuit_DurabilityType::NO_DURABILITY);
-        circuit.set_routes(Circuit_RouteType::ANY_ROUTE);
-        circuit.set_circuit_management_type("test_circuit".to_string());
-        circuit.set_application_metadata(b"test_data".to_vec());
-        circuit.set_comments("test circuit".to_string());
+    #[test]
+    // test that a destroy proposal for a circuit that does not exist is invalid
+    fn test_validate_destroy_circuit_does_not_exist() {
+        let pub_key = (0u8..33).collect::<Vec<_>>();
+        let shared = setup_admin_shared_with_key(&pub_key, "node_a");
 
-        circuit
+        if let Ok(_) = shared.validate_destroy_circuit("01234-ABCDE", &pub_key, "node_a") {
+            panic!("Should have been invalid because the circuit does not exist");
+        }
     }
 
-    fn setup_test_vote(circuit: &Circuit) -> CircuitProposalVote {
-        let mut circuit_vote = CircuitProposalVote::new();
-        circuit_vote.set_vote(CircuitProposalVote_Vote::ACCEPT);
-        circuit_vote.set_circuit_id(circuit.get_circuit_id().to_string());
-        let circuit_hash = sha256(circuit).unwrap();
-        circuit_vote.set_circuit_hash(circuit_hash);
+    #[test]
+    // test that a destroy proposal from a node that is not a member of the circuit is invalid
+    fn test_validate_destroy_circuit_requester_not_member() {
+        let pub_key = (0u8..33).collect::<Vec<_>>();
+        let mut shared = setup_admin_shared_with_key(&pub_key, "node_c");
+        shared
+            .update_splinter_state(&setup_test_circuit())
+            .expect("Unable to commit circuit");
 
-        circuit_vote
+        if let Ok(_) = shared.validate_destroy_circuit("01234-ABCDE", &pub_key, "node_c") {
+            panic!("Should have been invalid because the requester is not a member");
+        }
     }
 
-    fn setup_test_proposal(proposed_circuit: &Circuit) -> CircuitProposal {
+    #[test]
+    // test that a destroy proposal is rejected while another proposal for the circuit is pending
+    fn test_validate_destroy_circuit_pending_proposal() {
+        let pub_key = (0u8..33).collect::<Vec<_>>();
+        let mut shared = setup_admin_shared_with_key(&pub_key, "node_a");
+        let circuit = setup_test_circuit();
+        shared
+            .update_splinter_state(&circuit)
+            .expect("Unable to commit circuit");
+        shared
+            .add_proposal(setup_test_proposal(&circuit))
+            .expect("Unable to add proposal");
+
+        if let Ok(_) = shared.validate_destroy_circuit("01234-ABCDE", &pub_key, "node_a") {
+            panic!("Should have been invalid because a proposal is already pending");
+        }
+    }
+
+    #[test]
+    // test that the protobuf circuit built from splinter state matches the committed circuit
+    fn test_get_circuit_proto() {
+        let pub_key = (0u8..33).collect::<Vec<_>>();
+        let mut shared = setup_admin_shared_with_key(&pub_key, "node_a");
+        let mut circuit = setup_test_circuit();
+        shared
+            .update_splinter_state(&circuit)
+            .expect("Unable to commit circuit");
+
+        // comments are not stored in splinter state
+        circuit.clear_comments();
+
+        assert_eq!(
+            circuit,
+            shared
+                .get_circuit_proto("01234-ABCDE")
+                .expect("Unable to get circuit")
+        );
+    }
+
+    #[test]
+    // test that when an approved destroy proposal is committed, the circuit and its services are
+    // removed from splinter state and the proposal is removed
+    fn test_commit_destroy_circuit() {
+        let pub_key = (0u8..33).collect::<Vec<_>>();
+        let mut shared = setup_admin_shared_with_key(&pub_key, "node_a");
+        shared
+            .update_splinter_state(&setup_test_circuit())
+            .expect("Unable to commit circuit");
+        let service_id = ServiceId::new("01234-ABCDE".into(), "ABCD".into());
+        assert!(shared.splinter_state.has_service(&service_id).unwrap());
+
+        let circuit = shared
+            .get_circuit_proto("01234-ABCDE")
+            .expect("Unable to get circuit");
+        setup_pending_change(
+            &mut shared,
+            CircuitProposal_ProposalType::DESTROY,
+            circuit,
+            &pub_key,
+            &["node_b"],
+        );
+
+        shared.commit().expect("Unable to commit destroy proposal");
+
+        assert!(!shared.splinter_state.has_circuit("01234-ABCDE").unwrap());
+        assert!(!shared.splinter_state.has_service(&service_id).unwrap());
+        assert!(!shared.has_proposal("01234-ABCDE"));
+    }
+
+    #[test]
+    // test that a request to add a new node to a circuit is valid, and that the resulting circuit
+    // includes the new node
+    fn test_validate_add_node() {
+        let pub_key = (0u8..33).collect::<Vec<_>>();
+        let mut shared = setup_admin_shared_with_key(&pub_key, "node_a");
+        shared
+            .update_splinter_state(&setup_test_circuit())
+            .expect("Unable to commit circuit");
+
+        let mut request = admin::CircuitUpdateAddNodeRequest::new();
+        request.set_circuit_id("01234-ABCDE".into());
+        request.set_node(splinter_node("node_c", &["test://endpoint_c:0".into()]));
+
+        let circuit = shared
+            .validate_add_node(&request, &pub_key, "node_a")
+            .expect("Should have been valid");
+        let members = circuit
+            .get_members()
+            .iter()
+            .map(|member| member.get_node_id())
+            .collect::<Vec<_>>();
+        assert_eq!(vec!["node_a", "node_b", "node_c"], members);
+    }
+
+    #[test]
+    // test that a request to add a node that is already a member of the circuit is invalid
+    fn test_validate_add_node_existing_member() {
+        let pub_key = (0u8..33).collect::<Vec<_>>();
+        let mut shared = setup_admin_shared_with_key(&pub_key, "node_a");
+        shared
+            .update_splinter_state(&setup_test_circuit())
+            .expect("Unable to commit circuit");
+
+        let mut request = admin::CircuitUpdateAddNodeRequest::new();
+        request.set_circuit_id("01234-ABCDE".into());
+        request.set_node(splinter_node("node_b", &["test://endpoint_c:0".into()]));
+
+        if let Ok(_) = shared.validate_add_node(&request, &pub_key, "node_a") {
+            panic!("Should have been invalid because node_b is already a member");
+        }
+    }
+
+    #[test]
+    // test that removing a node from a circuit removes its services from the roster and from the
+    // peer services of the remaining services
+    fn test_validate_remove_node() {
+        let pub_key = (0u8..33).collect::<Vec<_>>();
+        let mut shared = setup_admin_shared_with_key(&pub_key, "node_a");
+        shared
+            .update_splinter_state(&setup_test_circuit_with_peer_services())
+            .expect("Unable to commit circuit");
+
+        let mut request = admin::CircuitUpdateRemoveNodeRequest::new();
+        request.set_circuit_id("01234-ABCDE".into());
+        request.set_node_id("node_b".into());
+
+        let circuit = shared
+            .validate_remove_node(&request, &pub_key, "node_a")
+            .expect("Should have been valid");
+
+        assert_eq!(1, circuit.get_members().len());
+        assert_eq!("node_a", circuit.get_members()[0].get_node_id());
+        assert_eq!(1, circuit.get_roster().len());
+        let service = &circuit.get_roster()[0];
+        assert_eq!("0123", service.get_service_id());
+        assert_eq!(PEER_SERVICES_ARG, service.get_arguments()[0].get_key());
+        assert_eq!("[]", service.get_arguments()[0].get_value());
+    }
+
+    #[test]
+    // test that only the nodes that are members before and after a change vote on the proposal
+    fn test_voting_members() {
+        let pub_key = (0u8..33).collect::<Vec<_>>();
+        let mut shared = setup_admin_shared_with_key(&pub_key, "node_a");
+        let circuit = setup_test_circuit();
+
+        // a new circuit is voted on by all proposed members
+        let expected = HashSet::from_iter(vec!["node_a".to_string(), "node_b".to_string()]);
+        assert_eq!(
+            expected,
+            shared
+                .get_voting_members(&setup_test_proposal(&circuit))
+                .unwrap()
+        );
+
+        shared
+            .update_splinter_state(&circuit)
+            .expect("Unable to commit circuit");
+
+        // an added node does not vote on its own addition
+        let mut request = admin::CircuitUpdateAddNodeRequest::new();
+        request.set_circuit_id("01234-ABCDE".into());
+        request.set_node(splinter_node("node_c", &["test://endpoint_c:0".into()]));
+        let add_node_circuit = shared
+            .validate_add_node(&request, &pub_key, "node_a")
+            .expect("Should have been valid");
+        assert_eq!(
+            expected,
+            shared
+                .get_voting_members(&setup_test_proposal(&add_node_circuit))
+                .unwrap()
+        );
+
+        // a removed node does not vote on its own removal
+        let mut request = admin::CircuitUpdateRemoveNodeRequest::new();
+        request.set_circuit_id("01234-ABCDE".into());
+        request.set_node_id("node_b".into());
+        let remove_node_circuit = shared
+            .validate_remove_node(&request, &pub_key, "node_a")
+            .expect("Should have been valid");
+        assert_eq!(
+            HashSet::from_iter(vec!["node_a".to_string()]),
+            shared
+                .get_voting_members(&setup_test_proposal(&remove_node_circuit))
+                .unwrap()
+        );
+    }
+
+    #[test]
+    // test that a node may not be removed if the voting rule of the resulting circuit requires its
+    // approval or a threshold of votes that the remaining members cannot reach, and that this node
+    // may be the node that is removed
+    fn test_validate_remove_node_voting_rule() {
+        let pub_key = (0u8..33).collect::<Vec<_>>();
+        let mut shared = setup_admin_shared_with_key(&pub_key, "node_a");
+
+        let mut request = admin::CircuitUpdateRemoveNodeRequest::new();
+        request.set_circuit_id("01234-ABCDE".into());
+        request.set_node_id("node_b".into());
+
+        let mut circuit = setup_test_circuit();
+        circuit
+            .mut_voting_rule()
+            .set_rule_type(VotingRule_RuleType::REQUIRED_APPROVERS);
+        circuit
+            .mut_voting_rule()
+            .set_required_approvers(RepeatedField::from_vec(vec!["node_b".to_string()]));
+        shared
+            .update_splinter_state(&circuit)
+            .expect("Unable to commit circuit");
+        if let Ok(_) = shared.validate_remove_node(&request, &pub_key, "node_a") {
+            panic!("Should have been invalid because the removed node is a required approver");
+        }
+
+        let mut circuit = setup_test_circuit();
+        circuit
+            .mut_voting_rule()
+            .set_rule_type(VotingRule_RuleType::THRESHOLD);
+        circuit.mut_voting_rule().set_threshold(2);
+        shared
+            .update_splinter_state(&circuit)
+            .expect("Unable to commit circuit");
+        if let Ok(_) = shared.validate_remove_node(&request, &pub_key, "node_a") {
+            panic!("Should have been invalid because the threshold exceeds the remaining members");
+        }
+
+        // node_b may remove this node from a circuit whose voting rule is unanimous
+        let mut shared = setup_admin_shared_with_key(&pub_key, "node_b");
+        shared
+            .update_splinter_state(&setup_test_circuit())
+            .expect("Unable to commit circuit");
+        request.set_node_id("node_a".into());
+        let circuit = shared
+            .validate_remove_node(&request, &pub_key, "node_b")
+            .expect("Should have been valid");
+        assert_eq!(1, circuit.get_members().len());
+        assert_eq!("node_b", circuit.get_members()[0].get_node_id());
+    }
+
+    #[test]
+    // test that when an approved remove node proposal is committed, the circuit in splinter state
+    // is updated and the removed node's services are removed from the service directory
+    fn test_commit_remove_node() {
+        let pub_key = (0u8..33).collect::<Vec<_>>();
+        let mut shared = setup_admin_shared_with_key(&pub_key, "node_a");
+        shared
+            .update_splinter_state(&setup_test_circuit())
+            .expect("Unable to commit circuit");
+        let service_id = ServiceId::new("01234-ABCDE".into(), "ABCD".into());
+        assert!(shared.splinter_state.has_service(&service_id).unwrap());
+
+        let mut request = admin::CircuitUpdateRemoveNodeRequest::new();
+        request.set_circuit_id("01234-ABCDE".into());
+        request.set_node_id("node_b".into());
+        let circuit = shared
+            .validate_remove_node(&request, &pub_key, "node_a")
+            .expect("Should have been valid");
+
+        // node_a is the only remaining member, so no other votes are required
+        setup_pending_change(
+            &mut shared,
+            CircuitProposal_ProposalType::REMOVE_NODE,
+            circuit,
+            &pub_key,
+            &[],
+        );
+
+        shared
+            .commit()
+            .expect("Unable to commit remove node proposal");
+
+        let circuit = shared
+            .splinter_state
+            .circuit("01234-ABCDE")
+            .unwrap()
+            .expect("Circuit should still exist");
+        assert_eq!(vec!["node_a".to_string()], circuit.members().to_vec());
+        assert!(!circuit.roster().contains("ABCD"));
+        assert!(!shared.splinter_state.has_service(&service_id).unwrap());
+    }
+
+    #[test]
+    // test that a roster update that adds a service is valid, and that the new service is added to
+    // the peer services of the existing services of the same type
+    fn test_validate_update_roster_add_service() {
+        let pub_key = (0u8..33).collect::<Vec<_>>();
+        let mut shared = setup_admin_shared_with_key(&pub_key, "node_a");
+        shared
+            .update_splinter_state(&setup_test_circuit_with_peer_services())
+            .expect("Unable to commit circuit");
+
+        let mut service = splinter_service("EFGH", "type_a");
+        service.set_allowed_nodes(RepeatedField::from_vec(vec!["node_b".to_string()]));
+        let mut request = admin::CircuitUpdateRosterRequest::new();
+        request.set_circuit_id("01234-ABCDE".into());
+        request.set_add_services(RepeatedField::from_vec(vec![service]));
+
+        let circuit = shared
+            .validate_update_roster(&request, &pub_key, "node_a")
+            .expect("Should have been valid");
+
+        let roster = circuit.get_roster();
+        assert_eq!(3, roster.len());
+        assert_eq!("ABCD", roster[0].get_service_id());
+        assert_eq!(
+            "[\"0123\",\"EFGH\"]",
+            roster[0].get_arguments()[0].get_value()
+        );
+        assert_eq!("0123", roster[1].get_service_id());
+        assert_eq!(
+            "[\"ABCD\",\"EFGH\"]",
+            roster[1].get_arguments()[0].get_value()
+        );
+        assert_eq!("EFGH", roster[2].get_service_id());
+    }
+
+    #[test]
+    // test that a roster update that adds a service with an invalid service id is invalid
+    fn test_validate_update_roster_invalid_service_id() {
+        let pub_key = (0u8..33).collect::<Vec<_>>();
+        let mut shared = setup_admin_shared_with_key(&pub_key, "node_a");
+        shared
+            .update_splinter_state(&setup_test_circuit())
+            .expect("Unable to commit circuit");
+
+        let mut service = splinter_service("EF-GH", "type_a");
+        service.set_allowed_nodes(RepeatedField::from_vec(vec!["node_b".to_string()]));
+        let mut request = admin::CircuitUpdateRosterRequest::new();
+        request.set_circuit_id("01234-ABCDE".into());
+        request.set_add_services(RepeatedField::from_vec(vec![service]));
+
+        if let Ok(_) = shared.validate_update_roster(&request, &pub_key, "node_a") {
+            panic!("Should have been invalid due to invalid service id");
+        }
+    }
+
+    #[test]
+    // test that a roster update that adds a service allowed on a node that is not a member of the
+    // circuit is invalid
+    fn test_validate_update_roster_bad_allowed_node() {
+        let pub_key = (0u8..33).collect::<Vec<_>>();
+        let mut shared = setup_admin_shared_with_key(&pub_key, "node_a");
+        shared
+            .update_splinter_state(&setup_test_circuit())
+            .expect("Unable to commit circuit");
+
+        let mut service = splinter_service("EFGH", "type_a");
+        service.set_allowed_nodes(RepeatedField::from_vec(vec!["node_c".to_string()]));
+        let mut request = admin::CircuitUpdateRosterRequest::new();
+        request.set_circuit_id("01234-ABCDE".into());
+        request.set_add_services(RepeatedField::from_vec(vec![service]));
+
+        if let Ok(_) = shared.validate_update_roster(&request, &pub_key, "node_a") {
+            panic!("Should have been invalid because node_c is not a member");
+        }
+    }
+
+    #[test]
+    // test that a roster update that adds a service that is already on the roster, or removes a
+    // service that is not on the roster, is invalid
+    fn test_validate_update_roster_bad_service() {
+        let pub_key = (0u8..33).collect::<Vec<_>>();
+        let mut shared = setup_admin_shared_with_key(&pub_key, "node_a");
+        shared
+            .update_splinter_state(&setup_test_circuit())
+            .expect("Unable to commit circuit");
+
+        let mut service = splinter_service("ABCD", "type_a");
+        service.set_allowed_nodes(RepeatedField::from_vec(vec!["node_b".to_string()]));
+        let mut request = admin::CircuitUpdateRosterRequest::new();
+        request.set_circuit_id("01234-ABCDE".into());
+        request.set_add_services(RepeatedField::from_vec(vec![service]));
+
+        if let Ok(_) = shared.validate_update_roster(&request, &pub_key, "node_a") {
+            panic!("Should have been invalid because ABCD is already on the roster");
+        }
+
+        let mut request = admin::CircuitUpdateRosterRequest::new();
+        request.set_circuit_id("01234-ABCDE".into());
+        request.set_remove_services(RepeatedField::from_vec(vec![splinter_service(
+            "EFGH", "type_a",
+        )]));
+
+        if let Ok(_) = shared.validate_update_roster(&request, &pub_key, "node_a") {
+            panic!("Should have been invalid because EFGH is not on the roster");
+        }
+    }
+
+    #[test]
+    // test that when an approved roster update is committed, the service directory is updated
+    // with the added and removed services
+    fn test_commit_update_roster() {
+        let pub_key = (0u8..33).collect::<Vec<_>>();
+        let mut shared = setup_admin_shared_with_key(&pub_key, "node_a");
+        shared
+            .update_splinter_state(&setup_test_circuit())
+            .expect("Unable to commit circuit");
+
+        let mut service = splinter_service("EFGH", "type_a");
+        service.set_allowed_nodes(RepeatedField::from_vec(vec!["node_b".to_string()]));
+        let mut request = admin::CircuitUpdateRosterRequest::new();
+        request.set_circuit_id("01234-ABCDE".into());
+        request.set_add_services(RepeatedField::from_vec(vec![service]));
+        request.set_remove_services(RepeatedField::from_vec(vec![splinter_service(
+            "ABCD", "type_a",
+        )]));
+        let circuit = shared
+            .validate_update_roster(&request, &pub_key, "node_a")
+            .expect("Should have been valid");
+
+        setup_pending_change(
+            &mut shared,
+            CircuitProposal_ProposalType::UPDATE_ROSTER,
+            circuit,
+            &pub_key,
+            &["node_b"],
+        );
+
+        shared
+            .commit()
+            .expect("Unable to commit roster update proposal");
+
+        let circuit = shared
+            .splinter_state
+            .circuit("01234-ABCDE")
+            .unwrap()
+            .expect("Circuit should still exist");
+        assert!(!circuit.roster().contains("ABCD"));
+        assert!(circuit.roster().contains("EFGH"));
+        assert!(!shared
+            .splinter_state
+            .has_service(&ServiceId::new("01234-ABCDE".into(), "ABCD".into()))
+            .unwrap());
+        assert!(shared
+            .splinter_state
+            .has_service(&ServiceId::new("01234-ABCDE".into(), "EFGH".into()))
+            .unwrap());
+    }
+
+    #[test]
+    // test that a request to update the application metadata of a circuit is valid, and that the
+    // resulting circuit has the new application metadata
+    fn test_validate_update_application_metadata() {
+        let pub_key = (0u8..33).collect::<Vec<_>>();
+        let mut shared = setup_admin_shared_with_key(&pub_key, "node_a");
+        shared
+            .update_splinter_state(&setup_test_circuit())
+            .expect("Unable to commit circuit");
+
+        let mut request = admin::CircuitUpdateApplicationMetadataRequest::new();
+        request.set_circuit_id("01234-ABCDE".into());
+        request.set_application_metedata(b"new_test_data".to_vec());
+
+        let circuit = shared
+            .validate_update_application_metadata(&request, &pub_key, "node_a")
+            .expect("Should have been valid");
+        assert_eq!(b"new_test_data", circuit.get_application_metadata());
+
+        // the metadata must change
+        request.set_application_metedata(b"test_data".to_vec());
+        if let Ok(_) = shared.validate_update_application_metadata(&request, &pub_key, "node_a") {
+            panic!("Should have been invalid because the metadata is unchanged");
+        }
+    }
+
+    #[test]
+    // test that when an approved application metadata update is committed, the new metadata is
+    // stored in splinter state
+    fn test_commit_update_application_metadata() {
+        let pub_key = (0u8..33).collect::<Vec<_>>();
+        let mut shared = setup_admin_shared_with_key(&pub_key, "node_a");
+        shared
+            .update_splinter_state(&setup_test_circuit())
+            .expect("Unable to commit circuit");
+
+        let mut request = admin::CircuitUpdateApplicationMetadataRequest::new();
+        request.set_circuit_id("01234-ABCDE".into());
+        request.set_application_metedata(b"new_test_data".to_vec());
+        let circuit = shared
+            .validate_update_application_metadata(&request, &pub_key, "node_a")
+            .expect("Should have been valid");
+
+        setup_pending_change(
+            &mut shared,
+            CircuitProposal_ProposalType::UPDATE_APPLICATION_METADATA,
+            circuit,
+            &pub_key,
+            &["node_b"],
+        );
+
+        shared
+            .commit()
+            .expect("Unable to commit application metadata proposal");
+
+        let circuit = shared
+            .splinter_state
+            .circuit("01234-ABCDE")
+            .unwrap()
+            .expect("Circuit should still exist");
+        assert_eq!(b"new_test_data", circuit.application_metadata());
+    }
+
+    #[test]
+    // test that a circuit can only be abandoned by a requester of this node, and only once
+    fn test_validate_abandon_circuit() {
+        let pub_key = (0u8..33).collect::<Vec<_>>();
+        let mut shared = setup_admin_shared_with_key(&pub_key, "node_a");
+        shared
+            .update_splinter_state(&setup_test_circuit())
+            .expect("Unable to commit circuit");
+
+        if let Err(err) = shared.validate_abandon_circuit("01234-ABCDE", &pub_key, "node_a") {
+            panic!("Should have been valid: {}", err);
+        }
+
+        if let Ok(_) = shared.validate_abandon_circuit("01234-ABCDE", &pub_key, "node_b") {
+            panic!("Should have been invalid because the requester is not this node");
+        }
+
+        shared
+            .abandon_circuit("01234-ABCDE")
+            .expect("Unable to abandon circuit");
+
+        if let Ok(_) = shared.validate_abandon_circuit("01234-ABCDE", &pub_key, "node_a") {
+            panic!("Should have been invalid because the circuit is already abandoned");
+        }
+    }
+
+    #[test]
+    // test that when a member abandons a circuit, it is marked as abandoned in splinter state and
+    // is no longer a voting member of proposals on the circuit
+    fn test_handle_circuit_abandon() {
+        let pub_key = (0u8..33).collect::<Vec<_>>();
+        let mut shared = setup_admin_shared_with_key(&pub_key, "node_a");
+        let circuit = setup_test_circuit();
+        shared
+            .update_splinter_state(&circuit)
+            .expect("Unable to commit circuit");
+
+        let mut circuit_abandon = admin::CircuitAbandon::new();
+        circuit_abandon.set_circuit_id("01234-ABCDE".into());
+
+        if let Ok(_) = shared.handle_circuit_abandon(circuit_abandon.clone(), "admin::node_c") {
+            panic!("Should have been invalid because node_c is not a member");
+        }
+
+        shared
+            .handle_circuit_abandon(circuit_abandon, "admin::node_b")
+            .expect("Unable to handle abandonment");
+
+        let state_circuit = shared
+            .splinter_state
+            .circuit("01234-ABCDE")
+            .unwrap()
+            .expect("Circuit should still exist");
+        assert!(state_circuit.is_abandoned_by("node_b"));
+        assert!(!state_circuit.is_abandoned_by("node_a"));
+
+        assert_eq!(
+            HashSet::from_iter(vec!["node_a".to_string()]),
+            shared
+                .get_voting_members(&setup_test_proposal(&circuit))
+                .unwrap()
+        );
+
+        // the abandoned member remains abandoned when the circuit is updated
+        shared
+            .update_splinter_state(&circuit)
+            .expect("Unable to commit circuit");
+        assert!(shared
+            .splinter_state
+            .circuit("01234-ABCDE")
+            .unwrap()
+            .expect("Circuit should still exist")
+            .is_abandoned_by("node_b"));
+    }
+
+    #[test]
+    // test that a request to join a circuit is only acted on once a requester of this node has
+    // accepted the same circuit definition
+    fn test_handle_join_request() {
+        let pub_key = (0u8..33).collect::<Vec<_>>();
+        let mut shared = setup_admin_shared_with_key(&pub_key, "node_a");
+        let circuit = setup_test_circuit();
+
+        let mut join_request = admin::CircuitJoinRequest::new();
+        join_request.set_circuit(circuit.clone());
+
+        if let Ok(_) = shared.handle_join_request(join_request.clone(), "admin::node_c") {
+            panic!("Should have been invalid because node_c is not a member");
+        }
+
+        if let Ok(_) = shared.validate_join_circuit(&circuit, &pub_key, "node_a") {
+            panic!("Should have been invalid because no request to join was received");
+        }
+
+        shared
+            .handle_join_request(join_request, "admin::node_b")
+            .expect("Unable to handle join request");
+        assert!(!shared.splinter_state.has_circuit("01234-ABCDE").unwrap());
+
+        let mut other_circuit = circuit.clone();
+        other_circuit.set_application_metadata(b"other_metadata".to_vec());
+        if let Ok(_) = shared.validate_join_circuit(&other_circuit, &pub_key, "node_a") {
+            panic!("Should have been invalid because the circuit does not match the request");
+        }
+
+        if let Ok(_) = shared.validate_join_circuit(&circuit, &pub_key, "node_b") {
+            panic!("Should have been invalid because the requester is not this node");
+        }
+
+        if let Err(err) = shared.validate_join_circuit(&circuit, &pub_key, "node_a") {
+            panic!("Should have been valid: {}", err);
+        }
+
+        shared
+            .join_circuit("01234-ABCDE")
+            .expect("Unable to join circuit");
+        assert!(shared.splinter_state.has_circuit("01234-ABCDE").unwrap());
+        assert!(!shared.pending_join_requests.contains_key("01234-ABCDE"));
+    }
+
+    #[test]
+    // test that a circuit with a voting rule that cannot be satisfied by its members is invalid
+    fn test_validate_circuit_invalid_voting_rule() {
+        let pub_key = (0u8..33).collect::<Vec<_>>();
+        let shared = setup_admin_shared_with_key(&pub_key, "node_a");
+
+        let mut circuit = setup_test_circuit();
+        circuit
+            .mut_voting_rule()
+            .set_rule_type(VotingRule_RuleType::THRESHOLD);
+        circuit.mut_voting_rule().set_threshold(2);
+        shared
+            .validate_create_circuit(&circuit, &pub_key, "node_a")
+            .expect("Should have been valid");
+
+        circuit.mut_voting_rule().set_threshold(3);
+        if let Ok(_) = shared.validate_create_circuit(&circuit, &pub_key, "node_a") {
+            panic!("Should have been invalid because the threshold exceeds the member count");
+        }
+
+        let mut circuit = setup_test_circuit();
+        circuit
+            .mut_voting_rule()
+            .set_rule_type(VotingRule_RuleType::REQUIRED_APPROVERS);
+        circuit
+            .mut_voting_rule()
+            .set_required_approvers(RepeatedField::from_vec(vec!["node_c".to_string()]));
+        if let Ok(_) = shared.validate_create_circuit(&circuit, &pub_key, "node_a") {
+            panic!("Should have been invalid because the required approver is not a member");
+        }
+    }
+
+    #[test]
+    // test that the status of a proposal is determined by the voting rule of the proposed
+    // circuit when creating a circuit, and by the rule of the existing circuit when changing one
+    fn test_check_approved_voting_rule() {
+        let pub_key = (0u8..33).collect::<Vec<_>>();
+        let mut shared = setup_admin_shared_with_key(&pub_key, "node_a");
+
+        let mut circuit = setup_test_circuit();
+        circuit
+            .mut_members()
+            .push(splinter_node("node_c", &["test://endpoint_c:0".into()]));
+        circuit
+            .mut_voting_rule()
+            .set_rule_type(VotingRule_RuleType::MAJORITY);
+
+        // the requester is not counted, so a majority of node_a and node_c must accept
+        let mut proposal = setup_test_proposal(&circuit);
+        assert_eq!(
+            CircuitProposalStatus::Pending,
+            shared.check_approved(&proposal).unwrap()
+        );
+
+        proposal.mut_votes().push(setup_accept_vote("node_a"));
+        assert_eq!(
+            CircuitProposalStatus::Pending,
+            shared.check_approved(&proposal).unwrap()
+        );
+
+        proposal.mut_votes().push(setup_accept_vote("node_c"));
+        assert_eq!(
+            CircuitProposalStatus::Accepted,
+            shared.check_approved(&proposal).unwrap()
+        );
+
+        // once the circuit exists, a change proposing a unanimous rule is still decided by the
+        // majority rule of the existing circuit
+        shared
+            .update_splinter_state(&circuit)
+            .expect("Unable to commit circuit");
+        circuit
+            .mut_voting_rule()
+            .set_rule_type(VotingRule_RuleType::UNANIMOUS);
+        let mut change_proposal = setup_test_proposal(&circuit);
+        change_proposal.set_votes(proposal.get_votes().to_vec().into());
+        assert_eq!(
+            CircuitProposalStatus::Accepted,
+            shared.check_approved(&change_proposal).unwrap()
+        );
+    }
+
+    #[test]
+    // test that the requester is not counted toward a majority or a threshold, so that a proposal
+    // to create a two node circuit is not approved until the other node accepts it, and that a
+    // requester that is a required approver accepts its own proposal
+    fn test_check_approved_requester() {
+        let pub_key = (0u8..33).collect::<Vec<_>>();
+        let shared = setup_admin_shared_with_key(&pub_key, "node_a");
+
+        let mut majority = VotingRule::new();
+        majority.set_rule_type(VotingRule_RuleType::MAJORITY);
+        let mut threshold = VotingRule::new();
+        threshold.set_rule_type(VotingRule_RuleType::THRESHOLD);
+        threshold.set_threshold(1);
+
+        for rule in vec![majority, threshold] {
+            let mut circuit = setup_test_circuit();
+            circuit.set_voting_rule(rule);
+
+            let mut proposal = setup_test_proposal(&circuit);
+            assert_eq!(
+                CircuitProposalStatus::Pending,
+                shared.check_approved(&proposal).unwrap()
+            );
+
+            proposal.mut_votes().push(setup_accept_vote("node_a"));
+            assert_eq!(
+                CircuitProposalStatus::Accepted,
+                shared.check_approved(&proposal).unwrap()
+            );
+        }
+
+        let mut circuit = setup_test_circuit();
+        circuit
+            .mut_voting_rule()
+            .set_rule_type(VotingRule_RuleType::REQUIRED_APPROVERS);
+        circuit
+            .mut_voting_rule()
+            .set_required_approvers(RepeatedField::from_vec(vec!["node_b".to_string()]));
+        assert_eq!(
+            CircuitProposalStatus::Accepted,
+            shared
+                .check_approved(&setup_test_proposal(&circuit))
+                .unwrap()
+        );
+    }
+
+    #[test]
+    // test that only the requester and the members whose votes can decide a proposal to create a
+    // circuit must agree on it, and that every member of an existing circuit must agree on a
+    // change to it
+    fn test_get_consensus_members_voting_rule() {
+        let pub_key = (0u8..33).collect::<Vec<_>>();
+        let mut shared = setup_admin_shared_with_key(&pub_key, "node_a");
+
+        let mut circuit = setup_test_circuit();
+        circuit
+            .mut_members()
+            .push(splinter_node("node_c", &["test://endpoint_c:0".into()]));
+        circuit
+            .mut_voting_rule()
+            .set_rule_type(VotingRule_RuleType::REQUIRED_APPROVERS);
+        circuit
+            .mut_voting_rule()
+            .set_required_approvers(RepeatedField::from_vec(vec!["node_a".to_string()]));
+
+        let consensus_members = |shared: &AdminServiceShared, circuit: &Circuit| {
+            shared
+                .get_consensus_members(&setup_test_proposal(circuit))
+                .unwrap()
+                .into_iter()
+                .map(|member| member.get_node_id().to_string())
+                .collect::<HashSet<_>>()
+        };
+
+        assert_eq!(
+            HashSet::from_iter(vec!["node_a".to_string(), "node_b".to_string()]),
+            consensus_members(&shared, &circuit)
+        );
+
+        shared
+            .update_splinter_state(&circuit)
+            .expect("Unable to commit circuit");
+        assert_eq!(
+            HashSet::from_iter(vec![
+                "node_a".to_string(),
+                "node_b".to_string(),
+                "node_c".to_string()
+            ]),
+            consensus_members(&shared, &circuit)
+        );
+    }
+
+    #[test]
+    // test that a payload with a proposal expiration in the past is invalid
+    fn test_validate_circuit_management_header_proposal_expiration() {
+        let pub_key = (0u8..33).collect::<Vec<_>>();
+        let shared = setup_admin_shared_with_key(&pub_key, "node_a");
+
+        let mut request = admin::CircuitCreateRequest::new();
+        request.set_circuit(setup_test_circuit());
+
+        let mut header = admin::CircuitManagementPayload_Header::new();
+        header.set_action(admin::CircuitManagementPayload_Action::CIRCUIT_CREATE_REQUEST);
+        header.set_requester(pub_key);
+        header.set_requester_node_id("node_a".to_string());
+        header.set_proposal_expires_at(1);
+        let mut payload = admin::CircuitManagementPayload::new();
+        payload.set_header(protobuf::Message::write_to_bytes(&header).unwrap());
+        payload.set_signature(HashSigner.sign(&payload.header).unwrap());
+        payload.set_circuit_create_request(request);
+
+        if let Ok(_) = shared.validate_circuit_management_payload(&payload, &header) {
+            panic!("Should have been invalid because the proposal expiration has passed");
+        }
+
+        header.set_proposal_expires_at(current_time_secs() + 3600);
+        payload.set_header(protobuf::Message::write_to_bytes(&header).unwrap());
+        if let Err(_) = shared.validate_circuit_management_payload(&payload, &header) {
+            panic!("Should have been valid");
+        }
+    }
+
+    #[test]
+    // test that expired proposals are removed, and that proposals that have not expired or that
+    // do not expire are kept
+    fn test_expire_proposals() {
+        let pub_key = (0u8..33).collect::<Vec<_>>();
+        let mut shared = setup_admin_shared_with_key(&pub_key, "node_a");
+
+        let mut circuit = setup_test_circuit();
+        let mut expired_proposal = setup_test_proposal(&circuit);
+        expired_proposal.set_expires_at(1);
+        shared.add_proposal(expired_proposal).unwrap();
+
+        circuit.set_circuit_id("56789-ABCDE".into());
+        let mut open_proposal = setup_test_proposal(&circuit);
+        open_proposal.set_expires_at(current_time_secs() + 3600);
+        shared.add_proposal(open_proposal).unwrap();
+
+        circuit.set_circuit_id("abcde-01234".into());
+        shared.add_proposal(setup_test_proposal(&circuit)).unwrap();
+
+        shared
+            .expire_proposals()
+            .expect("Unable to expire proposals");
+
+        assert!(!shared.has_proposal("01234-ABCDE"));
+        assert!(shared.has_proposal("56789-ABCDE"));
+        assert!(shared.has_proposal("abcde-01234"));
+    }
+
+    #[test]
+    // test that a proposal that has just expired is not removed until the tolerated clock skew
+    // has also passed, but that votes on it are already rejected
+    fn test_expire_proposals_skew_tolerance() {
+        let pub_key = (0u8..33).collect::<Vec<_>>();
+        let mut shared = setup_admin_shared_with_key(&pub_key, "node_a");
+
+        let circuit = setup_test_circuit();
+        let mut proposal = setup_test_proposal(&circuit);
+        proposal.set_expires_at(current_time_secs() - 1);
+        shared.add_proposal(proposal.clone()).unwrap();
+
+        shared
+            .expire_proposals()
+            .expect("Unable to expire proposals");
+        assert!(shared.has_proposal("01234-ABCDE"));
+
+        if let Ok(_) =
+            shared.validate_circuit_vote(&setup_test_vote(&circuit), &pub_key, &proposal, "node_a")
+        {
+            panic!("Should have been invalid because the proposal has expired");
+        }
+
+        proposal.set_expires_at(current_time_secs() - PROPOSAL_EXPIRATION_SKEW_TOLERANCE);
+        shared.add_proposal(proposal).unwrap();
+        shared
+            .expire_proposals()
+            .expect("Unable to expire proposals");
+        assert!(!shared.has_proposal("01234-ABCDE"));
+    }
+
+    #[test]
+    // test that an expired proposal does not block a new proposal for the same circuit, and that
+    // validating the new proposal does not remove the expired one
+    fn test_validate_circuit_with_expired_proposal() {
+        let pub_key = (0u8..33).collect::<Vec<_>>();
+        let mut shared = setup_admin_shared_with_key(&pub_key, "node_a");
+
+        let circuit = setup_test_circuit();
+        let mut proposal = setup_test_proposal(&circuit);
+        proposal.set_expires_at(current_time_secs() + 3600);
+        shared.add_proposal(proposal.clone()).unwrap();
+
+        if let Ok(_) = shared.validate_create_circuit(&circuit, &pub_key, "node_a") {
+            panic!("Should have been invalid because the circuit has an open proposal");
+        }
+
+        proposal.set_expires_at(1);
+        shared.add_proposal(proposal).unwrap();
+
+        if let Err(err) = shared.validate_create_circuit(&circuit, &pub_key, "node_a") {
+            panic!("Should have been valid: {}", err);
+        }
+        assert!(shared.has_proposal("01234-ABCDE"));
+    }
+
+    #[test]
+    // test that only the requester of a proposal may withdraw it, and only with the hash of the
+    // proposed circuit
+    fn test_validate_withdraw_proposal() {
+        let pub_key = (0u8..33).collect::<Vec<_>>();
+        let mut shared = setup_admin_shared_with_key(&pub_key, "node_a");
+
+        let circuit = setup_test_circuit();
+        let mut proposal = setup_test_proposal(&circuit);
+
+        let mut withdraw = admin::CircuitProposalWithdraw::new();
+        withdraw.set_circuit_id("01234-ABCDE".into());
+        withdraw.set_circuit_hash(proposal.get_circuit_hash().into());
+
+        if let Ok(_) = shared.validate_withdraw_proposal(&withdraw, &pub_key, "node_a") {
+            panic!("Should have been invalid because the proposal does not exist");
+        }
+
+        // the proposal was requested by node_b
+        shared.add_proposal(proposal.clone()).unwrap();
+        if let Ok(_) = shared.validate_withdraw_proposal(&withdraw, &pub_key, "node_a") {
+            panic!("Should have been invalid because node_a is not the requester");
+        }
+
+        proposal.set_requester(pub_key.clone());
+        proposal.set_requester_node_id("node_a".into());
+        shared.add_proposal(proposal.clone()).unwrap();
+        assert_eq!(
+            proposal,
+            shared
+                .validate_withdraw_proposal(&withdraw, &pub_key, "node_a")
+                .expect("Should have been valid")
+        );
+
+        withdraw.set_circuit_hash("bad_hash".into());
+        if let Ok(_) = shared.validate_withdraw_proposal(&withdraw, &pub_key, "node_a") {
+            panic!("Should have been invalid because the circuit hash does not match");
+        }
+    }
+
+    #[test]
+    // test that when a withdrawal is committed, the proposal is removed
+    fn test_commit_withdraw_proposal() {
+        let pub_key = (0u8..33).collect::<Vec<_>>();
+        let mut shared = setup_admin_shared_with_key(&pub_key, "node_a");
+
+        let mut proposal = setup_test_proposal(&setup_test_circuit());
+        proposal.set_requester(pub_key.clone());
+        proposal.set_requester_node_id("node_a".into());
+        shared.add_proposal(proposal.clone()).unwrap();
+
+        shared.pending_changes = Some(CircuitProposalContext {
+            circuit_proposal: proposal,
+            action: CircuitManagementPayload_Action::CIRCUIT_PROPOSAL_WITHDRAW,
+            signer_public_key: pub_key,
+        });
+
+        shared.commit().expect("Unable to commit withdrawal");
+
+        assert!(!shared.has_proposal("01234-ABCDE"));
+    }
+
+    pub fn setup_test_circuit() -> Circuit {
+        let mut service_a = SplinterService::new();
+        service_a.set_service_id("0123".to_string());
+        service_a.set_service_type("type_a".to_string());
+        service_a.set_allowed_nodes(RepeatedField::from_vec(vec!["node_a".to_string()]));
+
+        let mut service_b = SplinterService::new();
+        service_b.set_service_id("ABCD".to_string());
+        service_b.set_service_type("type_a".to_string());
+        service_b.set_allowed_nodes(RepeatedField::from_vec(vec!["node_b".to_string()]));
+
+        let mut node_a = SplinterNode::new();
+        node_a.set_node_id("node_a".to_string());
+        node_a.set_endpoints(vec!["test://endpoint_a:0".to_string()].into());
+
+        let mut node_b = SplinterNode::new();
+        node_b.set_node_id("node_b".to_string());
+        node_b.set_endpoints(vec!["test://endpoint_b:0".to_string()].into());
+
+        let mut circuit = Circuit::new();
+        circuit.set_circuit_id("01234-ABCDE".to_string());
+        circuit.set_members(RepeatedField::from_vec(vec![node_a, node_b]));
+        circuit.set_roster(RepeatedField::from_vec(vec![service_b, service_a]));
+        circuit.set_authorization_type(Circuit_AuthorizationType::TRUST_AUTHORIZATION);
+        circuit.set_persistence(Circuit_PersistenceType::ANY_PERSISTENCE);
+        circuit.set_durability(Circuit_DurabilityType::NO_DURABILITY);
+        circuit.set_routes(Circuit_RouteType::ANY_ROUTE);
+        circuit.set_circuit_management_type("test_circuit".to_string());
+        circuit.set_application_metadata(b"test_data".to_vec());
+        circuit.set_comments("test circuit".to_string());
+
+        circuit
+    }
+
+    fn setup_test_circuit_with_peer_services() -> Circuit {
+        let mut circuit = setup_test_circuit();
+        for service in circuit.mut_roster().iter_mut() {
+            let peer_service = if service.get_service_id() == "0123" {
+                "ABCD"
+            } else {
+                "0123"
+            };
+            let mut argument = SplinterService_Argument::new();
+            argument.set_key(PEER_SERVICES_ARG.into());
+            argument.set_value(format!("[\"{}\"]", peer_service));
+            service.set_arguments(RepeatedField::from_vec(vec![argument]));
+        }
+
+        circuit
+    }
+
+    fn setup_test_vote(circuit: &Circuit) -> CircuitProposalVote {
+        let mut circuit_vote = CircuitProposalVote::new();
+        circuit_vote.set_vote(CircuitProposalVote_Vote::ACCEPT);
+        circuit_vote.set_circuit_id(circuit.get_circuit_id().to_string());
+        let circuit_hash = sha256(circuit).unwrap();
+        circuit_vote.set_circuit_hash(circuit_hash);
+
+        circuit_vote
+    }
+
+    fn setup_test_proposal(proposed_circuit: &Circuit) -> CircuitProposal {
         let mut circuit_proposal = CircuitProposal::new();
         circuit_proposal.set_proposal_type(CircuitProposal_ProposalType::CREATE);
         circuit_proposal.set_circuit_id(proposed_circuit.get_circuit_id().into());
@@ -3246,6 +6175,75 @@ mod tests {
         circuit_proposal
     }
 
+    fn setup_accept_vote(voter_node_id: &str) -> CircuitProposal_VoteRecord {
+        let mut vote_record = CircuitProposal_VoteRecord::new();
+        vote_record.set_public_key(format!("test_signer_{}", voter_node_id).into_bytes());
+        vote_record.set_vote(CircuitProposalVote_Vote::ACCEPT);
+        vote_record.set_voter_node_id(voter_node_id.into());
+
+        vote_record
+    }
+
+    // Adds a proposal by node_a to change the circuit to the given definition, accepted by each of
+    // the given voters, and sets it as the pending change so that it is applied on commit.
+    fn setup_pending_change(
+        shared: &mut AdminServiceShared,
+        proposal_type: CircuitProposal_ProposalType,
+        circuit: Circuit,
+        requester: &[u8],
+        voters: &[&str],
+    ) {
+        let mut circuit_proposal = CircuitProposal::new();
+        circuit_proposal.set_proposal_type(proposal_type);
+        circuit_proposal.set_circuit_id(circuit.get_circuit_id().into());
+        circuit_proposal.set_circuit_hash(sha256(&circuit).unwrap());
+        circuit_proposal.set_circuit_proposal(circuit);
+        circuit_proposal.set_requester(requester.to_vec());
+        circuit_proposal.set_requester_node_id("node_a".into());
+        circuit_proposal.set_votes(RepeatedField::from_vec(
+            voters
+                .iter()
+                .map(|voter| setup_accept_vote(voter))
+                .collect(),
+        ));
+
+        shared
+            .add_proposal(circuit_proposal.clone())
+            .expect("Unable to add proposal");
+
+        let signer_public_key = circuit_proposal
+            .get_votes()
+            .last()
+            .map(|vote| vote.get_public_key().to_vec())
+            .unwrap_or_else(|| requester.to_vec());
+        shared.pending_changes = Some(CircuitProposalContext {
+            circuit_proposal,
+            action: CircuitManagementPayload_Action::CIRCUIT_PROPOSAL_VOTE,
+            signer_public_key,
+        });
+    }
+
+    fn setup_admin_shared_with_key(pub_key: &[u8], node_id: &str) -> AdminServiceShared {
+        let mut key_registry = StorageKeyRegistry::new("memory".to_string()).unwrap();
+        let key_info = KeyInfo::builder(pub_key.to_vec(), node_id.to_string()).build();
+        key_registry.save_key(key_info).unwrap();
+
+        AdminServiceShared::new(
+            "node_a".into(),
+            setup_orchestrator(),
+            #[cfg(feature = "service-arg-validation")]
+            HashMap::new(),
+            setup_peer_connector(),
+            Box::new(MockAuthInquisitor),
+            setup_splinter_state(),
+            Box::new(HashVerifier),
+            Box::new(key_registry),
+            Box::new(AllowAllKeyPermissionManager),
+            "memory",
+        )
+        .unwrap()
+    }
+
     fn setup_splinter_state() -> SplinterState {
         let mut storage = get_storage("memory", CircuitDirectory::new).unwrap();
         let circuit_directory = storage.write().clone();
@@ -0,0 +1,458 @@
+// Copyright 2018-2020 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Voting policies, which determine whether a circuit proposal has been approved based on the
+//! votes cast by the members that are permitted to vote on it.
+//!
+//! The requester of a proposal cannot vote on it, and is only counted as accepting it if the
+//! policy says so. A policy also determines which voting members can decide a proposal; only
+//! those members, along with the requester, need to agree on a proposal to create a circuit.
+
+use std::collections::HashSet;
+
+use crate::protos::admin::{VotingRule, VotingRule_RuleType};
+
+#[derive(Debug, PartialEq)]
+pub enum CircuitProposalStatus {
+    Accepted,
+    Rejected,
+    Pending,
+}
+
+/// The votes that have been cast on a proposal by the members permitted to vote on it. Votes
+/// from any other node are ignored.
+#[derive(Debug, Default)]
+pub struct VoteTally {
+    voters: HashSet<String>,
+    accepted: HashSet<String>,
+    rejected: HashSet<String>,
+}
+
+impl VoteTally {
+    pub fn new(voters: HashSet<String>) -> Self {
+        VoteTally {
+            voters,
+            ..Default::default()
+        }
+    }
+
+    pub fn accept(&mut self, node_id: &str) {
+        if self.voters.contains(node_id) {
+            self.accepted.insert(node_id.to_string());
+        }
+    }
+
+    pub fn reject(&mut self, node_id: &str) {
+        if self.voters.contains(node_id) {
+            self.rejected.insert(node_id.to_string());
+        }
+    }
+
+    pub fn voters(&self) -> &HashSet<String> {
+        &self.voters
+    }
+
+    pub fn accepted(&self) -> &HashSet<String> {
+        &self.accepted
+    }
+
+    pub fn rejected(&self) -> &HashSet<String> {
+        &self.rejected
+    }
+}
+
+/// A policy that decides the status of a proposal from its votes.
+pub trait VotingPolicy {
+    /// Returns `Accepted` or `Rejected` once the votes in the tally determine the outcome of the
+    /// proposal, or `Pending` if more votes are required.
+    fn evaluate(&self, tally: &VoteTally) -> CircuitProposalStatus;
+
+    /// Returns whether the requester of a proposal is counted as accepting it. If not, the
+    /// requester is not one of the voters in the tally.
+    fn counts_requester(&self) -> bool {
+        false
+    }
+
+    /// Returns the voting members whose votes can decide a proposal.
+    fn deciding_voters(&self, voters: &HashSet<String>) -> HashSet<String> {
+        voters.clone()
+    }
+}
+
+/// Every voting member must accept; a single rejection rejects the proposal.
+pub struct UnanimousPolicy;
+
+impl VotingPolicy for UnanimousPolicy {
+    fn evaluate(&self, tally: &VoteTally) -> CircuitProposalStatus {
+        if !tally.rejected().is_empty() {
+            CircuitProposalStatus::Rejected
+        } else if tally.accepted().len() == tally.voters().len() {
+            CircuitProposalStatus::Accepted
+        } else {
+            CircuitProposalStatus::Pending
+        }
+    }
+}
+
+/// More than half of the voting members must accept.
+pub struct MajorityPolicy;
+
+impl VotingPolicy for MajorityPolicy {
+    fn evaluate(&self, tally: &VoteTally) -> CircuitProposalStatus {
+        let voters = tally.voters().len();
+        if tally.accepted().len() * 2 > voters {
+            CircuitProposalStatus::Accepted
+        } else if tally.rejected().len() * 2 >= voters {
+            CircuitProposalStatus::Rejected
+        } else {
+            CircuitProposalStatus::Pending
+        }
+    }
+}
+
+/// At least `threshold` voting members must accept. If there are fewer voting members than the
+/// threshold, every voting member must accept.
+pub struct ThresholdPolicy {
+    threshold: usize,
+}
+
+impl ThresholdPolicy {
+    pub fn new(threshold: usize) -> Self {
+        ThresholdPolicy { threshold }
+    }
+}
+
+impl VotingPolicy for ThresholdPolicy {
+    fn evaluate(&self, tally: &VoteTally) -> CircuitProposalStatus {
+        let voters = tally.voters().len();
+        let threshold = self.threshold.min(voters);
+        if tally.accepted().len() >= threshold {
+            CircuitProposalStatus::Accepted
+        } else if voters - tally.rejected().len() < threshold {
+            CircuitProposalStatus::Rejected
+        } else {
+            CircuitProposalStatus::Pending
+        }
+    }
+}
+
+/// Every required approver that is a voting member must accept; votes from the other members do
+/// not affect the outcome. If none of the required approvers are voting members, every voting
+/// member must accept. A required approver that requests a proposal accepts it.
+pub struct RequiredApproversPolicy {
+    approvers: HashSet<String>,
+}
+
+impl RequiredApproversPolicy {
+    pub fn new(approvers: HashSet<String>) -> Self {
+        RequiredApproversPolicy { approvers }
+    }
+}
+
+impl VotingPolicy for RequiredApproversPolicy {
+    fn evaluate(&self, tally: &VoteTally) -> CircuitProposalStatus {
+        let approvers = self
+            .approvers
+            .intersection(tally.voters())
+            .collect::<Vec<_>>();
+
+        if approvers.is_empty() {
+            UnanimousPolicy.evaluate(tally)
+        } else if approvers
+            .iter()
+            .any(|approver| tally.rejected().contains(*approver))
+        {
+            CircuitProposalStatus::Rejected
+        } else if approvers
+            .iter()
+            .all(|approver| tally.accepted().contains(*approver))
+        {
+            CircuitProposalStatus::Accepted
+        } else {
+            CircuitProposalStatus::Pending
+        }
+    }
+
+    fn counts_requester(&self) -> bool {
+        true
+    }
+
+    fn deciding_voters(&self, voters: &HashSet<String>) -> HashSet<String> {
+        let approvers = self
+            .approvers
+            .intersection(voters)
+            .cloned()
+            .collect::<HashSet<_>>();
+
+        if approvers.is_empty() {
+            voters.clone()
+        } else {
+            approvers
+        }
+    }
+}
+
+/// Returns the policy that enforces the given voting rule. An unset rule is unanimous.
+pub fn voting_policy(rule: &VotingRule) -> Box<dyn VotingPolicy> {
+    match rule.get_rule_type() {
+        VotingRule_RuleType::UNSET_RULE_TYPE | VotingRule_RuleType::UNANIMOUS => {
+            Box::new(UnanimousPolicy)
+        }
+        VotingRule_RuleType::MAJORITY => Box::new(MajorityPolicy),
+        VotingRule_RuleType::THRESHOLD => {
+            Box::new(ThresholdPolicy::new(rule.get_threshold() as usize))
+        }
+        VotingRule_RuleType::REQUIRED_APPROVERS => Box::new(RequiredApproversPolicy::new(
+            rule.get_required_approvers().iter().cloned().collect(),
+        )),
+    }
+}
+
+/// Validates that a voting rule can be satisfied by the given circuit members.
+pub fn validate_voting_rule(rule: &VotingRule, members: &[String]) -> Result<(), String> {
+    match rule.get_rule_type() {
+        VotingRule_RuleType::THRESHOLD => {
+            let threshold = rule.get_threshold() as usize;
+            if threshold == 0 || threshold > members.len() {
+                return Err(format!(
+                    "Voting threshold must be between 1 and the number of members ({}), not {}",
+                    members.len(),
+                    threshold
+                ));
+            }
+        }
+        VotingRule_RuleType::REQUIRED_APPROVERS => {
+            let approvers = rule.get_required_approvers();
+            if approvers.is_empty() {
+                return Err("Voting rule must have at least one required approver".into());
+            }
+
+            if let Some(approver) = approvers
+                .iter()
+                .find(|approver| !members.contains(approver))
+            {
+                return Err(format!(
+                    "Required approver {} is not a member of the circuit",
+                    approver
+                ));
+            }
+        }
+        _ => {
+            if rule.get_threshold() != 0 || !rule.get_required_approvers().is_empty() {
+                return Err(format!(
+                    "Voting rule {:?} does not take a threshold or required approvers",
+                    rule.get_rule_type()
+                ));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use protobuf::RepeatedField;
+
+    #[test]
+    // test that a unanimous policy requires every voter to accept, and is rejected by any one
+    // rejection
+    fn test_unanimous_policy() {
+        let mut tally = setup_tally(&["a", "b", "c"]);
+        tally.accept("a");
+        tally.accept("b");
+        assert_eq!(
+            CircuitProposalStatus::Pending,
+            UnanimousPolicy.evaluate(&tally)
+        );
+
+        tally.accept("c");
+        assert_eq!(
+            CircuitProposalStatus::Accepted,
+            UnanimousPolicy.evaluate(&tally)
+        );
+
+        let mut tally = setup_tally(&["a", "b", "c"]);
+        tally.accept("a");
+        tally.reject("b");
+        assert_eq!(
+            CircuitProposalStatus::Rejected,
+            UnanimousPolicy.evaluate(&tally)
+        );
+    }
+
+    #[test]
+    // test that a majority policy is accepted once more than half of the voters accept, and is
+    // rejected once a majority can no longer be reached
+    fn test_majority_policy() {
+        let mut tally = setup_tally(&["a", "b", "c", "d"]);
+        tally.accept("a");
+        tally.accept("b");
+        assert_eq!(
+            CircuitProposalStatus::Pending,
+            MajorityPolicy.evaluate(&tally)
+        );
+
+        tally.accept("c");
+        assert_eq!(
+            CircuitProposalStatus::Accepted,
+            MajorityPolicy.evaluate(&tally)
+        );
+
+        let mut tally = setup_tally(&["a", "b", "c", "d"]);
+        tally.accept("a");
+        tally.reject("b");
+        assert_eq!(
+            CircuitProposalStatus::Pending,
+            MajorityPolicy.evaluate(&tally)
+        );
+
+        tally.reject("c");
+        assert_eq!(
+            CircuitProposalStatus::Rejected,
+            MajorityPolicy.evaluate(&tally)
+        );
+    }
+
+    #[test]
+    // test that a threshold policy is accepted once the threshold is reached, and is rejected
+    // once it can no longer be reached
+    fn test_threshold_policy() {
+        let policy = ThresholdPolicy::new(2);
+
+        let mut tally = setup_tally(&["a", "b", "c", "d"]);
+        tally.accept("a");
+        tally.reject("b");
+        tally.reject("c");
+        assert_eq!(CircuitProposalStatus::Pending, policy.evaluate(&tally));
+
+        tally.accept("d");
+        assert_eq!(CircuitProposalStatus::Accepted, policy.evaluate(&tally));
+
+        let mut tally = setup_tally(&["a", "b", "c"]);
+        tally.accept("a");
+        tally.reject("b");
+        tally.reject("c");
+        assert_eq!(CircuitProposalStatus::Rejected, policy.evaluate(&tally));
+
+        // the threshold is capped at the number of voters
+        let mut tally = setup_tally(&["a"]);
+        tally.accept("a");
+        assert_eq!(CircuitProposalStatus::Accepted, policy.evaluate(&tally));
+    }
+
+    #[test]
+    // test that a required approvers policy only depends on the votes of the required approvers
+    fn test_required_approvers_policy() {
+        let policy = RequiredApproversPolicy::new(vec!["a".to_string()].into_iter().collect());
+
+        let mut tally = setup_tally(&["a", "b", "c"]);
+        tally.reject("b");
+        assert_eq!(CircuitProposalStatus::Pending, policy.evaluate(&tally));
+
+        tally.accept("a");
+        assert_eq!(CircuitProposalStatus::Accepted, policy.evaluate(&tally));
+
+        let mut tally = setup_tally(&["a", "b", "c"]);
+        tally.accept("b");
+        tally.accept("c");
+        tally.reject("a");
+        assert_eq!(CircuitProposalStatus::Rejected, policy.evaluate(&tally));
+
+        // if no required approvers can vote, every voter must accept
+        let mut tally = setup_tally(&["b", "c"]);
+        tally.accept("b");
+        assert_eq!(CircuitProposalStatus::Pending, policy.evaluate(&tally));
+        tally.accept("c");
+        assert_eq!(CircuitProposalStatus::Accepted, policy.evaluate(&tally));
+    }
+
+    #[test]
+    // test that only a required approvers policy counts the requester as accepting a proposal
+    fn test_counts_requester() {
+        assert!(!UnanimousPolicy.counts_requester());
+        assert!(!MajorityPolicy.counts_requester());
+        assert!(!ThresholdPolicy::new(1).counts_requester());
+        assert!(RequiredApproversPolicy::new(HashSet::new()).counts_requester());
+    }
+
+    #[test]
+    // test that a required approvers policy is only decided by the required approvers that can
+    // vote, and that every other policy may be decided by any voter
+    fn test_deciding_voters() {
+        let voters = setup_tally(&["a", "b", "c"]).voters().clone();
+
+        assert_eq!(voters, MajorityPolicy.deciding_voters(&voters));
+        assert_eq!(voters, ThresholdPolicy::new(1).deciding_voters(&voters));
+
+        let policy = RequiredApproversPolicy::new(
+            vec!["a".to_string(), "d".to_string()].into_iter().collect(),
+        );
+        assert_eq!(
+            vec!["a".to_string()].into_iter().collect::<HashSet<_>>(),
+            policy.deciding_voters(&voters)
+        );
+
+        let policy = RequiredApproversPolicy::new(vec!["d".to_string()].into_iter().collect());
+        assert_eq!(voters, policy.deciding_voters(&voters));
+    }
+
+    #[test]
+    // test that votes from nodes that are not voters are ignored
+    fn test_tally_ignores_non_voters() {
+        let mut tally = setup_tally(&["a"]);
+        tally.reject("b");
+        tally.accept("a");
+        assert_eq!(
+            CircuitProposalStatus::Accepted,
+            UnanimousPolicy.evaluate(&tally)
+        );
+    }
+
+    #[test]
+    // test that voting rules are validated against the members of a circuit
+    fn test_validate_voting_rule() {
+        let members = vec!["a".to_string(), "b".to_string()];
+
+        assert!(validate_voting_rule(&VotingRule::new(), &members).is_ok());
+
+        let mut rule = VotingRule::new();
+        rule.set_rule_type(VotingRule_RuleType::THRESHOLD);
+        rule.set_threshold(2);
+        assert!(validate_voting_rule(&rule, &members).is_ok());
+        rule.set_threshold(3);
+        assert!(validate_voting_rule(&rule, &members).is_err());
+        rule.set_threshold(0);
+        assert!(validate_voting_rule(&rule, &members).is_err());
+
+        let mut rule = VotingRule::new();
+        rule.set_rule_type(VotingRule_RuleType::REQUIRED_APPROVERS);
+        assert!(validate_voting_rule(&rule, &members).is_err());
+        rule.set_required_approvers(RepeatedField::from_vec(vec!["b".into()]));
+        assert!(validate_voting_rule(&rule, &members).is_ok());
+        rule.set_required_approvers(RepeatedField::from_vec(vec!["c".into()]));
+        assert!(validate_voting_rule(&rule, &members).is_err());
+
+        let mut rule = VotingRule::new();
+        rule.set_rule_type(VotingRule_RuleType::MAJORITY);
+        rule.set_threshold(2);
+        assert!(validate_voting_rule(&rule, &members).is_err());
+    }
+
+    fn setup_tally(voters: &[&str]) -> VoteTally {
+        VoteTally::new(voters.iter().map(|voter| voter.to_string()).collect())
+    }
+}
@@ -120,7 +120,27 @@ impl Handler for CircuitDirectMessageHandler {
                         let node_id = service.node().id().to_string();
                         // If the service is on this node send message to the service, otherwise
                         // send the message to the node the service is connected to
-                        if node_id != self.node_id {
+                        if circuit.is_abandoned_by(&node_id) {
+                            // if the recipient's node has abandoned the circuit, send circuit
+                            // error
+                            let mut error_message = CircuitError::new();
+                            error_message.set_correlation_id(msg.get_correlation_id().to_string());
+                            error_message.set_service_id(msg_sender.into());
+                            error_message.set_circuit_name(circuit_name.into());
+                            error_message
+                                .set_error(CircuitError_Error::ERROR_RECIPIENT_ABANDONED_CIRCUIT);
+                            error_message.set_error_message(format!(
+                                "Recipient {} is on node {}, which has abandoned the circuit",
+                                recipient, node_id
+                            ));
+
+                            let msg_bytes = error_message.write_to_bytes()?;
+                            let network_msg_bytes = create_message(
+                                msg_bytes,
+                                CircuitMessageType::CIRCUIT_ERROR_MESSAGE,
+                            )?;
+                            (network_msg_bytes, context.source_peer_id().to_string())
+                        } else if node_id != self.node_id {
                             let msg_bytes = context.message_bytes().to_vec();
                             let network_msg_bytes = create_message(
                                 msg_bytes,
@@ -383,6 +403,86 @@ mod tests {
         )
     }
 
+    // Test that an error message is returned if the recipient service is on a node that has
+    // abandoned the circuit
+    #[test]
+    fn test_circuit_direct_message_handler_recipient_abandoned_circuit() {
+        run_test(
+            |mut listener, mut dispatcher, network1| {
+                let connection = listener.accept().expect("Cannot accept connection");
+                network1
+                    .add_peer("def".to_string(), connection)
+                    .expect("Unable to add peer");
+
+                // add the circuit, abandoned by node 123, and services to splinter state
+                let circuit = Circuit::builder()
+                    .with_id("alpha".into())
+                    .with_auth(AuthorizationType::Trust)
+                    .with_members(vec!["123".into(), "345".into()])
+                    .with_roster(vec!["abc".into(), "def".into()])
+                    .with_persistence(PersistenceType::Any)
+                    .with_durability(DurabilityType::NoDurability)
+                    .with_routes(RouteType::Any)
+                    .with_circuit_management_type("circuit_direct_test_app".into())
+                    .with_abandoned_members(vec!["123".into()])
+                    .build()
+                    .expect("Should have built a correct circuit");
+
+                let mut circuit_directory = CircuitDirectory::new();
+                circuit_directory.add_circuit("alpha".to_string(), circuit);
+
+                let state = SplinterState::new("memory".to_string(), circuit_directory);
+
+                let node_123 =
+                    SplinterNode::new("123".to_string(), vec!["123.0.0.1:0".to_string()]);
+                let node_345 =
+                    SplinterNode::new("345".to_string(), vec!["123.0.0.1:0".to_string()]);
+
+                let service_abc =
+                    Service::new("abc".to_string(), Some("abc_network".to_string()), node_123);
+                let service_def =
+                    Service::new("def".to_string(), Some("def_network".to_string()), node_345);
+                let abc_id = ServiceId::new("alpha".into(), "abc".into());
+                let def_id = ServiceId::new("alpha".into(), "def".into());
+                state.add_service(abc_id, service_abc).unwrap();
+                state.add_service(def_id, service_def).unwrap();
+
+                // add direct message handler to the dispatcher
+                let handler = CircuitDirectMessageHandler::new("345".to_string(), state);
+
+                dispatcher.set_handler(Box::new(handler));
+
+                // create direct message
+                let mut direct_message = CircuitDirectMessage::new();
+                direct_message.set_circuit("alpha".into());
+                direct_message.set_sender("def".into());
+                direct_message.set_recipient("abc".into());
+                direct_message.set_payload(b"test".to_vec());
+                direct_message.set_correlation_id("1234".into());
+                let direct_bytes = direct_message.write_to_bytes().unwrap();
+
+                // dispatcher message
+                dispatcher
+                    .dispatch(
+                        "def".into(),
+                        &CircuitMessageType::CIRCUIT_DIRECT_MESSAGE,
+                        direct_bytes.clone(),
+                    )
+                    .unwrap();
+            },
+            "345",
+            CircuitMessageType::CIRCUIT_ERROR_MESSAGE,
+            |msg: CircuitError| {
+                assert_eq!(msg.get_service_id(), "def");
+                assert_eq!(
+                    msg.get_error(),
+                    CircuitError_Error::ERROR_RECIPIENT_ABANDONED_CIRCUIT
+                );
+                assert_eq!(msg.get_correlation_id(), "1234");
+            },
+        )
+    }
+
     // Test that an error message is returned if the sender is not connected to the circuit
     #[test]
     fn test_circuit_direct_message_handler_sender_not_in_directory() {
@@ -29,6 +29,8 @@ use std::sync::{Arc, RwLock};
 
 use crate::circuit::directory::CircuitDirectory;
 use crate::circuit::service::{Service, ServiceId, SplinterNode};
+#[cfg(feature = "diesel")]
+use crate::circuit::store::diesel::DieselCircuitStore;
 use crate::circuit::store::{CircuitFilter, CircuitIter, CircuitStore, CircuitStoreError};
 use crate::storage::get_storage;
 
@@ -45,6 +47,15 @@ pub struct Circuit {
 
     #[serde(default = "Circuit::default_management_type")]
     circuit_management_type: String,
+
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    application_metadata: Vec<u8>,
+
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    abandoned_members: Vec<String>,
+
+    #[serde(default)]
+    voting_rule: VotingRule,
 }
 
 impl Circuit {
@@ -66,6 +77,9 @@ impl Circuit {
             durability: DurabilityType::NoDurability,
             routes: RouteType::Any,
             circuit_management_type: "".into(),
+            application_metadata: vec![],
+            abandoned_members: vec![],
+            voting_rule: VotingRule::Unanimous,
         }
     }
 
@@ -100,6 +114,27 @@ impl Circuit {
     pub fn circuit_management_type(&self) -> &str {
         &self.circuit_management_type
     }
+
+    pub fn application_metadata(&self) -> &[u8] {
+        &self.application_metadata
+    }
+
+    /// Returns the members that have abandoned the circuit.
+    pub fn abandoned_members(&self) -> &[String] {
+        &self.abandoned_members
+    }
+
+    /// Returns `true` if the given member has abandoned the circuit.
+    pub fn is_abandoned_by(&self, node_id: &str) -> bool {
+        self.abandoned_members
+            .iter()
+            .any(|member| member == node_id)
+    }
+
+    /// Returns the rule used to decide whether proposals to change the circuit are approved.
+    pub fn voting_rule(&self) -> &VotingRule {
+        &self.voting_rule
+    }
 }
 
 #[derive(Default)]
@@ -113,6 +148,9 @@ pub struct CircuitBuilder {
     routes: Option<RouteType>,
 
     circuit_management_type: Option<String>,
+    application_metadata: Vec<u8>,
+    abandoned_members: Vec<String>,
+    voting_rule: Option<VotingRule>,
 }
 
 impl CircuitBuilder {
@@ -163,6 +201,24 @@ impl CircuitBuilder {
         self
     }
 
+    pub fn with_application_metadata(mut self, application_metadata: Vec<u8>) -> Self {
+        self.application_metadata = application_metadata;
+
+        self
+    }
+
+    pub fn with_abandoned_members<I: IntoIterator<Item = String>>(mut self, members: I) -> Self {
+        self.abandoned_members.extend(members.into_iter());
+
+        self
+    }
+
+    pub fn with_voting_rule(mut self, voting_rule: VotingRule) -> Self {
+        self.voting_rule = Some(voting_rule);
+
+        self
+    }
+
     pub fn build(self) -> Result<Circuit, CircuitBuildError> {
         if self.members.is_empty() {
             return Err(CircuitBuildError(
@@ -192,6 +248,9 @@ impl CircuitBuilder {
             circuit_management_type: self
                 .circuit_management_type
                 .unwrap_or_else(Circuit::default_management_type),
+            application_metadata: self.application_metadata,
+            abandoned_members: self.abandoned_members,
+            voting_rule: self.voting_rule.unwrap_or_default(),
         })
     }
 }
@@ -358,6 +417,25 @@ pub enum RouteType {
     Any,
 }
 
+/// The rule that decides whether a proposal to change a circuit is approved by its members.
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub enum VotingRule {
+    /// Every voting member must accept the proposal.
+    Unanimous,
+    /// More than half of the voting members must accept the proposal.
+    Majority,
+    /// At least the given number of voting members must accept the proposal.
+    Threshold(u32),
+    /// Each of the given members must accept the proposal.
+    RequiredApprovers(Vec<String>),
+}
+
+impl Default for VotingRule {
+    fn default() -> Self {
+        VotingRule::Unanimous
+    }
+}
+
 pub enum RosterIter<'r> {
     Standard(std::slice::Iter<'r, ServiceDefinition>),
     Admin,
@@ -391,6 +469,10 @@ pub struct SplinterState {
     circuit_directory: Arc<RwLock<CircuitDirectory>>,
     // Service id to Service that contains the node the service is connected to. Not persisted.
     service_directory: Arc<RwLock<HashMap<ServiceId, Service>>>,
+    // If set, changes are persisted to this store a circuit or node at a time, instead of
+    // writing the whole circuit directory to the storage location.
+    #[cfg(feature = "diesel")]
+    circuit_store: Option<DieselCircuitStore>,
 }
 
 impl SplinterState {
@@ -399,7 +481,43 @@ impl SplinterState {
             storage_location,
             circuit_directory: Arc::new(RwLock::new(circuit_directory)),
             service_directory: Arc::new(RwLock::new(HashMap::new())),
+            #[cfg(feature = "diesel")]
+            circuit_store: None,
+        }
+    }
+
+    /// Creates a SplinterState that is loaded from, and persisted to, the given circuit store.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `SplinterStateError` if the circuits or nodes could not be loaded from the
+    /// store.
+    #[cfg(feature = "diesel")]
+    pub fn from_circuit_store(
+        circuit_store: DieselCircuitStore,
+    ) -> Result<Self, SplinterStateError> {
+        let mut circuit_directory = CircuitDirectory::new();
+
+        for node in circuit_store
+            .nodes()
+            .map_err(|err| SplinterStateError::from_source("Failed to load nodes".into(), err))?
+        {
+            circuit_directory.add_node(node.id().to_string(), node);
+        }
+
+        for circuit in circuit_store
+            .circuits(None)
+            .map_err(|err| SplinterStateError::from_source("Failed to load circuits".into(), err))?
+        {
+            circuit_directory.add_circuit(circuit.id().to_string(), circuit);
         }
+
+        Ok(SplinterState {
+            storage_location: "database".into(),
+            circuit_directory: Arc::new(RwLock::new(circuit_directory)),
+            service_directory: Arc::new(RwLock::new(HashMap::new())),
+            circuit_store: Some(circuit_store),
+        })
     }
 
     pub fn storage_location(&self) -> &str {
@@ -422,6 +540,48 @@ impl SplinterState {
         Ok(())
     }
 
+    fn commit_circuit(&self, circuit_name: &str) -> Result<(), SplinterStateError> {
+        #[cfg(feature = "diesel")]
+        {
+            if let Some(circuit_store) = &self.circuit_store {
+                let result = match self.circuit(circuit_name)? {
+                    Some(circuit) => circuit_store.add_circuit(circuit),
+                    None => circuit_store.remove_circuit(circuit_name),
+                };
+
+                return result.map_err(|err| {
+                    SplinterStateError::from_source(
+                        format!("Failed to commit circuit {}", circuit_name),
+                        err,
+                    )
+                });
+            }
+        }
+
+        self.commit_circuit_directory()
+    }
+
+    fn commit_node(&self, node_id: &str) -> Result<(), SplinterStateError> {
+        #[cfg(feature = "diesel")]
+        {
+            if let Some(circuit_store) = &self.circuit_store {
+                let result = match self.node(node_id)? {
+                    Some(node) => circuit_store.add_node(node),
+                    None => circuit_store.remove_node(node_id),
+                };
+
+                return result.map_err(|err| {
+                    SplinterStateError::from_source(
+                        format!("Failed to commit node {}", node_id),
+                        err,
+                    )
+                });
+            }
+        }
+
+        self.commit_circuit_directory()
+    }
+
     // ---------- methods to access service directory ----------
 
     pub fn get_service(&self, id: &ServiceId) -> Result<Option<Service>, SplinterStateError> {
@@ -486,9 +646,9 @@ impl SplinterState {
                 SplinterStateError::new("Failed to get write guard for circuit directory".into())
             })?;
 
-            circuit_directory.add_node(id, node);
+            circuit_directory.add_node(id.clone(), node);
         }
-        self.commit_circuit_directory()?;
+        self.commit_node(&id)?;
         Ok(())
     }
 
@@ -501,9 +661,36 @@ impl SplinterState {
             let mut circuit_directory = self.circuit_directory.write().map_err(|_| {
                 SplinterStateError::new("Failed to get write guard for circuit directory".into())
             })?;
-            circuit_directory.add_circuit(name, circuit);
+            circuit_directory.add_circuit(name.clone(), circuit);
+        }
+        self.commit_circuit(&name)?;
+        Ok(())
+    }
+
+    /// Record that a member node has abandoned a circuit. Messages will no longer be routed to
+    /// that node's services on the circuit.
+    pub fn add_abandoned_member(
+        &mut self,
+        circuit_name: &str,
+        node_id: &str,
+    ) -> Result<(), SplinterStateError> {
+        {
+            let mut circuit_directory = self.circuit_directory.write().map_err(|_| {
+                SplinterStateError::new("Failed to get write guard for circuit directory".into())
+            })?;
+            let mut circuit = circuit_directory
+                .circuit(circuit_name)
+                .cloned()
+                .ok_or_else(|| {
+                    SplinterStateError::new(format!("Circuit {} does not exist", circuit_name))
+                })?;
+
+            if !circuit.is_abandoned_by(node_id) {
+                circuit.abandoned_members.push(node_id.to_string());
+            }
+            circuit_directory.add_circuit(circuit_name.to_string(), circuit);
         }
-        self.commit_circuit_directory()?;
+        self.commit_circuit(circuit_name)?;
         Ok(())
     }
 
@@ -514,7 +701,7 @@ impl SplinterState {
             })?;
             circuit_directory.remove_node(id);
         }
-        self.commit_circuit_directory()?;
+        self.commit_node(id)?;
         Ok(())
     }
 
@@ -525,7 +712,7 @@ impl SplinterState {
             })?;
             circuit_directory.remove_circuit(name);
         }
-        self.commit_circuit_directory()?;
+        self.commit_circuit(name)?;
         Ok(())
     }
 
@@ -750,6 +937,46 @@ mod tests {
         assert!(storage.read().circuits().len() == 0);
     }
 
+    #[test]
+    fn test_abandoned_member_write_file() {
+        // create temp directoy
+        let temp_dir = TempDir::new("test_abandoned_member_write_file").unwrap();
+        let temp_dir = temp_dir.path().to_path_buf();
+
+        // setup empty state filename
+        let path = setup_storage(temp_dir);
+        let mut storage = get_storage(&path, CircuitDirectory::new).unwrap();
+        let circuit_directory = storage.write().clone();
+        let mut state = SplinterState::new(path.to_string(), circuit_directory);
+
+        let circuit = Circuit::builder()
+            .with_id("alpha".into())
+            .with_auth(AuthorizationType::Trust)
+            .with_members(vec!["123".into(), "456".into()])
+            .with_roster(vec!["abc".into(), "def".into()])
+            .with_persistence(PersistenceType::Any)
+            .with_durability(DurabilityType::NoDurability)
+            .with_routes(RouteType::Any)
+            .with_circuit_management_type("test_app".into())
+            .build()
+            .expect("Should have built a correct circuit");
+        state.add_circuit("alpha".into(), circuit).unwrap();
+
+        state.add_abandoned_member("alpha", "456").unwrap();
+        // adding the same member again has no effect
+        state.add_abandoned_member("alpha", "456").unwrap();
+
+        // reload storage and check that the abandoned member was written
+        let storage = get_storage(&path, CircuitDirectory::new).unwrap();
+        let circuit = storage.read().circuits().get("alpha").unwrap().clone();
+        assert!(circuit.is_abandoned_by("456"));
+        assert!(!circuit.is_abandoned_by("123"));
+        assert_eq!(circuit.abandoned_members(), &["456".to_string()]);
+
+        // a circuit that does not exist cannot be abandoned
+        assert!(state.add_abandoned_member("beta", "456").is_err());
+    }
+
     #[test]
     fn test_node_write_file() {
         // create temp directoy
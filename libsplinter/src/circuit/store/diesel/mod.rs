@@ -0,0 +1,104 @@
+// Copyright 2018-2020 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! A database-backed circuit store, powered by [`Diesel`](https://crates.io/crates/diesel).
+//!
+//! Each circuit is stored in its own row, so adding or removing a circuit only writes that
+//! circuit, rather than the entire circuit directory.
+//!
+//! Only PostgreSQL is supported; the store's tables are created by
+//! `admin::migrations::run_postgres_migrations`, and there are no SQLite migrations.
+
+mod models;
+mod operations;
+mod schema;
+
+use crate::circuit::service::SplinterNode;
+use crate::circuit::store::{CircuitFilter, CircuitIter, CircuitStore, CircuitStoreError};
+use crate::circuit::Circuit;
+use crate::database::ConnectionPool;
+
+use operations::{
+    add_circuit::CircuitStoreAddCircuitOperation as _, add_node::CircuitStoreAddNodeOperation as _,
+    fetch_circuit::CircuitStoreFetchCircuitOperation as _,
+    list_circuits::CircuitStoreListCircuitsOperation as _,
+    list_nodes::CircuitStoreListNodesOperation as _,
+    remove_circuit::CircuitStoreRemoveCircuitOperation as _,
+    remove_node::CircuitStoreRemoveNodeOperation as _, CircuitStoreOperations,
+};
+
+/// Manages creating, updating and fetching circuits and splinter nodes from a PostgreSQL
+/// database.
+#[derive(Clone)]
+pub struct DieselCircuitStore {
+    connection_pool: ConnectionPool,
+}
+
+impl DieselCircuitStore {
+    /// Creates a new DieselCircuitStore
+    ///
+    /// # Arguments
+    ///
+    ///  * `connection_pool`: connection pool to the PostgreSQL database
+    ///
+    pub fn new(connection_pool: ConnectionPool) -> Self {
+        DieselCircuitStore { connection_pool }
+    }
+
+    /// Adds a circuit to the store, replacing any existing circuit with the same ID.
+    pub fn add_circuit(&self, circuit: Circuit) -> Result<(), CircuitStoreError> {
+        CircuitStoreOperations::new(&*self.connection()?).add_circuit(circuit)
+    }
+
+    /// Removes the circuit with the given ID from the store, if it exists.
+    pub fn remove_circuit(&self, circuit_id: &str) -> Result<(), CircuitStoreError> {
+        CircuitStoreOperations::new(&*self.connection()?).remove_circuit(circuit_id)
+    }
+
+    /// Adds a splinter node to the store, replacing any existing node with the same ID.
+    pub fn add_node(&self, node: SplinterNode) -> Result<(), CircuitStoreError> {
+        CircuitStoreOperations::new(&*self.connection()?).add_node(node)
+    }
+
+    /// Removes the splinter node with the given ID from the store, if it exists.
+    pub fn remove_node(&self, node_id: &str) -> Result<(), CircuitStoreError> {
+        CircuitStoreOperations::new(&*self.connection()?).remove_node(node_id)
+    }
+
+    /// Returns all splinter nodes in the store.
+    pub fn nodes(&self) -> Result<Vec<SplinterNode>, CircuitStoreError> {
+        CircuitStoreOperations::new(&*self.connection()?).list_nodes()
+    }
+
+    fn connection(&self) -> Result<crate::database::Connection, CircuitStoreError> {
+        self.connection_pool
+            .get()
+            .map_err(|err| CircuitStoreError::new(err.to_string()))
+    }
+}
+
+impl CircuitStore for DieselCircuitStore {
+    fn circuits(&self, filter: Option<CircuitFilter>) -> Result<CircuitIter, CircuitStoreError> {
+        let circuits = CircuitStoreOperations::new(&*self.connection()?).list_circuits(filter)?;
+
+        Ok(CircuitIter::new(
+            circuits.len() as u64,
+            Box::new(circuits.into_iter()),
+        ))
+    }
+
+    fn circuit(&self, circuit_name: &str) -> Result<Option<Circuit>, CircuitStoreError> {
+        CircuitStoreOperations::new(&*self.connection()?).fetch_circuit(circuit_name)
+    }
+}
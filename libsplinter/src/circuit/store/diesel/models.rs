@@ -0,0 +1,42 @@
+// Copyright 2018-2020 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::schema::{circuit_members, circuits, splinter_nodes};
+
+/// A circuit definition, stored as JSON alongside the columns used for filtering.
+#[derive(Insertable, Queryable, Identifiable, PartialEq, Debug)]
+#[table_name = "circuits"]
+#[primary_key(circuit_id)]
+pub struct CircuitModel {
+    pub circuit_id: String,
+    pub circuit_management_type: String,
+    pub definition: String,
+}
+
+#[derive(Insertable, Queryable, Identifiable, PartialEq, Debug)]
+#[table_name = "circuit_members"]
+#[primary_key(circuit_id, node_id)]
+pub struct CircuitMemberModel {
+    pub circuit_id: String,
+    pub node_id: String,
+}
+
+/// A splinter node's endpoints, stored as a JSON array.
+#[derive(Insertable, Queryable, Identifiable, PartialEq, Debug)]
+#[table_name = "splinter_nodes"]
+#[primary_key(node_id)]
+pub struct SplinterNodeModel {
+    pub node_id: String,
+    pub endpoints: String,
+}
@@ -0,0 +1,84 @@
+// Copyright 2018-2020 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::CircuitStoreOperations;
+use crate::circuit::store::diesel::models::{CircuitMemberModel, CircuitModel};
+use crate::circuit::store::diesel::schema::{circuit_members, circuits};
+use crate::circuit::store::CircuitStoreError;
+use crate::circuit::Circuit;
+
+use diesel::{
+    dsl::{delete, insert_into},
+    prelude::*,
+};
+
+pub(in crate::circuit::store) trait CircuitStoreAddCircuitOperation {
+    fn add_circuit(&self, circuit: Circuit) -> Result<(), CircuitStoreError>;
+}
+
+impl<'a, C> CircuitStoreAddCircuitOperation for CircuitStoreOperations<'a, C>
+where
+    C: diesel::Connection,
+    <C as diesel::Connection>::Backend: diesel::backend::SupportsDefaultKeyword,
+    <C as diesel::Connection>::Backend: 'static,
+    String: diesel::deserialize::FromSql<diesel::sql_types::Text, C::Backend>,
+{
+    fn add_circuit(&self, circuit: Circuit) -> Result<(), CircuitStoreError> {
+        let definition = serde_json::to_string(&circuit).map_err(|err| {
+            CircuitStoreError::from_source(
+                format!("Failed to serialize circuit {}", circuit.id()),
+                err,
+            )
+        })?;
+
+        let circuit_model = CircuitModel {
+            circuit_id: circuit.id().to_string(),
+            circuit_management_type: circuit.circuit_management_type().to_string(),
+            definition,
+        };
+        let member_models = circuit
+            .members()
+            .into_iter()
+            .map(|node_id| CircuitMemberModel {
+                circuit_id: circuit.id().to_string(),
+                node_id: node_id.to_string(),
+            })
+            .collect::<Vec<_>>();
+
+        // Replace any existing definition of the circuit, so that adding a circuit that is
+        // already stored updates it in place.
+        self.conn
+            .transaction::<_, diesel::result::Error, _>(|| {
+                delete(circuit_members::table.filter(circuit_members::circuit_id.eq(circuit.id())))
+                    .execute(self.conn)?;
+                delete(circuits::table.filter(circuits::circuit_id.eq(circuit.id())))
+                    .execute(self.conn)?;
+
+                insert_into(circuits::table)
+                    .values(vec![circuit_model])
+                    .execute(self.conn)?;
+                insert_into(circuit_members::table)
+                    .values(member_models)
+                    .execute(self.conn)?;
+
+                Ok(())
+            })
+            .map_err(|err| {
+                CircuitStoreError::from_source(
+                    format!("Failed to add circuit {}", circuit.id()),
+                    err,
+                )
+            })
+    }
+}
@@ -0,0 +1,63 @@
+// Copyright 2018-2020 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::CircuitStoreOperations;
+use crate::circuit::service::SplinterNode;
+use crate::circuit::store::diesel::models::SplinterNodeModel;
+use crate::circuit::store::diesel::schema::splinter_nodes;
+use crate::circuit::store::CircuitStoreError;
+
+use diesel::{
+    dsl::{delete, insert_into},
+    prelude::*,
+};
+
+pub(in crate::circuit::store) trait CircuitStoreAddNodeOperation {
+    fn add_node(&self, node: SplinterNode) -> Result<(), CircuitStoreError>;
+}
+
+impl<'a, C> CircuitStoreAddNodeOperation for CircuitStoreOperations<'a, C>
+where
+    C: diesel::Connection,
+    <C as diesel::Connection>::Backend: diesel::backend::SupportsDefaultKeyword,
+    <C as diesel::Connection>::Backend: 'static,
+    String: diesel::deserialize::FromSql<diesel::sql_types::Text, C::Backend>,
+{
+    fn add_node(&self, node: SplinterNode) -> Result<(), CircuitStoreError> {
+        let endpoints = serde_json::to_string(node.endpoints()).map_err(|err| {
+            CircuitStoreError::from_source(
+                format!("Failed to serialize endpoints of node {}", node.id()),
+                err,
+            )
+        })?;
+        let node_model = SplinterNodeModel {
+            node_id: node.id().to_string(),
+            endpoints,
+        };
+
+        self.conn
+            .transaction::<_, diesel::result::Error, _>(|| {
+                delete(splinter_nodes::table.filter(splinter_nodes::node_id.eq(node.id())))
+                    .execute(self.conn)?;
+                insert_into(splinter_nodes::table)
+                    .values(vec![node_model])
+                    .execute(self.conn)?;
+
+                Ok(())
+            })
+            .map_err(|err| {
+                CircuitStoreError::from_source(format!("Failed to add node {}", node.id()), err)
+            })
+    }
+}
@@ -0,0 +1,60 @@
+// Copyright 2018-2020 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::CircuitStoreOperations;
+use crate::circuit::store::diesel::models::CircuitModel;
+use crate::circuit::store::diesel::schema::circuits;
+use crate::circuit::store::CircuitStoreError;
+use crate::circuit::Circuit;
+
+use diesel::{prelude::*, result::Error::NotFound};
+
+pub(in crate::circuit::store) trait CircuitStoreFetchCircuitOperation {
+    fn fetch_circuit(&self, circuit_id: &str) -> Result<Option<Circuit>, CircuitStoreError>;
+}
+
+impl<'a, C> CircuitStoreFetchCircuitOperation for CircuitStoreOperations<'a, C>
+where
+    C: diesel::Connection,
+    <C as diesel::Connection>::Backend: diesel::backend::SupportsDefaultKeyword,
+    <C as diesel::Connection>::Backend: 'static,
+    String: diesel::deserialize::FromSql<diesel::sql_types::Text, C::Backend>,
+{
+    fn fetch_circuit(&self, circuit_id: &str) -> Result<Option<Circuit>, CircuitStoreError> {
+        circuits::table
+            .filter(circuits::circuit_id.eq(circuit_id))
+            .first::<CircuitModel>(self.conn)
+            .map(Some)
+            .or_else(|err| if err == NotFound { Ok(None) } else { Err(err) })
+            .map_err(|err| {
+                CircuitStoreError::from_source(
+                    format!("Failed to fetch circuit {}", circuit_id),
+                    err,
+                )
+            })?
+            .map(|circuit_model| circuit_from_model(&circuit_model))
+            .transpose()
+    }
+}
+
+pub(super) fn circuit_from_model(
+    circuit_model: &CircuitModel,
+) -> Result<Circuit, CircuitStoreError> {
+    serde_json::from_str(&circuit_model.definition).map_err(|err| {
+        CircuitStoreError::from_source(
+            format!("Failed to deserialize circuit {}", circuit_model.circuit_id),
+            err,
+        )
+    })
+}
@@ -0,0 +1,68 @@
+// Copyright 2018-2020 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::fetch_circuit::circuit_from_model;
+use super::CircuitStoreOperations;
+use crate::circuit::store::diesel::models::CircuitModel;
+use crate::circuit::store::diesel::schema::{circuit_members, circuits};
+use crate::circuit::store::{CircuitFilter, CircuitStoreError};
+use crate::circuit::Circuit;
+
+use diesel::prelude::*;
+
+pub(in crate::circuit::store) trait CircuitStoreListCircuitsOperation {
+    fn list_circuits(
+        &self,
+        filter: Option<CircuitFilter>,
+    ) -> Result<Vec<Circuit>, CircuitStoreError>;
+}
+
+impl<'a, C> CircuitStoreListCircuitsOperation for CircuitStoreOperations<'a, C>
+where
+    C: diesel::Connection,
+    <C as diesel::Connection>::Backend: diesel::backend::SupportsDefaultKeyword,
+    <C as diesel::Connection>::Backend: 'static,
+    String: diesel::deserialize::FromSql<diesel::sql_types::Text, C::Backend>,
+{
+    fn list_circuits(
+        &self,
+        filter: Option<CircuitFilter>,
+    ) -> Result<Vec<Circuit>, CircuitStoreError> {
+        let circuit_models = match filter {
+            Some(CircuitFilter::WithMember(member)) => {
+                let circuit_ids = circuit_members::table
+                    .filter(circuit_members::node_id.eq(&member))
+                    .select(circuit_members::circuit_id)
+                    .load::<String>(self.conn)
+                    .map_err(|err| {
+                        CircuitStoreError::from_source(
+                            format!("Failed to list circuits with member {}", member),
+                            err,
+                        )
+                    })?;
+
+                circuits::table
+                    .filter(circuits::circuit_id.eq_any(circuit_ids))
+                    .order(circuits::circuit_id)
+                    .load::<CircuitModel>(self.conn)
+            }
+            None => circuits::table
+                .order(circuits::circuit_id)
+                .load::<CircuitModel>(self.conn),
+        }
+        .map_err(|err| CircuitStoreError::from_source("Failed to list circuits".into(), err))?;
+
+        circuit_models.iter().map(circuit_from_model).collect()
+    }
+}
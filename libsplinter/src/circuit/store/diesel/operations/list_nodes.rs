@@ -0,0 +1,54 @@
+// Copyright 2018-2020 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::CircuitStoreOperations;
+use crate::circuit::service::SplinterNode;
+use crate::circuit::store::diesel::models::SplinterNodeModel;
+use crate::circuit::store::diesel::schema::splinter_nodes;
+use crate::circuit::store::CircuitStoreError;
+
+use diesel::prelude::*;
+
+pub(in crate::circuit::store) trait CircuitStoreListNodesOperation {
+    fn list_nodes(&self) -> Result<Vec<SplinterNode>, CircuitStoreError>;
+}
+
+impl<'a, C> CircuitStoreListNodesOperation for CircuitStoreOperations<'a, C>
+where
+    C: diesel::Connection,
+    <C as diesel::Connection>::Backend: diesel::backend::SupportsDefaultKeyword,
+    <C as diesel::Connection>::Backend: 'static,
+    String: diesel::deserialize::FromSql<diesel::sql_types::Text, C::Backend>,
+{
+    fn list_nodes(&self) -> Result<Vec<SplinterNode>, CircuitStoreError> {
+        splinter_nodes::table
+            .load::<SplinterNodeModel>(self.conn)
+            .map_err(|err| CircuitStoreError::from_source("Failed to list nodes".into(), err))?
+            .into_iter()
+            .map(|node_model| {
+                let endpoints = serde_json::from_str(&node_model.endpoints).map_err(|err| {
+                    CircuitStoreError::from_source(
+                        format!(
+                            "Failed to deserialize endpoints of node {}",
+                            node_model.node_id
+                        ),
+                        err,
+                    )
+                })?;
+
+                Ok(SplinterNode::new(node_model.node_id, endpoints))
+            })
+            .collect()
+    }
+}
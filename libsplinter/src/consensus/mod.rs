@@ -15,6 +15,8 @@
 //! The API that defines interactions between consensus and a Splinter service.
 
 pub mod error;
+pub mod raft;
+mod timing;
 pub mod two_phase;
 
 use std::convert::{TryFrom, TryInto};
@@ -175,6 +177,26 @@ pub trait ProposalManager: Send {
 
     /// Consensus has rejected the given proposal.
     fn reject_proposal(&self, id: &ProposalId) -> Result<(), ProposalManagerError>;
+
+    /// Get the contents of the given proposal, which another node's manager can add with
+    /// `add_proposal`.
+    ///
+    /// Some consensus algorithms replicate the contents of proposals themselves, so that a node
+    /// that did not receive a proposal when it was created can still check and accept it.
+    ///
+    /// The default implementation returns no contents, since this is only useful for some
+    /// managers.
+    fn get_proposal_payload(&self, _id: &ProposalId) -> Result<Vec<u8>, ProposalManagerError> {
+        Ok(vec![])
+    }
+
+    /// Add the proposal with the given ID from its contents, as returned by
+    /// `get_proposal_payload` on another node, so that it can be checked.
+    ///
+    /// The default implementation does nothing, since this is only useful for some managers.
+    fn add_proposal(&self, _id: &ProposalId, _payload: &[u8]) -> Result<(), ProposalManagerError> {
+        Ok(())
+    }
 }
 
 /// Messages the `ProposalManager` sends to consensus
@@ -305,8 +327,10 @@ pub mod tests {
         last_proposal_id: RefCell<ProposalId>,
         accepted_proposals: Arc<Mutex<Vec<(ProposalId, Vec<u8>)>>>,
         rejected_proposals: Arc<Mutex<Vec<ProposalId>>>,
+        added_proposals: Arc<Mutex<Vec<(ProposalId, Vec<u8>)>>>,
         next_proposal_valid: Arc<AtomicBool>,
         return_proposal: Arc<AtomicBool>,
+        require_added_proposals: Arc<AtomicBool>,
         consensus_data: Option<Vec<u8>>,
     }
 
@@ -320,8 +344,10 @@ pub mod tests {
                 last_proposal_id: self.last_proposal_id.clone(),
                 accepted_proposals: self.accepted_proposals.clone(),
                 rejected_proposals: self.rejected_proposals.clone(),
+                added_proposals: self.added_proposals.clone(),
                 next_proposal_valid: self.next_proposal_valid.clone(),
                 return_proposal: self.return_proposal.clone(),
+                require_added_proposals: self.require_added_proposals.clone(),
                 consensus_data: self.consensus_data.clone(),
             }
         }
@@ -335,8 +361,10 @@ pub mod tests {
                 last_proposal_id: RefCell::new(ProposalId::default()),
                 accepted_proposals: Arc::new(Mutex::new(vec![])),
                 rejected_proposals: Arc::new(Mutex::new(vec![])),
+                added_proposals: Arc::new(Mutex::new(vec![])),
                 next_proposal_valid: Arc::new(AtomicBool::new(true)),
                 return_proposal: Arc::new(AtomicBool::new(true)),
+                require_added_proposals: Arc::new(AtomicBool::new(false)),
                 consensus_data: None,
             }
         }
@@ -350,6 +378,13 @@ pub mod tests {
                 .store(return_proposal, Ordering::Relaxed);
         }
 
+        /// If set, proposals are unknown until they have been added with their contents, as
+        /// returned by `proposal_payload`.
+        pub fn set_require_added_proposals(&self, require_added_proposals: bool) {
+            self.require_added_proposals
+                .store(require_added_proposals, Ordering::Relaxed);
+        }
+
         pub fn set_consensus_data(&mut self, data: Option<Vec<u8>>) {
             self.consensus_data = data;
         }
@@ -365,6 +400,19 @@ pub mod tests {
                 .lock()
                 .expect("failed to get rejected proposals")
         }
+
+        pub fn added_proposals(&self) -> MutexGuard<Vec<(ProposalId, Vec<u8>)>> {
+            self.added_proposals
+                .lock()
+                .expect("failed to get added proposals")
+        }
+    }
+
+    /// Returns the contents the mock proposal manager provides for the given proposal.
+    pub fn proposal_payload(id: &ProposalId) -> Vec<u8> {
+        let mut payload = b"payload-".to_vec();
+        payload.extend_from_slice(id.as_ref());
+        payload
     }
 
     impl ProposalManager for MockProposalManager {
@@ -406,6 +454,15 @@ pub mod tests {
         }
 
         fn check_proposal(&self, id: &ProposalId) -> Result<(), ProposalManagerError> {
+            if self.require_added_proposals.load(Ordering::Relaxed)
+                && !self
+                    .added_proposals()
+                    .iter()
+                    .any(|(added_id, payload)| added_id == id && payload == &proposal_payload(id))
+            {
+                return Err(ProposalManagerError::UnknownProposal(id.clone()));
+            }
+
             if self.next_proposal_valid.load(Ordering::Relaxed) {
                 self.update_sender
                     .send(ProposalUpdate::ProposalValid(id.clone()))
@@ -438,6 +495,19 @@ pub mod tests {
                 .push(id.clone());
             Ok(())
         }
+
+        fn get_proposal_payload(&self, id: &ProposalId) -> Result<Vec<u8>, ProposalManagerError> {
+            Ok(proposal_payload(id))
+        }
+
+        fn add_proposal(
+            &self,
+            id: &ProposalId,
+            payload: &[u8],
+        ) -> Result<(), ProposalManagerError> {
+            self.added_proposals().push((id.clone(), payload.to_vec()));
+            Ok(())
+        }
     }
 
     #[derive(Clone)]
@@ -0,0 +1,1913 @@
+// Copyright 2018-2020 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! A crash-fault-tolerant, leader-based consensus algorithm modeled on Raft and implemented as a
+//! `ConsensusEngine`.
+//!
+//! The nodes elect a leader, which appends proposals to a replicated log and sends the log to the
+//! other nodes (followers). A proposal is committed, and accepted by each node in log order, once
+//! it has been replicated to a majority of the nodes. Unlike two-phase commit, the network keeps
+//! committing proposals as long as a majority of the nodes are available; for instance, a network
+//! of three nodes tolerates one node being offline.
+//!
+//! Only one proposal is in flight at a time. The leader builds its own proposals; followers build
+//! proposals and forward their IDs to the leader, which checks them and appends them to the log
+//! when it is idle.
+//!
+//! The current term, vote, log, and the index of the last entry that was applied are kept in a
+//! `Storage`, and are written to it before the node responds to other nodes. A node that restarts
+//! with persistent storage never contradicts a vote or an acknowledged entry, and resumes applying
+//! entries after the last one it applied.
+//!
+//! Each log entry carries the contents of its proposal, as provided by the proposal manager's
+//! `get_proposal_payload`, and a node adds the proposal to its proposal manager with
+//! `add_proposal` before checking it. A node that was offline while proposals were committed
+//! therefore catches up from the leader's log when it comes back online.
+//!
+//! # Known limitations of this Raft implementation
+//!
+//! The log is never compacted, and the whole log, including the contents of every proposal, is
+//! written to storage each time it changes.
+//!
+//! A committed proposal that the proposal manager reports as invalid is never skipped; this node
+//! stops applying entries, and checks the proposal again periodically, until its state matches.
+
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::mpsc::{Receiver, RecvTimeoutError};
+use std::time::Duration;
+
+use protobuf::{Message, RepeatedField};
+use rand::Rng;
+
+use crate::consensus::timing::Timeout;
+use crate::consensus::{
+    ConsensusEngine, ConsensusEngineError, ConsensusMessage, ConsensusNetworkSender, PeerId,
+    ProposalId, ProposalManager, ProposalUpdate, StartupState,
+};
+use crate::hex::{as_hex, deserialize_hex};
+use crate::protos::raft::{
+    AppendEntries, AppendEntriesResponse, ForwardProposal, RaftLogEntry, RaftMessage,
+    RaftMessage_Type, RequestVote, RequestVoteResponse,
+};
+use crate::storage::Storage;
+
+const MESSAGE_RECV_TIMEOUT_MILLIS: u64 = 100;
+const PROPOSAL_RECV_TIMEOUT_MILLIS: u64 = 100;
+
+/// The leader sends heartbeats this many times per election timeout.
+const HEARTBEATS_PER_ELECTION_TIMEOUT: u32 = 5;
+
+#[derive(Debug)]
+enum Role {
+    Follower {
+        leader_id: Option<PeerId>,
+    },
+    Candidate {
+        votes: HashSet<PeerId>,
+    },
+    Leader {
+        next_index: HashMap<PeerId, u64>,
+        match_index: HashMap<PeerId, u64>,
+    },
+}
+
+/// The state of the proposal this node is preparing to add to the log
+#[derive(Debug, PartialEq)]
+enum PendingProposal {
+    Idle,
+    /// The proposal manager has been asked to create a proposal
+    Creating,
+    /// The leader is checking a proposal that was forwarded by a follower
+    Checking(ProposalId),
+    /// The follower has forwarded its proposal to the leader
+    Forwarded(ProposalId),
+}
+
+/// An entry in the replicated log; an empty proposal ID marks an entry without a proposal, which
+/// a new leader appends so that entries from previous terms can be committed.
+#[derive(Clone, Debug)]
+struct LogEntry {
+    term: u64,
+    proposal_id: ProposalId,
+    /// The contents of the proposal, which are added to the proposal manager before the proposal
+    /// is checked
+    payload: Vec<u8>,
+}
+
+impl LogEntry {
+    fn has_proposal(&self) -> bool {
+        !self.proposal_id.as_ref().is_empty()
+    }
+}
+
+/// The state that a node persists before it responds to other nodes: its current term, the
+/// candidate it voted for in that term, its log, and the index of the last entry it applied.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct RaftPersistentState {
+    current_term: u64,
+    voted_for: Option<Vec<u8>>,
+    log: Vec<PersistentLogEntry>,
+    last_applied: u64,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+struct PersistentLogEntry {
+    term: u64,
+    proposal_id: Vec<u8>,
+    #[serde(
+        default,
+        serialize_with = "as_hex",
+        deserialize_with = "deserialize_hex"
+    )]
+    payload: Vec<u8>,
+}
+
+pub struct RaftEngine {
+    id: PeerId,
+    peers: HashSet<PeerId>,
+    role: Role,
+    current_term: u64,
+    voted_for: Option<PeerId>,
+    /// The replicated log; the entry at log index `i` is stored at `log[i - 1]`
+    log: Vec<LogEntry>,
+    commit_index: u64,
+    last_applied: u64,
+    pending: PendingProposal,
+    /// Proposals forwarded to the leader by followers that are waiting to be checked
+    forwarded_proposals: VecDeque<ProposalId>,
+    /// The committed proposal that is being checked before it is accepted
+    applying: Option<ProposalId>,
+    /// The proposal whose changes the proposal manager currently has prepared
+    prepared: Option<ProposalId>,
+    /// Started when a committed proposal could not be applied; the proposal is checked again once
+    /// it expires
+    apply_retry_timeout: Option<Timeout>,
+    election_timeout_duration: Duration,
+    election_timeout: Timeout,
+    heartbeat_timeout: Timeout,
+    forward_timeout: Timeout,
+    storage: Box<dyn Storage<S = RaftPersistentState> + Send>,
+}
+
+impl RaftEngine {
+    /// Creates a new `RaftEngine`, which resumes from the state in the given storage. Followers
+    /// start an election if they have not heard from a leader for a random duration between one
+    /// and two times the given election timeout.
+    pub fn new(
+        election_timeout_duration: Duration,
+        storage: Box<dyn Storage<S = RaftPersistentState> + Send>,
+    ) -> Self {
+        let (current_term, voted_for, log, last_applied) = {
+            let state = storage.read();
+            (
+                state.current_term,
+                state.voted_for.clone().map(PeerId::from),
+                state
+                    .log
+                    .iter()
+                    .map(|entry| LogEntry {
+                        term: entry.term,
+                        proposal_id: entry.proposal_id.clone().into(),
+                        payload: entry.payload.clone(),
+                    })
+                    .collect(),
+                state.last_applied,
+            )
+        };
+
+        RaftEngine {
+            id: PeerId::default(),
+            peers: HashSet::new(),
+            role: Role::Follower { leader_id: None },
+            current_term,
+            voted_for,
+            log,
+            // The entries that were applied before a restart are known to be committed
+            commit_index: last_applied,
+            last_applied,
+            pending: PendingProposal::Idle,
+            forwarded_proposals: VecDeque::new(),
+            applying: None,
+            prepared: None,
+            apply_retry_timeout: None,
+            election_timeout_duration,
+            election_timeout: Timeout::new(election_timeout_duration),
+            heartbeat_timeout: Timeout::new(
+                election_timeout_duration / HEARTBEATS_PER_ELECTION_TIMEOUT,
+            ),
+            forward_timeout: Timeout::new(election_timeout_duration),
+            storage,
+        }
+    }
+
+    /// Write the current term, vote, log, and last applied index to storage. This must be done
+    /// before sending a message that depends on them.
+    fn persist(&mut self) {
+        let mut state = self.storage.write();
+        state.current_term = self.current_term;
+        state.voted_for = self.voted_for.clone().map(Into::into);
+        state.log = self
+            .log
+            .iter()
+            .map(|entry| PersistentLogEntry {
+                term: entry.term,
+                proposal_id: entry.proposal_id.clone().into(),
+                payload: entry.payload.clone(),
+            })
+            .collect();
+        state.last_applied = self.last_applied;
+    }
+
+    fn is_leader(&self) -> bool {
+        match self.role {
+            Role::Leader { .. } => true,
+            _ => false,
+        }
+    }
+
+    /// Returns whether or not the given number of nodes is a majority of the network
+    fn is_majority(&self, count: usize) -> bool {
+        count * 2 > self.peers.len() + 1
+    }
+
+    fn last_log_index(&self) -> u64 {
+        self.log.len() as u64
+    }
+
+    fn last_log_term(&self) -> u64 {
+        self.term_at(self.last_log_index())
+    }
+
+    /// Returns the term of the entry at the given log index, or 0 if there is no such entry.
+    fn term_at(&self, index: u64) -> u64 {
+        if index == 0 {
+            return 0;
+        }
+        self.log
+            .get(index as usize - 1)
+            .map(|entry| entry.term)
+            .unwrap_or(0)
+    }
+
+    fn reset_election_timeout(&mut self) {
+        let base_millis = self.election_timeout_duration.as_millis() as u64;
+        let jitter_millis = rand::thread_rng().gen_range(0, base_millis + 1);
+        self.election_timeout =
+            Timeout::new(self.election_timeout_duration + Duration::from_millis(jitter_millis));
+        self.election_timeout.start();
+    }
+
+    fn new_message(&self, message_type: RaftMessage_Type) -> RaftMessage {
+        let mut msg = RaftMessage::new();
+        msg.set_message_type(message_type);
+        msg.set_term(self.current_term);
+        msg
+    }
+
+    /// Send the message to each peer individually, so a peer that can't be reached doesn't
+    /// prevent the message from being sent to the others.
+    fn send_to_peers(&self, message: Vec<u8>, network_sender: &dyn ConsensusNetworkSender) {
+        for peer_id in &self.peers {
+            if let Err(err) = network_sender.send_to(peer_id, message.clone()) {
+                warn!("Failed to send message to peer {}: {}", peer_id, err);
+            }
+        }
+    }
+
+    fn handle_consensus_msg(
+        &mut self,
+        consensus_msg: ConsensusMessage,
+        network_sender: &dyn ConsensusNetworkSender,
+        proposal_manager: &dyn ProposalManager,
+    ) -> Result<(), ConsensusEngineError> {
+        let mut raft_msg: RaftMessage = protobuf::parse_from_bytes(&consensus_msg.message)?;
+        let origin_id = consensus_msg.origin_id;
+
+        if !self.peers.contains(&origin_id) {
+            warn!("Ignoring raft message from unknown peer {}", origin_id);
+            return Ok(());
+        }
+
+        if raft_msg.get_term() > self.current_term {
+            debug!(
+                "Received term {} from peer {}; updating term and becoming follower",
+                raft_msg.get_term(),
+                origin_id
+            );
+            self.become_follower(raft_msg.get_term(), None, proposal_manager)?;
+            self.persist();
+        }
+
+        let term = raft_msg.get_term();
+
+        match raft_msg.get_message_type() {
+            RaftMessage_Type::REQUEST_VOTE => {
+                self.handle_request_vote(
+                    origin_id,
+                    term,
+                    raft_msg.get_request_vote(),
+                    network_sender,
+                )?;
+            }
+            RaftMessage_Type::REQUEST_VOTE_RESPONSE => {
+                if term == self.current_term
+                    && raft_msg.get_request_vote_response().get_vote_granted()
+                {
+                    self.handle_vote_granted(origin_id, network_sender, proposal_manager)?;
+                }
+            }
+            RaftMessage_Type::APPEND_ENTRIES => {
+                self.handle_append_entries(
+                    origin_id,
+                    term,
+                    raft_msg.take_append_entries(),
+                    network_sender,
+                    proposal_manager,
+                )?;
+            }
+            RaftMessage_Type::APPEND_ENTRIES_RESPONSE => {
+                if term == self.current_term {
+                    self.handle_append_entries_response(
+                        origin_id,
+                        raft_msg.get_append_entries_response(),
+                        network_sender,
+                    )?;
+                }
+            }
+            RaftMessage_Type::FORWARD_PROPOSAL => {
+                let proposal_id =
+                    ProposalId::from(raft_msg.get_forward_proposal().get_proposal_id());
+                if self.is_leader() {
+                    debug!(
+                        "Proposal {} forwarded by peer {}; backlogging",
+                        proposal_id, origin_id
+                    );
+                    if !self.forwarded_proposals.contains(&proposal_id) {
+                        self.forwarded_proposals.push_back(proposal_id);
+                    }
+                } else {
+                    debug!(
+                        "Not leader, ignoring proposal {} forwarded by peer {}",
+                        proposal_id, origin_id
+                    );
+                }
+            }
+            RaftMessage_Type::UNSET_TYPE => warn!(
+                "Ignoring improperly specified raft message from {}",
+                origin_id
+            ),
+        }
+
+        Ok(())
+    }
+
+    fn handle_request_vote(
+        &mut self,
+        candidate_id: PeerId,
+        term: u64,
+        request: &RequestVote,
+        network_sender: &dyn ConsensusNetworkSender,
+    ) -> Result<(), ConsensusEngineError> {
+        let log_up_to_date = request.get_last_log_term() > self.last_log_term()
+            || (request.get_last_log_term() == self.last_log_term()
+                && request.get_last_log_index() >= self.last_log_index());
+        let not_voted = self
+            .voted_for
+            .as_ref()
+            .map(|voted_for| voted_for == &candidate_id)
+            .unwrap_or(true);
+
+        let vote_granted = term == self.current_term && log_up_to_date && not_voted;
+
+        if vote_granted {
+            debug!(
+                "Granting vote to peer {} for term {}",
+                candidate_id, self.current_term
+            );
+            self.voted_for = Some(candidate_id.clone());
+            self.persist();
+            self.reset_election_timeout();
+        } else {
+            debug!(
+                "Denying vote to peer {} for term {}",
+                candidate_id, self.current_term
+            );
+        }
+
+        let mut response = RequestVoteResponse::new();
+        response.set_vote_granted(vote_granted);
+
+        let mut msg = self.new_message(RaftMessage_Type::REQUEST_VOTE_RESPONSE);
+        msg.set_request_vote_response(response);
+
+        network_sender.send_to(&candidate_id, msg.write_to_bytes()?)?;
+
+        Ok(())
+    }
+
+    fn handle_vote_granted(
+        &mut self,
+        voter_id: PeerId,
+        network_sender: &dyn ConsensusNetworkSender,
+        proposal_manager: &dyn ProposalManager,
+    ) -> Result<(), ConsensusEngineError> {
+        let vote_count = match &mut self.role {
+            Role::Candidate { votes } => {
+                debug!("Received vote from peer {}", voter_id);
+                votes.insert(voter_id);
+                votes.len()
+            }
+            _ => return Ok(()),
+        };
+
+        if self.is_majority(vote_count) {
+            self.become_leader(network_sender, proposal_manager)?;
+        }
+
+        Ok(())
+    }
+
+    fn handle_append_entries(
+        &mut self,
+        leader_id: PeerId,
+        term: u64,
+        mut request: AppendEntries,
+        network_sender: &dyn ConsensusNetworkSender,
+        proposal_manager: &dyn ProposalManager,
+    ) -> Result<(), ConsensusEngineError> {
+        if term < self.current_term {
+            debug!(
+                "Rejecting entries from peer {} for stale term {}",
+                leader_id, term
+            );
+            return self.send_append_entries_response(
+                &leader_id,
+                false,
+                self.last_log_index(),
+                network_sender,
+            );
+        }
+
+        if self.is_leader() {
+            error!(
+                "Received entries from peer {}, which is also leader for term {}",
+                leader_id, term
+            );
+            return Ok(());
+        }
+
+        let following_leader = match &self.role {
+            Role::Follower {
+                leader_id: Some(current_leader),
+            } => current_leader == &leader_id,
+            _ => false,
+        };
+        if !following_leader {
+            info!("Following leader {} for term {}", leader_id, term);
+            self.role = Role::Follower {
+                leader_id: Some(leader_id.clone()),
+            };
+        }
+        self.reset_election_timeout();
+
+        let prev_log_index = request.get_prev_log_index();
+        if prev_log_index > self.last_log_index()
+            || self.term_at(prev_log_index) != request.get_prev_log_term()
+        {
+            debug!(
+                "Log does not contain the entry at index {} from peer {}",
+                prev_log_index, leader_id
+            );
+            let hint = std::cmp::min(self.last_log_index(), prev_log_index.saturating_sub(1));
+            return self.send_append_entries_response(&leader_id, false, hint, network_sender);
+        }
+
+        let mut index = prev_log_index;
+        let mut log_changed = false;
+        for mut entry in request.take_entries().into_iter() {
+            index += 1;
+
+            if index <= self.last_log_index() {
+                if self.term_at(index) == entry.get_term() {
+                    continue;
+                }
+                self.truncate_log(index, proposal_manager)?;
+            }
+
+            let proposal_id = ProposalId::from(entry.take_proposal_id());
+            if self.pending == PendingProposal::Forwarded(proposal_id.clone()) {
+                debug!("Forwarded proposal {} added to the log", proposal_id);
+                self.pending = PendingProposal::Idle;
+                self.forward_timeout.stop();
+            }
+
+            self.log.push(LogEntry {
+                term: entry.get_term(),
+                proposal_id,
+                payload: entry.take_payload(),
+            });
+            log_changed = true;
+        }
+
+        if log_changed {
+            self.persist();
+        }
+
+        if request.get_leader_commit() > self.commit_index {
+            self.commit_index = std::cmp::min(request.get_leader_commit(), index);
+        }
+
+        self.send_append_entries_response(&leader_id, true, index, network_sender)
+    }
+
+    fn handle_append_entries_response(
+        &mut self,
+        peer_id: PeerId,
+        response: &AppendEntriesResponse,
+        network_sender: &dyn ConsensusNetworkSender,
+    ) -> Result<(), ConsensusEngineError> {
+        let last_log_index = self.last_log_index();
+
+        let resend = match &mut self.role {
+            Role::Leader {
+                next_index,
+                match_index,
+            } => {
+                if response.get_success() {
+                    let peer_match_index = match_index.entry(peer_id.clone()).or_insert(0);
+                    if response.get_last_log_index() > *peer_match_index {
+                        *peer_match_index = response.get_last_log_index();
+                    }
+                    next_index.insert(peer_id.clone(), *peer_match_index + 1);
+                    false
+                } else {
+                    let peer_next_index = next_index
+                        .entry(peer_id.clone())
+                        .or_insert(last_log_index + 1);
+                    *peer_next_index = std::cmp::max(
+                        std::cmp::min(
+                            peer_next_index.saturating_sub(1),
+                            response.get_last_log_index() + 1,
+                        ),
+                        1,
+                    );
+                    true
+                }
+            }
+            _ => return Ok(()),
+        };
+
+        if resend {
+            self.send_append_entries(&peer_id, network_sender)?;
+        } else {
+            self.advance_commit_index();
+        }
+
+        Ok(())
+    }
+
+    fn send_append_entries_response(
+        &self,
+        leader_id: &PeerId,
+        success: bool,
+        last_log_index: u64,
+        network_sender: &dyn ConsensusNetworkSender,
+    ) -> Result<(), ConsensusEngineError> {
+        let mut response = AppendEntriesResponse::new();
+        response.set_success(success);
+        response.set_last_log_index(last_log_index);
+
+        let mut msg = self.new_message(RaftMessage_Type::APPEND_ENTRIES_RESPONSE);
+        msg.set_append_entries_response(response);
+
+        network_sender.send_to(leader_id, msg.write_to_bytes()?)?;
+
+        Ok(())
+    }
+
+    /// Send the entries the given peer is missing, or an empty heartbeat if it has all of them.
+    fn send_append_entries(
+        &self,
+        peer_id: &PeerId,
+        network_sender: &dyn ConsensusNetworkSender,
+    ) -> Result<(), ConsensusEngineError> {
+        let next_index = match &self.role {
+            Role::Leader { next_index, .. } => next_index
+                .get(peer_id)
+                .copied()
+                .unwrap_or(1)
+                .max(1)
+                .min(self.last_log_index() + 1),
+            _ => return Ok(()),
+        };
+        let prev_log_index = next_index - 1;
+
+        let entries = self.log[prev_log_index as usize..]
+            .iter()
+            .map(|entry| {
+                let mut log_entry = RaftLogEntry::new();
+                log_entry.set_term(entry.term);
+                log_entry.set_proposal_id(entry.proposal_id.clone().into());
+                log_entry.set_payload(entry.payload.clone());
+                log_entry
+            })
+            .collect::<Vec<_>>();
+
+        let mut request = AppendEntries::new();
+        request.set_prev_log_index(prev_log_index);
+        request.set_prev_log_term(self.term_at(prev_log_index));
+        request.set_entries(RepeatedField::from_vec(entries));
+        request.set_leader_commit(self.commit_index);
+
+        let mut msg = self.new_message(RaftMessage_Type::APPEND_ENTRIES);
+        msg.set_append_entries(request);
+
+        network_sender.send_to(peer_id, msg.write_to_bytes()?)?;
+
+        Ok(())
+    }
+
+    fn send_append_entries_to_peers(&self, network_sender: &dyn ConsensusNetworkSender) {
+        for peer_id in &self.peers {
+            if let Err(err) = self.send_append_entries(peer_id, network_sender) {
+                warn!("Failed to send entries to peer {}: {}", peer_id, err);
+            }
+        }
+    }
+
+    /// Remove the entries starting at the given log index, which conflict with the leader's log.
+    fn truncate_log(
+        &mut self,
+        index: u64,
+        proposal_manager: &dyn ProposalManager,
+    ) -> Result<(), ConsensusEngineError> {
+        debug!("Removing conflicting log entries from index {}", index);
+
+        let removed = self.log.split_off(index as usize - 1);
+        if let Some(prepared) = self.prepared.clone() {
+            if removed.iter().any(|entry| entry.proposal_id == prepared) {
+                debug!("Rejecting removed proposal {}", prepared);
+                self.prepared = None;
+                proposal_manager.reject_proposal(&prepared)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Commit the entries of the current term that have been replicated to a majority of nodes.
+    fn advance_commit_index(&mut self) {
+        let commit_index = match &self.role {
+            Role::Leader { match_index, .. } => {
+                let mut commit_index = self.commit_index;
+                for index in (self.commit_index + 1)..=self.last_log_index() {
+                    // Entries from previous terms are only committed indirectly, by committing an
+                    // entry from the current term.
+                    if self.term_at(index) != self.current_term {
+                        continue;
+                    }
+
+                    let replicated = 1 + match_index
+                        .values()
+                        .filter(|peer_match_index| **peer_match_index >= index)
+                        .count();
+                    if self.is_majority(replicated) {
+                        commit_index = index;
+                    }
+                }
+                commit_index
+            }
+            _ => return,
+        };
+
+        if commit_index > self.commit_index {
+            debug!("Committed log entries up to index {}", commit_index);
+            self.commit_index = commit_index;
+        }
+    }
+
+    fn become_follower(
+        &mut self,
+        term: u64,
+        leader_id: Option<PeerId>,
+        proposal_manager: &dyn ProposalManager,
+    ) -> Result<(), ConsensusEngineError> {
+        if term > self.current_term {
+            self.current_term = term;
+            self.voted_for = None;
+        }
+
+        if self.is_leader() {
+            info!("Stepping down as leader in term {}", self.current_term);
+            self.heartbeat_timeout.stop();
+            self.forwarded_proposals.clear();
+
+            if let PendingProposal::Checking(proposal_id) = &self.pending {
+                debug!("Rejecting forwarded proposal {}", proposal_id);
+                proposal_manager.reject_proposal(proposal_id)?;
+                self.pending = PendingProposal::Idle;
+            }
+        }
+
+        self.role = Role::Follower { leader_id };
+        self.reset_election_timeout();
+
+        Ok(())
+    }
+
+    fn start_election(
+        &mut self,
+        network_sender: &dyn ConsensusNetworkSender,
+        proposal_manager: &dyn ProposalManager,
+    ) -> Result<(), ConsensusEngineError> {
+        self.current_term += 1;
+        self.voted_for = Some(self.id.clone());
+
+        info!("Starting election for term {}", self.current_term);
+
+        let mut votes = HashSet::new();
+        votes.insert(self.id.clone());
+        self.role = Role::Candidate { votes };
+        self.persist();
+        self.reset_election_timeout();
+
+        if self.is_majority(1) {
+            return self.become_leader(network_sender, proposal_manager);
+        }
+
+        let mut request = RequestVote::new();
+        request.set_last_log_index(self.last_log_index());
+        request.set_last_log_term(self.last_log_term());
+
+        let mut msg = self.new_message(RaftMessage_Type::REQUEST_VOTE);
+        msg.set_request_vote(request);
+
+        self.send_to_peers(msg.write_to_bytes()?, network_sender);
+
+        Ok(())
+    }
+
+    fn become_leader(
+        &mut self,
+        network_sender: &dyn ConsensusNetworkSender,
+        proposal_manager: &dyn ProposalManager,
+    ) -> Result<(), ConsensusEngineError> {
+        info!("Elected leader for term {}", self.current_term);
+
+        let next_index = self.last_log_index() + 1;
+        self.role = Role::Leader {
+            next_index: self
+                .peers
+                .iter()
+                .map(|peer_id| (peer_id.clone(), next_index))
+                .collect(),
+            match_index: self
+                .peers
+                .iter()
+                .map(|peer_id| (peer_id.clone(), 0))
+                .collect(),
+        };
+        self.election_timeout.stop();
+
+        // A proposal this node forwarded to the previous leader may never have been added to the
+        // log; reject it, since its changes may be out of date.
+        if let PendingProposal::Forwarded(proposal_id) = &self.pending {
+            debug!("Rejecting forwarded proposal {}", proposal_id);
+            proposal_manager.reject_proposal(proposal_id)?;
+            self.pending = PendingProposal::Idle;
+            self.forward_timeout.stop();
+        }
+
+        self.log.push(LogEntry {
+            term: self.current_term,
+            proposal_id: ProposalId::default(),
+            payload: vec![],
+        });
+        self.persist();
+        self.advance_commit_index();
+
+        self.send_append_entries_to_peers(network_sender);
+        self.heartbeat_timeout.start();
+
+        Ok(())
+    }
+
+    /// Append the proposal, which this node has prepared, to the log along with its contents. If
+    /// the contents can't be retrieved from the proposal manager, the proposal is rejected.
+    fn append_proposal(
+        &mut self,
+        proposal_id: ProposalId,
+        network_sender: &dyn ConsensusNetworkSender,
+        proposal_manager: &dyn ProposalManager,
+    ) -> Result<(), ConsensusEngineError> {
+        let payload = match proposal_manager.get_proposal_payload(&proposal_id) {
+            Ok(payload) => payload,
+            Err(err) => {
+                warn!(
+                    "Unable to get the contents of proposal {}; rejecting it: {}",
+                    proposal_id, err
+                );
+                proposal_manager.reject_proposal(&proposal_id)?;
+                return Ok(());
+            }
+        };
+
+        debug!(
+            "Appending proposal {} to the log at index {}",
+            proposal_id,
+            self.last_log_index() + 1
+        );
+
+        self.prepared = Some(proposal_id.clone());
+        self.log.push(LogEntry {
+            term: self.current_term,
+            proposal_id,
+            payload,
+        });
+        self.persist();
+        self.advance_commit_index();
+
+        self.send_append_entries_to_peers(network_sender);
+        self.heartbeat_timeout.start();
+
+        Ok(())
+    }
+
+    fn handle_proposal_update(
+        &mut self,
+        update: ProposalUpdate,
+        network_sender: &dyn ConsensusNetworkSender,
+        proposal_manager: &dyn ProposalManager,
+    ) -> Result<(), ConsensusEngineError> {
+        match update {
+            ProposalUpdate::ProposalCreated(None) => {
+                if let PendingProposal::Creating = self.pending {
+                    self.pending = PendingProposal::Idle;
+                }
+            }
+            ProposalUpdate::ProposalCreated(Some(proposal)) => {
+                debug!("Proposal created: {}", proposal.id);
+                self.pending = PendingProposal::Idle;
+
+                let leader_id = match &self.role {
+                    Role::Follower { leader_id } => leader_id.clone(),
+                    _ => None,
+                };
+
+                if self.is_leader() {
+                    self.append_proposal(proposal.id, network_sender, proposal_manager)?;
+                } else if let Some(leader_id) = leader_id {
+                    debug!(
+                        "Forwarding proposal {} to leader {}",
+                        proposal.id, leader_id
+                    );
+
+                    let mut forward = ForwardProposal::new();
+                    forward.set_proposal_id(proposal.id.clone().into());
+
+                    let mut msg = self.new_message(RaftMessage_Type::FORWARD_PROPOSAL);
+                    msg.set_forward_proposal(forward);
+
+                    network_sender.send_to(&leader_id, msg.write_to_bytes()?)?;
+
+                    self.pending = PendingProposal::Forwarded(proposal.id);
+                    self.forward_timeout.start();
+                } else {
+                    debug!("No leader; rejecting proposal {}", proposal.id);
+                    proposal_manager.reject_proposal(&proposal.id)?;
+                }
+            }
+            ProposalUpdate::ProposalValid(proposal_id) => {
+                if self.applying.as_ref() == Some(&proposal_id) {
+                    debug!("Committed proposal valid: {}", proposal_id);
+                    self.applying = None;
+                    self.prepared = Some(proposal_id);
+                } else if self.pending == PendingProposal::Checking(proposal_id.clone()) {
+                    debug!("Forwarded proposal valid: {}", proposal_id);
+                    self.pending = PendingProposal::Idle;
+                    self.append_proposal(proposal_id, network_sender, proposal_manager)?;
+                } else {
+                    warn!("Got valid message for unknown proposal: {}", proposal_id);
+                }
+            }
+            ProposalUpdate::ProposalInvalid(proposal_id) => {
+                if self.applying.as_ref() == Some(&proposal_id) {
+                    // The entry is committed, so it must not be skipped; it is checked again after
+                    // a back-off, which gives the proposal manager time to bring this node's state
+                    // back in line with the network.
+                    error!(
+                        "Committed proposal {} is invalid; this node's state does not match the \
+                         network, waiting to check it again",
+                        proposal_id
+                    );
+                    self.applying = None;
+                    self.start_apply_retry_timeout();
+                } else if self.pending == PendingProposal::Checking(proposal_id.clone()) {
+                    debug!("Rejecting invalid forwarded proposal {}", proposal_id);
+                    self.pending = PendingProposal::Idle;
+                    proposal_manager.reject_proposal(&proposal_id)?;
+                } else {
+                    warn!("Got invalid message for unknown proposal: {}", proposal_id);
+                }
+            }
+            ProposalUpdate::ProposalAccepted(proposal_id) => {
+                info!("proposal accepted: {}", proposal_id);
+            }
+            ProposalUpdate::ProposalAcceptFailed(proposal_id, err) => {
+                error!(
+                    "failed to accept proposal {} due to error: {}",
+                    proposal_id, err
+                );
+            }
+            other => {
+                debug!("ignoring update: {:?}", other);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Start an election if the leader has not been heard from, send heartbeats if this node is
+    /// the leader, and stop waiting for a forwarded proposal that the leader has not added to the
+    /// log.
+    fn check_timeouts(
+        &mut self,
+        network_sender: &dyn ConsensusNetworkSender,
+        proposal_manager: &dyn ProposalManager,
+    ) -> Result<(), ConsensusEngineError> {
+        if self.is_leader() {
+            if self.heartbeat_timeout.check_expired() {
+                self.send_append_entries_to_peers(network_sender);
+                self.heartbeat_timeout.start();
+            }
+        } else if self.election_timeout.check_expired() {
+            self.start_election(network_sender, proposal_manager)?;
+        }
+
+        if let PendingProposal::Forwarded(proposal_id) = &self.pending {
+            if self.forward_timeout.check_expired() {
+                // The proposal is not rejected, since the leader may still add it to the log;
+                // this node only stops waiting for it.
+                warn!(
+                    "Leader has not added forwarded proposal {} to the log",
+                    proposal_id
+                );
+                self.pending = PendingProposal::Idle;
+                self.forward_timeout.stop();
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Accept the committed entries that have not been applied yet, in log order. Proposals that
+    /// were not created or checked by this node are added to the proposal manager from the log and
+    /// checked before they are accepted.
+    fn apply_committed_entries(
+        &mut self,
+        proposal_manager: &dyn ProposalManager,
+    ) -> Result<(), ConsensusEngineError> {
+        while self.last_applied < self.commit_index {
+            let entry = self.log[self.last_applied as usize].clone();
+
+            if !entry.has_proposal() {
+                self.last_applied += 1;
+                self.persist();
+                continue;
+            }
+
+            if self.prepared.as_ref() == Some(&entry.proposal_id) {
+                debug!("Accepting proposal {}", entry.proposal_id);
+                self.prepared = None;
+                self.last_applied += 1;
+                proposal_manager.accept_proposal(&entry.proposal_id, None)?;
+                self.persist();
+                continue;
+            }
+
+            if self.applying.as_ref() != Some(&entry.proposal_id) {
+                if let Some(timeout) = self.apply_retry_timeout.as_mut() {
+                    if !timeout.check_expired() {
+                        break;
+                    }
+                    self.apply_retry_timeout = None;
+                }
+
+                // The proposal manager may not know the proposal if this node was offline when it
+                // was created
+                if let Err(err) = proposal_manager.add_proposal(&entry.proposal_id, &entry.payload)
+                {
+                    debug!(
+                        "Unable to add committed proposal {}: {}",
+                        entry.proposal_id, err
+                    );
+                    self.start_apply_retry_timeout();
+                    break;
+                }
+
+                match proposal_manager.check_proposal(&entry.proposal_id) {
+                    Ok(()) => {
+                        debug!("Checking committed proposal {}", entry.proposal_id);
+                        // Checking a proposal replaces any changes that were prepared
+                        self.prepared = None;
+                        self.applying = Some(entry.proposal_id);
+                    }
+                    Err(err) => {
+                        debug!(
+                            "Unable to check committed proposal {}: {}",
+                            entry.proposal_id, err
+                        );
+                        self.start_apply_retry_timeout();
+                    }
+                }
+            }
+
+            break;
+        }
+
+        Ok(())
+    }
+
+    /// Wait before checking the next committed proposal again.
+    fn start_apply_retry_timeout(&mut self) {
+        let mut timeout = Timeout::new(self.election_timeout_duration);
+        timeout.start();
+        self.apply_retry_timeout = Some(timeout);
+    }
+
+    /// If not doing anything, try to get the next proposal. The leader checks proposals forwarded
+    /// by followers before asking the proposal manager for a new one; followers ask the proposal
+    /// manager for proposals to forward to the leader.
+    fn get_next_proposal(
+        &mut self,
+        proposal_manager: &dyn ProposalManager,
+    ) -> Result<(), ConsensusEngineError> {
+        if self.pending != PendingProposal::Idle
+            || self.applying.is_some()
+            || self.last_applied < self.commit_index
+        {
+            return Ok(());
+        }
+
+        match &self.role {
+            Role::Leader { .. } => {
+                // Only one proposal is in flight at a time
+                if self.commit_index < self.last_log_index() {
+                    return Ok(());
+                }
+
+                if let Some(proposal_id) = self.forwarded_proposals.pop_front() {
+                    debug!("Checking forwarded proposal {}", proposal_id);
+                    match proposal_manager.check_proposal(&proposal_id) {
+                        Ok(()) => {
+                            self.prepared = None;
+                            self.pending = PendingProposal::Checking(proposal_id);
+                        }
+                        Err(err) => warn!(
+                            "Unable to check forwarded proposal {}: {}",
+                            proposal_id, err
+                        ),
+                    }
+                    return Ok(());
+                }
+            }
+            Role::Follower { leader_id: Some(_) } => {}
+            _ => return Ok(()),
+        }
+
+        match proposal_manager.create_proposal(None, vec![]) {
+            Ok(()) => self.pending = PendingProposal::Creating,
+            Err(err) => error!("Error while creating proposal: {}", err),
+        }
+
+        Ok(())
+    }
+}
+
+impl ConsensusEngine for RaftEngine {
+    fn name(&self) -> &str {
+        "raft"
+    }
+
+    fn version(&self) -> &str {
+        "0.1"
+    }
+
+    fn additional_protocols(&self) -> Vec<(String, String)> {
+        vec![]
+    }
+
+    fn run(
+        &mut self,
+        consensus_messages: Receiver<ConsensusMessage>,
+        proposal_updates: Receiver<ProposalUpdate>,
+        network_sender: Box<dyn ConsensusNetworkSender>,
+        proposal_manager: Box<dyn ProposalManager>,
+        startup_state: StartupState,
+    ) -> Result<(), ConsensusEngineError> {
+        let message_timeout = Duration::from_millis(MESSAGE_RECV_TIMEOUT_MILLIS);
+        let proposal_timeout = Duration::from_millis(PROPOSAL_RECV_TIMEOUT_MILLIS);
+
+        self.id = startup_state.id;
+
+        for id in startup_state.peer_ids {
+            self.peers.insert(id);
+        }
+
+        self.reset_election_timeout();
+
+        loop {
+            if let Err(err) = self.check_timeouts(&*network_sender, &*proposal_manager) {
+                error!("Failed to handle timeouts: {}", err);
+            }
+
+            if let Err(err) = self.apply_committed_entries(&*proposal_manager) {
+                error!("Failed to apply committed entries: {}", err);
+            }
+
+            if let Err(err) = self.get_next_proposal(&*proposal_manager) {
+                error!("Failed to get next proposal: {}", err);
+            }
+
+            // Get and handle a consensus message if there is one
+            match consensus_messages.recv_timeout(message_timeout) {
+                Ok(consensus_message) => {
+                    if let Err(err) = self.handle_consensus_msg(
+                        consensus_message,
+                        &*network_sender,
+                        &*proposal_manager,
+                    ) {
+                        error!("error while handling consensus message: {}", err);
+                    }
+                }
+                Err(RecvTimeoutError::Timeout) => {}
+                Err(RecvTimeoutError::Disconnected) => {
+                    info!("consensus message receiver disconnected");
+                    break;
+                }
+            }
+
+            // Get and handle a proposal update if there is one
+            match proposal_updates.recv_timeout(proposal_timeout) {
+                Ok(ProposalUpdate::Shutdown) => {
+                    info!("received shutdown");
+                    break;
+                }
+                Ok(update) => {
+                    if let Err(err) =
+                        self.handle_proposal_update(update, &*network_sender, &*proposal_manager)
+                    {
+                        error!("error while handling proposal update: {}", err);
+                    }
+                }
+                Err(RecvTimeoutError::Timeout) => {}
+                Err(RecvTimeoutError::Disconnected) => {
+                    info!("proposal update receiver disconnected");
+                    break;
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    use std::sync::mpsc::channel;
+
+    use tempdir::TempDir;
+
+    use crate::consensus::tests::{
+        proposal_payload, MockConsensusNetworkSender, MockProposalManager,
+    };
+    use crate::storage::{MemStorage, YamlStorage};
+
+    const SHORT_ELECTION_TIMEOUT_MILLIS: u64 = 100;
+    const ELECTION_TIMEOUT_MILLIS: u64 = 500;
+    const LONG_ELECTION_TIMEOUT_MILLIS: u64 = 60000;
+
+    /// Verify that the engine properly shuts down when it receives the Shutdown update.
+    #[test]
+    fn test_shutdown() {
+        let (update_tx, update_rx) = channel();
+        let (_, consensus_msg_rx) = channel();
+
+        let manager = MockProposalManager::new(update_tx.clone());
+        let network = MockConsensusNetworkSender::new();
+        let startup_state = StartupState {
+            id: vec![0].into(),
+            peer_ids: vec![vec![1].into()],
+            last_proposal: None,
+        };
+
+        let mut engine = RaftEngine::new(
+            Duration::from_millis(LONG_ELECTION_TIMEOUT_MILLIS),
+            memory_storage(),
+        );
+        let thread = std::thread::spawn(move || {
+            engine
+                .run(
+                    consensus_msg_rx,
+                    update_rx,
+                    Box::new(network),
+                    Box::new(manager),
+                    startup_state,
+                )
+                .expect("engine failed")
+        });
+
+        update_tx
+            .send(ProposalUpdate::Shutdown)
+            .expect("failed to send shutdown");
+        thread.join().expect("failed to join engine thread");
+    }
+
+    /// Verify that a node without peers elects itself leader and commits its own proposals.
+    #[test]
+    fn test_single_node() {
+        let (update_tx, update_rx) = channel();
+        let (_consensus_msg_tx, consensus_msg_rx) = channel();
+
+        let manager = MockProposalManager::new(update_tx.clone());
+        let network = MockConsensusNetworkSender::new();
+        let startup_state = StartupState {
+            id: vec![0].into(),
+            peer_ids: vec![],
+            last_proposal: None,
+        };
+
+        let mut engine = RaftEngine::new(
+            Duration::from_millis(SHORT_ELECTION_TIMEOUT_MILLIS),
+            memory_storage(),
+        );
+        let manager_clone = manager.clone();
+        let thread = std::thread::spawn(move || {
+            engine
+                .run(
+                    consensus_msg_rx,
+                    update_rx,
+                    Box::new(network),
+                    Box::new(manager_clone),
+                    startup_state,
+                )
+                .expect("engine failed")
+        });
+
+        // Verify that the first two proposals are accepted in order
+        loop {
+            let accepted = manager.accepted_proposals();
+            if accepted.len() >= 2 {
+                assert_eq!(accepted[0].0, vec![1].into());
+                assert_eq!(accepted[1].0, vec![2].into());
+                break;
+            }
+        }
+
+        update_tx
+            .send(ProposalUpdate::Shutdown)
+            .expect("failed to send shutdown");
+        thread.join().expect("failed to join engine thread");
+    }
+
+    /// Test the leader of a 3 node network where one of the other nodes is offline. The node
+    /// starts an election, is elected with the vote of the online node, and commits a proposal
+    /// once the online node has replicated it, without hearing from the offline node.
+    #[test]
+    fn test_leader_commits_with_majority() {
+        let (update_tx, update_rx) = channel();
+        let (consensus_msg_tx, consensus_msg_rx) = channel();
+
+        let manager = MockProposalManager::new(update_tx.clone());
+        let network = MockConsensusNetworkSender::new();
+        let startup_state = StartupState {
+            id: vec![0].into(),
+            peer_ids: vec![vec![1].into(), vec![2].into()],
+            last_proposal: None,
+        };
+
+        let mut engine = RaftEngine::new(
+            Duration::from_millis(ELECTION_TIMEOUT_MILLIS),
+            memory_storage(),
+        );
+        let network_clone = network.clone();
+        let manager_clone = manager.clone();
+        let thread = std::thread::spawn(move || {
+            engine
+                .run(
+                    consensus_msg_rx,
+                    update_rx,
+                    Box::new(network_clone),
+                    Box::new(manager_clone),
+                    startup_state,
+                )
+                .expect("engine failed")
+        });
+
+        // Check that a vote is requested from the online node
+        let term = loop {
+            let request = sent_message_to(&network, &vec![1].into(), |msg| {
+                msg.get_message_type() == RaftMessage_Type::REQUEST_VOTE
+            });
+            if let Some(request) = request {
+                assert_eq!(request.get_request_vote().get_last_log_index(), 0);
+                break request.get_term();
+            }
+        };
+
+        // Grant the vote
+        let mut response = RequestVoteResponse::new();
+        response.set_vote_granted(true);
+        let mut msg = RaftMessage::new();
+        msg.set_message_type(RaftMessage_Type::REQUEST_VOTE_RESPONSE);
+        msg.set_term(term);
+        msg.set_request_vote_response(response);
+        consensus_msg_tx
+            .send(ConsensusMessage::new(
+                msg.write_to_bytes().expect("failed to write vote"),
+                vec![1].into(),
+            ))
+            .expect("failed to send vote");
+
+        // Check that the new leader sends its first entry to the online node, and acknowledge it
+        loop {
+            let request = sent_message_to(&network, &vec![1].into(), |msg| {
+                msg.get_message_type() == RaftMessage_Type::APPEND_ENTRIES
+            });
+            if let Some(request) = request {
+                assert_eq!(request.get_term(), term);
+                let entries = request.get_append_entries().get_entries();
+                assert_eq!(entries.len(), 1);
+                assert!(entries[0].get_proposal_id().is_empty());
+                break;
+            }
+        }
+        consensus_msg_tx
+            .send(append_entries_response_msg(term, 1))
+            .expect("failed to send response");
+
+        // Check that the leader sends the proposal it created to the online node
+        loop {
+            let request = sent_message_to(&network, &vec![1].into(), |msg| {
+                msg.get_message_type() == RaftMessage_Type::APPEND_ENTRIES
+                    && msg.get_append_entries().get_prev_log_index() == 1
+                    && !msg.get_append_entries().get_entries().is_empty()
+            });
+            if let Some(request) = request {
+                let entries = request.get_append_entries().get_entries();
+                assert_eq!(entries.len(), 1);
+                assert_eq!(entries[0].get_proposal_id(), vec![1].as_slice());
+                assert_eq!(
+                    entries[0].get_payload(),
+                    proposal_payload(&vec![1].into()).as_slice()
+                );
+                break;
+            }
+        }
+        assert!(manager.accepted_proposals().is_empty());
+
+        // Acknowledge the proposal from the online node only
+        consensus_msg_tx
+            .send(append_entries_response_msg(term, 2))
+            .expect("failed to send response");
+
+        // Verify the proposal was accepted
+        loop {
+            if let Some((id, _)) = manager.accepted_proposals().get(0) {
+                assert_eq!(id, &vec![1].into());
+                break;
+            }
+        }
+
+        update_tx
+            .send(ProposalUpdate::Shutdown)
+            .expect("failed to send shutdown");
+        thread.join().expect("failed to join engine thread");
+    }
+
+    /// Test a follower by replicating a proposal from the leader, then committing it. The
+    /// follower must acknowledge the entries, and check and accept the proposal only once the
+    /// leader has committed it.
+    #[test]
+    fn test_follower() {
+        let (update_tx, update_rx) = channel();
+        let (consensus_msg_tx, consensus_msg_rx) = channel();
+
+        let manager = MockProposalManager::new(update_tx.clone());
+        manager.set_return_proposal(false);
+        let network = MockConsensusNetworkSender::new();
+        let startup_state = StartupState {
+            id: vec![1].into(),
+            peer_ids: vec![vec![0].into(), vec![2].into()],
+            last_proposal: None,
+        };
+
+        let mut engine = RaftEngine::new(
+            Duration::from_millis(LONG_ELECTION_TIMEOUT_MILLIS),
+            memory_storage(),
+        );
+        let network_clone = network.clone();
+        let manager_clone = manager.clone();
+        let thread = std::thread::spawn(move || {
+            engine
+                .run(
+                    consensus_msg_rx,
+                    update_rx,
+                    Box::new(network_clone),
+                    Box::new(manager_clone),
+                    startup_state,
+                )
+                .expect("engine failed")
+        });
+
+        // Replicate the entries from the leader without committing them
+        consensus_msg_tx
+            .send(append_entries_msg(1, 0, 0, vec![vec![], vec![5]], 0))
+            .expect("failed to send entries");
+
+        loop {
+            let response = sent_message_to(&network, &vec![0].into(), |msg| {
+                msg.get_message_type() == RaftMessage_Type::APPEND_ENTRIES_RESPONSE
+            });
+            if let Some(response) = response {
+                assert!(response.get_append_entries_response().get_success());
+                assert_eq!(
+                    response.get_append_entries_response().get_last_log_index(),
+                    2
+                );
+                break;
+            }
+        }
+        assert!(manager.accepted_proposals().is_empty());
+
+        // Commit the entries
+        consensus_msg_tx
+            .send(append_entries_msg(1, 2, 1, vec![], 2))
+            .expect("failed to send heartbeat");
+
+        // Verify the proposal was accepted
+        loop {
+            if let Some((id, _)) = manager.accepted_proposals().get(0) {
+                assert_eq!(id, &vec![5].into());
+                break;
+            }
+        }
+
+        // Entries that don't match the log are rejected
+        consensus_msg_tx
+            .send(append_entries_msg(1, 4, 1, vec![vec![6]], 2))
+            .expect("failed to send entries");
+
+        loop {
+            let response = sent_message_to(&network, &vec![0].into(), |msg| {
+                msg.get_message_type() == RaftMessage_Type::APPEND_ENTRIES_RESPONSE
+                    && !msg.get_append_entries_response().get_success()
+            });
+            if let Some(response) = response {
+                assert_eq!(
+                    response.get_append_entries_response().get_last_log_index(),
+                    2
+                );
+                break;
+            }
+        }
+
+        update_tx
+            .send(ProposalUpdate::Shutdown)
+            .expect("failed to send shutdown");
+        thread.join().expect("failed to join engine thread");
+    }
+
+    /// Verify that a follower that was offline while proposals were committed catches up from the
+    /// leader's log when it restarts, although its proposal manager never received the proposals.
+    ///
+    /// 1. Start a follower with YAML storage, and have it accept a proposal committed by the leader
+    /// 2. Shut down the follower
+    /// 3. Restart the follower with the same storage and a proposal manager that only knows the
+    ///    proposals that are added from the log
+    /// 4. Send the entries that were committed while the follower was offline, and verify that it
+    ///    adds each proposal with its contents from the log and accepts them in order, without
+    ///    accepting the first proposal again
+    #[test]
+    fn test_follower_catches_up_after_restart() {
+        let temp_dir = TempDir::new("test_follower_catches_up_after_restart").expect("no temp dir");
+        let path = temp_dir
+            .path()
+            .join("raft.yaml")
+            .to_str()
+            .expect("invalid path")
+            .to_string();
+
+        let (update_tx, update_rx) = channel();
+        let (consensus_msg_tx, consensus_msg_rx) = channel();
+
+        let manager = MockProposalManager::new(update_tx.clone());
+        manager.set_return_proposal(false);
+        let mut engine = RaftEngine::new(
+            Duration::from_millis(LONG_ELECTION_TIMEOUT_MILLIS),
+            yaml_storage(&path),
+        );
+        let manager_clone = manager.clone();
+        let thread = std::thread::spawn(move || {
+            engine
+                .run(
+                    consensus_msg_rx,
+                    update_rx,
+                    Box::new(MockConsensusNetworkSender::new()),
+                    Box::new(manager_clone),
+                    follower_startup_state(),
+                )
+                .expect("engine failed")
+        });
+
+        consensus_msg_tx
+            .send(append_entries_msg(1, 0, 0, vec![vec![], vec![5]], 2))
+            .expect("failed to send entries");
+        loop {
+            if let Some((id, _)) = manager.accepted_proposals().get(0) {
+                assert_eq!(id, &vec![5].into());
+                break;
+            }
+        }
+
+        update_tx
+            .send(ProposalUpdate::Shutdown)
+            .expect("failed to send shutdown");
+        thread.join().expect("failed to join engine thread");
+
+        let (update_tx, update_rx) = channel();
+        let (consensus_msg_tx, consensus_msg_rx) = channel();
+
+        let manager = MockProposalManager::new(update_tx.clone());
+        manager.set_return_proposal(false);
+        manager.set_require_added_proposals(true);
+        let mut engine = RaftEngine::new(
+            Duration::from_millis(LONG_ELECTION_TIMEOUT_MILLIS),
+            yaml_storage(&path),
+        );
+        let manager_clone = manager.clone();
+        let thread = std::thread::spawn(move || {
+            engine
+                .run(
+                    consensus_msg_rx,
+                    update_rx,
+                    Box::new(MockConsensusNetworkSender::new()),
+                    Box::new(manager_clone),
+                    follower_startup_state(),
+                )
+                .expect("engine failed")
+        });
+
+        consensus_msg_tx
+            .send(append_entries_msg(1, 2, 1, vec![vec![6], vec![7]], 4))
+            .expect("failed to send entries");
+        loop {
+            if manager.accepted_proposals().len() == 2 {
+                break;
+            }
+        }
+
+        assert_eq!(
+            manager
+                .accepted_proposals()
+                .iter()
+                .map(|(id, _)| id.clone())
+                .collect::<Vec<_>>(),
+            vec![vec![6].into(), vec![7].into()]
+        );
+        assert_eq!(
+            *manager.added_proposals(),
+            vec![
+                (vec![6].into(), proposal_payload(&vec![6].into())),
+                (vec![7].into(), proposal_payload(&vec![7].into())),
+            ]
+        );
+
+        update_tx
+            .send(ProposalUpdate::Shutdown)
+            .expect("failed to send shutdown");
+        thread.join().expect("failed to join engine thread");
+    }
+
+    /// Verify that a committed proposal that is invalid is not skipped. The follower must neither
+    /// accept nor reject the proposal while it is invalid, and must accept it once it is valid.
+    #[test]
+    fn test_invalid_committed_proposal_not_skipped() {
+        let (update_tx, update_rx) = channel();
+        let (consensus_msg_tx, consensus_msg_rx) = channel();
+
+        let manager = MockProposalManager::new(update_tx.clone());
+        manager.set_return_proposal(false);
+        manager.set_next_proposal_valid(false);
+        let network = MockConsensusNetworkSender::new();
+        let startup_state = StartupState {
+            id: vec![1].into(),
+            peer_ids: vec![vec![0].into(), vec![2].into()],
+            last_proposal: None,
+        };
+
+        let mut engine = RaftEngine::new(
+            Duration::from_millis(ELECTION_TIMEOUT_MILLIS),
+            memory_storage(),
+        );
+        let manager_clone = manager.clone();
+        let thread = std::thread::spawn(move || {
+            engine
+                .run(
+                    consensus_msg_rx,
+                    update_rx,
+                    Box::new(network),
+                    Box::new(manager_clone),
+                    startup_state,
+                )
+                .expect("engine failed")
+        });
+
+        // Replicate and commit the entries
+        consensus_msg_tx
+            .send(append_entries_msg(1, 0, 0, vec![vec![], vec![5]], 2))
+            .expect("failed to send entries");
+
+        // The invalid proposal is neither accepted nor rejected
+        std::thread::sleep(Duration::from_millis(2 * ELECTION_TIMEOUT_MILLIS));
+        assert!(manager.accepted_proposals().is_empty());
+        assert!(manager.rejected_proposals().is_empty());
+
+        // Once the proposal is valid, it is accepted
+        manager.set_next_proposal_valid(true);
+        loop {
+            if let Some((id, _)) = manager.accepted_proposals().get(0) {
+                assert_eq!(id, &vec![5].into());
+                break;
+            }
+        }
+
+        update_tx
+            .send(ProposalUpdate::Shutdown)
+            .expect("failed to send shutdown");
+        thread.join().expect("failed to join engine thread");
+    }
+
+    /// Verify that a node grants its vote to only one candidate per term, and only to a candidate
+    /// whose log is at least as up-to-date as its own.
+    #[test]
+    fn test_request_vote() {
+        let (update_tx, _update_rx) = channel();
+
+        let manager = MockProposalManager::new(update_tx);
+        let network = MockConsensusNetworkSender::new();
+
+        let mut engine = RaftEngine::new(
+            Duration::from_millis(LONG_ELECTION_TIMEOUT_MILLIS),
+            memory_storage(),
+        );
+        engine.id = vec![0].into();
+        engine.peers.insert(vec![1].into());
+        engine.peers.insert(vec![2].into());
+        engine.log.push(LogEntry {
+            term: 1,
+            proposal_id: vec![1].into(),
+            payload: vec![],
+        });
+        engine.current_term = 1;
+
+        // Candidate 1's log is behind
+        engine
+            .handle_consensus_msg(request_vote_msg(vec![1], 2, 0, 0), &network, &manager)
+            .expect("failed to handle vote request");
+        // Candidate 2's log is up-to-date
+        engine
+            .handle_consensus_msg(request_vote_msg(vec![2], 2, 1, 1), &network, &manager)
+            .expect("failed to handle vote request");
+        // Candidate 1 can't get the vote, since it was already granted to candidate 2
+        engine
+            .handle_consensus_msg(request_vote_msg(vec![1], 2, 1, 1), &network, &manager)
+            .expect("failed to handle vote request");
+
+        let votes = network
+            .sent_messages()
+            .iter()
+            .map(|(msg, peer_id)| {
+                let msg: RaftMessage =
+                    protobuf::parse_from_bytes(msg).expect("failed to parse message");
+                (
+                    peer_id.clone(),
+                    msg.get_request_vote_response().get_vote_granted(),
+                )
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            votes,
+            vec![
+                (vec![1].into(), false),
+                (vec![2].into(), true),
+                (vec![1].into(), false),
+            ]
+        );
+        assert_eq!(engine.current_term, 2);
+        assert_eq!(engine.voted_for, Some(vec![2].into()));
+    }
+
+    /// Verify that a node writes its term, vote and log to storage before responding, and that a
+    /// node that restarts with the same storage does not contradict them.
+    ///
+    /// 1. Create a node with YAML storage, grant a vote to candidate 1 for term 2, and append an
+    ///    entry from candidate 1 as leader
+    /// 2. Verify that the vote and the entry were written to storage before the responses
+    /// 3. Restart the node with the same storage and verify that it restored its term, vote and log
+    /// 4. Verify that the restarted node denies its vote to candidate 2 for the same term
+    #[test]
+    fn test_persist_before_responding() {
+        let temp_dir = TempDir::new("test_persist_before_responding").expect("no temp dir");
+        let path = temp_dir
+            .path()
+            .join("raft.yaml")
+            .to_str()
+            .expect("invalid path")
+            .to_string();
+
+        let (update_tx, _update_rx) = channel();
+        let manager = MockProposalManager::new(update_tx);
+        let network = MockConsensusNetworkSender::new();
+
+        let mut engine = RaftEngine::new(
+            Duration::from_millis(LONG_ELECTION_TIMEOUT_MILLIS),
+            yaml_storage(&path),
+        );
+        engine.id = vec![0].into();
+        engine.peers.insert(vec![1].into());
+        engine.peers.insert(vec![2].into());
+
+        engine
+            .handle_consensus_msg(request_vote_msg(vec![1], 2, 0, 0), &network, &manager)
+            .expect("failed to handle vote request");
+        let mut append_msg = append_entries_msg(2, 0, 0, vec![vec![1]], 0);
+        append_msg.origin_id = vec![1].into();
+        engine
+            .handle_consensus_msg(append_msg, &network, &manager)
+            .expect("failed to handle entries");
+        assert_eq!(network.sent_messages().len(), 2);
+
+        {
+            let state = yaml_storage(&path);
+            let state = state.read();
+            assert_eq!(state.current_term, 2);
+            assert_eq!(state.voted_for, Some(vec![1]));
+            assert_eq!(
+                state.log,
+                vec![PersistentLogEntry {
+                    term: 2,
+                    proposal_id: vec![1],
+                    payload: proposal_payload(&vec![1].into()),
+                }]
+            );
+        }
+        drop(engine);
+
+        let mut engine = RaftEngine::new(
+            Duration::from_millis(LONG_ELECTION_TIMEOUT_MILLIS),
+            yaml_storage(&path),
+        );
+        engine.id = vec![0].into();
+        engine.peers.insert(vec![1].into());
+        engine.peers.insert(vec![2].into());
+        assert_eq!(engine.current_term, 2);
+        assert_eq!(engine.voted_for, Some(vec![1].into()));
+        assert_eq!(engine.last_log_index(), 1);
+        assert_eq!(engine.last_log_term(), 2);
+
+        engine
+            .handle_consensus_msg(request_vote_msg(vec![2], 2, 1, 2), &network, &manager)
+            .expect("failed to handle vote request");
+        let vote = sent_message_to(&network, &vec![2].into(), |msg| {
+            msg.get_message_type() == RaftMessage_Type::REQUEST_VOTE_RESPONSE
+        })
+        .expect("no vote response sent");
+        assert!(!vote.get_request_vote_response().get_vote_granted());
+    }
+
+    /// The startup state of node 1, which follows node 0
+    fn follower_startup_state() -> StartupState {
+        StartupState {
+            id: vec![1].into(),
+            peer_ids: vec![vec![0].into(), vec![2].into()],
+            last_proposal: None,
+        }
+    }
+
+    fn memory_storage() -> Box<dyn Storage<S = RaftPersistentState> + Send> {
+        Box::new(MemStorage::new(RaftPersistentState::default).expect("failed to create storage"))
+    }
+
+    fn yaml_storage(path: &str) -> Box<dyn Storage<S = RaftPersistentState> + Send> {
+        Box::new(
+            YamlStorage::new(path, RaftPersistentState::default).expect("failed to create storage"),
+        )
+    }
+
+    /// Returns the last message sent to the given peer that matches the predicate.
+    fn sent_message_to<F>(
+        network: &MockConsensusNetworkSender,
+        peer_id: &PeerId,
+        predicate: F,
+    ) -> Option<RaftMessage>
+    where
+        F: Fn(&RaftMessage) -> bool,
+    {
+        network
+            .sent_messages()
+            .iter()
+            .filter(|(_, recipient)| recipient == peer_id)
+            .map(|(msg, _)| protobuf::parse_from_bytes(msg).expect("failed to parse message"))
+            .filter(|msg| predicate(msg))
+            .last()
+    }
+
+    fn append_entries_msg(
+        term: u64,
+        prev_log_index: u64,
+        prev_log_term: u64,
+        proposal_ids: Vec<Vec<u8>>,
+        leader_commit: u64,
+    ) -> ConsensusMessage {
+        let entries = proposal_ids
+            .into_iter()
+            .map(|proposal_id| {
+                let mut entry = RaftLogEntry::new();
+                entry.set_term(term);
+                if !proposal_id.is_empty() {
+                    entry.set_payload(proposal_payload(&proposal_id.clone().into()));
+                }
+                entry.set_proposal_id(proposal_id);
+                entry
+            })
+            .collect();
+
+        let mut request = AppendEntries::new();
+        request.set_prev_log_index(prev_log_index);
+        request.set_prev_log_term(prev_log_term);
+        request.set_entries(RepeatedField::from_vec(entries));
+        request.set_leader_commit(leader_commit);
+
+        let mut msg = RaftMessage::new();
+        msg.set_message_type(RaftMessage_Type::APPEND_ENTRIES);
+        msg.set_term(term);
+        msg.set_append_entries(request);
+
+        ConsensusMessage::new(
+            msg.write_to_bytes().expect("failed to write entries"),
+            vec![0].into(),
+        )
+    }
+
+    fn append_entries_response_msg(term: u64, last_log_index: u64) -> ConsensusMessage {
+        let mut response = AppendEntriesResponse::new();
+        response.set_success(true);
+        response.set_last_log_index(last_log_index);
+
+        let mut msg = RaftMessage::new();
+        msg.set_message_type(RaftMessage_Type::APPEND_ENTRIES_RESPONSE);
+        msg.set_term(term);
+        msg.set_append_entries_response(response);
+
+        ConsensusMessage::new(
+            msg.write_to_bytes().expect("failed to write response"),
+            vec![1].into(),
+        )
+    }
+
+    fn request_vote_msg(
+        candidate_id: Vec<u8>,
+        term: u64,
+        last_log_index: u64,
+        last_log_term: u64,
+    ) -> ConsensusMessage {
+        let mut request = RequestVote::new();
+        request.set_last_log_index(last_log_index);
+        request.set_last_log_term(last_log_term);
+
+        let mut msg = RaftMessage::new();
+        msg.set_message_type(RaftMessage_Type::REQUEST_VOTE);
+        msg.set_term(term);
+        msg.set_request_vote(request);
+
+        ConsensusMessage::new(
+            msg.write_to_bytes().expect("failed to write vote request"),
+            candidate_id.into(),
+        )
+    }
+}
@@ -28,6 +28,7 @@ mod error;
 pub mod insecure;
 #[cfg(feature = "rest-api")]
 pub mod rest_api;
+pub mod roles;
 pub mod storage;
 
 use std::collections::HashMap;
@@ -19,7 +19,7 @@ use std::fmt::Write;
 
 use serde::Serializer;
 
-use crate::actix_web::{error::BlockingError, web, HttpResponse};
+use crate::actix_web::{error::BlockingError, web, HttpRequest, HttpResponse};
 use crate::futures::{future::IntoFuture, Future};
 use crate::protocol;
 use crate::rest_api::{
@@ -27,6 +27,7 @@ use crate::rest_api::{
     Method, ProtocolVersionRangeGuard, Resource, RestResourceProvider,
 };
 
+use super::roles::{RoleBasedKeyPermissionManager, PROPOSER_ROLE, VOTER_ROLE};
 use super::{KeyInfo, KeyRegistry, KeyRegistryError};
 
 #[derive(Debug, Serialize, Clone, PartialEq)]
@@ -77,6 +78,49 @@ impl RestResourceProvider for KeyRegistryManager {
     }
 }
 
+#[derive(Debug, Serialize, Clone, PartialEq)]
+struct KeyRolesResponse {
+    #[serde(serialize_with = "as_hex")]
+    public_key: Vec<u8>,
+    roles: Vec<String>,
+}
+
+/// Provides the REST resources for listing, granting and revoking the roles of public keys.
+///
+/// The requests that grant and revoke roles are not authenticated, so those resources are only
+/// provided if role changes have been explicitly allowed; otherwise roles may only be changed by
+/// editing the role file.
+pub struct KeyRolesManager {
+    permission_manager: RoleBasedKeyPermissionManager,
+    allow_role_changes: bool,
+}
+
+impl KeyRolesManager {
+    pub fn new(
+        permission_manager: RoleBasedKeyPermissionManager,
+        allow_role_changes: bool,
+    ) -> Self {
+        Self {
+            permission_manager,
+            allow_role_changes,
+        }
+    }
+}
+
+impl RestResourceProvider for KeyRolesManager {
+    fn resources(&self) -> Vec<Resource> {
+        let mut resources = vec![make_list_key_roles_resource(
+            self.permission_manager.clone(),
+        )];
+
+        if self.allow_role_changes {
+            resources.push(make_key_role_resource(self.permission_manager.clone()));
+        }
+
+        resources
+    }
+}
+
 fn make_fetch_key_resource(key_registry: Box<dyn KeyRegistry>) -> Resource {
     Resource::build("/admin/keys/{public_key}")
         .add_request_guard(ProtocolVersionRangeGuard::new(
@@ -204,6 +248,123 @@ fn make_list_key_resources(key_registry: Box<dyn KeyRegistry>) -> Resource {
         })
 }
 
+fn make_list_key_roles_resource(permission_manager: RoleBasedKeyPermissionManager) -> Resource {
+    Resource::build("/admin/keys/{public_key}/roles")
+        .add_request_guard(ProtocolVersionRangeGuard::new(
+            protocol::ADMIN_LIST_KEY_ROLES_MIN,
+            protocol::ADMIN_PROTOCOL_VERSION,
+        ))
+        .add_method(Method::Get, move |req, _| {
+            let public_key = match parse_hex(req.match_info().get("public_key").unwrap_or("")) {
+                Ok(public_key) => public_key,
+                Err(err_msg) => {
+                    return Box::new(
+                        HttpResponse::BadRequest()
+                            .json(json!({ "message": err_msg }))
+                            .into_future(),
+                    )
+                }
+            };
+
+            let manager = permission_manager.clone();
+            Box::new(
+                web::block(move || {
+                    manager
+                        .roles(&public_key)
+                        .map(|roles| KeyRolesResponse { public_key, roles })
+                        .map_err(|err| err.to_string())
+                })
+                .then(|res| match res {
+                    Ok(key_roles) => Ok(HttpResponse::Ok().json(json!({ "data": key_roles }))),
+                    Err(err) => {
+                        error!("Unable to read key roles: {}", err);
+                        Ok(HttpResponse::InternalServerError().into())
+                    }
+                }),
+            )
+        })
+}
+
+fn make_key_role_resource(permission_manager: RoleBasedKeyPermissionManager) -> Resource {
+    let permission_manager1 = permission_manager.clone();
+    Resource::build("/admin/keys/{public_key}/roles/{role}")
+        .add_request_guard(ProtocolVersionRangeGuard::new(
+            protocol::ADMIN_MODIFY_KEY_ROLE_MIN,
+            protocol::ADMIN_PROTOCOL_VERSION,
+        ))
+        .add_method(Method::Put, move |req, _| {
+            let (public_key, role) = match parse_key_role_path(&req) {
+                Ok(key_role) => key_role,
+                Err(err_msg) => {
+                    return Box::new(
+                        HttpResponse::BadRequest()
+                            .json(json!({ "message": err_msg }))
+                            .into_future(),
+                    )
+                }
+            };
+
+            let manager = permission_manager.clone();
+            Box::new(
+                web::block(move || {
+                    manager
+                        .grant_role(&public_key, &role)
+                        .map_err(|err| err.to_string())
+                })
+                .then(|res| match res {
+                    Ok(_) => Ok(HttpResponse::Ok().finish()),
+                    Err(err) => {
+                        error!("Unable to grant key role: {}", err);
+                        Ok(HttpResponse::InternalServerError().into())
+                    }
+                }),
+            )
+        })
+        .add_method(Method::Delete, move |req, _| {
+            let (public_key, role) = match parse_key_role_path(&req) {
+                Ok(key_role) => key_role,
+                Err(err_msg) => {
+                    return Box::new(
+                        HttpResponse::BadRequest()
+                            .json(json!({ "message": err_msg }))
+                            .into_future(),
+                    )
+                }
+            };
+
+            let manager = permission_manager1.clone();
+            Box::new(
+                web::block(move || {
+                    manager
+                        .revoke_role(&public_key, &role)
+                        .map_err(|err| err.to_string())
+                })
+                .then(|res| match res {
+                    Ok(true) => Ok(HttpResponse::Ok().finish()),
+                    Ok(false) => Ok(HttpResponse::NotFound()
+                        .json(json!({ "message": "key has not been granted role" }))),
+                    Err(err) => {
+                        error!("Unable to revoke key role: {}", err);
+                        Ok(HttpResponse::InternalServerError().into())
+                    }
+                }),
+            )
+        })
+}
+
+fn parse_key_role_path(req: &HttpRequest) -> Result<(Vec<u8>, String), String> {
+    let public_key = parse_hex(req.match_info().get("public_key").unwrap_or(""))?;
+    let role = req.match_info().get("role").unwrap_or("").to_string();
+    if role != PROPOSER_ROLE && role != VOTER_ROLE {
+        return Err(format!(
+            "{} is not a valid role; must be {} or {}",
+            role, PROPOSER_ROLE, VOTER_ROLE
+        ));
+    }
+
+    Ok((public_key, role))
+}
+
 fn as_hex<S>(data: &[u8], serializer: S) -> Result<S::Ok, S::Error>
 where
     S: Serializer,
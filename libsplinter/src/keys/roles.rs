@@ -0,0 +1,308 @@
+// Copyright 2018-2020 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Provides a role-based KeyPermissionManager, backed by a role file.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::{Arc, RwLock};
+
+use serde_derive::{Deserialize, Serialize};
+
+use crate::hex::to_hex;
+use crate::storage::get_storage;
+
+use super::{KeyPermissionError, KeyPermissionManager};
+
+/// The role that permits a public key to propose circuits and circuit changes.
+pub const PROPOSER_ROLE: &str = "proposer";
+/// The role that permits a public key to vote on circuit proposals.
+pub const VOTER_ROLE: &str = "voter";
+
+/// A KeyPermissionManager that permits a public key only for the roles it has been granted.
+///
+/// Roles are stored in a role file, which maps hex-encoded public keys to the list of roles
+/// granted to that key.  For example:
+///
+/// ```yaml
+/// 0283a14e0a17cb7f665311e9b5560f4cde2b502f17e2d03223e15d90d9318d7482:
+///   - proposer
+///   - voter
+/// ```
+///
+/// The role file is backed by the `storage` module, and therefore supports the same formats
+/// available.  Clones of this manager share the same underlying roles, so roles granted or
+/// revoked via one instance are immediately visible to all others.
+#[derive(Clone)]
+pub struct RoleBasedKeyPermissionManager {
+    storage_location: String,
+    persisted_roles: Arc<RwLock<PersistedKeyRoles>>,
+}
+
+impl RoleBasedKeyPermissionManager {
+    /// Constructs a new RoleBasedKeyPermissionManager using the given location.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `KeyPermissionError` if the persisted roles fail to load.
+    pub fn new(storage_location: String) -> Result<Self, KeyPermissionError> {
+        let persisted_roles = get_storage(&storage_location, PersistedKeyRoles::default)
+            .map_err(|err: String| KeyPermissionError {
+                context: format!("unable to load storage: {}", err),
+                source: None,
+            })?
+            .read()
+            .clone();
+
+        Ok(Self {
+            storage_location,
+            persisted_roles: Arc::new(RwLock::new(persisted_roles)),
+        })
+    }
+
+    pub fn storage_location(&self) -> &str {
+        &self.storage_location
+    }
+
+    /// Returns the roles granted to the given public key, in sorted order.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `KeyPermissionError` if the roles could not be read.
+    pub fn roles(&self, public_key: &[u8]) -> Result<Vec<String>, KeyPermissionError> {
+        let persisted_roles = self
+            .persisted_roles
+            .read()
+            .map_err(|_| KeyPermissionError {
+                context: "Persisted key roles lock was poisoned".into(),
+                source: None,
+            })?;
+
+        Ok(persisted_roles
+            .roles
+            .get(&to_hex(public_key))
+            .map(|roles| roles.iter().cloned().collect())
+            .unwrap_or_else(Vec::new))
+    }
+
+    /// Grants the given role to the public key.
+    ///
+    /// Returns `true` if the role was newly granted, or `false` if the key already held it.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `KeyPermissionError` if the role is empty or the roles could not be written.
+    pub fn grant_role(&self, public_key: &[u8], role: &str) -> Result<bool, KeyPermissionError> {
+        if role.is_empty() {
+            return Err(KeyPermissionError {
+                context: "Role must not be empty".into(),
+                source: None,
+            });
+        }
+
+        let mut persisted_roles = self
+            .persisted_roles
+            .write()
+            .map_err(|_| KeyPermissionError {
+                context: "Persisted key roles lock was poisoned".into(),
+                source: None,
+            })?;
+
+        let granted = persisted_roles
+            .roles
+            .entry(to_hex(public_key))
+            .or_insert_with(BTreeSet::new)
+            .insert(role.to_string());
+
+        if granted {
+            self.write_roles(&persisted_roles)?;
+        }
+
+        Ok(granted)
+    }
+
+    /// Revokes the given role from the public key.
+    ///
+    /// Returns `true` if the role was revoked, or `false` if the key did not hold it.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `KeyPermissionError` if the roles could not be written.
+    pub fn revoke_role(&self, public_key: &[u8], role: &str) -> Result<bool, KeyPermissionError> {
+        let mut persisted_roles = self
+            .persisted_roles
+            .write()
+            .map_err(|_| KeyPermissionError {
+                context: "Persisted key roles lock was poisoned".into(),
+                source: None,
+            })?;
+
+        let hex_key = to_hex(public_key);
+        let revoked = match persisted_roles.roles.get_mut(&hex_key) {
+            Some(roles) => roles.remove(role),
+            None => false,
+        };
+
+        if revoked {
+            if persisted_roles
+                .roles
+                .get(&hex_key)
+                .map(BTreeSet::is_empty)
+                .unwrap_or(false)
+            {
+                persisted_roles.roles.remove(&hex_key);
+            }
+            self.write_roles(&persisted_roles)?;
+        }
+
+        Ok(revoked)
+    }
+
+    fn write_roles(&self, persisted_roles: &PersistedKeyRoles) -> Result<(), KeyPermissionError> {
+        // Replace stored roles with the current roles
+        let mut storage = get_storage(self.storage_location(), || persisted_roles.clone())
+            .map_err(|err: String| KeyPermissionError {
+                context: format!("unable to load key roles: {}", err),
+                source: None,
+            })?;
+
+        // when this is dropped the new state will be written to storage
+        **storage.write() = persisted_roles.clone();
+
+        Ok(())
+    }
+}
+
+impl KeyPermissionManager for RoleBasedKeyPermissionManager {
+    fn is_permitted(&self, public_key: &[u8], role: &str) -> Result<bool, KeyPermissionError> {
+        let persisted_roles = self
+            .persisted_roles
+            .read()
+            .map_err(|_| KeyPermissionError {
+                context: "Persisted key roles lock was poisoned".into(),
+                source: None,
+            })?;
+
+        let permitted = persisted_roles
+            .roles
+            .get(&to_hex(public_key))
+            .map(|roles| roles.contains(role))
+            .unwrap_or(false);
+
+        if !permitted {
+            debug!("Denying {} access to {}", to_hex(public_key), role);
+        }
+
+        Ok(permitted)
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Default, Clone)]
+struct PersistedKeyRoles {
+    #[serde(flatten)]
+    roles: BTreeMap<String, BTreeSet<String>>,
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::File;
+    use std::io::Write;
+
+    use tempdir::TempDir;
+
+    use super::*;
+    use crate::hex::parse_hex;
+
+    /// Test that roles loaded from a role file are used to answer permission checks.
+    ///
+    /// 1. write a role file granting one key both roles and another only the voter role
+    /// 2. verify that each key is permitted only for the roles it was granted
+    /// 3. verify that an unknown key is not permitted for any role
+    #[test]
+    fn test_is_permitted_from_role_file() {
+        let temp_dir = TempDir::new("test_is_permitted_from_role_file").unwrap();
+        let mut temp_dir_path = temp_dir.path().to_path_buf();
+        temp_dir_path.push("key_roles.yaml");
+
+        let mut file = File::create(&temp_dir_path).expect("unable to create file");
+        file.write_all(b"abcdef:\n  - proposer\n  - voter\n012345:\n  - voter\n")
+            .expect("could not write file");
+
+        let manager = RoleBasedKeyPermissionManager::new(
+            temp_dir_path
+                .to_str()
+                .expect("could not create path str")
+                .to_string(),
+        )
+        .expect("could not load file");
+
+        let public_key1 = parse_hex("abcdef").expect("unable to parse abcdef");
+        let public_key2 = parse_hex("012345").expect("unable to parse 012345");
+        let unknown_key = parse_hex("fedcba").expect("unable to parse fedcba");
+
+        assert!(manager.is_permitted(&public_key1, "proposer").unwrap());
+        assert!(manager.is_permitted(&public_key1, "voter").unwrap());
+        assert!(!manager.is_permitted(&public_key2, "proposer").unwrap());
+        assert!(manager.is_permitted(&public_key2, "voter").unwrap());
+        assert!(!manager.is_permitted(&unknown_key, "proposer").unwrap());
+        assert!(!manager.is_permitted(&unknown_key, "voter").unwrap());
+    }
+
+    /// Test that granted and revoked roles are persisted to the role file.
+    ///
+    /// 1. grant a key the proposer role and verify it is only granted once
+    /// 2. reload the role file and verify the role is still granted
+    /// 3. revoke the role and verify it is only revoked once
+    /// 4. reload the role file and verify the role is no longer granted
+    #[test]
+    fn test_grant_and_revoke_role() {
+        let temp_dir = TempDir::new("test_grant_and_revoke_role").unwrap();
+        let mut temp_dir_path = temp_dir.path().to_path_buf();
+        temp_dir_path.push("key_roles.yaml");
+        let location = temp_dir_path
+            .to_str()
+            .expect("could not create path str")
+            .to_string();
+
+        let public_key = parse_hex("abcdef").expect("unable to parse abcdef");
+
+        let manager =
+            RoleBasedKeyPermissionManager::new(location.clone()).expect("could not load file");
+        assert!(manager.grant_role(&public_key, "proposer").unwrap());
+        assert!(!manager.grant_role(&public_key, "proposer").unwrap());
+        assert_eq!(
+            vec!["proposer".to_string()],
+            manager.roles(&public_key).unwrap()
+        );
+
+        let reloaded =
+            RoleBasedKeyPermissionManager::new(location.clone()).expect("could not load file");
+        assert!(reloaded.is_permitted(&public_key, "proposer").unwrap());
+
+        assert!(manager.revoke_role(&public_key, "proposer").unwrap());
+        assert!(!manager.revoke_role(&public_key, "proposer").unwrap());
+        assert!(manager.roles(&public_key).unwrap().is_empty());
+
+        let reloaded = RoleBasedKeyPermissionManager::new(location).expect("could not load file");
+        assert!(!reloaded.is_permitted(&public_key, "proposer").unwrap());
+    }
+
+    /// Test that an empty role cannot be granted.
+    #[test]
+    fn test_grant_empty_role() {
+        let manager = RoleBasedKeyPermissionManager::new("memory".into())
+            .expect("could not create in-memory manager");
+
+        assert!(manager.grant_role(b"abcdef", "").is_err());
+    }
+}
@@ -15,7 +15,8 @@
 use protobuf::Message;
 
 use crate::network::auth::{
-    AuthorizationAction, AuthorizationInquisitor, AuthorizationManager, AuthorizationState,
+    AuthorizationAction, AuthorizationActionError, AuthorizationInquisitor, AuthorizationManager,
+    AuthorizationState,
 };
 use crate::network::dispatch::{
     DispatchError, DispatchMessageSender, Dispatcher, FromMessageBytes, Handler, MessageContext,
@@ -23,8 +24,9 @@ use crate::network::dispatch::{
 };
 use crate::network::sender::NetworkMessageSender;
 use crate::protos::authorization::{
-    AuthorizationError, AuthorizationMessage, AuthorizationMessageType, AuthorizedMessage,
-    ConnectRequest, ConnectRequest_HandshakeMode, ConnectResponse,
+    AuthorizationError, AuthorizationError_AuthorizationErrorType, AuthorizationMessage,
+    AuthorizationMessageType, AuthorizedMessage, ChallengeNonceRequest, ChallengeNonceResponse,
+    ChallengeSubmitRequest, ConnectRequest, ConnectRequest_HandshakeMode, ConnectResponse,
     ConnectResponse_AuthorizationType, TrustRequest,
 };
 use crate::protos::network::{NetworkMessage, NetworkMessageType};
@@ -35,7 +37,7 @@ use crate::protos::network::{NetworkMessage, NetworkMessageType};
 /// The dispatcher is provided the given network sender for response messages, and the network
 /// itself to handle updating identities (or removing connections with authorization failures).
 ///
-/// The identity provided is sent to connections for Trust authorizations.
+/// The identity provided is sent to connections for Trust and Challenge authorizations.
 pub fn create_authorization_dispatcher(
     auth_manager: AuthorizationManager,
     network_sender: NetworkMessageSender,
@@ -48,6 +50,18 @@ pub fn create_authorization_dispatcher(
 
     auth_dispatcher.set_handler(Box::new(TrustRequestHandler::new(auth_manager.clone())));
 
+    auth_dispatcher.set_handler(Box::new(ChallengeNonceRequestHandler::new(
+        auth_manager.clone(),
+    )));
+
+    auth_dispatcher.set_handler(Box::new(ChallengeNonceResponseHandler::new(
+        auth_manager.clone(),
+    )));
+
+    auth_dispatcher.set_handler(Box::new(ChallengeSubmitRequestHandler::new(
+        auth_manager.clone(),
+    )));
+
     auth_dispatcher.set_handler(Box::new(AuthorizedHandler));
 
     auth_dispatcher.set_handler(Box::new(AuthorizationErrorHandler::new(auth_manager)));
@@ -241,10 +255,13 @@ impl Handler for ConnectRequestHandler {
                     );
                 }
 
+                let accepted_authorization_type = if self.auth_manager.is_challenge_enabled() {
+                    ConnectResponse_AuthorizationType::CHALLENGE
+                } else {
+                    ConnectResponse_AuthorizationType::TRUST
+                };
                 let mut response = ConnectResponse::new();
-                response.set_accepted_authorization_types(vec![
-                    ConnectResponse_AuthorizationType::TRUST,
-                ]);
+                response.set_accepted_authorization_types(vec![accepted_authorization_type]);
                 sender
                     .send(
                         context.source_id().clone(),
@@ -313,8 +330,26 @@ impl Handler for ConnectResponseHandler {
             context.source_peer_id(),
             msg
         );
-        if msg
-            .get_accepted_authorization_types()
+        let accepted_authorization_types = msg.get_accepted_authorization_types();
+        if self.auth_manager.is_challenge_enabled()
+            && accepted_authorization_types
+                .iter()
+                .any(|t| t == &ConnectResponse_AuthorizationType::CHALLENGE)
+        {
+            self.auth_manager
+                .request_challenge_nonce(context.source_peer_id());
+            sender
+                .send(
+                    context.source_id().clone(),
+                    wrap_in_network_auth_envelopes(
+                        AuthorizationMessageType::CHALLENGE_NONCE_REQUEST,
+                        ChallengeNonceRequest::new(),
+                    )?,
+                )
+                .map_err(|(recipient, payload)| {
+                    DispatchError::NetworkSendError((recipient.into(), payload))
+                })?;
+        } else if accepted_authorization_types
             .iter()
             .any(|t| t == &ConnectResponse_AuthorizationType::TRUST)
         {
@@ -331,6 +366,12 @@ impl Handler for ConnectResponseHandler {
                 .map_err(|(recipient, payload)| {
                     DispatchError::NetworkSendError((recipient.into(), payload))
                 })?;
+        } else {
+            warn!(
+                "Peer {} does not accept a supported authorization type: {:?}",
+                context.source_peer_id(),
+                accepted_authorization_types
+            );
         }
         Ok(())
     }
@@ -366,6 +407,10 @@ impl Handler for TrustRequestHandler {
             context.source_peer_id(),
             AuthorizationAction::TrustIdentifying(msg.get_identity().to_string()),
         ) {
+            Err(err @ AuthorizationActionError::UnacceptedAuthorizationType(_))
+            | Err(err @ AuthorizationActionError::CertificateIdentityMismatch(_)) => {
+                reject_authorization(&self.auth_manager, context, sender, &err.to_string())?;
+            }
             Err(err) => {
                 debug!(
                     "Ignoring trust request message from peer {}: {}",
@@ -398,6 +443,217 @@ impl Handler for TrustRequestHandler {
     }
 }
 
+/// Handler for the ChallengeNonceRequest Authorization Message Type
+struct ChallengeNonceRequestHandler {
+    auth_manager: AuthorizationManager,
+}
+
+impl ChallengeNonceRequestHandler {
+    fn new(auth_manager: AuthorizationManager) -> Self {
+        ChallengeNonceRequestHandler { auth_manager }
+    }
+}
+
+impl Handler for ChallengeNonceRequestHandler {
+    type Source = PeerId;
+    type MessageType = AuthorizationMessageType;
+    type Message = ChallengeNonceRequest;
+
+    fn match_type(&self) -> Self::MessageType {
+        AuthorizationMessageType::CHALLENGE_NONCE_REQUEST
+    }
+
+    fn handle(
+        &self,
+        _: Self::Message,
+        context: &MessageContext<Self::Source, Self::MessageType>,
+        sender: &dyn MessageSender<Self::Source>,
+    ) -> Result<(), DispatchError> {
+        match self.auth_manager.next_state(
+            context.source_peer_id(),
+            AuthorizationAction::ChallengeNonceRequesting,
+        ) {
+            Err(err @ AuthorizationActionError::UnacceptedAuthorizationType(_)) => {
+                reject_authorization(&self.auth_manager, context, sender, &err.to_string())?;
+            }
+            Err(err) => {
+                debug!(
+                    "Ignoring challenge nonce request message from peer {}: {}",
+                    context.source_peer_id(),
+                    err
+                );
+            }
+            Ok(AuthorizationState::ChallengeNonceSent(nonce)) => {
+                debug!(
+                    "Sending challenge nonce to peer {}",
+                    context.source_peer_id()
+                );
+                let mut nonce_response = ChallengeNonceResponse::new();
+                nonce_response.set_nonce(nonce);
+                nonce_response.set_identity(self.auth_manager.identity.clone());
+                sender
+                    .send(
+                        context.source_id().clone(),
+                        wrap_in_network_auth_envelopes(
+                            AuthorizationMessageType::CHALLENGE_NONCE_RESPONSE,
+                            nonce_response,
+                        )?,
+                    )
+                    .map_err(|(recipient, payload)| {
+                        DispatchError::NetworkSendError((recipient.into(), payload))
+                    })?;
+            }
+            Ok(next_state) => panic!("Should not have been able to transition to {}", next_state),
+        }
+        Ok(())
+    }
+}
+
+/// Handler for the ChallengeNonceResponse Authorization Message Type
+struct ChallengeNonceResponseHandler {
+    auth_manager: AuthorizationManager,
+}
+
+impl ChallengeNonceResponseHandler {
+    fn new(auth_manager: AuthorizationManager) -> Self {
+        ChallengeNonceResponseHandler { auth_manager }
+    }
+}
+
+impl Handler for ChallengeNonceResponseHandler {
+    type Source = PeerId;
+    type MessageType = AuthorizationMessageType;
+    type Message = ChallengeNonceResponse;
+
+    fn match_type(&self) -> Self::MessageType {
+        AuthorizationMessageType::CHALLENGE_NONCE_RESPONSE
+    }
+
+    fn handle(
+        &self,
+        msg: Self::Message,
+        context: &MessageContext<Self::Source, Self::MessageType>,
+        sender: &dyn MessageSender<Self::Source>,
+    ) -> Result<(), DispatchError> {
+        match self.auth_manager.sign_challenge_nonce(
+            context.source_peer_id(),
+            msg.get_nonce(),
+            msg.get_identity(),
+        ) {
+            Ok(Some((public_key, signature))) => send_challenge_submit_request(
+                &self.auth_manager,
+                context.source_id().clone(),
+                public_key,
+                signature,
+                sender,
+            ),
+            Ok(None) => {
+                debug!(
+                    "Deferring challenge nonce from peer {} until it has proven its identity",
+                    context.source_peer_id()
+                );
+                Ok(())
+            }
+            Err(err) => {
+                warn!(
+                    "Refusing to sign challenge nonce from peer {}: {}",
+                    context.source_peer_id(),
+                    err
+                );
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Handler for the ChallengeSubmitRequest Authorization Message Type
+struct ChallengeSubmitRequestHandler {
+    auth_manager: AuthorizationManager,
+}
+
+impl ChallengeSubmitRequestHandler {
+    fn new(auth_manager: AuthorizationManager) -> Self {
+        ChallengeSubmitRequestHandler { auth_manager }
+    }
+}
+
+impl Handler for ChallengeSubmitRequestHandler {
+    type Source = PeerId;
+    type MessageType = AuthorizationMessageType;
+    type Message = ChallengeSubmitRequest;
+
+    fn match_type(&self) -> Self::MessageType {
+        AuthorizationMessageType::CHALLENGE_SUBMIT_REQUEST
+    }
+
+    fn handle(
+        &self,
+        mut msg: Self::Message,
+        context: &MessageContext<Self::Source, Self::MessageType>,
+        sender: &dyn MessageSender<Self::Source>,
+    ) -> Result<(), DispatchError> {
+        let identity = msg.take_identity();
+        match self.auth_manager.next_state(
+            context.source_peer_id(),
+            AuthorizationAction::ChallengeSubmitting {
+                identity: identity.clone(),
+                public_key: msg.take_public_key(),
+                signature: msg.take_signature(),
+            },
+        ) {
+            Err(err @ AuthorizationActionError::UnacceptedAuthorizationType(_))
+            | Err(err @ AuthorizationActionError::ChallengeFailed(_))
+            | Err(err @ AuthorizationActionError::CertificateIdentityMismatch(_)) => {
+                reject_authorization(&self.auth_manager, context, sender, &err.to_string())?;
+            }
+            Err(err) => {
+                debug!(
+                    "Ignoring challenge submit request message from peer {}: {}",
+                    context.source_peer_id(),
+                    err
+                );
+            }
+            Ok(AuthorizationState::Authorized) => {
+                debug!(
+                    "Sending Authorized message to peer {} (formerly {})",
+                    identity,
+                    context.source_peer_id()
+                );
+                let auth_msg = AuthorizedMessage::new();
+                sender
+                    .send(
+                        identity.clone().into(),
+                        wrap_in_network_auth_envelopes(
+                            AuthorizationMessageType::AUTHORIZE,
+                            auth_msg,
+                        )?,
+                    )
+                    .map_err(|(recipient, payload)| {
+                        DispatchError::NetworkSendError((recipient.into(), payload))
+                    })?;
+
+                // Now that the peer has proven its identity, prove this node's identity to it
+                match self.auth_manager.sign_deferred_challenge_nonce(&identity) {
+                    Ok(Some((public_key, signature))) => send_challenge_submit_request(
+                        &self.auth_manager,
+                        identity.into(),
+                        public_key,
+                        signature,
+                        sender,
+                    )?,
+                    Ok(None) => (),
+                    Err(err) => warn!(
+                        "Unable to sign challenge nonce from peer {}: {}",
+                        identity, err
+                    ),
+                }
+            }
+            Ok(next_state) => panic!("Should not have been able to transition to {}", next_state),
+        }
+        Ok(())
+    }
+}
+
 /// Handler for the Authorization Error Message Type
 struct AuthorizationErrorHandler {
     auth_manager: AuthorizationManager,
@@ -448,6 +704,70 @@ impl Handler for AuthorizationErrorHandler {
     }
 }
 
+/// Sends an authorization error to the peer and removes its connection.
+fn reject_authorization(
+    auth_manager: &AuthorizationManager,
+    context: &MessageContext<PeerId, AuthorizationMessageType>,
+    sender: &dyn MessageSender<PeerId>,
+    error_message: &str,
+) -> Result<(), DispatchError> {
+    warn!(
+        "Rejecting authorization of peer {}: {}",
+        context.source_peer_id(),
+        error_message
+    );
+
+    let mut error = AuthorizationError::new();
+    error.set_error_type(AuthorizationError_AuthorizationErrorType::AUTHORIZATION_REJECTED);
+    error.set_error_message(error_message.into());
+    sender
+        .send(
+            context.source_id().clone(),
+            wrap_in_network_auth_envelopes(AuthorizationMessageType::AUTHORIZATION_ERROR, error)?,
+        )
+        .map_err(|(recipient, payload)| {
+            DispatchError::NetworkSendError((recipient.into(), payload))
+        })?;
+
+    if let Err(err) =
+        auth_manager.next_state(context.source_peer_id(), AuthorizationAction::Unauthorizing)
+    {
+        warn!(
+            "Unable to unauthorize peer {}: {}",
+            context.source_peer_id(),
+            err
+        );
+    }
+
+    Ok(())
+}
+
+/// Sends this node's signature of the peer's challenge nonce to the peer.
+fn send_challenge_submit_request(
+    auth_manager: &AuthorizationManager,
+    recipient: PeerId,
+    public_key: Vec<u8>,
+    signature: Vec<u8>,
+    sender: &dyn MessageSender<PeerId>,
+) -> Result<(), DispatchError> {
+    let mut submit_request = ChallengeSubmitRequest::new();
+    submit_request.set_identity(auth_manager.identity.clone());
+    submit_request.set_public_key(public_key);
+    submit_request.set_signature(signature);
+    sender
+        .send(
+            recipient,
+            wrap_in_network_auth_envelopes(
+                AuthorizationMessageType::CHALLENGE_SUBMIT_REQUEST,
+                submit_request,
+            )?,
+        )
+        .map_err(|(recipient, payload)| {
+            DispatchError::NetworkSendError((recipient.into(), payload))
+        })?;
+    Ok(())
+}
+
 fn wrap_in_network_auth_envelopes<M: protobuf::Message>(
     msg_type: AuthorizationMessageType,
     auth_msg: M,
@@ -472,12 +792,13 @@ mod tests {
     use protobuf::Message;
 
     use crate::mesh::Mesh;
+    use crate::network::auth::tests::create_challenge_authorization;
     use crate::network::sender;
     use crate::network::Network;
     use crate::protos::authorization::{
         AuthorizationError, AuthorizationError_AuthorizationErrorType, AuthorizationMessage,
-        AuthorizedMessage, ConnectRequest, ConnectResponse, ConnectResponse_AuthorizationType,
-        TrustRequest,
+        AuthorizedMessage, ChallengeNonceRequest, ChallengeNonceResponse, ConnectRequest,
+        ConnectResponse, ConnectResponse_AuthorizationType, TrustRequest,
     };
     use crate::protos::network::{NetworkMessage, NetworkMessageType};
     use crate::transport::socket::TcpTransport;
@@ -698,6 +1019,95 @@ mod tests {
         );
     }
 
+    // Test that the node responds to a challenge nonce request with a nonce, when challenge
+    // authorization is enabled
+    #[test]
+    fn challenge_nonce_request_dispatch() {
+        let (network, peer_id) = create_network_with_initial_temp_peer();
+
+        let auth_mgr = AuthorizationManager::new_with_challenge(
+            network.clone(),
+            "mock_identity".into(),
+            create_challenge_authorization(),
+        );
+        let network_message_queue = sender::Builder::new()
+            .with_network(network.clone())
+            .build()
+            .expect("Unable to create queue");
+        let network_sender = network_message_queue.new_network_sender();
+
+        let mut tcp_transport = TcpTransport::default();
+        let mut listener = tcp_transport
+            .listen("tcp://localhost:0")
+            .expect("Cannot listen for connections");
+        let endpoint = listener.endpoint();
+        let dispatcher = create_authorization_dispatcher(auth_mgr, network_sender);
+
+        std::thread::spawn(move || {
+            let connection = listener.accept().expect("Cannot accept connection");
+            network
+                .add_peer(peer_id.clone(), connection)
+                .expect("Unable to add peer");
+            // Begin the connection process, otherwise, the request will fail
+            let mut msg = ConnectRequest::new();
+            msg.set_handshake_mode(ConnectRequest_HandshakeMode::UNIDIRECTIONAL);
+            let msg_bytes = msg.write_to_bytes().expect("Unable to serialize message");
+            assert_eq!(
+                Ok(()),
+                dispatcher.dispatch(
+                    peer_id.clone().into(),
+                    &AuthorizationMessageType::CONNECT_REQUEST,
+                    msg_bytes
+                )
+            );
+
+            let msg_bytes = ChallengeNonceRequest::new()
+                .write_to_bytes()
+                .expect("Unable to serialize message");
+            assert_eq!(
+                Ok(()),
+                dispatcher.dispatch(
+                    peer_id.into(),
+                    &AuthorizationMessageType::CHALLENGE_NONCE_REQUEST,
+                    msg_bytes
+                )
+            );
+        });
+
+        let mesh2 = Mesh::new(1, 1);
+        let network2 = Network::new(mesh2.clone(), 0).unwrap();
+        let connection = tcp_transport
+            .connect(&endpoint)
+            .expect("Unable to connect to inproc");
+        network2
+            .add_peer("mock_identity".to_string(), connection)
+            .expect("Unable to add peer");
+
+        let network_message = network2
+            .recv()
+            .expect("Unable to receive message over the network");
+
+        let connect_res_msg: ConnectResponse = expect_auth_message(
+            AuthorizationMessageType::CONNECT_RESPONSE,
+            network_message.payload(),
+        );
+        assert_eq!(
+            vec![ConnectResponse_AuthorizationType::CHALLENGE],
+            connect_res_msg.get_accepted_authorization_types().to_vec()
+        );
+
+        let network_message = network2
+            .recv()
+            .expect("Unable to receive message over the network");
+
+        let nonce_res_msg: ChallengeNonceResponse = expect_auth_message(
+            AuthorizationMessageType::CHALLENGE_NONCE_RESPONSE,
+            network_message.payload(),
+        );
+        assert!(!nonce_res_msg.get_nonce().is_empty());
+        assert_eq!("mock_identity", nonce_res_msg.get_identity());
+    }
+
     // Test that an AuthorizationError message is properly handled
     // 1. Configure the dispatcher
     // 2. Dispatch a connect message for a peer id
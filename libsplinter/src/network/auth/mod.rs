@@ -21,13 +21,30 @@ use std::sync::{
     Arc, Mutex,
 };
 
-use crate::network::Network;
+use rand::Rng;
+
+use crate::hex::to_hex;
+use crate::network::{is_temp_peer_id, Network};
+use crate::node_registry::NodeRegistryReader;
+use crate::signing::{SignatureVerifier, Signer};
+
+/// The number of random bytes in a challenge nonce.
+const CHALLENGE_NONCE_SIZE: usize = 64;
+
+/// The prefix of the challenge messages signed with a node key, which keeps the signatures from
+/// being valid for any other use of the key.
+const CHALLENGE_SIGNATURE_PREFIX: &[u8] = b"splinter-auth-challenge:";
+
+/// The node metadata key that maps a node in the node registry to the identity named in its TLS
+/// certificate.
+pub const CERTIFICATE_IDENTITY_METADATA_KEY: &str = "certificate_identity";
 
 /// The states of a connection during authorization.
 #[derive(PartialEq, Debug, Clone)]
 enum AuthorizationState {
     Unknown,
     Connecting,
+    ChallengeNonceSent(Vec<u8>),
     Authorized,
     Unauthorized,
     Internal,
@@ -38,6 +55,7 @@ impl fmt::Display for AuthorizationState {
         f.write_str(match self {
             AuthorizationState::Unknown => "Unknown",
             AuthorizationState::Connecting => "Connecting",
+            AuthorizationState::ChallengeNonceSent(_) => "ChallengeNonceSent",
             AuthorizationState::Authorized => "Authorized",
             AuthorizationState::Unauthorized => "Unauthorized",
             AuthorizationState::Internal => "Internal",
@@ -52,6 +70,12 @@ type Identity = String;
 enum AuthorizationAction {
     Connecting,
     TrustIdentifying(Identity),
+    ChallengeNonceRequesting,
+    ChallengeSubmitting {
+        identity: Identity,
+        public_key: Vec<u8>,
+        signature: Vec<u8>,
+    },
     Unauthorizing,
 }
 
@@ -60,6 +84,8 @@ impl fmt::Display for AuthorizationAction {
         f.write_str(match self {
             AuthorizationAction::Connecting => "Connecting",
             AuthorizationAction::TrustIdentifying(_) => "TrustIdentifying",
+            AuthorizationAction::ChallengeNonceRequesting => "ChallengeNonceRequesting",
+            AuthorizationAction::ChallengeSubmitting { .. } => "ChallengeSubmitting",
             AuthorizationAction::Unauthorizing => "Unauthorizing",
         })
     }
@@ -71,6 +97,9 @@ enum AuthorizationActionError {
     AlreadyConnecting,
     InvalidMessageOrder(AuthorizationState, AuthorizationAction),
     ConnectionLost,
+    UnacceptedAuthorizationType(String),
+    ChallengeFailed(String),
+    CertificateIdentityMismatch(String),
 }
 
 impl fmt::Display for AuthorizationActionError {
@@ -85,6 +114,15 @@ impl fmt::Display for AuthorizationActionError {
             AuthorizationActionError::ConnectionLost => {
                 f.write_str("Connection lost while authorizing peer")
             }
+            AuthorizationActionError::UnacceptedAuthorizationType(auth_type) => {
+                write!(f, "{} authorization is not accepted", auth_type)
+            }
+            AuthorizationActionError::ChallengeFailed(msg) => {
+                write!(f, "Challenge authorization failed: {}", msg)
+            }
+            AuthorizationActionError::CertificateIdentityMismatch(msg) => {
+                write!(f, "Certificate identity verification failed: {}", msg)
+            }
         }
     }
 }
@@ -111,6 +149,156 @@ pub trait AuthorizationInquisitor: Send {
     fn is_authorized(&self, peer_id: &str) -> bool;
 }
 
+/// The node key and the peer key verification used for challenge authorization.
+///
+/// A connecting node proves its identity by signing a nonce provided by the receiving node,
+/// together with the receiving node's identity. The receiving node verifies the signature against
+/// its own identity, and that the public key is one of the keys registered for the claimed
+/// identity in its node registry. A signature is therefore only valid for the node that issued
+/// the nonce, and can not be relayed to authorize with another node.
+///
+/// Both nodes prove their identity to each other. The node that initiated the connection proves
+/// its identity first, and the node that accepted the connection only signs the initiator's nonce
+/// once the initiator has proven its own identity. Otherwise, a node that connects to this node
+/// could relay a nonce issued by another node and obtain this node's signature for it.
+pub struct ChallengeAuthorization {
+    signer: Box<dyn Signer + Send>,
+    signature_verifier: Box<dyn SignatureVerifier>,
+    node_registry: Box<dyn NodeRegistryReader>,
+}
+
+impl ChallengeAuthorization {
+    /// Constructs a ChallengeAuthorization
+    ///
+    /// The signer is used to sign nonces with this node's key, while the signature verifier and
+    /// the node registry are used to verify the nonces signed by connecting peers.
+    pub fn new(
+        signer: Box<dyn Signer + Send>,
+        signature_verifier: Box<dyn SignatureVerifier>,
+        node_registry: Box<dyn NodeRegistryReader>,
+    ) -> Self {
+        ChallengeAuthorization {
+            signer,
+            signature_verifier,
+            node_registry,
+        }
+    }
+
+    /// Verifies that the signature is valid for the challenge message of the nonce and this
+    /// node's identity, and that the public key is registered for the identity.
+    fn verify(
+        &self,
+        nonce: &[u8],
+        local_identity: &str,
+        identity: &str,
+        public_key: &[u8],
+        signature: &[u8],
+    ) -> Result<(), AuthorizationActionError> {
+        let message = challenge_message(nonce, local_identity);
+        match self
+            .signature_verifier
+            .verify(&message, signature, public_key)
+        {
+            Ok(true) => (),
+            Ok(false) => {
+                return Err(AuthorizationActionError::ChallengeFailed(
+                    "invalid nonce signature".into(),
+                ))
+            }
+            Err(err) => return Err(AuthorizationActionError::ChallengeFailed(err.to_string())),
+        }
+
+        let node = self
+            .node_registry
+            .fetch_node(identity)
+            .map_err(|err| AuthorizationActionError::ChallengeFailed(err.to_string()))?
+            .ok_or_else(|| {
+                AuthorizationActionError::ChallengeFailed(format!(
+                    "node {} is not in the node registry",
+                    identity
+                ))
+            })?;
+
+        let public_key = to_hex(public_key);
+        if node.keys.iter().any(|key| key == &public_key) {
+            Ok(())
+        } else {
+            Err(AuthorizationActionError::ChallengeFailed(format!(
+                "public key {} is not registered for node {}",
+                public_key, identity
+            )))
+        }
+    }
+}
+
+/// The verification that binds a peer's claimed identity to the identity its transport
+/// authenticated it with, such as the names in its TLS certificate or the public key of a Noise
+/// connection.
+///
+/// A peer's claimed node ID is accepted if its certificate names the node ID itself, or names the
+/// value of the `certificate_identity` metadata entry for the node ID in the node registry, or if
+/// the transport authenticated one of the node's keys in the node registry. Peers whose connection
+/// was not authenticated by their transport are rejected.
+pub struct CertificateIdentityBinding {
+    node_registry: Box<dyn NodeRegistryReader>,
+}
+
+impl CertificateIdentityBinding {
+    /// Constructs a CertificateIdentityBinding
+    ///
+    /// The node registry is used to look up the certificate identity for nodes whose certificate
+    /// does not name their node ID.
+    pub fn new(node_registry: Box<dyn NodeRegistryReader>) -> Self {
+        CertificateIdentityBinding { node_registry }
+    }
+
+    /// Verifies that the identity is one of the peer's certificate identities, or is mapped to
+    /// one of them in the node registry, either by its certificate identity metadata or by its
+    /// keys.
+    fn verify(
+        &self,
+        identity: &str,
+        peer_identities: Option<Vec<String>>,
+    ) -> Result<(), AuthorizationActionError> {
+        let peer_identities = peer_identities.ok_or_else(|| {
+            AuthorizationActionError::CertificateIdentityMismatch(
+                "connection was not authenticated with a certificate".into(),
+            )
+        })?;
+
+        if peer_identities
+            .iter()
+            .any(|peer_identity| peer_identity == identity)
+        {
+            return Ok(());
+        }
+
+        let node = self.node_registry.fetch_node(identity).map_err(|err| {
+            AuthorizationActionError::CertificateIdentityMismatch(err.to_string())
+        })?;
+
+        match node {
+            Some(node)
+                if node
+                    .metadata
+                    .get(CERTIFICATE_IDENTITY_METADATA_KEY)
+                    .map(|mapped_identity| peer_identities.contains(mapped_identity))
+                    .unwrap_or(false)
+                    || node.keys.iter().any(|key| peer_identities.contains(key)) =>
+            {
+                Ok(())
+            }
+            _ => Err(AuthorizationActionError::CertificateIdentityMismatch(
+                format!(
+                    "certificate identities [{}] do not match node {}",
+                    peer_identities.join(", "),
+                    identity
+                ),
+            )),
+        }
+    }
+}
+
 /// Manages authorization states for connections on a network.
 #[derive(Clone)]
 pub struct AuthorizationManager {
@@ -121,9 +309,46 @@ pub struct AuthorizationManager {
 
 impl AuthorizationManager {
     /// Constructs an AuthorizationManager
+    ///
+    /// Peers are authorized using trust authorization, where a peer's identity is accepted as
+    /// provided.
     pub fn new(network: Network, identity: Identity) -> Self {
+        Self::build(network, identity, None)
+    }
+
+    /// Constructs an AuthorizationManager that uses challenge authorization
+    ///
+    /// Peers must sign a nonce with a key registered for their identity in the node registry;
+    /// trust authorization is not accepted.
+    pub fn new_with_challenge(
+        network: Network,
+        identity: Identity,
+        challenge_authorization: ChallengeAuthorization,
+    ) -> Self {
+        Self::build(network, identity, Some(challenge_authorization))
+    }
+
+    /// Requires peers to claim an identity that is bound to the identity their connection was
+    /// authenticated with by the transport, regardless of the authorization type used.
+    pub fn with_certificate_identity_binding(
+        self,
+        certificate_identity_binding: CertificateIdentityBinding,
+    ) -> Self {
+        mutex_lock_unwrap!(self.shared).certificate_identity_binding =
+            Some(certificate_identity_binding);
+        self
+    }
+
+    fn build(
+        network: Network,
+        identity: Identity,
+        challenge_authorization: Option<ChallengeAuthorization>,
+    ) -> Self {
         let (disconnect_send, disconnect_receive) = channel();
-        let shared = Arc::new(Mutex::new(ManagedAuthorizations::new(disconnect_receive)));
+        let shared = Arc::new(Mutex::new(ManagedAuthorizations::new(
+            disconnect_receive,
+            challenge_authorization,
+        )));
 
         network.add_disconnect_listener(Box::new(move |peer_id: &str| {
             match disconnect_send.send(peer_id.to_string()) {
@@ -139,6 +364,122 @@ impl AuthorizationManager {
         }
     }
 
+    /// Indicates whether or not this manager authorizes peers using challenge authorization.
+    fn is_challenge_enabled(&self) -> bool {
+        mutex_lock_unwrap!(self.shared)
+            .challenge_authorization
+            .is_some()
+    }
+
+    /// Records that this node has requested a challenge nonce from the peer, in order to prove
+    /// its own identity to the peer.
+    fn request_challenge_nonce(&self, peer_id: &str) {
+        mutex_lock_unwrap!(self.shared)
+            .outbound_challenges
+            .insert(peer_id.to_string(), OutboundChallenge::NonceRequested);
+    }
+
+    /// Signs the challenge message for a nonce received from the peer with this node's key,
+    /// returning the public key and the signature.
+    ///
+    /// A nonce is only signed once, and only if this node has requested it from the peer. The
+    /// signature is bound to the identity of the node that issued the nonce; if the peer's node
+    /// ID is already known, the nonce must have been issued by that node. Once signed, the peer
+    /// may only authorize with this node under the same identity.
+    ///
+    /// If the peer connected to this node and has not yet proven its identity, the nonce is not
+    /// signed and `None` is returned. The nonce is signed by `sign_deferred_challenge_nonce` once
+    /// the peer has been authorized.
+    fn sign_challenge_nonce(
+        &self,
+        peer_id: &str,
+        nonce: &[u8],
+        verifier_identity: &str,
+    ) -> Result<Option<(Vec<u8>, Vec<u8>)>, AuthorizationActionError> {
+        let mut shared = mutex_lock_unwrap!(self.shared);
+        if shared.challenge_authorization.is_none() {
+            return Err(AuthorizationActionError::UnacceptedAuthorizationType(
+                "Challenge".into(),
+            ));
+        }
+
+        match shared.outbound_challenges.get(peer_id) {
+            Some(OutboundChallenge::NonceRequested) => (),
+            _ => {
+                return Err(AuthorizationActionError::ChallengeFailed(
+                    "a challenge nonce was not requested from this peer".into(),
+                ))
+            }
+        }
+
+        if !is_temp_peer_id(peer_id) && peer_id != verifier_identity {
+            return Err(AuthorizationActionError::ChallengeFailed(format!(
+                "challenge nonce was issued by {}, but peer is {}",
+                verifier_identity, peer_id
+            )));
+        }
+
+        if !self.network.is_outbound(peer_id)
+            && shared.states.get(peer_id) != Some(&AuthorizationState::Authorized)
+        {
+            shared.outbound_challenges.insert(
+                peer_id.to_string(),
+                OutboundChallenge::NonceReceived {
+                    nonce: nonce.to_vec(),
+                    verifier_identity: verifier_identity.to_string(),
+                },
+            );
+            return Ok(None);
+        }
+
+        Self::sign_outbound_challenge(&mut shared, peer_id, nonce, verifier_identity).map(Some)
+    }
+
+    /// Signs the nonce that was received from the peer before it proved its identity, now that
+    /// the peer has been authorized, returning the public key and the signature.
+    ///
+    /// The nonce is signed for the peer's verified identity. Returns `None` if there is no such
+    /// nonce.
+    fn sign_deferred_challenge_nonce(
+        &self,
+        peer_id: &str,
+    ) -> Result<Option<(Vec<u8>, Vec<u8>)>, AuthorizationActionError> {
+        let mut shared = mutex_lock_unwrap!(self.shared);
+        if shared.states.get(peer_id) != Some(&AuthorizationState::Authorized) {
+            return Ok(None);
+        }
+
+        let nonce = match shared.outbound_challenges.get(peer_id) {
+            Some(OutboundChallenge::NonceReceived { nonce, .. }) => nonce.clone(),
+            _ => return Ok(None),
+        };
+
+        Self::sign_outbound_challenge(&mut shared, peer_id, &nonce, peer_id).map(Some)
+    }
+
+    fn sign_outbound_challenge(
+        shared: &mut ManagedAuthorizations,
+        peer_id: &str,
+        nonce: &[u8],
+        verifier_identity: &str,
+    ) -> Result<(Vec<u8>, Vec<u8>), AuthorizationActionError> {
+        let challenge_authorization = shared.challenge_authorization.as_ref().ok_or_else(|| {
+            AuthorizationActionError::UnacceptedAuthorizationType("Challenge".into())
+        })?;
+        let signature = challenge_authorization
+            .signer
+            .sign(&challenge_message(nonce, verifier_identity))
+            .map_err(|err| AuthorizationActionError::ChallengeFailed(err.to_string()))?;
+        let public_key = challenge_authorization.signer.public_key().to_vec();
+
+        shared.outbound_challenges.insert(
+            peer_id.to_string(),
+            OutboundChallenge::Signed(verifier_identity.to_string()),
+        );
+
+        Ok((public_key, signature))
+    }
+
     /// Transitions from one authorization state to another
     ///
     /// Errors
@@ -155,6 +496,7 @@ impl AuthorizationManager {
         let removals = shared.disconnect_receiver.try_iter().collect::<Vec<_>>();
         for peer_id in removals.into_iter() {
             shared.states.remove(&peer_id);
+            shared.outbound_challenges.remove(&peer_id);
         }
 
         let cur_state = shared
@@ -198,8 +540,18 @@ impl AuthorizationManager {
             },
             AuthorizationState::Connecting => match action {
                 AuthorizationAction::Connecting => Err(AuthorizationActionError::AlreadyConnecting),
+                AuthorizationAction::TrustIdentifying(_)
+                    if shared.challenge_authorization.is_some() =>
+                {
+                    Err(AuthorizationActionError::UnacceptedAuthorizationType(
+                        "Trust".into(),
+                    ))
+                }
                 AuthorizationAction::TrustIdentifying(new_peer_id) => {
-                    // Verify pub key allowed
+                    if let Some(binding) = &shared.certificate_identity_binding {
+                        binding.verify(&new_peer_id, self.network.get_peer_identities(peer_id))?;
+                    }
+
                     shared.states.remove(peer_id);
                     self.network
                         .update_peer_id(peer_id.to_string(), new_peer_id.clone())
@@ -214,6 +566,22 @@ impl AuthorizationManager {
                     );
                     Ok(AuthorizationState::Authorized)
                 }
+                AuthorizationAction::ChallengeNonceRequesting => {
+                    if shared.challenge_authorization.is_none() {
+                        return Err(AuthorizationActionError::UnacceptedAuthorizationType(
+                            "Challenge".into(),
+                        ));
+                    }
+
+                    let mut nonce = vec![0u8; CHALLENGE_NONCE_SIZE];
+                    rand::thread_rng().fill(&mut nonce[..]);
+
+                    let next_state = AuthorizationState::ChallengeNonceSent(nonce);
+                    shared
+                        .states
+                        .insert(peer_id.to_string(), next_state.clone());
+                    Ok(next_state)
+                }
                 AuthorizationAction::Unauthorizing => {
                     shared.states.remove(peer_id);
                     self.network
@@ -226,6 +594,82 @@ impl AuthorizationManager {
                     );
                     Ok(AuthorizationState::Unauthorized)
                 }
+                _ => Err(AuthorizationActionError::InvalidMessageOrder(
+                    AuthorizationState::Connecting,
+                    action,
+                )),
+            },
+            AuthorizationState::ChallengeNonceSent(ref nonce) => match action {
+                AuthorizationAction::Connecting => Err(AuthorizationActionError::AlreadyConnecting),
+                AuthorizationAction::ChallengeSubmitting {
+                    identity,
+                    public_key,
+                    signature,
+                } => {
+                    let nonce = nonce.clone();
+                    shared
+                        .challenge_authorization
+                        .as_ref()
+                        .ok_or_else(|| {
+                            AuthorizationActionError::UnacceptedAuthorizationType(
+                                "Challenge".into(),
+                            )
+                        })?
+                        .verify(&nonce, &self.identity, &identity, &public_key, &signature)?;
+                    if let Some(binding) = &shared.certificate_identity_binding {
+                        binding.verify(&identity, self.network.get_peer_identities(peer_id))?;
+                    }
+
+                    // If the peer has already issued a nonce to this node, the peer must be the
+                    // node that issued it
+                    if let Some(OutboundChallenge::Signed(verifier_identity))
+                    | Some(OutboundChallenge::NonceReceived {
+                        verifier_identity, ..
+                    }) = shared.outbound_challenges.get(peer_id)
+                    {
+                        if verifier_identity != &identity {
+                            return Err(AuthorizationActionError::ChallengeFailed(format!(
+                                "peer claimed identity {} after issuing a nonce as {}",
+                                identity, verifier_identity
+                            )));
+                        }
+                    }
+                    if let Some(outbound_challenge) = shared.outbound_challenges.remove(peer_id) {
+                        shared
+                            .outbound_challenges
+                            .insert(identity.clone(), outbound_challenge);
+                    }
+
+                    shared.states.remove(peer_id);
+                    self.network
+                        .update_peer_id(peer_id.to_string(), identity.clone())
+                        .map_err(|_| AuthorizationActionError::ConnectionLost)?;
+                    shared
+                        .states
+                        .insert(identity.clone(), AuthorizationState::Authorized);
+                    Self::notify_callbacks(
+                        &shared.callbacks,
+                        &identity,
+                        PeerAuthorizationState::Authorized,
+                    );
+                    Ok(AuthorizationState::Authorized)
+                }
+                AuthorizationAction::Unauthorizing => {
+                    shared.states.remove(peer_id);
+                    self.network
+                        .remove_connection(&peer_id.to_string())
+                        .map_err(|_| AuthorizationActionError::ConnectionLost)?;
+                    Self::notify_callbacks(
+                        &shared.callbacks,
+                        peer_id,
+                        PeerAuthorizationState::Unauthorized,
+                    );
+                    Ok(AuthorizationState::Unauthorized)
+                }
+                _ => Err(AuthorizationActionError::InvalidMessageOrder(
+                    AuthorizationState::ChallengeNonceSent(nonce.clone()),
+                    action,
+                )),
             },
             AuthorizationState::Authorized => match action {
                 AuthorizationAction::Unauthorizing => {
@@ -287,6 +731,7 @@ impl AuthorizationInquisitor for AuthorizationManager {
         let removals = shared.disconnect_receiver.try_iter().collect::<Vec<_>>();
         for peer_id in removals.into_iter() {
             shared.states.remove(&peer_id);
+            shared.outbound_challenges.remove(&peer_id);
         }
 
         if let Some(state) = shared.states.get(peer_id) {
@@ -297,18 +742,52 @@ impl AuthorizationInquisitor for AuthorizationManager {
     }
 }
 
+/// The state of this node's own challenge authorization with a peer, where it proves its
+/// identity to the peer.
+#[derive(PartialEq, Debug, Clone)]
+enum OutboundChallenge {
+    /// A challenge nonce was requested from the peer, but has not been received
+    NonceRequested,
+    /// The peer's nonce was received, but will not be signed until the peer has proven its
+    /// identity
+    NonceReceived {
+        nonce: Vec<u8>,
+        verifier_identity: Identity,
+    },
+    /// The peer's nonce was signed for the given identity
+    Signed(Identity),
+}
+
+/// Returns the message that is signed to prove a node's identity to the node with the given
+/// identity, which issued the nonce.
+fn challenge_message(nonce: &[u8], verifier_identity: &str) -> Vec<u8> {
+    let mut message = CHALLENGE_SIGNATURE_PREFIX.to_vec();
+    message.extend_from_slice(nonce);
+    message.extend_from_slice(verifier_identity.as_bytes());
+    message
+}
+
 struct ManagedAuthorizations {
     states: HashMap<String, AuthorizationState>,
+    outbound_challenges: HashMap<String, OutboundChallenge>,
     callbacks: Vec<Box<dyn AuthorizationCallback>>,
     disconnect_receiver: Receiver<String>,
+    challenge_authorization: Option<ChallengeAuthorization>,
+    certificate_identity_binding: Option<CertificateIdentityBinding>,
 }
 
 impl ManagedAuthorizations {
-    fn new(disconnect_receiver: Receiver<String>) -> Self {
+    fn new(
+        disconnect_receiver: Receiver<String>,
+        challenge_authorization: Option<ChallengeAuthorization>,
+    ) -> Self {
         Self {
             states: Default::default(),
+            outbound_challenges: Default::default(),
             callbacks: Default::default(),
             disconnect_receiver,
+            challenge_authorization,
+            certificate_identity_binding: None,
         }
     }
 }
@@ -350,6 +829,10 @@ mod tests {
 
     use crate::mesh::Mesh;
     use crate::network::Network;
+    use crate::node_registry::{
+        MetadataPredicate, Node, NodeBuilder, NodeRegistryError, NodeRegistryReader,
+    };
+    use crate::signing::hash::{HashSigner, HashVerifier};
     use crate::transport::{
         ConnectError, Connection, DisconnectError, RecvError, SendError, Transport,
     };
@@ -562,6 +1045,533 @@ mod tests {
         );
     }
 
+    /// This test runs through the challenge authorization state machine happy path. It traverses
+    /// through each state, Unknown -> Connecting -> ChallengeNonceSent -> Authorized and verifies
+    /// that the response for is_authorized is correct at each stage.
+    #[test]
+    fn challenge_state_machine_valid() {
+        let (network, peer_id) = create_network_with_initial_temp_peer();
+
+        let auth_manager = AuthorizationManager::new_with_challenge(
+            network.clone(),
+            "mock_identity".into(),
+            create_challenge_authorization(),
+        );
+
+        assert_eq!(
+            Ok(AuthorizationState::Connecting),
+            auth_manager.next_state(&peer_id, AuthorizationAction::Connecting)
+        );
+
+        let nonce = match auth_manager
+            .next_state(&peer_id, AuthorizationAction::ChallengeNonceRequesting)
+        {
+            Ok(AuthorizationState::ChallengeNonceSent(nonce)) => nonce,
+            res => panic!("Should have sent a challenge nonce but got {:?}", res),
+        };
+        assert_eq!(CHALLENGE_NONCE_SIZE, nonce.len());
+        assert!(!auth_manager.is_authorized(&peer_id));
+
+        // verify that it cannot be connected again.
+        assert_eq!(
+            Err(AuthorizationActionError::AlreadyConnecting),
+            auth_manager.next_state(&peer_id, AuthorizationAction::Connecting)
+        );
+
+        // Submit the signed nonce and verify that it is authorized
+        let (public_key, signature) = sign_challenge(&nonce, "mock_identity");
+        let new_peer_id = "abcd".to_string();
+        assert_eq!(
+            Ok(AuthorizationState::Authorized),
+            auth_manager.next_state(
+                &peer_id,
+                AuthorizationAction::ChallengeSubmitting {
+                    identity: new_peer_id.clone(),
+                    public_key,
+                    signature,
+                }
+            )
+        );
+        // we no longer have the temp id
+        assert!(!auth_manager.is_authorized(&peer_id));
+        // but we now have the new identified peer
+        assert!(auth_manager.is_authorized(&new_peer_id));
+        assert_eq!(vec![new_peer_id.clone()], network.peer_ids());
+    }
+
+    /// This test verifies that a challenge submission is rejected if the signature does not match
+    /// the nonce, or if the public key is not registered for the given identity.
+    #[test]
+    fn challenge_state_machine_invalid_submission() {
+        let (network, peer_id) = create_network_with_initial_temp_peer();
+
+        let auth_manager = AuthorizationManager::new_with_challenge(
+            network.clone(),
+            "mock_identity".into(),
+            create_challenge_authorization(),
+        );
+
+        assert_eq!(
+            Ok(AuthorizationState::Connecting),
+            auth_manager.next_state(&peer_id, AuthorizationAction::Connecting)
+        );
+        let nonce = match auth_manager
+            .next_state(&peer_id, AuthorizationAction::ChallengeNonceRequesting)
+        {
+            Ok(AuthorizationState::ChallengeNonceSent(nonce)) => nonce,
+            res => panic!("Should have sent a challenge nonce but got {:?}", res),
+        };
+
+        // sign something other than the nonce
+        let (public_key, bad_signature) = sign_challenge(b"not the nonce", "mock_identity");
+        match auth_manager.next_state(
+            &peer_id,
+            AuthorizationAction::ChallengeSubmitting {
+                identity: "abcd".into(),
+                public_key: public_key.clone(),
+                signature: bad_signature,
+            },
+        ) {
+            Err(AuthorizationActionError::ChallengeFailed(_)) => (),
+            res => panic!("Should have failed the challenge but got {:?}", res),
+        }
+
+        // the key is not registered for this node
+        let (_, signature) = sign_challenge(&nonce, "mock_identity");
+        match auth_manager.next_state(
+            &peer_id,
+            AuthorizationAction::ChallengeSubmitting {
+                identity: "efgh".into(),
+                public_key,
+                signature,
+            },
+        ) {
+            Err(AuthorizationActionError::ChallengeFailed(_)) => (),
+            res => panic!("Should have failed the challenge but got {:?}", res),
+        }
+
+        assert!(!auth_manager.is_authorized(&peer_id));
+        assert!(!auth_manager.is_authorized("abcd"));
+        assert!(!auth_manager.is_authorized("efgh"));
+    }
+
+    /// This test verifies that a challenge nonce relayed from another node can not be used to
+    /// impersonate this node, and that this node only signs nonces that it has requested.
+    ///
+    /// 1. Node "node-b" issues a nonce to a connection that will claim to be "abcd"
+    /// 2. Have a peer connect to "node-a" and relay the nonce as "node-b", and verify that
+    ///    "node-a" does not sign it before the peer has proven its identity
+    /// 3. Verify that the peer can not prove another identity than the one it claimed, and that
+    ///    "node-a" still does not sign the nonce
+    /// 4. Have a peer connect to "node-a" and relay the nonce as "abcd", prove its identity as
+    ///    "abcd", and verify that "node-b" rejects the signature that "node-a" then provides
+    /// 5. Verify that "node-a" refuses to sign the nonce for its peer "node-m" before requesting
+    ///    one, or for another node than "node-m", on a connection it initiated
+    /// 6. Have "node-a" sign the relayed nonce for "node-m", and verify that "node-b" rejects the
+    ///    signature, and that "node-a" does not sign another nonce without a new request
+    /// 7. Have "node-a" sign a nonce for "node-b" on a connection it initiated to an unknown
+    ///    node, and verify that the peer can not authorize under another identity
+    #[test]
+    fn challenge_relayed_nonce_rejected() {
+        let (network_b, peer_id) = create_network_with_initial_temp_peer();
+        let node_b = AuthorizationManager::new_with_challenge(
+            network_b,
+            "node-b".into(),
+            create_challenge_authorization(),
+        );
+        assert_eq!(
+            Ok(AuthorizationState::Connecting),
+            node_b.next_state(&peer_id, AuthorizationAction::Connecting)
+        );
+        let nonce = match node_b.next_state(&peer_id, AuthorizationAction::ChallengeNonceRequesting)
+        {
+            Ok(AuthorizationState::ChallengeNonceSent(nonce)) => nonce,
+            res => panic!("Should have sent a challenge nonce but got {:?}", res),
+        };
+
+        let (network_a, inbound_peer_id) = create_network_with_initial_temp_peer();
+        let node_a = AuthorizationManager::new_with_challenge(
+            network_a,
+            "node-a".into(),
+            create_challenge_authorization(),
+        );
+        assert_eq!(
+            Ok(AuthorizationState::Connecting),
+            node_a.next_state(&inbound_peer_id, AuthorizationAction::Connecting)
+        );
+        node_a.request_challenge_nonce(&inbound_peer_id);
+        assert_eq!(
+            Ok(None),
+            node_a.sign_challenge_nonce(&inbound_peer_id, &nonce, "node-b")
+        );
+
+        let peer_nonce = match node_a.next_state(
+            &inbound_peer_id,
+            AuthorizationAction::ChallengeNonceRequesting,
+        ) {
+            Ok(AuthorizationState::ChallengeNonceSent(nonce)) => nonce,
+            res => panic!("Should have sent a challenge nonce but got {:?}", res),
+        };
+        let (public_key, signature) = sign_challenge(&peer_nonce, "node-a");
+        match node_a.next_state(
+            &inbound_peer_id,
+            AuthorizationAction::ChallengeSubmitting {
+                identity: "abcd".into(),
+                public_key,
+                signature,
+            },
+        ) {
+            Err(AuthorizationActionError::ChallengeFailed(_)) => (),
+            res => panic!("Should have failed the challenge but got {:?}", res),
+        }
+        assert!(!node_a.is_authorized("abcd"));
+        assert_eq!(Ok(None), node_a.sign_deferred_challenge_nonce("abcd"));
+        assert_eq!(
+            Ok(None),
+            node_a.sign_deferred_challenge_nonce(&inbound_peer_id)
+        );
+
+        let (network_a, inbound_peer_id) = create_network_with_initial_temp_peer();
+        let node_a = AuthorizationManager::new_with_challenge(
+            network_a,
+            "node-a".into(),
+            create_challenge_authorization(),
+        );
+        assert_eq!(
+            Ok(AuthorizationState::Connecting),
+            node_a.next_state(&inbound_peer_id, AuthorizationAction::Connecting)
+        );
+        node_a.request_challenge_nonce(&inbound_peer_id);
+        assert_eq!(
+            Ok(None),
+            node_a.sign_challenge_nonce(&inbound_peer_id, &nonce, "abcd")
+        );
+        let peer_nonce = match node_a.next_state(
+            &inbound_peer_id,
+            AuthorizationAction::ChallengeNonceRequesting,
+        ) {
+            Ok(AuthorizationState::ChallengeNonceSent(nonce)) => nonce,
+            res => panic!("Should have sent a challenge nonce but got {:?}", res),
+        };
+        let (public_key, signature) = sign_challenge(&peer_nonce, "node-a");
+        assert_eq!(
+            Ok(AuthorizationState::Authorized),
+            node_a.next_state(
+                &inbound_peer_id,
+                AuthorizationAction::ChallengeSubmitting {
+                    identity: "abcd".into(),
+                    public_key,
+                    signature,
+                },
+            )
+        );
+        let (public_key, signature) = node_a
+            .sign_deferred_challenge_nonce("abcd")
+            .expect("Unable to sign nonce")
+            .expect("Deferred nonce was not signed");
+        match node_b.next_state(
+            &peer_id,
+            AuthorizationAction::ChallengeSubmitting {
+                identity: "abcd".into(),
+                public_key,
+                signature,
+            },
+        ) {
+            Err(AuthorizationActionError::ChallengeFailed(_)) => (),
+            res => panic!("Should have failed the challenge but got {:?}", res),
+        }
+        assert!(!node_b.is_authorized("abcd"));
+        assert_eq!(Ok(None), node_a.sign_deferred_challenge_nonce("abcd"));
+
+        let node_a = AuthorizationManager::new_with_challenge(
+            create_network_with_peer("node-m"),
+            "abcd".into(),
+            create_challenge_authorization(),
+        );
+        match node_a.sign_challenge_nonce("node-m", &nonce, "node-m") {
+            Err(AuthorizationActionError::ChallengeFailed(_)) => (),
+            res => panic!("Should have refused to sign the nonce but got {:?}", res),
+        }
+
+        node_a.request_challenge_nonce("node-m");
+        match node_a.sign_challenge_nonce("node-m", &nonce, "node-b") {
+            Err(AuthorizationActionError::ChallengeFailed(_)) => (),
+            res => panic!("Should have refused to sign the nonce but got {:?}", res),
+        }
+
+        let (public_key, signature) = node_a
+            .sign_challenge_nonce("node-m", &nonce, "node-m")
+            .expect("Unable to sign nonce")
+            .expect("Nonce was not signed");
+        match node_b.next_state(
+            &peer_id,
+            AuthorizationAction::ChallengeSubmitting {
+                identity: "abcd".into(),
+                public_key,
+                signature,
+            },
+        ) {
+            Err(AuthorizationActionError::ChallengeFailed(_)) => (),
+            res => panic!("Should have failed the challenge but got {:?}", res),
+        }
+        assert!(!node_b.is_authorized("abcd"));
+
+        match node_a.sign_challenge_nonce("node-m", &nonce, "node-m") {
+            Err(AuthorizationActionError::ChallengeFailed(_)) => (),
+            res => panic!("Should have refused to sign the nonce but got {:?}", res),
+        }
+
+        let (network_a, temp_peer_id) = create_network_with_outbound_temp_peer();
+        let node_a = AuthorizationManager::new_with_challenge(
+            network_a,
+            "mock_identity".into(),
+            create_challenge_authorization(),
+        );
+        assert_eq!(
+            Ok(AuthorizationState::Connecting),
+            node_a.next_state(&temp_peer_id, AuthorizationAction::Connecting)
+        );
+        node_a.request_challenge_nonce(&temp_peer_id);
+        node_a
+            .sign_challenge_nonce(&temp_peer_id, &nonce, "node-b")
+            .expect("Unable to sign nonce")
+            .expect("Nonce was not signed");
+
+        let peer_nonce =
+            match node_a.next_state(&temp_peer_id, AuthorizationAction::ChallengeNonceRequesting) {
+                Ok(AuthorizationState::ChallengeNonceSent(nonce)) => nonce,
+                res => panic!("Should have sent a challenge nonce but got {:?}", res),
+            };
+        let (public_key, signature) = sign_challenge(&peer_nonce, "mock_identity");
+        match node_a.next_state(
+            &temp_peer_id,
+            AuthorizationAction::ChallengeSubmitting {
+                identity: "abcd".into(),
+                public_key,
+                signature,
+            },
+        ) {
+            Err(AuthorizationActionError::ChallengeFailed(_)) => (),
+            res => panic!("Should have failed the challenge but got {:?}", res),
+        }
+        assert!(!node_a.is_authorized("abcd"));
+    }
+
+    /// This test verifies that trust authorization is not accepted when challenge authorization
+    /// is enabled, and that challenge authorization is not accepted when it is not enabled.
+    #[test]
+    fn unaccepted_authorization_types() {
+        let (network, peer_id) = create_network_with_initial_temp_peer();
+        let auth_manager = AuthorizationManager::new_with_challenge(
+            network.clone(),
+            "mock_identity".into(),
+            create_challenge_authorization(),
+        );
+
+        assert_eq!(
+            Ok(AuthorizationState::Connecting),
+            auth_manager.next_state(&peer_id, AuthorizationAction::Connecting)
+        );
+        assert_eq!(
+            Err(AuthorizationActionError::UnacceptedAuthorizationType(
+                "Trust".into()
+            )),
+            auth_manager.next_state(
+                &peer_id,
+                AuthorizationAction::TrustIdentifying("abcd".into())
+            )
+        );
+        assert!(!auth_manager.is_authorized("abcd"));
+
+        let (network, peer_id) = create_network_with_initial_temp_peer();
+        let auth_manager = AuthorizationManager::new(network.clone(), "mock_identity".into());
+
+        assert_eq!(
+            Ok(AuthorizationState::Connecting),
+            auth_manager.next_state(&peer_id, AuthorizationAction::Connecting)
+        );
+        assert_eq!(
+            Err(AuthorizationActionError::UnacceptedAuthorizationType(
+                "Challenge".into()
+            )),
+            auth_manager.next_state(&peer_id, AuthorizationAction::ChallengeNonceRequesting)
+        );
+    }
+
+    /// This test verifies that, with certificate identity binding, a trusted identity is only
+    /// accepted if it matches the peer's certificate identities.
+    ///
+    /// 1. Claim an identity that is not named in the peer's certificate and verify it is rejected
+    /// 2. Claim an identity from a peer without a certificate and verify it is rejected
+    /// 3. Claim the identity named in the peer's certificate and verify it is authorized
+    #[test]
+    fn certificate_identity_binding_trust() {
+        let (network, peer_id) =
+            create_network_with_temp_peer(Some(vec!["abcd".into(), "abcd.example.com".into()]));
+        let auth_manager = AuthorizationManager::new(network.clone(), "mock_identity".into())
+            .with_certificate_identity_binding(CertificateIdentityBinding::new(Box::new(
+                MockNodeRegistry(vec![]),
+            )));
+
+        assert_eq!(
+            Ok(AuthorizationState::Connecting),
+            auth_manager.next_state(&peer_id, AuthorizationAction::Connecting)
+        );
+        match auth_manager.next_state(
+            &peer_id,
+            AuthorizationAction::TrustIdentifying("efgh".into()),
+        ) {
+            Err(AuthorizationActionError::CertificateIdentityMismatch(_)) => (),
+            res => panic!("Should have rejected the identity but got {:?}", res),
+        }
+        assert!(!auth_manager.is_authorized("efgh"));
+
+        let (uncertified_network, uncertified_peer_id) = create_network_with_initial_temp_peer();
+        let uncertified_auth_manager =
+            AuthorizationManager::new(uncertified_network, "mock_identity".into())
+                .with_certificate_identity_binding(CertificateIdentityBinding::new(Box::new(
+                    MockNodeRegistry(vec![]),
+                )));
+        assert_eq!(
+            Ok(AuthorizationState::Connecting),
+            uncertified_auth_manager
+                .next_state(&uncertified_peer_id, AuthorizationAction::Connecting)
+        );
+        match uncertified_auth_manager.next_state(
+            &uncertified_peer_id,
+            AuthorizationAction::TrustIdentifying("abcd".into()),
+        ) {
+            Err(AuthorizationActionError::CertificateIdentityMismatch(_)) => (),
+            res => panic!("Should have rejected the identity but got {:?}", res),
+        }
+        assert!(!uncertified_auth_manager.is_authorized("abcd"));
+
+        assert_eq!(
+            Ok(AuthorizationState::Authorized),
+            auth_manager.next_state(
+                &peer_id,
+                AuthorizationAction::TrustIdentifying("abcd".into())
+            )
+        );
+        assert!(auth_manager.is_authorized("abcd"));
+        assert_eq!(vec!["abcd".to_string()], network.peer_ids());
+    }
+
+    /// This test verifies that, with certificate identity binding, an identity is accepted if the
+    /// node registry maps it to one of the peer's certificate identities, and that the binding is
+    /// also applied to challenge authorization.
+    ///
+    /// 1. Submit a valid challenge for "abcd" from a peer whose certificate names the identity
+    ///    that the node registry maps to "abcd", and verify it is authorized
+    /// 2. Submit a valid challenge for "abcd" from a peer whose certificate names another
+    ///    identity, and verify it is rejected
+    /// 3. Submit a valid challenge for "abcd" from a peer whose transport authenticated one of
+    ///    the keys of "abcd" in the node registry, and verify it is authorized
+    #[test]
+    fn certificate_identity_binding_registry_mapping() {
+        let node = NodeBuilder::new("abcd")
+            .with_endpoint("tcp://localhost:8044")
+            .with_metadata(CERTIFICATE_IDENTITY_METADATA_KEY, "node-abcd.example.com")
+            .with_key("0a1b2c")
+            .build()
+            .expect("Unable to build node");
+
+        for &(cert_identity, expected_authorized) in &[
+            ("node-abcd.example.com", true),
+            ("node-efgh.example.com", false),
+            ("0a1b2c", true),
+        ] {
+            let (network, peer_id) =
+                create_network_with_temp_peer(Some(vec![cert_identity.into()]));
+            let auth_manager = AuthorizationManager::new_with_challenge(
+                network.clone(),
+                "mock_identity".into(),
+                create_challenge_authorization(),
+            )
+            .with_certificate_identity_binding(CertificateIdentityBinding::new(Box::new(
+                MockNodeRegistry(vec![node.clone()]),
+            )));
+
+            assert_eq!(
+                Ok(AuthorizationState::Connecting),
+                auth_manager.next_state(&peer_id, AuthorizationAction::Connecting)
+            );
+            let nonce = match auth_manager
+                .next_state(&peer_id, AuthorizationAction::ChallengeNonceRequesting)
+            {
+                Ok(AuthorizationState::ChallengeNonceSent(nonce)) => nonce,
+                res => panic!("Should have sent a challenge nonce but got {:?}", res),
+            };
+            let (public_key, signature) = sign_challenge(&nonce, "mock_identity");
+
+            let result = auth_manager.next_state(
+                &peer_id,
+                AuthorizationAction::ChallengeSubmitting {
+                    identity: "abcd".into(),
+                    public_key,
+                    signature,
+                },
+            );
+            if expected_authorized {
+                assert_eq!(Ok(AuthorizationState::Authorized), result);
+            } else {
+                match result {
+                    Err(AuthorizationActionError::CertificateIdentityMismatch(_)) => (),
+                    res => panic!("Should have rejected the identity but got {:?}", res),
+                }
+            }
+            assert_eq!(expected_authorized, auth_manager.is_authorized("abcd"));
+        }
+    }
+
+    /// Creates a challenge authorization where the node "abcd" is registered with the key of the
+    /// HashSigner.
+    pub(super) fn create_challenge_authorization() -> ChallengeAuthorization {
+        let node = NodeBuilder::new("abcd")
+            .with_endpoint("tcp://localhost:8044")
+            .with_key(to_hex(HashSigner.public_key()))
+            .build()
+            .expect("Unable to build node");
+
+        ChallengeAuthorization::new(
+            Box::new(HashSigner),
+            Box::new(HashVerifier),
+            Box::new(MockNodeRegistry(vec![node])),
+        )
+    }
+
+    /// Signs the challenge message for the nonce and the verifier's identity with the HashSigner,
+    /// returning the public key and the signature.
+    fn sign_challenge(nonce: &[u8], verifier_identity: &str) -> (Vec<u8>, Vec<u8>) {
+        let signature = HashSigner
+            .sign(&challenge_message(nonce, verifier_identity))
+            .expect("Unable to sign nonce");
+        (HashSigner.public_key().to_vec(), signature)
+    }
+
+    struct MockNodeRegistry(Vec<Node>);
+
+    impl NodeRegistryReader for MockNodeRegistry {
+        fn list_nodes<'a, 'b: 'a>(
+            &'b self,
+            _predicates: &'a [MetadataPredicate],
+        ) -> Result<Box<dyn Iterator<Item = Node> + Send + 'a>, NodeRegistryError> {
+            Ok(Box::new(self.0.clone().into_iter()))
+        }
+
+        fn count_nodes(&self, _predicates: &[MetadataPredicate]) -> Result<u32, NodeRegistryError> {
+            Ok(self.0.len() as u32)
+        }
+
+        fn fetch_node(&self, identity: &str) -> Result<Option<Node>, NodeRegistryError> {
+            Ok(self
+                .0
+                .iter()
+                .find(|node| node.identity == identity)
+                .cloned())
+        }
+    }
+
     fn create_network_with_initial_temp_peer() -> (Network, String) {
         let network = Network::new(Mesh::new(5, 5), 0).unwrap();
 
@@ -580,6 +1590,43 @@ mod tests {
         (network, peer_id)
     }
 
+    /// Creates a network with a single temp peer, whose connection was initiated by this node.
+    fn create_network_with_outbound_temp_peer() -> (Network, String) {
+        let network = Network::new(Mesh::new(5, 5), 0).unwrap();
+
+        let peer_id = network
+            .add_outbound_connection(Box::new(MockConnection(None)))
+            .expect("Unable to add connection to network");
+
+        (network, peer_id)
+    }
+
+    /// Creates a network with a single peer, whose node ID is known.
+    fn create_network_with_peer(peer_id: &str) -> Network {
+        let network = Network::new(Mesh::new(5, 5), 0).unwrap();
+
+        network
+            .add_peer(peer_id.to_string(), Box::new(MockConnection(None)))
+            .expect("Unable to add peer to network");
+
+        network
+    }
+
+    /// Creates a network with a single temp peer, whose connection was authenticated with the
+    /// given identities.
+    fn create_network_with_temp_peer(peer_identities: Option<Vec<String>>) -> (Network, String) {
+        let network = Network::new(Mesh::new(5, 5), 0).unwrap();
+
+        network
+            .add_connection(Box::new(MockConnection(peer_identities)))
+            .expect("Unable to add connection to network");
+
+        // We only have one peer, so we can grab this id as the temp id.
+        let peer_id = network.peer_ids()[0].clone();
+
+        (network, peer_id)
+    }
+
     struct MockConnectingTransport;
 
     impl Transport for MockConnectingTransport {
@@ -588,7 +1635,7 @@ mod tests {
         }
 
         fn connect(&mut self, _: &str) -> Result<Box<dyn Connection>, ConnectError> {
-            Ok(Box::new(MockConnection))
+            Ok(Box::new(MockConnection(None)))
         }
 
         fn listen(
@@ -599,7 +1646,7 @@ mod tests {
         }
     }
 
-    struct MockConnection;
+    struct MockConnection(Option<Vec<String>>);
 
     impl Connection for MockConnection {
         fn send(&mut self, _message: &[u8]) -> Result<(), SendError> {
@@ -625,6 +1672,10 @@ mod tests {
         fn evented(&self) -> &dyn mio::Evented {
             &MockEvented
         }
+
+        fn peer_identities(&self) -> Option<Vec<String>> {
+            self.0.clone()
+        }
     }
 
     struct MockEvented;
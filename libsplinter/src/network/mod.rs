@@ -32,7 +32,7 @@ pub mod sender;
 use protobuf::Message;
 use uuid::Uuid;
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::sync::{Arc, Mutex, RwLock};
 use std::thread;
 use std::time::Duration;
@@ -45,6 +45,15 @@ use crate::mesh::{
 use crate::protos::network::{NetworkHeartbeat, NetworkMessage, NetworkMessageType};
 use crate::transport::Connection;
 
+/// The prefix of the peer ids given to connections until they have completed authorization.
+const TEMP_PEER_ID_PREFIX: &str = "temp-";
+
+/// Indicates whether or not the peer id is a temporary id, given to a connection whose node id is
+/// not known until it has completed authorization.
+pub fn is_temp_peer_id(peer_id: &str) -> bool {
+    peer_id.starts_with(TEMP_PEER_ID_PREFIX)
+}
+
 #[derive(Debug)]
 pub struct NetworkMessageWrapper {
     peer_id: String,
@@ -83,6 +92,8 @@ struct PeerMap {
     peers: BiHashMap<String, String>,
     redirects: HashMap<String, String>,
     endpoints: BiHashMap<String, String>,
+    identities: HashMap<String, Vec<String>>,
+    outbound: HashSet<String>,
 }
 
 /// A map of Peer IDs to mesh IDs, which also maintains a redirect table for updated peer ids.
@@ -92,6 +103,8 @@ impl PeerMap {
             peers: BiHashMap::new(),
             redirects: HashMap::new(),
             endpoints: BiHashMap::new(),
+            identities: HashMap::new(),
+            outbound: HashSet::new(),
         }
     }
 
@@ -105,18 +118,36 @@ impl PeerMap {
             .collect()
     }
 
-    /// Insert a new peer id for a given mesh id
-    fn insert(&mut self, peer_id: String, mesh_id: String, endpoint: String) {
+    /// Insert a new peer id for a given mesh id, along with the identities its connection was
+    /// authenticated with, if any, and whether this node initiated the connection
+    fn insert(
+        &mut self,
+        peer_id: String,
+        mesh_id: String,
+        endpoint: String,
+        identities: Option<Vec<String>>,
+        outbound: bool,
+    ) {
         self.peers.insert(peer_id.clone(), mesh_id);
+        if let Some(identities) = identities {
+            self.identities.insert(peer_id.clone(), identities);
+        }
+        if outbound {
+            self.outbound.insert(peer_id.clone());
+        } else {
+            self.outbound.remove(&peer_id);
+        }
         self.endpoints.insert(peer_id, endpoint);
     }
 
-    /// Remove a peer id, its endpoint and all of its redirects
+    /// Remove a peer id, its endpoint, its identities and all of its redirects
     fn remove(&mut self, peer_id: &str) -> Option<String> {
         info!("Removing peer: {}", peer_id);
         self.redirects
             .retain(|_, target_peer_id| target_peer_id != peer_id);
         self.endpoints.remove_by_key(peer_id);
+        self.identities.remove(peer_id);
+        self.outbound.remove(peer_id);
         self.peers
             .remove_by_key(peer_id)
             .map(|(_, mesh_id)| mesh_id)
@@ -132,6 +163,12 @@ impl PeerMap {
             if let Some((_, endpoint)) = self.endpoints.remove_by_key(&old_peer_id) {
                 self.endpoints.insert(new_peer_id.clone(), endpoint);
             }
+            if let Some(identities) = self.identities.remove(&old_peer_id) {
+                self.identities.insert(new_peer_id.clone(), identities);
+            }
+            if self.outbound.remove(&old_peer_id) {
+                self.outbound.insert(new_peer_id.clone());
+            }
             // update the old forwards
             for (_, v) in self
                 .redirects
@@ -179,6 +216,25 @@ impl PeerMap {
     fn get_peer_by_endpoint(&self, endpoint: &str) -> Option<String> {
         self.endpoints.get_by_value(endpoint).cloned()
     }
+
+    /// Returns the identities the connection for the given peer id was authenticated with
+    fn get_peer_identities(&self, peer_id: &str) -> Option<Vec<String>> {
+        self.redirects
+            .get(peer_id)
+            .and_then(|target_peer_id| self.identities.get(target_peer_id))
+            .or_else(|| self.identities.get(peer_id))
+            .cloned()
+    }
+
+    /// Returns whether this node initiated the connection for the given peer id
+    fn is_outbound(&self, peer_id: &str) -> bool {
+        let target_peer_id = self
+            .redirects
+            .get(peer_id)
+            .map(String::as_str)
+            .unwrap_or(peer_id);
+        self.outbound.contains(target_peer_id)
+    }
 }
 
 #[derive(Clone)]
@@ -239,6 +295,19 @@ impl Network {
         rwlock_read_unwrap!(self.peers).get_peer_by_endpoint(endpoint)
     }
 
+    /// Returns the identities that the peer's connection was authenticated with by its transport,
+    /// such as the names in a verified TLS certificate.
+    ///
+    /// Returns `None` if the peer is unknown, or if its transport does not authenticate peers.
+    pub fn get_peer_identities(&self, peer_id: &str) -> Option<Vec<String>> {
+        rwlock_read_unwrap!(self.peers).get_peer_identities(peer_id)
+    }
+
+    /// Returns whether this node initiated the connection to the peer, rather than accepting it.
+    pub fn is_outbound(&self, peer_id: &str) -> bool {
+        rwlock_read_unwrap!(self.peers).is_outbound(peer_id)
+    }
+
     pub fn add_disconnect_listener(&self, listener: Box<dyn DisconnectListener>) {
         match self.disconnect_listeners.lock() {
             Ok(mut listeners) => {
@@ -261,17 +330,37 @@ impl Network {
         }
     }
 
+    /// Adds a connection that was accepted by this node, under a temporary peer id.
     pub fn add_connection(
         &self,
         connection: Box<dyn Connection>,
+    ) -> Result<String, ConnectionError> {
+        self.add_temp_peer(connection, false)
+    }
+
+    /// Adds a connection that this node initiated, under a temporary peer id.
+    ///
+    /// This is used when the node id of the peer at the remote endpoint is not known yet.
+    pub fn add_outbound_connection(
+        &self,
+        connection: Box<dyn Connection>,
+    ) -> Result<String, ConnectionError> {
+        self.add_temp_peer(connection, true)
+    }
+
+    fn add_temp_peer(
+        &self,
+        connection: Box<dyn Connection>,
+        outbound: bool,
     ) -> Result<String, ConnectionError> {
         let mut peers = rwlock_write_unwrap!(self.peers);
         let endpoint = connection.remote_endpoint();
+        let identities = connection.peer_identities();
         let mesh_id = format!("{}", Uuid::new_v4());
         self.mesh.add(connection, mesh_id.clone())?;
         // Temp peer id until the connection has completed authorization
-        let peer_id = format!("temp-{}", Uuid::new_v4());
-        peers.insert(peer_id.clone(), mesh_id, endpoint);
+        let peer_id = format!("{}{}", TEMP_PEER_ID_PREFIX, Uuid::new_v4());
+        peers.insert(peer_id.clone(), mesh_id, endpoint, identities, outbound);
         Ok(peer_id)
     }
 
@@ -289,7 +378,7 @@ impl Network {
         Ok(())
     }
 
-    /// Adds a peer with a given id.
+    /// Adds a peer with a given id, whose connection was initiated by this node.
     ///
     /// Note that while this peer id is specified explicitly, the connection will still require to
     /// complete the authorization handshake, at which point its node id that the remote connection
@@ -302,9 +391,10 @@ impl Network {
         // we already know the peers unique id
         let mut peers = rwlock_write_unwrap!(self.peers);
         let endpoint = connection.remote_endpoint();
+        let identities = connection.peer_identities();
         let mesh_id = format!("{}", Uuid::new_v4());
         self.mesh.add(connection, mesh_id.clone())?;
-        peers.insert(peer_id, mesh_id, endpoint);
+        peers.insert(peer_id, mesh_id, endpoint, identities, true);
         Ok(())
     }
 
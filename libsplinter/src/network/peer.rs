@@ -153,7 +153,7 @@ impl PeerConnector {
         debug!("Successfully connected to {}", connection.remote_endpoint());
         let temp_peer_id = self
             .network
-            .add_connection(connection)
+            .add_outbound_connection(connection)
             .map_err(|err| PeerConnectorError::add_peer_failed(endpoint, err.to_string()))?;
 
         let connect_request_msg_bytes = create_connect_request().map_err(|err| {
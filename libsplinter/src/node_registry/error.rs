@@ -83,6 +83,7 @@ pub enum InvalidNodeError {
     EmptyEndpoint,
     EmptyIdentity,
     EmptyDisplayName,
+    EmptyKey,
     InvalidIdentity(String, String), // (identity, message)
     MissingEndpoints,
 }
@@ -95,6 +96,7 @@ impl Error for InvalidNodeError {
             InvalidNodeError::EmptyEndpoint => None,
             InvalidNodeError::EmptyIdentity => None,
             InvalidNodeError::EmptyDisplayName => None,
+            InvalidNodeError::EmptyKey => None,
             InvalidNodeError::InvalidIdentity(..) => None,
             InvalidNodeError::MissingEndpoints => None,
         }
@@ -115,6 +117,7 @@ impl fmt::Display for InvalidNodeError {
             InvalidNodeError::EmptyDisplayName => {
                 write!(f, "node must have non-empty display_name")
             }
+            InvalidNodeError::EmptyKey => write!(f, "node key cannot be empty"),
             InvalidNodeError::InvalidIdentity(identity, msg) => {
                 write!(f, "identity {} is invalid: {}", identity, msg)
             }
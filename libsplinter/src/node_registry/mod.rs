@@ -58,6 +58,9 @@ pub struct Node {
     pub display_name: String,
     /// A map with node metadata.
     pub metadata: HashMap<String, String>,
+    /// The hex-encoded public keys that the node may use to prove its identity when connecting.
+    #[serde(default)]
+    pub keys: Vec<String>,
 }
 
 /// A builder for creating new nodes.
@@ -66,6 +69,7 @@ pub struct NodeBuilder {
     endpoints: Vec<String>,
     display_name: Option<String>,
     metadata: HashMap<String, String>,
+    keys: Vec<String>,
 }
 
 impl NodeBuilder {
@@ -76,6 +80,7 @@ impl NodeBuilder {
             endpoints: vec![],
             display_name: None,
             metadata: HashMap::new(),
+            keys: vec![],
         }
     }
 
@@ -103,6 +108,18 @@ impl NodeBuilder {
         self
     }
 
+    /// Add the hex-encoded public `key` to the builder.
+    pub fn with_key<S: Into<String>>(mut self, key: S) -> Self {
+        self.keys.push(key.into());
+        self
+    }
+
+    /// Add all of the hex-encoded public `keys` to the builder.
+    pub fn with_keys<V: Into<Vec<String>>>(mut self, keys: V) -> Self {
+        self.keys.append(&mut keys.into());
+        self
+    }
+
     /// Attempt to build the `Node`.
     pub fn build(self) -> Result<Node, InvalidNodeError> {
         let identity = self.identity;
@@ -115,6 +132,7 @@ impl NodeBuilder {
             endpoints: self.endpoints,
             display_name,
             metadata: self.metadata,
+            keys: self.keys,
         };
 
         check_node_required_fields_are_not_empty(&node)?;
@@ -318,6 +336,8 @@ fn check_node_required_fields_are_not_empty(node: &Node) -> Result<(), InvalidNo
         Err(InvalidNodeError::EmptyEndpoint)
     } else if node.display_name.is_empty() {
         Err(InvalidNodeError::EmptyDisplayName)
+    } else if node.keys.iter().any(|key| key.is_empty()) {
+        Err(InvalidNodeError::EmptyKey)
     } else {
         Ok(())
     }
@@ -380,6 +380,30 @@ mod test {
         })
     }
 
+    ///
+    /// Verifies that reading from a YAML file that contains a node with an empty string in its
+    /// keys returns InvalidNodeError::EmptyKey.
+    ///
+    #[test]
+    fn test_read_yaml_empty_key_error() {
+        run_test(|test_yaml_file_path| {
+            let mut node = get_node_1();
+            node.keys = vec!["".to_string()];
+
+            write_to_file(&vec![node], test_yaml_file_path);
+
+            let result = LocalYamlNodeRegistry::new(test_yaml_file_path);
+            match result {
+                Ok(_) => panic!("Node with empty key in YAML file. Error should be returned"),
+                Err(NodeRegistryError::InvalidNode(InvalidNodeError::EmptyKey)) => {}
+                Err(err) => panic!(
+                    "Should have gotten InvalidNodeError::EmptyKey but got {}",
+                    err
+                ),
+            }
+        })
+    }
+
     ///
     /// Verifies that reading from a YAML file that contains a node with no endpoints returns
     /// InvalidNodeError::MissingEndpoints.
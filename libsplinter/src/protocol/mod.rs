@@ -40,6 +40,10 @@ pub(crate) const ADMIN_FETCH_NODE_MIN: u32 = 1;
 pub(crate) const ADMIN_LIST_KEYS_MIN: u32 = 1;
 #[cfg(feature = "rest-api")]
 pub(crate) const ADMIN_FETCH_KEY_MIN: u32 = 1;
+#[cfg(feature = "rest-api")]
+pub(crate) const ADMIN_LIST_KEY_ROLES_MIN: u32 = 1;
+#[cfg(feature = "rest-api")]
+pub(crate) const ADMIN_MODIFY_KEY_ROLE_MIN: u32 = 1;
 
 pub const SCABBARD_PROTOCOL_VERSION: u32 = 1;
 
@@ -49,11 +53,23 @@ pub(crate) const SCABBARD_SUBSCRIBE_PROTOCOL_MIN: u32 = 1;
 pub(crate) const SCABBARD_ADD_BATCHES_PROTOCOL_MIN: u32 = 1;
 #[cfg(feature = "rest-api")]
 pub(crate) const SCABBARD_BATCH_STATUSES_PROTOCOL_MIN: u32 = 1;
+#[cfg(feature = "rest-api")]
+pub(crate) const SCABBARD_GET_RECEIPT_PROTOCOL_MIN: u32 = 1;
+#[cfg(feature = "rest-api")]
+pub(crate) const SCABBARD_LIST_RECEIPTS_PROTOCOL_MIN: u32 = 1;
+#[cfg(feature = "rest-api")]
+pub(crate) const SCABBARD_GET_PRUNING_PROTOCOL_MIN: u32 = 1;
+#[cfg(feature = "rest-api")]
+pub(crate) const SCABBARD_PRUNE_PROTOCOL_MIN: u32 = 1;
+#[cfg(feature = "rest-api")]
+pub(crate) const SCABBARD_GET_STATUS_PROTOCOL_MIN: u32 = 1;
 
 #[cfg(all(feature = "scabbard-get-state", feature = "rest-api"))]
 pub(crate) const SCABBARD_GET_STATE_PROTOCOL_MIN: u32 = 1;
 #[cfg(all(feature = "scabbard-get-state", feature = "rest-api"))]
 pub(crate) const SCABBARD_LIST_STATE_PROTOCOL_MIN: u32 = 1;
+#[cfg(all(feature = "scabbard-get-state", feature = "rest-api"))]
+pub(crate) const SCABBARD_LIST_STATE_ROOTS_PROTOCOL_MIN: u32 = 1;
 
 #[cfg(feature = "biome")]
 pub const BIOME_PROTOCOL_VERSION: u32 = 1;
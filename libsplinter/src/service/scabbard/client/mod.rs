@@ -21,7 +21,7 @@ use transact::protocol::batch::Batch;
 use crate::hex::parse_hex;
 use crate::protocol::SCABBARD_PROTOCOL_VERSION;
 
-use super::SERVICE_TYPE;
+use super::{Receipt, ScabbardStatus, SERVICE_TYPE};
 
 pub use error::Error;
 use submit::{submit_batches, wait_for_batches};
@@ -59,14 +59,52 @@ impl ScabbardClient {
         }
     }
 
+    /// Get the status of the specified scabbard service, including the number of batches waiting
+    /// in its queue. Submitters can use this to throttle their submissions.
+    pub fn get_status(&self, service_id: &ServiceId) -> Result<ScabbardStatus, Error> {
+        let url = Url::parse(&format!(
+            "{}/{}/{}/{}/status",
+            &self.url,
+            SERVICE_TYPE,
+            service_id.circuit(),
+            service_id.service_id()
+        ))
+        .map_err(|err| Error::new_with_source("invalid URL", err.into()))?;
+
+        let request = Client::new().get(url);
+        let response = request
+            .header("SplinterProtocolVersion", SCABBARD_PROTOCOL_VERSION)
+            .send()
+            .map_err(|err| Error::new_with_source("request failed", err.into()))?;
+
+        if response.status().is_success() {
+            response.json().map_err(|err| {
+                Error::new_with_source("failed to deserialize response body", err.into())
+            })
+        } else {
+            let status = response.status();
+            let msg: ErrorResponse = response.json().map_err(|err| {
+                Error::new_with_source("failed to deserialize error response body", err.into())
+            })?;
+            Err(Error::new(&format!(
+                "failed to get status: {}: {}",
+                status, msg
+            )))
+        }
+    }
+
+    /// Get the value at the given address in the state of the specified scabbard service. If
+    /// `state_root` is provided, the value as of that state root is returned; otherwise, the
+    /// current value is returned.
     pub fn get_state_at_address(
         &self,
         service_id: &ServiceId,
         address: &str,
+        state_root: Option<&str>,
     ) -> Result<Option<Vec<u8>>, Error> {
         parse_hex(address).map_err(|err| Error::new_with_source("invalid address", err.into()))?;
 
-        let url = Url::parse(&format!(
+        let mut url = Url::parse(&format!(
             "{}/{}/{}/{}/state/{}",
             &self.url,
             SERVICE_TYPE,
@@ -75,6 +113,11 @@ impl ScabbardClient {
             address
         ))
         .map_err(|err| Error::new_with_source("invalid URL", err.into()))?;
+        if let Some(state_root) = state_root {
+            parse_hex(state_root)
+                .map_err(|err| Error::new_with_source("invalid state root", err.into()))?;
+            url.set_query(Some(&format!("state_root={}", state_root)));
+        }
 
         let request = Client::new().get(url);
         let response = request
@@ -100,10 +143,14 @@ impl ScabbardClient {
         }
     }
 
+    /// Get all entries under the given address prefix (or all entries if `None`) in the state
+    /// of the specified scabbard service. If `state_root` is provided, the entries as of that
+    /// state root are returned; otherwise, the current entries are returned.
     pub fn get_state_with_prefix(
         &self,
         service_id: &ServiceId,
         prefix: Option<&str>,
+        state_root: Option<&str>,
     ) -> Result<Vec<StateEntry>, Error> {
         let mut url = Url::parse(&format!(
             "{}/{}/{}/{}/state",
@@ -119,7 +166,12 @@ impl ScabbardClient {
             if prefix.len() > 70 {
                 return Err(Error::new("prefix must be less than 70 characters"));
             }
-            url.set_query(Some(&format!("prefix={}", prefix)))
+            url.query_pairs_mut().append_pair("prefix", prefix);
+        }
+        if let Some(state_root) = state_root {
+            parse_hex(state_root)
+                .map_err(|err| Error::new_with_source("invalid state root", err.into()))?;
+            url.query_pairs_mut().append_pair("state_root", state_root);
         }
 
         let request = Client::new().get(url);
@@ -143,6 +195,137 @@ impl ScabbardClient {
             )))
         }
     }
+
+    /// List up to `limit` of the state roots most recently committed by the specified scabbard
+    /// service, newest first, along with the IDs of the batches that produced them.
+    pub fn list_state_roots(
+        &self,
+        service_id: &ServiceId,
+        limit: Option<usize>,
+    ) -> Result<Vec<StateRootEntry>, Error> {
+        let mut url = Url::parse(&format!(
+            "{}/{}/{}/{}/state_roots",
+            &self.url,
+            SERVICE_TYPE,
+            service_id.circuit(),
+            service_id.service_id()
+        ))
+        .map_err(|err| Error::new_with_source("invalid URL", err.into()))?;
+        if let Some(limit) = limit {
+            url.set_query(Some(&format!("limit={}", limit)));
+        }
+
+        let request = Client::new().get(url);
+        let response = request
+            .header("SplinterProtocolVersion", SCABBARD_PROTOCOL_VERSION)
+            .send()
+            .map_err(|err| Error::new_with_source("request failed", err.into()))?;
+
+        if response.status().is_success() {
+            response.json().map_err(|err| {
+                Error::new_with_source("failed to deserialize response body", err.into())
+            })
+        } else {
+            let status = response.status();
+            let msg: ErrorResponse = response.json().map_err(|err| {
+                Error::new_with_source("failed to deserialize error response body", err.into())
+            })?;
+            Err(Error::new(&format!(
+                "failed to list state roots: {}: {}",
+                status, msg
+            )))
+        }
+    }
+
+    /// Get the receipt of the transaction with the given ID from the specified scabbard service,
+    /// which includes the state changes, events, and data produced by the transaction. Returns
+    /// `None` if the transaction has not been committed.
+    pub fn get_receipt(
+        &self,
+        service_id: &ServiceId,
+        transaction_id: &str,
+    ) -> Result<Option<Receipt>, Error> {
+        let url = Url::parse(&format!(
+            "{}/{}/{}/{}/receipts/{}",
+            &self.url,
+            SERVICE_TYPE,
+            service_id.circuit(),
+            service_id.service_id(),
+            transaction_id
+        ))
+        .map_err(|err| Error::new_with_source("invalid URL", err.into()))?;
+
+        let request = Client::new().get(url);
+        let response = request
+            .header("SplinterProtocolVersion", SCABBARD_PROTOCOL_VERSION)
+            .send()
+            .map_err(|err| Error::new_with_source("request failed", err.into()))?;
+
+        if response.status().is_success() {
+            Ok(Some(response.json().map_err(|err| {
+                Error::new_with_source("failed to deserialize response body", err.into())
+            })?))
+        } else if response.status().as_u16() == 404 {
+            Ok(None)
+        } else {
+            let status = response.status();
+            let msg: ErrorResponse = response.json().map_err(|err| {
+                Error::new_with_source("failed to deserialize error response body", err.into())
+            })?;
+            Err(Error::new(&format!(
+                "failed to get receipt: {}: {}",
+                status, msg
+            )))
+        }
+    }
+
+    /// List a page of the transaction receipts committed by the specified scabbard service, in
+    /// the order they were committed. The page starts at `offset` (default is 0) and includes up
+    /// to `limit` receipts (default is 100).
+    pub fn list_receipts(
+        &self,
+        service_id: &ServiceId,
+        offset: Option<usize>,
+        limit: Option<usize>,
+    ) -> Result<ReceiptPage, Error> {
+        let mut url = Url::parse(&format!(
+            "{}/{}/{}/{}/receipts",
+            &self.url,
+            SERVICE_TYPE,
+            service_id.circuit(),
+            service_id.service_id()
+        ))
+        .map_err(|err| Error::new_with_source("invalid URL", err.into()))?;
+        if let Some(offset) = offset {
+            url.query_pairs_mut()
+                .append_pair("offset", &offset.to_string());
+        }
+        if let Some(limit) = limit {
+            url.query_pairs_mut()
+                .append_pair("limit", &limit.to_string());
+        }
+
+        let request = Client::new().get(url);
+        let response = request
+            .header("SplinterProtocolVersion", SCABBARD_PROTOCOL_VERSION)
+            .send()
+            .map_err(|err| Error::new_with_source("request failed", err.into()))?;
+
+        if response.status().is_success() {
+            response.json().map_err(|err| {
+                Error::new_with_source("failed to deserialize response body", err.into())
+            })
+        } else {
+            let status = response.status();
+            let msg: ErrorResponse = response.json().map_err(|err| {
+                Error::new_with_source("failed to deserialize error response body", err.into())
+            })?;
+            Err(Error::new(&format!(
+                "failed to list receipts: {}: {}",
+                status, msg
+            )))
+        }
+    }
 }
 
 /// A fully-qualified service ID (circuit and service ID)
@@ -210,6 +393,71 @@ impl StateEntry {
     }
 }
 
+/// A state root committed by a scabbard service
+#[derive(Deserialize, Debug)]
+pub struct StateRootEntry {
+    state_root: String,
+    batch_ids: Vec<String>,
+    timestamp: u64,
+}
+
+impl StateRootEntry {
+    pub fn state_root(&self) -> &str {
+        &self.state_root
+    }
+
+    /// The IDs of the batches that were committed to produce the state root; empty if the state
+    /// root was synced from a peer.
+    pub fn batch_ids(&self) -> &[String] {
+        &self.batch_ids
+    }
+
+    /// The time the state root was committed, in milliseconds since the Unix epoch.
+    pub fn timestamp(&self) -> u64 {
+        self.timestamp
+    }
+}
+
+/// A page of the transaction receipts committed by a scabbard service
+#[derive(Deserialize, Debug)]
+pub struct ReceiptPage {
+    data: Vec<Receipt>,
+    paging: PageInfo,
+}
+
+impl ReceiptPage {
+    /// The receipts in this page, in the order they were committed
+    pub fn receipts(&self) -> &[Receipt] {
+        &self.data
+    }
+
+    /// The position of the first receipt in this page among all of the service's receipts
+    pub fn offset(&self) -> usize {
+        self.paging.offset
+    }
+
+    /// The total number of receipts committed by the service
+    pub fn total(&self) -> usize {
+        self.paging.total
+    }
+
+    /// The offset of the next page, or `None` if this is the last page.
+    pub fn next_offset(&self) -> Option<usize> {
+        let next_offset = self.paging.offset + self.data.len();
+        if self.data.is_empty() || next_offset >= self.paging.total {
+            None
+        } else {
+            Some(next_offset)
+        }
+    }
+}
+
+#[derive(Deserialize, Debug)]
+struct PageInfo {
+    offset: usize,
+    total: usize,
+}
+
 #[derive(Deserialize, Debug)]
 struct ErrorResponse {
     message: String,
@@ -238,4 +486,33 @@ mod tests {
         assert_eq!(service_id.circuit(), "circuit");
         assert_eq!(service_id.service_id(), "service_id");
     }
+
+    /// Verify that a `ReceiptPage` can be deserialized from a list receipts response, and that
+    /// the offset of the next page is only given if there are more receipts.
+    #[test]
+    fn receipt_page_next_offset() {
+        let page: ReceiptPage = serde_json::from_str(
+            r#"{
+                "data": [{
+                    "transaction_id": "ab",
+                    "state_changes": [{"Set": {"key": "0011", "value": [1]}}],
+                    "events": [],
+                    "data": []
+                }],
+                "paging": {"offset": 1, "limit": 1, "total": 3}
+            }"#,
+        )
+        .expect("failed to parse page");
+        assert_eq!(page.receipts().len(), 1);
+        assert_eq!(page.receipts()[0].transaction_id, "ab");
+        assert_eq!(page.offset(), 1);
+        assert_eq!(page.total(), 3);
+        assert_eq!(page.next_offset(), Some(2));
+
+        let page: ReceiptPage = serde_json::from_str(
+            r#"{"data": [], "paging": {"offset": 3, "limit": 1, "total": 3}}"#,
+        )
+        .expect("failed to parse page");
+        assert_eq!(page.next_offset(), None);
+    }
 }
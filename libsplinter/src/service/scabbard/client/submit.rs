@@ -21,7 +21,7 @@ use std::{
 
 use reqwest::{
     blocking::{Client, RequestBuilder, Response},
-    Url,
+    StatusCode, Url,
 };
 
 use transact::{protocol::batch::Batch, protos::IntoBytes};
@@ -45,8 +45,29 @@ pub fn submit_batches(
     let body = batches.into_bytes()?;
 
     debug!("Submitting batches via {}", url);
-    let request = Client::new().post(url).body(body);
-    let response = perform_request(request)?;
+    let response = Client::new()
+        .post(url)
+        .body(body)
+        .header("SplinterProtocolVersion", SCABBARD_PROTOCOL_VERSION)
+        .send()
+        .map_err(|err| Error::new_with_source("request failed", err.into()))?;
+
+    // The service rejects batches while its queue is full; report when to retry
+    if response.status() == StatusCode::TOO_MANY_REQUESTS {
+        let retry_after = response
+            .headers()
+            .get("Retry-After")
+            .and_then(|value| value.to_str().ok())
+            .unwrap_or("a few");
+        return Err(Error::new(&format!(
+            "batch queue is full; retry after {} seconds",
+            retry_after
+        )));
+    }
+
+    let response = response
+        .error_for_status()
+        .map_err(|err| Error::new_with_source("received error status code", err.into()))?;
 
     let batch_link: Link = response.json().map_err(|err| {
         Error::new_with_source("failed to parse response as batch link", err.into())
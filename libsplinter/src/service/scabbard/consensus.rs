@@ -19,8 +19,10 @@ use std::thread::{Builder, JoinHandle};
 use std::time::Duration;
 
 use protobuf::Message;
-use transact::protos::IntoBytes;
+use transact::protocol::batch::BatchPair;
+use transact::protos::{FromBytes, IntoBytes, ProtoConversionError};
 
+use crate::consensus::raft::{RaftEngine, RaftPersistentState};
 use crate::consensus::two_phase::TwoPhaseEngine;
 use crate::consensus::{
     error::{ConsensusSendError, ProposalManagerError},
@@ -32,6 +34,48 @@ use crate::protos::scabbard::{ProposedBatch, ScabbardMessage, ScabbardMessage_Ty
 use super::error::{ScabbardConsensusManagerError, ScabbardError};
 use super::shared::ScabbardShared;
 use super::state::ScabbardState;
+use super::storage::ScabbardStorage;
+use super::sync::request_state_sync;
+
+/// The consensus algorithm used by a scabbard service
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ConsensusType {
+    /// Two-phase commit, which requires every service to verify each proposal
+    TwoPhase,
+    /// Raft, which commits a proposal once it has been replicated to a majority of services
+    Raft {
+        /// How long a follower waits to hear from the leader before starting an election
+        election_timeout: Duration,
+    },
+}
+
+impl Default for ConsensusType {
+    fn default() -> Self {
+        ConsensusType::TwoPhase
+    }
+}
+
+const DEFAULT_MAX_BATCHES_PER_PROPOSAL: usize = 100;
+const DEFAULT_MAX_PROPOSAL_BYTES: usize = 10 * 1024 * 1024; // 10 MiB
+
+/// Limits on the batches that are included in a single consensus proposal
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ProposalLimits {
+    /// The maximum number of batches in a proposal
+    pub max_batches: usize,
+    /// The maximum combined size (in bytes) of the batches in a proposal; a proposal will always
+    /// include at least one batch, even if that batch alone exceeds this size
+    pub max_bytes: usize,
+}
+
+impl Default for ProposalLimits {
+    fn default() -> Self {
+        ProposalLimits {
+            max_batches: DEFAULT_MAX_BATCHES_PER_PROPOSAL,
+            max_bytes: DEFAULT_MAX_PROPOSAL_BYTES,
+        }
+    }
+}
 
 /// Component used by the service to manage and interact with consenus
 pub struct ScabbardConsensusManager {
@@ -49,6 +93,11 @@ impl ScabbardConsensusManager {
         state: Arc<Mutex<ScabbardState>>,
         // The coordinator timeout for the two-phase commit consensus engine
         coordinator_timeout: Duration,
+        consensus_type: ConsensusType,
+        proposal_limits: ProposalLimits,
+        // The storage, and the name within it, in which the consensus engine keeps its state
+        storage: &ScabbardStorage,
+        storage_name: &str,
     ) -> Result<Self, ScabbardConsensusManagerError> {
         let peer_ids = shared
             .lock()
@@ -66,6 +115,7 @@ impl ScabbardConsensusManager {
             proposal_update_tx.clone(),
             shared.clone(),
             state,
+            proposal_limits,
         );
         let consensus_network_sender =
             ScabbardConsensusNetworkSender::new(service_id.clone(), shared);
@@ -75,18 +125,27 @@ impl ScabbardConsensusManager {
             last_proposal: None,
         };
 
+        let mut engine: Box<dyn ConsensusEngine> = match consensus_type {
+            ConsensusType::TwoPhase => Box::new(TwoPhaseEngine::new(coordinator_timeout)),
+            ConsensusType::Raft { election_timeout } => {
+                let raft_state = storage
+                    .open_consensus_state(storage_name, RaftPersistentState::default)
+                    .map_err(|err| ScabbardConsensusManagerError(Box::new(err)))?;
+                Box::new(RaftEngine::new(election_timeout, raft_state))
+            }
+        };
+
         let thread_handle = Builder::new()
             .name(format!("consensus-{}", service_id))
             .spawn(move || {
-                let mut two_phase_engine = TwoPhaseEngine::new(coordinator_timeout);
-                if let Err(err) = two_phase_engine.run(
+                if let Err(err) = engine.run(
                     consensus_msg_rx,
                     proposal_update_rx,
                     Box::new(consensus_network_sender),
                     Box::new(proposal_manager),
                     startup_state,
                 ) {
-                    error!("two phase consensus exited with an error: {}", err)
+                    error!("{} consensus exited with an error: {}", engine.name(), err)
                 }
             })
             .map_err(|err| ScabbardConsensusManagerError(Box::new(err)))?;
@@ -135,6 +194,7 @@ pub struct ScabbardProposalManager {
     proposal_update_sender: Sender<ProposalUpdate>,
     shared: Arc<Mutex<ScabbardShared>>,
     state: Arc<Mutex<ScabbardState>>,
+    proposal_limits: ProposalLimits,
 }
 
 impl ScabbardProposalManager {
@@ -143,21 +203,67 @@ impl ScabbardProposalManager {
         proposal_update_sender: Sender<ProposalUpdate>,
         shared: Arc<Mutex<ScabbardShared>>,
         state: Arc<Mutex<ScabbardState>>,
+        proposal_limits: ProposalLimits,
     ) -> Self {
         ScabbardProposalManager {
             service_id,
             proposal_update_sender,
             shared,
             state,
+            proposal_limits,
         }
     }
+
+    /// Builds the message that carries a proposal and its batches to the other services.
+    fn build_proposed_batch(
+        &self,
+        proposal: Proposal,
+        batches: Vec<BatchPair>,
+        handler_set_hash: String,
+    ) -> Result<ProposedBatch, ProposalManagerError> {
+        let mut proposed_batch = ProposedBatch::new();
+        proposed_batch.set_proposal(
+            proposal
+                .try_into()
+                .map_err(|err| ProposalManagerError::Internal(Box::new(err)))?,
+        );
+        let batch_bytes = batches
+            .into_iter()
+            .map(|batch| batch.into_bytes())
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|err| ProposalManagerError::Internal(Box::new(err)))?;
+        // Services that do not support multiple batches per proposal only read the single
+        // `batch` field, so set it as well when there is only one batch.
+        if batch_bytes.len() == 1 {
+            proposed_batch.set_batch(batch_bytes[0].clone());
+        }
+        proposed_batch.set_batches(batch_bytes.into());
+        proposed_batch.set_service_id(self.service_id.clone());
+        proposed_batch.set_handler_set_hash(handler_set_hash);
+
+        Ok(proposed_batch)
+    }
+
+    fn request_state_sync(&self) -> Result<(), ProposalManagerError> {
+        let mut shared = self
+            .shared
+            .lock()
+            .map_err(|_| ProposalManagerError::Internal(Box::new(ScabbardError::LockPoisoned)))?;
+        let state = self
+            .state
+            .lock()
+            .map_err(|_| ProposalManagerError::Internal(Box::new(ScabbardError::LockPoisoned)))?;
+
+        request_state_sync(&self.service_id, &mut shared, &state, false)
+            .map_err(|err| ProposalManagerError::Internal(Box::new(err)))
+    }
 }
 
 impl ProposalManager for ScabbardProposalManager {
     fn create_proposal(
         &self,
-        // Ignoring previous proposal ID and consensus data, because this service and two phase
-        // consensus don't care about it.
+        // Ignoring previous proposal ID and consensus data, because this service and its consensus
+        // engines don't care about it.
         _previous_proposal_id: Option<ProposalId>,
         _consensus_data: Vec<u8>,
     ) -> Result<(), ProposalManagerError> {
@@ -166,37 +272,40 @@ impl ProposalManager for ScabbardProposalManager {
             .lock()
             .map_err(|_| ProposalManagerError::Internal(Box::new(ScabbardError::LockPoisoned)))?;
 
-        if let Some(batch) = shared.pop_batch_from_queue() {
-            let expected_hash = self
-                .state
-                .lock()
-                .map_err(|_| ProposalManagerError::Internal(Box::new(ScabbardError::LockPoisoned)))?
-                .prepare_change(batch.clone())
+        // Don't propose anything until this service's state has been synced with its peers
+        if shared.is_syncing() {
+            self.proposal_update_sender
+                .send(ProposalUpdate::ProposalCreated(None))?;
+            return Ok(());
+        }
+
+        let batches = shared.pop_batches_from_queue(
+            self.proposal_limits.max_batches,
+            self.proposal_limits.max_bytes,
+        );
+
+        if !batches.is_empty() {
+            let mut state = self.state.lock().map_err(|_| {
+                ProposalManagerError::Internal(Box::new(ScabbardError::LockPoisoned))
+            })?;
+            let expected_hash = state
+                .prepare_change(batches.clone())
                 .map_err(|err| ProposalManagerError::Internal(Box::new(err)))?;
+            let handler_set_hash = state.handler_set_hash().to_string();
+            drop(state);
 
             // Intentionally leaving out the previous_id and proposal_height fields, since this
-            // service and two phase consensus don't use them. This means the proposal ID can just
+            // service and its consensus engines don't use them. This means the proposal ID can just
             // be the summary.
             let mut proposal = Proposal::default();
             proposal.id = expected_hash.as_bytes().into();
             proposal.summary = expected_hash.as_bytes().into();
 
-            shared.add_proposed_batch(proposal.id.clone(), batch.clone());
+            shared.add_proposed_batches(proposal.id.clone(), batches.clone());
 
             // Send the proposal to the other services
-            let mut proposed_batch = ProposedBatch::new();
-            proposed_batch.set_proposal(
-                proposal
-                    .clone()
-                    .try_into()
-                    .map_err(|err| ProposalManagerError::Internal(Box::new(err)))?,
-            );
-            proposed_batch.set_batch(
-                batch
-                    .into_bytes()
-                    .map_err(|err| ProposalManagerError::Internal(Box::new(err)))?,
-            );
-            proposed_batch.set_service_id(self.service_id.clone());
+            let proposed_batch =
+                self.build_proposed_batch(proposal.clone(), batches, handler_set_hash)?;
 
             let mut msg = ScabbardMessage::new();
             msg.set_message_type(ScabbardMessage_Type::PROPOSED_BATCH);
@@ -209,10 +318,12 @@ impl ProposalManager for ScabbardProposalManager {
                 .network_sender()
                 .ok_or(ProposalManagerError::NotReady)?;
 
+            // A service that can't be reached should not prevent the proposal from being sent to
+            // the others, since consensus may not require every service to verify it.
             for service in shared.peer_services() {
-                sender
-                    .send(service, msg_bytes.as_slice())
-                    .map_err(|err| ProposalManagerError::Internal(Box::new(err)))?;
+                if let Err(err) = sender.send(service, msg_bytes.as_slice()) {
+                    warn!("Failed to send proposed batch to {}: {}", service, err);
+                }
             }
 
             self.proposal_update_sender
@@ -226,24 +337,51 @@ impl ProposalManager for ScabbardProposalManager {
     }
 
     fn check_proposal(&self, id: &ProposalId) -> Result<(), ProposalManagerError> {
-        let batch = self
-            .shared
-            .lock()
-            .map_err(|_| ProposalManagerError::Internal(Box::new(ScabbardError::LockPoisoned)))?
-            .get_proposed_batch(id)
-            .ok_or_else(|| ProposalManagerError::UnknownProposal(id.clone()))?
-            .clone();
+        let batches = {
+            let shared = self.shared.lock().map_err(|_| {
+                ProposalManagerError::Internal(Box::new(ScabbardError::LockPoisoned))
+            })?;
+
+            if shared.is_syncing() {
+                debug!("Unable to check proposal {} while syncing state", id);
+
+                self.proposal_update_sender
+                    .send(ProposalUpdate::ProposalInvalid(id.clone()))?;
+
+                return Ok(());
+            }
+
+            if shared.is_mismatched_proposal(id) {
+                warn!(
+                    "Proposal {} was created with a different set of transaction handlers",
+                    id
+                );
+
+                self.proposal_update_sender
+                    .send(ProposalUpdate::ProposalInvalid(id.clone()))?;
+
+                return Ok(());
+            }
+
+            shared
+                .get_proposed_batches(id)
+                .ok_or_else(|| ProposalManagerError::UnknownProposal(id.clone()))?
+                .clone()
+        };
 
         let hash = self
             .state
             .lock()
             .map_err(|_| ProposalManagerError::Internal(Box::new(ScabbardError::LockPoisoned)))?
-            .prepare_change(batch)
+            .prepare_change(batches)
             .map_err(|err| ProposalManagerError::Internal(Box::new(err)))?;
 
         if hash.as_bytes() != id.as_ref() {
             warn!("Hash mismatch: expected {} but was {}", id, hash);
 
+            // This service's state may have fallen behind its peers
+            self.request_state_sync()?;
+
             self.proposal_update_sender
                 .send(ProposalUpdate::ProposalInvalid(id.clone()))?;
         } else {
@@ -257,8 +395,8 @@ impl ProposalManager for ScabbardProposalManager {
     fn accept_proposal(
         &self,
         id: &ProposalId,
-        // Ignoring consensus data, because this service and two phase consensus don't care about
-        // it.
+        // Ignoring consensus data, because this service and its consensus engines don't care
+        // about it.
         _consensus_data: Option<Vec<u8>>,
     ) -> Result<(), ProposalManagerError> {
         let mut shared = self
@@ -267,7 +405,7 @@ impl ProposalManager for ScabbardProposalManager {
             .map_err(|_| ProposalManagerError::Internal(Box::new(ScabbardError::LockPoisoned)))?;
 
         shared
-            .remove_proposed_batch(id)
+            .remove_proposed_batches(id)
             .ok_or_else(|| ProposalManagerError::UnknownProposal(id.clone()))?;
 
         self.state
@@ -291,7 +429,7 @@ impl ProposalManager for ScabbardProposalManager {
             .map_err(|_| ProposalManagerError::Internal(Box::new(ScabbardError::LockPoisoned)))?;
 
         shared
-            .remove_proposed_batch(id)
+            .remove_proposed_batches(id)
             .ok_or_else(|| ProposalManagerError::UnknownProposal(id.clone()))?;
 
         self.state
@@ -304,6 +442,91 @@ impl ProposalManager for ScabbardProposalManager {
 
         Ok(())
     }
+
+    /// Returns a serialized `ProposedBatch` message with the proposal's batches.
+    fn get_proposal_payload(&self, id: &ProposalId) -> Result<Vec<u8>, ProposalManagerError> {
+        let shared = self
+            .shared
+            .lock()
+            .map_err(|_| ProposalManagerError::Internal(Box::new(ScabbardError::LockPoisoned)))?;
+        let batches = shared
+            .get_proposed_batches(id)
+            .ok_or_else(|| ProposalManagerError::UnknownProposal(id.clone()))?
+            .clone();
+        let handler_set_hash = self
+            .state
+            .lock()
+            .map_err(|_| ProposalManagerError::Internal(Box::new(ScabbardError::LockPoisoned)))?
+            .handler_set_hash()
+            .to_string();
+        drop(shared);
+
+        let mut proposal = Proposal::default();
+        proposal.id = id.clone();
+        proposal.summary = id.clone().into();
+
+        self.build_proposed_batch(proposal, batches, handler_set_hash)?
+            .write_to_bytes()
+            .map_err(|err| ProposalManagerError::Internal(Box::new(err)))
+    }
+
+    /// Adds the batches of a serialized `ProposedBatch` message, which was replicated by
+    /// consensus, to the proposed batches.
+    fn add_proposal(&self, id: &ProposalId, payload: &[u8]) -> Result<(), ProposalManagerError> {
+        // Entries replicated before proposal contents were included in the log have no payload;
+        // their batches can only have been received from the proposing service.
+        if payload.is_empty() {
+            return Ok(());
+        }
+
+        let proposed_batch: ProposedBatch = protobuf::parse_from_bytes(payload)
+            .map_err(|err| ProposalManagerError::Internal(Box::new(err)))?;
+        let proposal = Proposal::try_from(proposed_batch.get_proposal())
+            .map_err(|err| ProposalManagerError::Internal(Box::new(err)))?;
+        if &proposal.id != id {
+            return Err(ProposalManagerError::UnknownProposal(id.clone()));
+        }
+        let batches = parse_proposed_batches(&proposed_batch)
+            .map_err(|err| ProposalManagerError::Internal(Box::new(err)))?;
+
+        let handler_set_hash = proposed_batch.get_handler_set_hash();
+        let handlers_match = handler_set_hash.is_empty()
+            || handler_set_hash
+                == self
+                    .state
+                    .lock()
+                    .map_err(|_| {
+                        ProposalManagerError::Internal(Box::new(ScabbardError::LockPoisoned))
+                    })?
+                    .handler_set_hash();
+
+        let mut shared = self
+            .shared
+            .lock()
+            .map_err(|_| ProposalManagerError::Internal(Box::new(ScabbardError::LockPoisoned)))?;
+        if !handlers_match {
+            shared.add_mismatched_proposal(id.clone());
+        }
+        shared.add_proposed_batches(id.clone(), batches);
+
+        Ok(())
+    }
+}
+
+/// Parses the batches of a `ProposedBatch` message. Services that do not support multiple batches
+/// per proposal only set the single `batch` field.
+pub fn parse_proposed_batches(
+    proposed_batch: &ProposedBatch,
+) -> Result<Vec<BatchPair>, ProtoConversionError> {
+    if proposed_batch.get_batches().is_empty() {
+        Ok(vec![BatchPair::from_bytes(proposed_batch.get_batch())?])
+    } else {
+        proposed_batch
+            .get_batches()
+            .iter()
+            .map(|batch| BatchPair::from_bytes(batch))
+            .collect()
+    }
 }
 
 pub struct ScabbardConsensusNetworkSender {
@@ -378,6 +601,7 @@ mod tests {
 
     use std::collections::{HashSet, VecDeque};
 
+    use crate::service::scabbard::tests::make_batch;
     use crate::service::tests::*;
     use crate::signing::hash::HashVerifier;
 
@@ -392,6 +616,7 @@ mod tests {
 
         let shared = Arc::new(Mutex::new(ScabbardShared::new(
             VecDeque::new(),
+            10,
             Some(Box::new(service_sender.clone())),
             peer_services.clone(),
             Box::new(HashVerifier),
@@ -474,4 +699,84 @@ mod tests {
         assert_eq!(consensus_message.message, vec![1]);
         assert_eq!(consensus_message.origin_id, "0".as_bytes().into());
     }
+
+    /// Verify that the contents of a proposal, as replicated by consensus, can be added to the
+    /// proposal manager of a service that never received the proposal.
+    ///
+    /// 1. Add the batches of a proposal to one service and get the proposal's payload
+    /// 2. Verify that the payload is not added under another proposal ID
+    /// 3. Add the payload to another service and verify that it has the proposal's batches
+    #[test]
+    fn proposal_payload_round_trip() {
+        let proposal_id: ProposalId = b"proposal".to_vec().into();
+        let batches = vec![make_batch(1), make_batch(2)];
+
+        let (source, _) = create_proposal_manager("0");
+        source
+            .shared
+            .lock()
+            .expect("shared lock poisoned")
+            .add_proposed_batches(proposal_id.clone(), batches.clone());
+        let payload = source
+            .get_proposal_payload(&proposal_id)
+            .expect("failed to get payload");
+
+        let (target, _) = create_proposal_manager("1");
+        assert!(target
+            .add_proposal(&b"other".to_vec().into(), &payload)
+            .is_err());
+        target
+            .add_proposal(&proposal_id, &payload)
+            .expect("failed to add proposal");
+
+        let shared = target.shared.lock().expect("shared lock poisoned");
+        let added_batches = shared
+            .get_proposed_batches(&proposal_id)
+            .expect("proposal not added");
+        assert_eq!(
+            added_batches
+                .iter()
+                .map(|batch| batch.batch().header_signature())
+                .collect::<Vec<_>>(),
+            batches
+                .iter()
+                .map(|batch| batch.batch().header_signature())
+                .collect::<Vec<_>>()
+        );
+        assert!(!shared.is_mismatched_proposal(&proposal_id));
+        assert!(shared
+            .get_proposed_batches(&b"other".to_vec().into())
+            .is_none());
+    }
+
+    fn create_proposal_manager(
+        service_id: &str,
+    ) -> (
+        ScabbardProposalManager,
+        std::sync::mpsc::Receiver<ProposalUpdate>,
+    ) {
+        let (update_tx, update_rx) = channel();
+        let shared = Arc::new(Mutex::new(ScabbardShared::new(
+            VecDeque::new(),
+            10,
+            None,
+            HashSet::new(),
+            Box::new(HashVerifier),
+        )));
+        let state = Arc::new(Mutex::new(
+            ScabbardState::new(&ScabbardStorage::Memory, service_id, vec![], vec![])
+                .expect("failed to create state"),
+        ));
+
+        (
+            ScabbardProposalManager::new(
+                service_id.into(),
+                update_tx,
+                shared,
+                state,
+                ProposalLimits::default(),
+            ),
+            update_rx,
+        )
+    }
 }
@@ -24,6 +24,8 @@ use transact::state::merkle::StateDatabaseError;
 
 #[derive(Debug)]
 pub enum ScabbardError {
+    /// The batch queue does not have room for the submitted batches
+    BatchQueueFull,
     BatchVerificationFailed(Box<dyn Error + Send>),
     ConsensusFailed(ScabbardConsensusManagerError),
     InitializationFailed(Box<dyn Error + Send>),
@@ -31,11 +33,16 @@ pub enum ScabbardError {
     MessageTypeUnset,
     NotConnected,
     StateInteractionFailed(ScabbardStateError),
+    StateSyncFailed(Box<dyn Error + Send>),
+    /// More batches were submitted at once than the batch queue can hold; contains the queue's
+    /// limit
+    TooManyBatches(usize),
 }
 
 impl Error for ScabbardError {
     fn source(&self) -> Option<&(dyn Error + 'static)> {
         match self {
+            ScabbardError::BatchQueueFull => None,
             ScabbardError::BatchVerificationFailed(err) => Some(&**err),
             ScabbardError::ConsensusFailed(err) => Some(err),
             ScabbardError::InitializationFailed(err) => Some(&**err),
@@ -43,6 +50,8 @@ impl Error for ScabbardError {
             ScabbardError::MessageTypeUnset => None,
             ScabbardError::NotConnected => None,
             ScabbardError::StateInteractionFailed(err) => Some(err),
+            ScabbardError::StateSyncFailed(err) => Some(&**err),
+            ScabbardError::TooManyBatches(_) => None,
         }
     }
 }
@@ -50,6 +59,7 @@ impl Error for ScabbardError {
 impl std::fmt::Display for ScabbardError {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         match self {
+            ScabbardError::BatchQueueFull => write!(f, "batch queue is full"),
             ScabbardError::BatchVerificationFailed(err) => {
                 write!(f, "failed to verify batch: {}", err)
             }
@@ -65,6 +75,12 @@ impl std::fmt::Display for ScabbardError {
             ScabbardError::StateInteractionFailed(err) => {
                 write!(f, "interaction with scabbard state failed: {}", err)
             }
+            ScabbardError::StateSyncFailed(err) => write!(f, "failed to sync state: {}", err),
+            ScabbardError::TooManyBatches(max) => write!(
+                f,
+                "more batches were submitted than the batch queue can hold ({})",
+                max
+            ),
         }
     }
 }
@@ -12,9 +12,8 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::iter::FromIterator;
-use std::path::Path;
 use std::time::Duration;
 
 #[cfg(feature = "service-arg-validation")]
@@ -24,20 +23,20 @@ use crate::service::validation::{ServiceArgValidationError, ServiceArgValidator}
 use crate::service::{FactoryCreateError, Service, ServiceFactory};
 use crate::signing::SignatureVerifierFactory;
 
-use super::{Scabbard, SERVICE_TYPE};
+use super::handler::TransactionHandlerFactory;
+#[cfg(feature = "service-arg-validation")]
+use super::handler::SABRE_FAMILY_NAME;
+use super::{
+    ConsensusType, ProposalLimits, PruningPolicy, Scabbard, ScabbardStorage, SERVICE_TYPE,
+};
 
-const DEFAULT_STATE_DB_DIR: &str = "/var/lib/splinter";
-const DEFAULT_STATE_DB_SIZE: usize = 1 << 30; // 1024 ** 3
-const DEFAULT_RECEIPT_DB_DIR: &str = "/var/lib/splinter";
-const DEFAULT_RECEIPT_DB_SIZE: usize = 1 << 30; // 1024 ** 3
+const DEFAULT_ELECTION_TIMEOUT: u64 = 5000; // 5 seconds
 
 pub struct ScabbardFactory {
     service_types: Vec<String>,
-    state_db_dir: String,
-    state_db_size: usize,
-    receipt_db_dir: String,
-    receipt_db_size: usize,
+    storage: ScabbardStorage,
     signature_verifier_factory: Box<dyn SignatureVerifierFactory>,
+    transaction_handler_factories: BTreeMap<String, Box<dyn TransactionHandlerFactory>>,
 }
 
 impl ScabbardFactory {
@@ -50,13 +49,36 @@ impl ScabbardFactory {
     ) -> Self {
         ScabbardFactory {
             service_types: vec![SERVICE_TYPE.into()],
-            state_db_dir: state_db_dir.unwrap_or_else(|| DEFAULT_STATE_DB_DIR.into()),
-            state_db_size: state_db_size.unwrap_or(DEFAULT_STATE_DB_SIZE),
-            receipt_db_dir: receipt_db_dir.unwrap_or_else(|| DEFAULT_RECEIPT_DB_DIR.into()),
-            receipt_db_size: receipt_db_size.unwrap_or(DEFAULT_RECEIPT_DB_SIZE),
+            storage: ScabbardStorage::lmdb(
+                state_db_dir,
+                state_db_size,
+                receipt_db_dir,
+                receipt_db_size,
+            ),
             signature_verifier_factory,
+            transaction_handler_factories: BTreeMap::new(),
         }
     }
+
+    /// Registers a factory for native transaction handlers, keyed by the family name of its
+    /// handlers. Services will only run the handler if its family is listed in their
+    /// `transaction_handlers` argument. Registering a factory for a family that already has one
+    /// replaces the existing factory.
+    pub fn with_transaction_handler_factory(
+        mut self,
+        factory: Box<dyn TransactionHandlerFactory>,
+    ) -> Self {
+        self.transaction_handler_factories
+            .insert(factory.family_name().to_string(), factory);
+        self
+    }
+
+    /// Sets the storage that services' state and transaction receipts are kept in, replacing the
+    /// LMDB storage configured by `new`.
+    pub fn with_storage(mut self, storage: ScabbardStorage) -> Self {
+        self.storage = storage;
+        self
+    }
 }
 
 #[cfg(feature = "service-arg-validation")]
@@ -96,6 +118,73 @@ impl ServiceArgValidator for ScabbardArgValidator {
             }
         }
 
+        if let Some(consensus) = args.get("consensus") {
+            if consensus != "2pc" && consensus != "raft" {
+                return Err(ServiceArgValidationError(format!(
+                    "{} is not a supported consensus algorithm",
+                    consensus
+                )));
+            }
+        }
+
+        if let Some(election_timeout) = args.get("election_timeout") {
+            election_timeout.parse::<u64>().map_err(|err| {
+                ServiceArgValidationError(format!("invalid election_timeout: {}", err))
+            })?;
+        }
+
+        if let Some(transaction_handlers) = args.get("transaction_handlers") {
+            let families: Vec<String> =
+                serde_json::from_str(transaction_handlers).map_err(|err| {
+                    ServiceArgValidationError(format!(
+                        "failed to parse transaction_handlers list: {}",
+                        err
+                    ))
+                })?;
+
+            let mut unique_families = HashSet::new();
+            for family in families {
+                if family == SABRE_FAMILY_NAME {
+                    return Err(ServiceArgValidationError(format!(
+                        "{} transaction handler is always run and must not be listed",
+                        SABRE_FAMILY_NAME
+                    )));
+                }
+                if !unique_families.insert(family.clone()) {
+                    return Err(ServiceArgValidationError(format!(
+                        "transaction handler {} is listed more than once",
+                        family
+                    )));
+                }
+            }
+        }
+
+        for arg in &[
+            "max_batches_per_proposal",
+            "max_proposal_bytes",
+            "max_queued_batches",
+        ] {
+            if let Some(value) = args.get(*arg) {
+                match value.parse::<usize>() {
+                    Ok(0) => {
+                        return Err(ServiceArgValidationError(format!(
+                            "{} must be greater than 0",
+                            arg
+                        )))
+                    }
+                    Ok(_) => (),
+                    Err(err) => {
+                        return Err(ServiceArgValidationError(format!(
+                            "invalid {}: {}",
+                            arg, err
+                        )))
+                    }
+                }
+            }
+        }
+
+        parse_pruning_policy(args).map_err(ServiceArgValidationError)?;
+
         Ok(())
     }
 }
@@ -114,6 +203,32 @@ impl ServiceFactory for ScabbardFactory {
     /// - `coordinator_timeout`: the length of time (in milliseconds) that the network has to
     ///   commit a proposal before the coordinator rejects it (if not provided, default is 30
     ///   seconds)
+    /// - `consensus`: the consensus algorithm used to commit batches; either `2pc` for two-phase
+    ///   commit, which requires every service to be available, or `raft`, which only requires a
+    ///   majority of the services to be available (if not provided, default is `2pc`)
+    /// - `election_timeout`: when using `raft` consensus, the length of time (in milliseconds)
+    ///   that a service waits to hear from the leader before starting an election (if not
+    ///   provided, default is 5 seconds)
+    /// - `max_batches_per_proposal`: the maximum number of batches that will be included in a
+    ///   single consensus proposal (if not provided, default is 100)
+    /// - `max_proposal_bytes`: the maximum combined size (in bytes) of the batches in a single
+    ///   consensus proposal; a proposal always includes at least one batch (if not provided,
+    ///   default is 10 MiB)
+    /// - `max_queued_batches`: the maximum number of submitted batches that may be waiting to be
+    ///   proposed; batches submitted while the queue is full are rejected (if not provided,
+    ///   default is 1000)
+    /// - `transaction_handlers`: list of the families of native transaction handlers that this
+    ///   service will run in addition to Sabre, formatted as a serialized JSON array of strings; a
+    ///   handler factory must be registered with this factory for each family. Every service on
+    ///   the circuit should be given the same list, since proposals created by a service with a
+    ///   different set of handlers will be rejected (if not provided, only Sabre is run).
+    /// - `state_pruning_keep_roots`: prune old state, retaining only the given number of most
+    ///   recently committed state roots
+    /// - `state_pruning_max_age`: prune old state, retaining only the state roots committed
+    ///   within the given number of seconds
+    ///
+    /// At most one of the state pruning arguments may be provided; if neither is provided, state
+    /// is not pruned.
     fn create(
         &self,
         service_id: String,
@@ -134,8 +249,6 @@ impl ServiceFactory for ScabbardFactory {
                 })?
                 .into_iter(),
         );
-        let state_db_dir = Path::new(&self.state_db_dir);
-        let receipt_db_dir = Path::new(&self.receipt_db_dir);
         let admin_keys_str = args.get("admin_keys").ok_or_else(|| {
             FactoryCreateError::InvalidArguments("admin_keys argument not provided".into())
         })?;
@@ -157,17 +270,79 @@ impl ServiceFactory for ScabbardFactory {
             })
             .transpose()?;
 
+        let consensus_type = match args.get("consensus").map(String::as_str) {
+            None | Some("2pc") => ConsensusType::TwoPhase,
+            Some("raft") => {
+                let election_timeout = match args.get("election_timeout") {
+                    Some(timeout) => timeout.parse::<u64>().map_err(|err| {
+                        FactoryCreateError::InvalidArguments(format!(
+                            "invalid election_timeout: {}",
+                            err
+                        ))
+                    })?,
+                    None => DEFAULT_ELECTION_TIMEOUT,
+                };
+                ConsensusType::Raft {
+                    election_timeout: Duration::from_millis(election_timeout),
+                }
+            }
+            Some(consensus) => {
+                return Err(FactoryCreateError::InvalidArguments(format!(
+                    "unsupported consensus: {}",
+                    consensus
+                )))
+            }
+        };
+
+        let mut proposal_limits = ProposalLimits::default();
+        if let Some(max_batches) = parse_limit(&args, "max_batches_per_proposal")? {
+            proposal_limits.max_batches = max_batches;
+        }
+        if let Some(max_bytes) = parse_limit(&args, "max_proposal_bytes")? {
+            proposal_limits.max_bytes = max_bytes;
+        }
+        let max_queued_batches = parse_limit(&args, "max_queued_batches")?;
+
+        let transaction_handlers = match args.get("transaction_handlers") {
+            Some(transaction_handlers) => serde_json::from_str::<Vec<String>>(transaction_handlers)
+                .map_err(|err| {
+                    FactoryCreateError::InvalidArguments(format!(
+                        "failed to parse transaction_handlers list: {}",
+                        err
+                    ))
+                })?
+                .iter()
+                .map(|family| {
+                    self.transaction_handler_factories
+                        .get(family)
+                        .map(|factory| factory.create_handler())
+                        .ok_or_else(|| {
+                            FactoryCreateError::InvalidArguments(format!(
+                                "no transaction handler available for family {}",
+                                family
+                            ))
+                        })
+                })
+                .collect::<Result<Vec<_>, _>>()?,
+            None => vec![],
+        };
+
+        let pruning_policy =
+            parse_pruning_policy(&args).map_err(FactoryCreateError::InvalidArguments)?;
+
         let service = Scabbard::new(
             service_id,
             circuit_id,
             peer_services,
-            &state_db_dir,
-            self.state_db_size,
-            &receipt_db_dir,
-            self.receipt_db_size,
+            &self.storage,
             self.signature_verifier_factory.create_verifier(),
             admin_keys,
             coordinator_timeout,
+            consensus_type,
+            proposal_limits,
+            max_queued_batches,
+            transaction_handlers,
+            pruning_policy,
         )
         .map_err(|err| FactoryCreateError::CreationFailed(Box::new(err)))?;
 
@@ -181,20 +356,72 @@ impl ServiceFactory for ScabbardFactory {
         endpoints.push(super::rest_api::make_add_batches_to_queue_endpoint());
         endpoints.push(super::rest_api::make_subscribe_endpoint());
         endpoints.push(super::rest_api::make_get_batch_status_endpoint());
+        endpoints.push(super::rest_api::make_get_receipt_endpoint());
+        endpoints.push(super::rest_api::make_list_receipts_endpoint());
+        endpoints.push(super::rest_api::make_get_pruning_endpoint());
+        endpoints.push(super::rest_api::make_prune_endpoint());
+        endpoints.push(super::rest_api::make_get_status_endpoint());
         #[cfg(feature = "scabbard-get-state")]
         {
             endpoints.push(super::rest_api::make_get_state_at_address_endpoint());
             endpoints.push(super::rest_api::make_get_state_with_prefix_endpoint());
+            endpoints.push(super::rest_api::make_get_state_roots_endpoint());
         }
 
         endpoints
     }
 }
 
+/// Parses the given limit argument, which must be a positive integer if provided.
+fn parse_limit(
+    args: &HashMap<String, String>,
+    arg: &str,
+) -> Result<Option<usize>, FactoryCreateError> {
+    args.get(arg)
+        .map(|value| match value.parse::<usize>() {
+            Ok(0) => Err(FactoryCreateError::InvalidArguments(format!(
+                "{} must be greater than 0",
+                arg
+            ))),
+            Ok(limit) => Ok(limit),
+            Err(err) => Err(FactoryCreateError::InvalidArguments(format!(
+                "invalid {}: {}",
+                arg, err
+            ))),
+        })
+        .transpose()
+}
+
+/// Parses the state pruning policy from the `state_pruning_keep_roots` or
+/// `state_pruning_max_age` argument; at most one of these may be provided.
+fn parse_pruning_policy(args: &HashMap<String, String>) -> Result<Option<PruningPolicy>, String> {
+    match (
+        args.get("state_pruning_keep_roots"),
+        args.get("state_pruning_max_age"),
+    ) {
+        (Some(_), Some(_)) => Err(
+            "state_pruning_keep_roots and state_pruning_max_age must not both be provided".into(),
+        ),
+        (Some(keep_roots), None) => match keep_roots.parse::<usize>() {
+            Ok(0) => Err("state_pruning_keep_roots must be greater than 0".into()),
+            Ok(keep_roots) => Ok(Some(PruningPolicy::KeepRoots(keep_roots))),
+            Err(err) => Err(format!("invalid state_pruning_keep_roots: {}", err)),
+        },
+        (None, Some(max_age)) => max_age
+            .parse::<u64>()
+            .map(|max_age| Some(PruningPolicy::MaxAge(Duration::from_secs(max_age))))
+            .map_err(|err| format!("invalid state_pruning_max_age: {}", err)),
+        (None, None) => Ok(None),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    use transact::handler::{ApplyError, TransactionContext, TransactionHandler};
+    use transact::protocol::transaction::TransactionPair;
+
     use crate::signing::hash::HashVerifier;
 
     /// Verify that the scabbard factory produces a valid `Scabbard` instance.
@@ -234,6 +461,181 @@ mod tests {
         assert_eq!(scabbard.coordinator_timeout, Duration::from_millis(123));
     }
 
+    /// Verify that the `consensus` and `election_timeout` service arguments are properly set for a
+    /// new `Scabbard` instance.
+    #[test]
+    fn create_with_raft_consensus() {
+        let factory = get_factory();
+        let mut args = get_mock_args();
+        args.insert("consensus".into(), "raft".into());
+        args.insert("election_timeout".into(), "456".into());
+
+        let service = factory
+            .create("".into(), "", "", args)
+            .expect("failed to create service");
+        let scabbard = (&*service)
+            .as_any()
+            .downcast_ref::<Scabbard>()
+            .expect("failed to downcast Service to Scabbard");
+
+        assert_eq!(
+            scabbard.consensus_type,
+            ConsensusType::Raft {
+                election_timeout: Duration::from_millis(456)
+            }
+        );
+    }
+
+    /// Verify that the `max_batches_per_proposal` and `max_proposal_bytes` service arguments are
+    /// properly set for a new `Scabbard` instance.
+    #[test]
+    fn create_with_proposal_limits() {
+        let factory = get_factory();
+        let mut args = get_mock_args();
+        args.insert("max_batches_per_proposal".into(), "25".into());
+        args.insert("max_proposal_bytes".into(), "4096".into());
+
+        let service = factory
+            .create("".into(), "", "", args)
+            .expect("failed to create service");
+        let scabbard = (&*service)
+            .as_any()
+            .downcast_ref::<Scabbard>()
+            .expect("failed to downcast Service to Scabbard");
+
+        assert_eq!(
+            scabbard.proposal_limits,
+            ProposalLimits {
+                max_batches: 25,
+                max_bytes: 4096,
+            }
+        );
+    }
+
+    /// Verify that `Scabbard` creation fails when a proposal limit is zero.
+    #[test]
+    fn create_with_zero_proposal_limit() {
+        let factory = get_factory();
+        let mut args = get_mock_args();
+        args.insert("max_batches_per_proposal".into(), "0".into());
+
+        assert!(
+            factory.create("".into(), "", "", args).is_err(),
+            "Creating factory with zero max_batches_per_proposal did not fail"
+        );
+    }
+
+    /// Verify that the `max_queued_batches` service argument is properly set for a new
+    /// `Scabbard` instance, and that creation fails if it is zero.
+    #[test]
+    fn create_with_max_queued_batches() {
+        let factory = get_factory();
+        let mut args = get_mock_args();
+        args.insert("max_queued_batches".into(), "5".into());
+
+        let service = factory
+            .create("".into(), "", "", args)
+            .expect("failed to create service");
+        let scabbard = (&*service)
+            .as_any()
+            .downcast_ref::<Scabbard>()
+            .expect("failed to downcast Service to Scabbard");
+        assert_eq!(
+            scabbard
+                .status()
+                .expect("failed to get status")
+                .max_queued_batches,
+            5
+        );
+
+        let mut args = get_mock_args();
+        args.insert("max_queued_batches".into(), "0".into());
+        assert!(
+            factory.create("".into(), "", "", args).is_err(),
+            "Creating factory with zero max_queued_batches did not fail"
+        );
+    }
+
+    /// Verify that a `Scabbard` instance can be created with a registered native transaction
+    /// handler.
+    #[test]
+    fn create_with_transaction_handlers() {
+        let factory =
+            get_factory().with_transaction_handler_factory(Box::new(MockTransactionHandlerFactory));
+        let mut args = get_mock_args();
+        args.insert("transaction_handlers".into(), "[\"mock\"]".into());
+
+        factory
+            .create("".into(), "", "", args)
+            .expect("failed to create service");
+    }
+
+    /// Verify that `Scabbard` creation fails when the `transaction_handlers` argument lists a
+    /// family that does not have a registered handler factory.
+    #[test]
+    fn create_with_unknown_transaction_handler() {
+        let factory =
+            get_factory().with_transaction_handler_factory(Box::new(MockTransactionHandlerFactory));
+        let mut args = get_mock_args();
+        args.insert("transaction_handlers".into(), "[\"unknown\"]".into());
+
+        assert!(
+            factory.create("".into(), "", "", args).is_err(),
+            "Creating factory with unknown transaction handler did not fail"
+        );
+    }
+
+    /// Verify that the `state_pruning_keep_roots` service argument is properly set for a new
+    /// `Scabbard` instance.
+    #[test]
+    fn create_with_pruning_policy() {
+        let factory = get_factory();
+        let mut args = get_mock_args();
+        args.insert("state_pruning_keep_roots".into(), "10".into());
+
+        let service = factory
+            .create("".into(), "", "", args)
+            .expect("failed to create service");
+        let scabbard = (&*service)
+            .as_any()
+            .downcast_ref::<Scabbard>()
+            .expect("failed to downcast Service to Scabbard");
+
+        assert_eq!(
+            scabbard
+                .pruning_policy()
+                .expect("failed to get pruning policy"),
+            Some(PruningPolicy::KeepRoots(10))
+        );
+    }
+
+    /// Verify that `Scabbard` creation fails when both state pruning arguments are provided.
+    #[test]
+    fn create_with_conflicting_pruning_policies() {
+        let factory = get_factory();
+        let mut args = get_mock_args();
+        args.insert("state_pruning_keep_roots".into(), "10".into());
+        args.insert("state_pruning_max_age".into(), "3600".into());
+
+        assert!(
+            factory.create("".into(), "", "", args).is_err(),
+            "Creating factory with conflicting pruning policies did not fail"
+        );
+    }
+
+    /// Verify that `Scabbard` creation fails when the `consensus` argument is not supported.
+    #[test]
+    fn create_with_unsupported_consensus() {
+        let factory = get_factory();
+        let mut args = get_mock_args();
+        args.insert("consensus".into(), "pbft".into());
+
+        assert!(
+            factory.create("".into(), "", "", args).is_err(),
+            "Creating factory with unsupported consensus did not fail"
+        );
+    }
+
     /// Verify that `Scabbard` creation fails when the `peer_services` argument isn't specified.
     #[test]
     fn create_without_peer_services() {
@@ -261,13 +663,8 @@ mod tests {
     }
 
     fn get_factory() -> ScabbardFactory {
-        ScabbardFactory::new(
-            Some("/tmp".into()),
-            Some(1024 * 1024),
-            Some("/tmp".into()),
-            Some(1024 * 1024),
-            Box::new(HashVerifier),
-        )
+        ScabbardFactory::new(None, None, None, None, Box::new(HashVerifier))
+            .with_storage(ScabbardStorage::Memory)
     }
 
     fn get_mock_args() -> HashMap<String, String> {
@@ -284,4 +681,40 @@ mod tests {
         );
         args
     }
+
+    struct MockTransactionHandlerFactory;
+
+    impl TransactionHandlerFactory for MockTransactionHandlerFactory {
+        fn family_name(&self) -> &str {
+            "mock"
+        }
+
+        fn create_handler(&self) -> Box<dyn TransactionHandler> {
+            Box::new(MockTransactionHandler {
+                family_versions: vec!["1.0".into()],
+            })
+        }
+    }
+
+    struct MockTransactionHandler {
+        family_versions: Vec<String>,
+    }
+
+    impl TransactionHandler for MockTransactionHandler {
+        fn family_name(&self) -> &str {
+            "mock"
+        }
+
+        fn family_versions(&self) -> &[String] {
+            &self.family_versions
+        }
+
+        fn apply(
+            &self,
+            _transaction: &TransactionPair,
+            _context: &mut dyn TransactionContext,
+        ) -> Result<(), ApplyError> {
+            Ok(())
+        }
+    }
 }
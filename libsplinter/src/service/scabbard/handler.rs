@@ -0,0 +1,59 @@
+// Copyright 2018-2020 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Support for running native transaction handlers in scabbard alongside Sabre.
+
+use openssl::hash::{hash, MessageDigest};
+use transact::handler::TransactionHandler;
+
+use crate::hex::to_hex;
+
+/// The family name of the Sabre transaction handler, which is always run by scabbard
+#[cfg(feature = "service-arg-validation")]
+pub const SABRE_FAMILY_NAME: &str = "sabre";
+
+/// Creates native transaction handlers for scabbard services.
+///
+/// Each scabbard service runs its own executor, so a factory is registered with the
+/// `ScabbardFactory` and used to create a new handler for every service.
+pub trait TransactionHandlerFactory: Send {
+    /// The family name of the transactions that created handlers will process.
+    fn family_name(&self) -> &str;
+
+    /// Creates a new transaction handler.
+    fn create_handler(&self) -> Box<dyn TransactionHandler>;
+}
+
+/// Computes a digest that identifies a set of transaction handlers, so that services can verify
+/// they are executing transactions with the same handlers as their peers.
+///
+/// The digest covers the family name and versions of each handler, and does not depend on the
+/// order of the handlers.
+pub fn handler_set_hash(handlers: &[&dyn TransactionHandler]) -> Result<String, String> {
+    let mut families = handlers
+        .iter()
+        .map(|handler| {
+            format!(
+                "{}:{}",
+                handler.family_name(),
+                handler.family_versions().join(",")
+            )
+        })
+        .collect::<Vec<_>>();
+    families.sort();
+
+    hash(MessageDigest::sha256(), families.join(";").as_bytes())
+        .map(|digest| to_hex(&*digest))
+        .map_err(|err| format!("failed to hash transaction handler set: {}", err))
+}
@@ -13,29 +13,32 @@
 // limitations under the License.
 
 //! Scabbard is a Splinter `Service` that runs the Sawtooth Sabre smart contract engine using the
-//! `transact` library for state. Scabbard uses two-phase consensus to reach agreement on
-//! transactions.
+//! `transact` library for state. Scabbard uses two-phase consensus (by default) or Raft consensus
+//! to reach agreement on transactions.
 
 #[cfg(feature = "scabbard-client")]
 pub mod client;
 mod consensus;
 mod error;
 mod factory;
+mod handler;
+mod prune;
 #[cfg(feature = "rest-api")]
 mod rest_api;
 mod shared;
 mod state;
+mod storage;
+mod sync;
 
 use std::any::Any;
 use std::collections::{HashSet, VecDeque};
 use std::convert::TryFrom;
-use std::path::Path;
 use std::sync::{Arc, Mutex};
 use std::time::Duration;
 
 use openssl::hash::{hash, MessageDigest};
+use transact::handler::TransactionHandler;
 use transact::protocol::batch::BatchPair;
-use transact::protos::FromBytes;
 
 use crate::consensus::{Proposal, ProposalUpdate};
 use crate::hex::to_hex;
@@ -47,22 +50,31 @@ use super::{
     ServiceStartError, ServiceStopError,
 };
 
-use consensus::ScabbardConsensusManager;
+use consensus::{parse_proposed_batches, ScabbardConsensusManager};
+pub use consensus::{ConsensusType, ProposalLimits};
 use error::ScabbardError;
 #[cfg(feature = "service-arg-validation")]
 pub use factory::ScabbardArgValidator;
 pub use factory::ScabbardFactory;
+pub use handler::TransactionHandlerFactory;
+use prune::StatePruner;
+pub use prune::{PruneMetrics, PruneResult, PruningPolicy};
 use shared::ScabbardShared;
 #[cfg(feature = "scabbard-get-state")]
 use state::StateIter;
-pub use state::{BatchInfo, BatchInfoIter, BatchStatus, Events, StateChange, StateChangeEvent};
+pub use state::{
+    BatchInfo, BatchInfoIter, BatchStatus, CommitEntry, Events, Receipt, ReceiptEvent, StateChange,
+    StateChangeEvent,
+};
 use state::{ScabbardState, StateSubscriber};
+pub use storage::ScabbardStorage;
 
 const SERVICE_TYPE: &str = "scabbard";
 
 const DEFAULT_COORDINATOR_TIMEOUT: u64 = 30; // 30 seconds
+const DEFAULT_MAX_QUEUED_BATCHES: usize = 1000;
 
-/// A service for running Sawtooth Sabre smart contracts with two-phase commit consensus.
+/// A service for running Sawtooth Sabre smart contracts with two-phase commit or Raft consensus.
 #[derive(Clone)]
 pub struct Scabbard {
     circuit_id: String,
@@ -71,7 +83,13 @@ pub struct Scabbard {
     state: Arc<Mutex<ScabbardState>>,
     /// The coordinator timeout for the two-phase commit consensus engine
     coordinator_timeout: Duration,
+    consensus_type: ConsensusType,
+    proposal_limits: ProposalLimits,
+    /// The storage, and the name within it, in which the consensus engine keeps its state
+    storage: ScabbardStorage,
+    storage_name: String,
     consensus: Arc<Mutex<Option<ScabbardConsensusManager>>>,
+    pruner: Arc<Mutex<Option<StatePruner>>>,
 }
 
 impl Scabbard {
@@ -82,22 +100,33 @@ impl Scabbard {
         circuit_id: &str,
         // List of other scabbard services on the same circuit that this service shares state with
         peer_services: HashSet<String>,
-        // The directory in which to create sabre's LMDB database
-        state_db_dir: &Path,
-        // The size of sabre's LMDB database
-        state_db_size: usize,
-        // The directory in which to create the transaction receipt store's LMDB database
-        receipt_db_dir: &Path,
-        // The size of the transaction receipt store's LMDB database
-        receipt_db_size: usize,
+        // The storage in which to create the service's state and transaction receipt databases
+        storage: &ScabbardStorage,
         signature_verifier: Box<dyn SignatureVerifier>,
         // The public keys that are authorized to create and manage sabre contracts
         admin_keys: Vec<String>,
         // The coordinator timeout for the two-phase commit consensus engine; if `None`, the
         // default value will be used (30 seconds).
         coordinator_timeout: Option<Duration>,
+        // The consensus algorithm used to commit batches
+        consensus_type: ConsensusType,
+        // The limits on the batches included in each consensus proposal
+        proposal_limits: ProposalLimits,
+        // The maximum number of submitted batches that may be waiting to be proposed; if `None`,
+        // the default value will be used (1000 batches).
+        max_queued_batches: Option<usize>,
+        // Native transaction handlers to run in addition to Sabre
+        transaction_handlers: Vec<Box<dyn TransactionHandler>>,
+        // The policy used to prune old state roots; if `None`, state is not pruned.
+        pruning_policy: Option<PruningPolicy>,
     ) -> Result<Self, ScabbardError> {
-        let shared = ScabbardShared::new(VecDeque::new(), None, peer_services, signature_verifier);
+        let shared = ScabbardShared::new(
+            VecDeque::new(),
+            max_queued_batches.unwrap_or(DEFAULT_MAX_QUEUED_BATCHES),
+            None,
+            peer_services,
+            signature_verifier,
+        );
 
         let hash = hash(
             MessageDigest::sha256(),
@@ -105,16 +134,9 @@ impl Scabbard {
         )
         .map(|digest| to_hex(&*digest))
         .map_err(|err| ScabbardError::InitializationFailed(Box::new(err)))?;
-        let state_db_path = state_db_dir.join(format!("{}-state.lmdb", hash));
-        let receipt_db_path = receipt_db_dir.join(format!("{}-receipts.lmdb", hash));
-        let state = ScabbardState::new(
-            state_db_path.as_path(),
-            state_db_size,
-            receipt_db_path.as_path(),
-            receipt_db_size,
-            admin_keys,
-        )
-        .map_err(|err| ScabbardError::InitializationFailed(Box::new(err)))?;
+        let mut state = ScabbardState::new(storage, &hash, admin_keys, transaction_handlers)
+            .map_err(|err| ScabbardError::InitializationFailed(Box::new(err)))?;
+        state.set_pruning_policy(pruning_policy);
 
         let coordinator_timeout =
             coordinator_timeout.unwrap_or_else(|| Duration::from_secs(DEFAULT_COORDINATOR_TIMEOUT));
@@ -125,31 +147,104 @@ impl Scabbard {
             shared: Arc::new(Mutex::new(shared)),
             state: Arc::new(Mutex::new(state)),
             coordinator_timeout,
+            consensus_type,
+            proposal_limits,
+            storage: storage.clone(),
+            storage_name: hash,
             consensus: Arc::new(Mutex::new(None)),
+            pruner: Arc::new(Mutex::new(None)),
         })
     }
 
+    /// Returns `true` if the given state root exists in this service's state.
     #[cfg(feature = "scabbard-get-state")]
-    pub fn get_state_at_address(&self, address: &str) -> Result<Option<Vec<u8>>, ScabbardError> {
+    pub fn has_state_root(&self, state_root: &str) -> Result<bool, ScabbardError> {
         Ok(self
             .state
             .lock()
             .map_err(|_| ScabbardError::LockPoisoned)?
-            .get_state_at_address(address)?)
+            .has_state_root(state_root)?)
     }
 
+    /// Gets the value at the given address, as of the given state root (or the current state
+    /// root if `None`).
+    #[cfg(feature = "scabbard-get-state")]
+    pub fn get_state_at_address(
+        &self,
+        address: &str,
+        state_root: Option<&str>,
+    ) -> Result<Option<Vec<u8>>, ScabbardError> {
+        Ok(self
+            .state
+            .lock()
+            .map_err(|_| ScabbardError::LockPoisoned)?
+            .get_state_at_address(address, state_root)?)
+    }
+
+    /// Gets the entries whose addresses begin with the given prefix (or all entries if `None`),
+    /// as of the given state root (or the current state root if `None`).
     #[cfg(feature = "scabbard-get-state")]
     pub fn get_state_with_prefix(
         &self,
         prefix: Option<&str>,
+        state_root: Option<&str>,
     ) -> Result<Box<StateIter>, ScabbardError> {
         Ok(self
             .state
             .lock()
             .map_err(|_| ScabbardError::LockPoisoned)?
-            .get_state_with_prefix(prefix)?)
+            .get_state_with_prefix(prefix, state_root)?)
+    }
+
+    /// Lists up to `limit` of the most recently committed state roots, newest first, along with
+    /// the IDs of the batches that produced them.
+    #[cfg(feature = "scabbard-get-state")]
+    pub fn get_commit_log(&self, limit: usize) -> Result<Vec<CommitEntry>, ScabbardError> {
+        Ok(self
+            .state
+            .lock()
+            .map_err(|_| ScabbardError::LockPoisoned)?
+            .get_commit_log(limit)?)
     }
 
+    /// Returns the policy used to prune this service's state, if pruning is enabled.
+    pub fn pruning_policy(&self) -> Result<Option<PruningPolicy>, ScabbardError> {
+        Ok(self
+            .state
+            .lock()
+            .map_err(|_| ScabbardError::LockPoisoned)?
+            .pruning_policy()
+            .cloned())
+    }
+
+    /// Returns the totals for all of the times this service's state has been pruned.
+    pub fn pruning_metrics(&self) -> Result<PruneMetrics, ScabbardError> {
+        Ok(self
+            .state
+            .lock()
+            .map_err(|_| ScabbardError::LockPoisoned)?
+            .prune_metrics()
+            .clone())
+    }
+
+    /// Immediately prunes the state roots that are no longer retained by this service's pruning
+    /// policy.
+    pub fn prune_state(&self) -> Result<PruneResult, ScabbardError> {
+        Ok(self
+            .state
+            .lock()
+            .map_err(|_| ScabbardError::LockPoisoned)?
+            .prune()?)
+    }
+
+    /// Verifies the given batches and adds them to the queue of batches to be proposed. Returns
+    /// a link for checking the status of the batches, or `None` if any of the batches are
+    /// invalid.
+    ///
+    /// If the queue does not have room for all of the batches, none of them are added and
+    /// `ScabbardError::BatchQueueFull` is returned. If there are more batches than the queue can
+    /// ever hold, `ScabbardError::TooManyBatches` is returned instead, since retrying the same
+    /// submission would never succeed.
     pub fn add_batches(
         &self,
         batches: Vec<BatchPair>,
@@ -159,6 +254,14 @@ impl Scabbard {
             .lock()
             .map_err(|_| ScabbardError::LockPoisoned)?;
 
+        if batches.len() > shared.max_queued_batches() {
+            return Err(ScabbardError::TooManyBatches(shared.max_queued_batches()));
+        }
+
+        if !shared.has_queue_capacity(batches.len()) {
+            return Err(ScabbardError::BatchQueueFull);
+        }
+
         if shared.verify_batches(&batches)? {
             let mut link = format!(
                 "/scabbard/{}/{}/batch_statuses?ids=",
@@ -186,6 +289,35 @@ impl Scabbard {
         }
     }
 
+    /// Returns the status of the service, including the depth of its batch queue.
+    pub fn status(&self) -> Result<ScabbardStatus, ScabbardError> {
+        let (batch_queue_depth, max_queued_batches, syncing) = {
+            let shared = self
+                .shared
+                .lock()
+                .map_err(|_| ScabbardError::LockPoisoned)?;
+            (
+                shared.batch_queue_len(),
+                shared.max_queued_batches(),
+                shared.is_syncing(),
+            )
+        };
+
+        let current_state_root = self
+            .state
+            .lock()
+            .map_err(|_| ScabbardError::LockPoisoned)?
+            .current_state_root()
+            .to_string();
+
+        Ok(ScabbardStatus {
+            batch_queue_depth,
+            max_queued_batches,
+            current_state_root,
+            syncing,
+        })
+    }
+
     /// Get the `BatchInfo` for each specified batch.
     ///
     /// # Arguments
@@ -213,6 +345,29 @@ impl Scabbard {
             .get_events_since(event_id)?)
     }
 
+    /// Gets the receipt of the committed transaction with the given ID, if there is one.
+    pub fn get_receipt(&self, transaction_id: &str) -> Result<Option<Receipt>, ScabbardError> {
+        Ok(self
+            .state
+            .lock()
+            .map_err(|_| ScabbardError::LockPoisoned)?
+            .get_receipt(transaction_id)?)
+    }
+
+    /// Lists up to `limit` transaction receipts in the order they were committed, starting at
+    /// `offset`, along with the total number of receipts.
+    pub fn list_receipts(
+        &self,
+        offset: usize,
+        limit: usize,
+    ) -> Result<(Vec<Receipt>, usize), ScabbardError> {
+        Ok(self
+            .state
+            .lock()
+            .map_err(|_| ScabbardError::LockPoisoned)?
+            .list_receipts(offset, limit)?)
+    }
+
     pub fn add_state_subscriber(
         &self,
         subscriber: Box<dyn StateSubscriber>,
@@ -253,6 +408,20 @@ impl Service for Scabbard {
             .map_err(|_| ServiceStartError::PoisonedLock("shared lock poisoned".into()))?
             .set_network_sender(service_registry.connect(self.service_id())?);
 
+        // Catch up with any changes that peers committed while this service was offline
+        {
+            let mut shared = self
+                .shared
+                .lock()
+                .map_err(|_| ServiceStartError::PoisonedLock("shared lock poisoned".into()))?;
+            let state = self
+                .state
+                .lock()
+                .map_err(|_| ServiceStartError::PoisonedLock("state lock poisoned".into()))?;
+            sync::request_state_sync(self.service_id(), &mut shared, &state, false)
+                .map_err(|err| ServiceStartError::Internal(Box::new(err)))?;
+        }
+
         // Setup consensus
         consensus.replace(
             ScabbardConsensusManager::new(
@@ -260,10 +429,31 @@ impl Service for Scabbard {
                 self.shared.clone(),
                 self.state.clone(),
                 self.coordinator_timeout,
+                self.consensus_type,
+                self.proposal_limits,
+                &self.storage,
+                &self.storage_name,
             )
             .map_err(|err| ServiceStartError::Internal(Box::new(ScabbardError::from(err))))?,
         );
 
+        // Setup state pruning
+        let pruning_enabled = self
+            .state
+            .lock()
+            .map_err(|_| ServiceStartError::PoisonedLock("state lock poisoned".into()))?
+            .pruning_policy()
+            .is_some();
+        if pruning_enabled {
+            self.pruner
+                .lock()
+                .map_err(|_| ServiceStartError::PoisonedLock("pruner lock poisoned".into()))?
+                .replace(
+                    StatePruner::new(self.service_id(), self.state.clone())
+                        .map_err(|err| ServiceStartError::Internal(Box::new(err)))?,
+                );
+        }
+
         Ok(())
     }
 
@@ -282,6 +472,16 @@ impl Service for Scabbard {
             .shutdown()
             .map_err(|err| ServiceStopError::Internal(Box::new(ScabbardError::from(err))))?;
 
+        // Shutdown state pruning
+        if let Some(pruner) = self
+            .pruner
+            .lock()
+            .map_err(|_| ServiceStopError::PoisonedLock("pruner lock poisoned".into()))?
+            .take()
+        {
+            pruner.shutdown();
+        }
+
         self.shared
             .lock()
             .map_err(|_| ServiceStopError::PoisonedLock("shared lock poisoned".into()))?
@@ -314,7 +514,7 @@ impl Service for Scabbard {
     fn handle_message(
         &self,
         message_bytes: &[u8],
-        _message_context: &ServiceMessageContext,
+        message_context: &ServiceMessageContext,
     ) -> Result<(), ServiceError> {
         let message: ScabbardMessage = protobuf::parse_from_bytes(message_bytes)?;
 
@@ -331,13 +531,31 @@ impl Service for Scabbard {
                 let proposed_batch = message.get_proposed_batch();
 
                 let proposal = Proposal::try_from(proposed_batch.get_proposal())?;
-                let batch = BatchPair::from_bytes(proposed_batch.get_batch())
+                let batches = parse_proposed_batches(proposed_batch)
                     .map_err(|err| ServiceError::UnableToHandleMessage(Box::new(err)))?;
 
-                self.shared
+                let handler_set_hash = proposed_batch.get_handler_set_hash();
+                let handlers_match = handler_set_hash.is_empty()
+                    || handler_set_hash
+                        == self
+                            .state
+                            .lock()
+                            .map_err(|_| ServiceError::PoisonedLock("state lock poisoned".into()))?
+                            .handler_set_hash();
+
+                let mut shared = self
+                    .shared
                     .lock()
-                    .map_err(|_| ServiceError::PoisonedLock("shared lock poisoned".into()))?
-                    .add_proposed_batch(proposal.id.clone(), batch);
+                    .map_err(|_| ServiceError::PoisonedLock("shared lock poisoned".into()))?;
+                if !handlers_match {
+                    warn!(
+                        "Service {} does not have the same transaction handlers as this service",
+                        proposed_batch.get_service_id()
+                    );
+                    shared.add_mismatched_proposal(proposal.id.clone());
+                }
+                shared.add_proposed_batches(proposal.id.clone(), batches);
+                drop(shared);
 
                 self.consensus
                     .lock()
@@ -350,6 +568,44 @@ impl Service for Scabbard {
                     ))
                     .map_err(|err| ServiceError::UnableToHandleMessage(Box::new(err)))
             }
+            ScabbardMessage_Type::STATE_SYNC_REQUEST => {
+                let shared = self
+                    .shared
+                    .lock()
+                    .map_err(|_| ServiceError::PoisonedLock("shared lock poisoned".into()))?;
+                let state = self
+                    .state
+                    .lock()
+                    .map_err(|_| ServiceError::PoisonedLock("state lock poisoned".into()))?;
+
+                sync::handle_state_sync_request(
+                    self.service_id(),
+                    &message_context.sender,
+                    message.get_state_sync_request(),
+                    &shared,
+                    &state,
+                )
+                .map_err(|err| ServiceError::UnableToHandleMessage(Box::new(err)))
+            }
+            ScabbardMessage_Type::STATE_SYNC_RESPONSE => {
+                let mut shared = self
+                    .shared
+                    .lock()
+                    .map_err(|_| ServiceError::PoisonedLock("shared lock poisoned".into()))?;
+                let mut state = self
+                    .state
+                    .lock()
+                    .map_err(|_| ServiceError::PoisonedLock("state lock poisoned".into()))?;
+
+                sync::handle_state_sync_response(
+                    self.service_id(),
+                    &message_context.sender,
+                    message.get_state_sync_response(),
+                    &mut shared,
+                    &mut state,
+                )
+                .map_err(|err| ServiceError::UnableToHandleMessage(Box::new(err)))
+            }
             ScabbardMessage_Type::UNSET => Err(ServiceError::InvalidMessageFormat(Box::new(
                 ScabbardError::MessageTypeUnset,
             ))),
@@ -365,10 +621,27 @@ pub struct BatchListPath {
     link: String,
 }
 
+/// The status of a scabbard service
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ScabbardStatus {
+    /// The number of submitted batches that are waiting to be proposed
+    pub batch_queue_depth: usize,
+    /// The maximum number of batches that may be waiting to be proposed
+    pub max_queued_batches: usize,
+    /// The service's current state root
+    pub current_state_root: String,
+    /// Whether the service is syncing its state with its peers
+    pub syncing: bool,
+}
+
 #[cfg(test)]
 pub mod tests {
     use super::*;
 
+    use transact::protocol::batch::BatchBuilder;
+    use transact::protocol::transaction::{HashMethod, TransactionBuilder};
+    use transact::signing::hash::HashSigner;
+
     use crate::service::tests::*;
     use crate::signing::hash::HashVerifier;
 
@@ -379,13 +652,15 @@ pub mod tests {
             "new_scabbard".into(),
             "test_circuit",
             HashSet::new(),
-            Path::new("/tmp"),
-            1024 * 1024,
-            Path::new("/tmp"),
-            1024 * 1024,
+            &ScabbardStorage::Memory,
             Box::new(HashVerifier),
             vec![],
             None,
+            ConsensusType::TwoPhase,
+            ProposalLimits::default(),
+            None,
+            vec![],
+            None,
         )
         .expect("failed to create service");
         assert_eq!(service.service_id(), "new_scabbard");
@@ -400,13 +675,15 @@ pub mod tests {
             "thread_cleanup".into(),
             "test_circuit",
             HashSet::new(),
-            Path::new("/tmp"),
-            1024 * 1024,
-            Path::new("/tmp"),
-            1024 * 1024,
+            &ScabbardStorage::Memory,
             Box::new(HashVerifier),
             vec![],
             None,
+            ConsensusType::TwoPhase,
+            ProposalLimits::default(),
+            None,
+            vec![],
+            None,
         )
         .expect("failed to create service");
         let registry = MockServiceNetworkRegistry::new();
@@ -421,15 +698,73 @@ pub mod tests {
             "connect_and_disconnect".into(),
             "test_circuit",
             HashSet::new(),
-            Path::new("/tmp"),
-            1024 * 1024,
-            Path::new("/tmp"),
-            1024 * 1024,
+            &ScabbardStorage::Memory,
             Box::new(HashVerifier),
             vec![],
             None,
+            ConsensusType::TwoPhase,
+            ProposalLimits::default(),
+            None,
+            vec![],
+            None,
         )
         .expect("failed to create service");
         test_connect_and_disconnect(&mut service);
     }
+
+    /// Tests that submitting more batches than the batch queue can hold is rejected as too many
+    /// batches, rather than as a full queue that may be retried.
+    ///
+    /// 1. Create a service with a batch queue that holds a single batch
+    /// 2. Submit two batches and verify that `ScabbardError::TooManyBatches` is returned with the
+    ///    queue's limit
+    /// 3. Verify that neither batch was queued
+    #[test]
+    fn add_batches_too_many() {
+        let service = Scabbard::new(
+            "add_batches_too_many".into(),
+            "test_circuit",
+            HashSet::new(),
+            &ScabbardStorage::Memory,
+            Box::new(HashVerifier),
+            vec![],
+            None,
+            ConsensusType::TwoPhase,
+            ProposalLimits::default(),
+            Some(1),
+            vec![],
+            None,
+        )
+        .expect("failed to create service");
+
+        match service.add_batches(vec![make_batch(0), make_batch(1)]) {
+            Err(ScabbardError::TooManyBatches(max)) => assert_eq!(max, 1),
+            Err(err) => panic!("expected TooManyBatches, got {}", err),
+            Ok(_) => panic!("expected TooManyBatches, but the batches were accepted"),
+        }
+
+        let status = service.status().expect("failed to get status");
+        assert_eq!(status.batch_queue_depth, 0);
+    }
+
+    /// Builds a batch with a single transaction, whose nonce and payload are the given nonce.
+    pub fn make_batch(nonce: u8) -> BatchPair {
+        let signer = HashSigner::default();
+        let txn = TransactionBuilder::new()
+            .with_batcher_public_key(vec![])
+            .with_family_name("test".into())
+            .with_family_version("1.0".into())
+            .with_inputs(vec![])
+            .with_outputs(vec![])
+            .with_nonce(vec![nonce])
+            .with_payload(vec![nonce])
+            .with_payload_hash_method(HashMethod::SHA512)
+            .build(&signer)
+            .expect("failed to build transaction");
+
+        BatchBuilder::new()
+            .with_transactions(vec![txn])
+            .build_pair(&signer)
+            .expect("failed to build batch")
+    }
 }
@@ -0,0 +1,126 @@
+// Copyright 2018-2020 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Pruning removes the merkle nodes of old state roots from scabbard's state database.
+//!
+//! Every commit adds a new state root to the merkle tree, and the nodes that are only reachable
+//! from old state roots are kept indefinitely unless they are pruned. When a `PruningPolicy` is
+//! configured, a background thread periodically prunes the committed state roots that the policy
+//! no longer retains; pruning may also be triggered manually. Pruned state roots can no longer be
+//! queried.
+//!
+//! Note that LMDB reuses the pages freed by pruning for new data, but does not shrink the
+//! database file.
+
+use std::sync::mpsc::{channel, RecvTimeoutError, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread::{Builder, JoinHandle};
+use std::time::Duration;
+
+use super::error::ScabbardError;
+use super::state::ScabbardState;
+
+/// How often the background pruner checks for state roots to prune
+const PRUNING_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Determines which committed state roots are retained when state is pruned. The current state
+/// root is always retained.
+#[derive(Clone, Debug, PartialEq)]
+pub enum PruningPolicy {
+    /// Retain the given number of most recently committed state roots
+    KeepRoots(usize),
+    /// Retain the state roots committed within the given duration
+    MaxAge(Duration),
+}
+
+/// The result of a single pruning run
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+pub struct PruneResult {
+    /// The number of state roots that were pruned
+    pub roots_pruned: u64,
+    /// The number of merkle nodes that were removed from the state database
+    pub nodes_removed: u64,
+}
+
+/// Totals for all of the pruning runs performed since the service was created
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+pub struct PruneMetrics {
+    /// The number of times state has been pruned
+    pub runs: u64,
+    /// The total number of state roots that have been pruned
+    pub roots_pruned: u64,
+    /// The total number of merkle nodes that have been removed from the state database
+    pub nodes_removed: u64,
+    /// When state was last pruned, in milliseconds since the Unix epoch
+    pub last_pruned: Option<u64>,
+}
+
+impl PruneMetrics {
+    pub fn record(&mut self, result: &PruneResult, timestamp: u64) {
+        self.runs += 1;
+        self.roots_pruned += result.roots_pruned;
+        self.nodes_removed += result.nodes_removed;
+        self.last_pruned = Some(timestamp);
+    }
+}
+
+/// Periodically prunes a scabbard service's state in a separate thread.
+pub struct StatePruner {
+    shutdown_tx: Sender<()>,
+    thread_handle: JoinHandle<()>,
+}
+
+impl StatePruner {
+    /// Start pruning the given state in a separate thread.
+    pub fn new(service_id: &str, state: Arc<Mutex<ScabbardState>>) -> Result<Self, ScabbardError> {
+        let (shutdown_tx, shutdown_rx) = channel();
+
+        let thread_handle = Builder::new()
+            .name(format!("state-pruner-{}", service_id))
+            .spawn(move || loop {
+                match shutdown_rx.recv_timeout(PRUNING_INTERVAL) {
+                    Err(RecvTimeoutError::Timeout) => {}
+                    Ok(()) | Err(RecvTimeoutError::Disconnected) => break,
+                }
+
+                match state.lock() {
+                    Ok(mut state) => {
+                        if let Err(err) = state.prune() {
+                            error!("Unable to prune state: {}", err);
+                        }
+                    }
+                    Err(_) => {
+                        error!("State lock poisoned; stopping state pruner");
+                        break;
+                    }
+                }
+            })
+            .map_err(|err| ScabbardError::InitializationFailed(Box::new(err)))?;
+
+        Ok(StatePruner {
+            shutdown_tx,
+            thread_handle,
+        })
+    }
+
+    /// Consumes self and shuts down the pruning thread.
+    pub fn shutdown(self) {
+        // If the thread has already exited, the receiver will have been dropped
+        let _ = self.shutdown_tx.send(());
+
+        self.thread_handle
+            .join()
+            .unwrap_or_else(|err| error!("state pruner thread failed: {:?}", err));
+    }
+}
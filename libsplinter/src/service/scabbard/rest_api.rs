@@ -23,15 +23,28 @@ use crate::actix_web::{web, Error as ActixError, HttpResponse};
 use crate::futures::{stream::Stream, Future, IntoFuture};
 use crate::protocol;
 use crate::rest_api::{
-    new_websocket_event_sender, EventSender, Method, ProtocolVersionRangeGuard, Request,
+    new_websocket_event_sender,
+    paging::{get_response_paging_info, Paging, DEFAULT_LIMIT, DEFAULT_OFFSET},
+    EventSender, Method, ProtocolVersionRangeGuard, Request,
 };
 use crate::service::rest_api::ServiceEndpoint;
 
-use super::error::StateSubscriberError;
-use super::state::{StateChangeEvent, StateSubscriber};
-use super::{Scabbard, SERVICE_TYPE};
+use super::error::{ScabbardError, StateSubscriberError};
+use super::state::{Receipt, StateChangeEvent, StateSubscriber};
+use super::{PruningPolicy, Scabbard, SERVICE_TYPE};
 
 const DEFAULT_BATCH_STATUS_WAIT_SECS: u64 = 300;
+/// How long clients are asked to wait before resubmitting batches that were rejected because the
+/// batch queue was full
+const BATCH_QUEUE_FULL_RETRY_SECS: u64 = 5;
+#[cfg(feature = "scabbard-get-state")]
+const DEFAULT_STATE_ROOTS_LIMIT: usize = 100;
+
+#[derive(Serialize)]
+struct ListReceiptsResponse {
+    data: Vec<Receipt>,
+    paging: Paging,
+}
 
 struct WsStateSubscriber {
     sender: EventSender<StateChangeEvent>,
@@ -191,6 +204,24 @@ pub fn make_add_batches_to_queue_endpoint() -> ServiceEndpoint {
                                     "message": "no valid batches provided"
                                 }))
                                 .into_future(),
+                            Err(ScabbardError::BatchQueueFull) => HttpResponse::TooManyRequests()
+                                .header("Retry-After", BATCH_QUEUE_FULL_RETRY_SECS.to_string())
+                                .json(json!({
+                                    "message": "batch queue is full; retry later",
+                                    "retry_after": BATCH_QUEUE_FULL_RETRY_SECS,
+                                }))
+                                .into_future(),
+                            Err(ScabbardError::TooManyBatches(max)) => {
+                                HttpResponse::PayloadTooLarge()
+                                    .json(json!({
+                                        "message": format!(
+                                            "too many batches submitted; at most {} batches may \
+                                             be submitted at once",
+                                            max
+                                        ),
+                                    }))
+                                    .into_future()
+                            }
                             Err(err) => {
                                 error!("Failed to add batches: {}", err);
                                 HttpResponse::InternalServerError()
@@ -326,7 +357,25 @@ pub fn make_get_state_at_address_endpoint() -> ServiceEndpoint {
                 .get("address")
                 .expect("address should not be none");
 
-            Box::new(match scabbard.get_state_at_address(address) {
+            let query: web::Query<HashMap<String, String>> =
+                if let Ok(q) = web::Query::from_query(request.query_string()) {
+                    q
+                } else {
+                    return Box::new(
+                        HttpResponse::BadRequest()
+                            .json(json!({
+                                "message": "Invalid query"
+                            }))
+                            .into_future(),
+                    );
+                };
+
+            let state_root = query.get("state_root").map(String::as_str);
+            if let Some(response) = check_state_root(scabbard, state_root) {
+                return Box::new(response.into_future());
+            }
+
+            Box::new(match scabbard.get_state_at_address(address, state_root) {
                 Ok(Some(value)) => HttpResponse::Ok().json(value).into_future(),
                 Ok(None) => HttpResponse::NotFound()
                     .json(json!({
@@ -386,7 +435,12 @@ pub fn make_get_state_with_prefix_endpoint() -> ServiceEndpoint {
 
             let prefix = query.get("prefix").map(String::as_str);
 
-            Box::new(match scabbard.get_state_with_prefix(prefix) {
+            let state_root = query.get("state_root").map(String::as_str);
+            if let Some(response) = check_state_root(scabbard, state_root) {
+                return Box::new(response.into_future());
+            }
+
+            Box::new(match scabbard.get_state_with_prefix(prefix, state_root) {
                 Ok(state_iter) => {
                     let res = state_iter
                         .map(|res| {
@@ -426,3 +480,388 @@ pub fn make_get_state_with_prefix_endpoint() -> ServiceEndpoint {
         ))],
     }
 }
+
+#[cfg(feature = "scabbard-get-state")]
+pub fn make_get_state_roots_endpoint() -> ServiceEndpoint {
+    ServiceEndpoint {
+        service_type: SERVICE_TYPE.into(),
+        route: "/state_roots".into(),
+        method: Method::Get,
+        handler: Arc::new(move |request, _, service| {
+            let scabbard = match service.as_any().downcast_ref::<Scabbard>() {
+                Some(s) => s,
+                None => {
+                    error!("Failed to downcast to scabbard service");
+                    return Box::new(
+                        HttpResponse::InternalServerError()
+                            .json(json!({
+                                "message": "An internal error occurred"
+                            }))
+                            .into_future(),
+                    );
+                }
+            };
+
+            let query: web::Query<HashMap<String, String>> =
+                if let Ok(q) = web::Query::from_query(request.query_string()) {
+                    q
+                } else {
+                    return Box::new(
+                        HttpResponse::BadRequest()
+                            .json(json!({
+                                "message": "Invalid query"
+                            }))
+                            .into_future(),
+                    );
+                };
+
+            let limit = match query.get("limit").map(|limit| limit.parse::<usize>()) {
+                Some(Ok(limit)) => limit,
+                Some(Err(_)) => {
+                    return Box::new(
+                        HttpResponse::BadRequest()
+                            .json(json!({
+                                "message": "Invalid limit"
+                            }))
+                            .into_future(),
+                    )
+                }
+                None => DEFAULT_STATE_ROOTS_LIMIT,
+            };
+
+            Box::new(match scabbard.get_commit_log(limit) {
+                Ok(entries) => HttpResponse::Ok().json(entries).into_future(),
+                Err(err) => {
+                    error!("Failed to get state roots: {}", err);
+                    HttpResponse::InternalServerError()
+                        .json(json!({
+                            "message": "An internal error occurred"
+                        }))
+                        .into_future()
+                }
+            })
+        }),
+        request_guards: vec![Box::new(ProtocolVersionRangeGuard::new(
+            protocol::SCABBARD_LIST_STATE_ROOTS_PROTOCOL_MIN,
+            protocol::SCABBARD_PROTOCOL_VERSION,
+        ))],
+    }
+}
+
+pub fn make_get_receipt_endpoint() -> ServiceEndpoint {
+    ServiceEndpoint {
+        service_type: SERVICE_TYPE.into(),
+        route: "/receipts/{transaction_id}".into(),
+        method: Method::Get,
+        handler: Arc::new(move |request, _, service| {
+            let scabbard = match service.as_any().downcast_ref::<Scabbard>() {
+                Some(s) => s,
+                None => {
+                    error!("Failed to downcast to scabbard service");
+                    return Box::new(
+                        HttpResponse::InternalServerError()
+                            .json(json!({
+                                "message": "An internal error occurred"
+                            }))
+                            .into_future(),
+                    );
+                }
+            };
+
+            let transaction_id = request
+                .match_info()
+                .get("transaction_id")
+                .expect("transaction_id should not be none");
+
+            Box::new(match scabbard.get_receipt(transaction_id) {
+                Ok(Some(receipt)) => HttpResponse::Ok().json(receipt).into_future(),
+                Ok(None) => HttpResponse::NotFound()
+                    .json(json!({
+                        "message": format!("receipt not found: {}", transaction_id)
+                    }))
+                    .into_future(),
+                Err(err) => {
+                    error!("Failed to get receipt: {}", err);
+                    HttpResponse::InternalServerError()
+                        .json(json!({
+                            "message": "An internal error occurred"
+                        }))
+                        .into_future()
+                }
+            })
+        }),
+        request_guards: vec![Box::new(ProtocolVersionRangeGuard::new(
+            protocol::SCABBARD_GET_RECEIPT_PROTOCOL_MIN,
+            protocol::SCABBARD_PROTOCOL_VERSION,
+        ))],
+    }
+}
+
+pub fn make_list_receipts_endpoint() -> ServiceEndpoint {
+    ServiceEndpoint {
+        service_type: SERVICE_TYPE.into(),
+        route: "/receipts".into(),
+        method: Method::Get,
+        handler: Arc::new(move |request, _, service| {
+            let scabbard = match service.as_any().downcast_ref::<Scabbard>() {
+                Some(s) => s,
+                None => {
+                    error!("Failed to downcast to scabbard service");
+                    return Box::new(
+                        HttpResponse::InternalServerError()
+                            .json(json!({
+                                "message": "An internal error occurred"
+                            }))
+                            .into_future(),
+                    );
+                }
+            };
+
+            let query: web::Query<HashMap<String, String>> =
+                if let Ok(q) = web::Query::from_query(request.query_string()) {
+                    q
+                } else {
+                    return Box::new(
+                        HttpResponse::BadRequest()
+                            .json(json!({
+                                "message": "Invalid query"
+                            }))
+                            .into_future(),
+                    );
+                };
+
+            let offset = match query.get("offset").map(|offset| offset.parse::<usize>()) {
+                Some(Ok(offset)) => offset,
+                Some(Err(_)) => {
+                    return Box::new(
+                        HttpResponse::BadRequest()
+                            .json(json!({
+                                "message": "Invalid offset"
+                            }))
+                            .into_future(),
+                    )
+                }
+                None => DEFAULT_OFFSET,
+            };
+
+            let limit = match query.get("limit").map(|limit| limit.parse::<usize>()) {
+                Some(Ok(limit)) if limit > 0 => limit,
+                Some(_) => {
+                    return Box::new(
+                        HttpResponse::BadRequest()
+                            .json(json!({
+                                "message": "Invalid limit"
+                            }))
+                            .into_future(),
+                    )
+                }
+                None => DEFAULT_LIMIT,
+            };
+
+            let link = format!("{}?", request.uri().path());
+
+            Box::new(match scabbard.list_receipts(offset, limit) {
+                Ok((data, total)) => HttpResponse::Ok()
+                    .json(ListReceiptsResponse {
+                        data,
+                        paging: get_response_paging_info(Some(limit), Some(offset), &link, total),
+                    })
+                    .into_future(),
+                Err(err) => {
+                    error!("Failed to list receipts: {}", err);
+                    HttpResponse::InternalServerError()
+                        .json(json!({
+                            "message": "An internal error occurred"
+                        }))
+                        .into_future()
+                }
+            })
+        }),
+        request_guards: vec![Box::new(ProtocolVersionRangeGuard::new(
+            protocol::SCABBARD_LIST_RECEIPTS_PROTOCOL_MIN,
+            protocol::SCABBARD_PROTOCOL_VERSION,
+        ))],
+    }
+}
+
+pub fn make_get_pruning_endpoint() -> ServiceEndpoint {
+    ServiceEndpoint {
+        service_type: SERVICE_TYPE.into(),
+        route: "/pruning".into(),
+        method: Method::Get,
+        handler: Arc::new(move |_, _, service| {
+            let scabbard = match service.as_any().downcast_ref::<Scabbard>() {
+                Some(s) => s,
+                None => {
+                    error!("Failed to downcast to scabbard service");
+                    return Box::new(
+                        HttpResponse::InternalServerError()
+                            .json(json!({
+                                "message": "An internal error occurred"
+                            }))
+                            .into_future(),
+                    );
+                }
+            };
+
+            let pruning = scabbard.pruning_policy().and_then(|policy| {
+                let metrics = scabbard.pruning_metrics()?;
+                Ok((policy, metrics))
+            });
+
+            Box::new(match pruning {
+                Ok((policy, metrics)) => {
+                    let policy = match policy {
+                        Some(PruningPolicy::KeepRoots(keep_roots)) => {
+                            json!({ "keep_roots": keep_roots })
+                        }
+                        Some(PruningPolicy::MaxAge(max_age)) => {
+                            json!({ "max_age": max_age.as_secs() })
+                        }
+                        None => json!(null),
+                    };
+
+                    HttpResponse::Ok()
+                        .json(json!({
+                            "policy": policy,
+                            "metrics": metrics,
+                        }))
+                        .into_future()
+                }
+                Err(err) => {
+                    error!("Failed to get pruning status: {}", err);
+                    HttpResponse::InternalServerError()
+                        .json(json!({
+                            "message": "An internal error occurred"
+                        }))
+                        .into_future()
+                }
+            })
+        }),
+        request_guards: vec![Box::new(ProtocolVersionRangeGuard::new(
+            protocol::SCABBARD_GET_PRUNING_PROTOCOL_MIN,
+            protocol::SCABBARD_PROTOCOL_VERSION,
+        ))],
+    }
+}
+
+pub fn make_prune_endpoint() -> ServiceEndpoint {
+    ServiceEndpoint {
+        service_type: SERVICE_TYPE.into(),
+        route: "/prune".into(),
+        method: Method::Post,
+        handler: Arc::new(move |_, _, service| {
+            let scabbard = match service.as_any().downcast_ref::<Scabbard>() {
+                Some(s) => s,
+                None => {
+                    error!("Failed to downcast to scabbard service");
+                    return Box::new(
+                        HttpResponse::InternalServerError()
+                            .json(json!({
+                                "message": "An internal error occurred"
+                            }))
+                            .into_future(),
+                    );
+                }
+            };
+
+            match scabbard.pruning_policy() {
+                Ok(Some(_)) => (),
+                Ok(None) => {
+                    return Box::new(
+                        HttpResponse::BadRequest()
+                            .json(json!({
+                                "message": "state pruning is not enabled for this service"
+                            }))
+                            .into_future(),
+                    )
+                }
+                Err(err) => {
+                    error!("Failed to get pruning policy: {}", err);
+                    return Box::new(
+                        HttpResponse::InternalServerError()
+                            .json(json!({
+                                "message": "An internal error occurred"
+                            }))
+                            .into_future(),
+                    );
+                }
+            }
+
+            Box::new(match scabbard.prune_state() {
+                Ok(result) => HttpResponse::Ok().json(result).into_future(),
+                Err(err) => {
+                    error!("Failed to prune state: {}", err);
+                    HttpResponse::InternalServerError()
+                        .json(json!({
+                            "message": "An internal error occurred"
+                        }))
+                        .into_future()
+                }
+            })
+        }),
+        request_guards: vec![Box::new(ProtocolVersionRangeGuard::new(
+            protocol::SCABBARD_PRUNE_PROTOCOL_MIN,
+            protocol::SCABBARD_PROTOCOL_VERSION,
+        ))],
+    }
+}
+
+pub fn make_get_status_endpoint() -> ServiceEndpoint {
+    ServiceEndpoint {
+        service_type: SERVICE_TYPE.into(),
+        route: "/status".into(),
+        method: Method::Get,
+        handler: Arc::new(move |_, _, service| {
+            let scabbard = match service.as_any().downcast_ref::<Scabbard>() {
+                Some(s) => s,
+                None => {
+                    error!("Failed to downcast to scabbard service");
+                    return Box::new(
+                        HttpResponse::InternalServerError()
+                            .json(json!({
+                                "message": "An internal error occurred"
+                            }))
+                            .into_future(),
+                    );
+                }
+            };
+
+            Box::new(match scabbard.status() {
+                Ok(status) => HttpResponse::Ok().json(status).into_future(),
+                Err(err) => {
+                    error!("Failed to get status: {}", err);
+                    HttpResponse::InternalServerError()
+                        .json(json!({
+                            "message": "An internal error occurred"
+                        }))
+                        .into_future()
+                }
+            })
+        }),
+        request_guards: vec![Box::new(ProtocolVersionRangeGuard::new(
+            protocol::SCABBARD_GET_STATUS_PROTOCOL_MIN,
+            protocol::SCABBARD_PROTOCOL_VERSION,
+        ))],
+    }
+}
+
+/// Checks that the requested state root, if any, exists; returns the response to send if it
+/// does not.
+#[cfg(feature = "scabbard-get-state")]
+fn check_state_root(scabbard: &Scabbard, state_root: Option<&str>) -> Option<HttpResponse> {
+    let state_root = state_root?;
+
+    match scabbard.has_state_root(state_root) {
+        Ok(true) => None,
+        Ok(false) => Some(HttpResponse::NotFound().json(json!({
+            "message": format!("state root not found: {}", state_root)
+        }))),
+        Err(err) => {
+            error!("Failed to check state root: {}", err);
+            Some(HttpResponse::InternalServerError().json(json!({
+                "message": "An internal error occurred"
+            })))
+        }
+    }
+}
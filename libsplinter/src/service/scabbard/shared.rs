@@ -13,6 +13,7 @@
 // limitations under the License.
 
 use std::collections::{HashMap, HashSet, VecDeque};
+use std::time::{Duration, Instant};
 
 use transact::protocol::batch::BatchPair;
 use transact::protocol::transaction::{HashMethod, TransactionHeader};
@@ -31,28 +32,45 @@ pub struct ScabbardShared {
     /// Queue of batches that have been submitted locally via the REST API, but have not yet been
     /// proposed.
     batch_queue: VecDeque<BatchPair>,
+    /// The maximum number of batches that may be waiting in the queue; batches submitted while
+    /// the queue is full are rejected.
+    max_queued_batches: usize,
     /// Used to send messages to other services; set when the service is started and unset when the
     /// service is stopped.
     network_sender: Option<Box<dyn ServiceNetworkSender>>,
     /// List of service IDs that this service is configured to communicate and share state with.
     peer_services: HashSet<String>,
     /// Tracks which batches are currently being evaluated, indexed by corresponding proposal IDs.
-    proposed_batches: HashMap<ProposalId, BatchPair>,
+    proposed_batches: HashMap<ProposalId, Vec<BatchPair>>,
+    /// Proposals that were received from a service with a different set of transaction handlers;
+    /// these will always be found invalid when checked.
+    mismatched_proposals: HashSet<ProposalId>,
+    /// Set while this service is syncing its state with its peers; the service does not
+    /// participate in consensus until the sync completes or this deadline passes.
+    state_sync_deadline: Option<Instant>,
+    /// The state roots reported by the peers that have responded to the current state sync
+    /// request, indexed by service ID.
+    state_sync_roots: HashMap<String, String>,
     signature_verifier: Box<dyn SignatureVerifier>,
 }
 
 impl ScabbardShared {
     pub fn new(
         batch_queue: VecDeque<BatchPair>,
+        max_queued_batches: usize,
         network_sender: Option<Box<dyn ServiceNetworkSender>>,
         peer_services: HashSet<String>,
         signature_verifier: Box<dyn SignatureVerifier>,
     ) -> Self {
         ScabbardShared {
             batch_queue,
+            max_queued_batches,
             network_sender,
             peer_services,
             proposed_batches: HashMap::new(),
+            mismatched_proposals: HashSet::new(),
+            state_sync_deadline: None,
+            state_sync_roots: HashMap::new(),
             signature_verifier,
         }
     }
@@ -61,8 +79,49 @@ impl ScabbardShared {
         self.batch_queue.push_back(batch)
     }
 
-    pub fn pop_batch_from_queue(&mut self) -> Option<BatchPair> {
-        self.batch_queue.pop_front()
+    /// Returns the number of batches that are waiting in the queue.
+    pub fn batch_queue_len(&self) -> usize {
+        self.batch_queue.len()
+    }
+
+    pub fn max_queued_batches(&self) -> usize {
+        self.max_queued_batches
+    }
+
+    /// Returns `true` if the given number of batches can be added to the queue without exceeding
+    /// its limit.
+    pub fn has_queue_capacity(&self, count: usize) -> bool {
+        self.batch_queue.len() + count <= self.max_queued_batches
+    }
+
+    /// Removes batches from the front of the queue, in order, until either `max_batches` batches
+    /// have been taken or taking the next batch would exceed `max_bytes`. The first batch is
+    /// always taken, regardless of its size, so an oversized batch can't block the queue.
+    pub fn pop_batches_from_queue(
+        &mut self,
+        max_batches: usize,
+        max_bytes: usize,
+    ) -> Vec<BatchPair> {
+        let mut batches = vec![];
+        let mut total_bytes = 0;
+
+        while batches.len() < max_batches {
+            let size = match self.batch_queue.front() {
+                Some(batch) => batch_size(batch),
+                None => break,
+            };
+
+            if !batches.is_empty() && total_bytes + size > max_bytes {
+                break;
+            }
+
+            if let Some(batch) = self.batch_queue.pop_front() {
+                total_bytes += size;
+                batches.push(batch);
+            }
+        }
+
+        batches
     }
 
     pub fn network_sender(&self) -> Option<&dyn ServiceNetworkSender> {
@@ -81,22 +140,61 @@ impl ScabbardShared {
         &self.peer_services
     }
 
-    pub fn add_proposed_batch(
+    pub fn add_proposed_batches(
         &mut self,
         proposal_id: ProposalId,
-        batch: BatchPair,
-    ) -> Option<BatchPair> {
-        self.proposed_batches.insert(proposal_id, batch)
+        batches: Vec<BatchPair>,
+    ) -> Option<Vec<BatchPair>> {
+        self.proposed_batches.insert(proposal_id, batches)
     }
 
-    pub fn get_proposed_batch(&self, proposal_id: &ProposalId) -> Option<&BatchPair> {
+    pub fn get_proposed_batches(&self, proposal_id: &ProposalId) -> Option<&Vec<BatchPair>> {
         self.proposed_batches.get(proposal_id)
     }
 
-    pub fn remove_proposed_batch(&mut self, proposal_id: &ProposalId) -> Option<BatchPair> {
+    pub fn remove_proposed_batches(&mut self, proposal_id: &ProposalId) -> Option<Vec<BatchPair>> {
+        self.mismatched_proposals.remove(proposal_id);
         self.proposed_batches.remove(&proposal_id)
     }
 
+    pub fn add_mismatched_proposal(&mut self, proposal_id: ProposalId) {
+        self.mismatched_proposals.insert(proposal_id);
+    }
+
+    pub fn is_mismatched_proposal(&self, proposal_id: &ProposalId) -> bool {
+        self.mismatched_proposals.contains(proposal_id)
+    }
+
+    pub fn start_state_sync(&mut self, timeout: Duration) {
+        self.state_sync_deadline = Some(Instant::now() + timeout);
+        self.state_sync_roots.clear();
+    }
+
+    pub fn finish_state_sync(&mut self) {
+        self.state_sync_deadline = None;
+        self.state_sync_roots.clear();
+    }
+
+    /// Records the state root reported by a peer in response to the current state sync request,
+    /// and returns whether a quorum of peers (more than half) have reported that state root.
+    pub fn add_state_sync_root(&mut self, service_id: &str, state_root: &str) -> bool {
+        self.state_sync_roots
+            .insert(service_id.to_string(), state_root.to_string());
+
+        let agreeing = self
+            .state_sync_roots
+            .values()
+            .filter(|root| *root == state_root)
+            .count();
+        agreeing > self.peer_services.len() / 2
+    }
+
+    pub fn is_syncing(&self) -> bool {
+        self.state_sync_deadline
+            .map(|deadline| Instant::now() < deadline)
+            .unwrap_or(false)
+    }
+
     pub fn verify_batches(&self, batches: &[BatchPair]) -> Result<bool, ScabbardError> {
         for batch in batches {
             let batch_pub_key = batch.header().signer_public_key();
@@ -196,3 +294,112 @@ impl ScabbardShared {
         Ok(true)
     }
 }
+
+/// Approximates the serialized size of a batch from the sizes of its headers, signatures, and
+/// transactions.
+fn batch_size(batch: &BatchPair) -> usize {
+    batch.batch().header().len()
+        + batch.batch().header_signature().len()
+        + batch
+            .batch()
+            .transactions()
+            .iter()
+            .map(|txn| txn.header().len() + txn.header_signature().len() + txn.payload().len())
+            .sum::<usize>()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::service::scabbard::tests::make_batch;
+    use crate::signing::hash::HashVerifier;
+
+    /// Verify that batches are taken from the queue in order and within the configured limits.
+    ///
+    /// 1. Add three batches to the queue
+    /// 2. Pop with a max count of 2 and verify that the first two batches are returned
+    /// 3. Pop with a max size smaller than a single batch and verify that the remaining batch is
+    ///    still returned
+    /// 4. Verify that the queue is now empty
+    #[test]
+    fn pop_batches_within_limits() {
+        let mut shared = ScabbardShared::new(
+            VecDeque::new(),
+            10,
+            None,
+            HashSet::new(),
+            Box::new(HashVerifier),
+        );
+
+        let batches = (0..3).map(make_batch).collect::<Vec<_>>();
+        for batch in batches.iter().cloned() {
+            shared.add_batch_to_queue(batch);
+        }
+
+        let popped = shared.pop_batches_from_queue(2, std::usize::MAX);
+        assert_eq!(signatures(&popped), signatures(&batches[..2]));
+
+        let popped = shared.pop_batches_from_queue(2, 1);
+        assert_eq!(signatures(&popped), signatures(&batches[2..]));
+
+        assert!(shared.pop_batches_from_queue(2, std::usize::MAX).is_empty());
+    }
+
+    /// Verify that the max size stops batches from being taken once it would be exceeded.
+    #[test]
+    fn pop_batches_max_bytes() {
+        let mut shared = ScabbardShared::new(
+            VecDeque::new(),
+            10,
+            None,
+            HashSet::new(),
+            Box::new(HashVerifier),
+        );
+
+        let batches = (0..3).map(make_batch).collect::<Vec<_>>();
+        for batch in batches.iter().cloned() {
+            shared.add_batch_to_queue(batch);
+        }
+
+        let max_bytes = batch_size(&batches[0]) + batch_size(&batches[1]);
+        let popped = shared.pop_batches_from_queue(10, max_bytes);
+        assert_eq!(signatures(&popped), signatures(&batches[..2]));
+    }
+
+    /// Verify that the queue only reports capacity for batches that fit within its limit.
+    ///
+    /// 1. Create a queue with a limit of 2 batches and verify that it has capacity for 2 batches,
+    ///    but not 3
+    /// 2. Add 2 batches and verify that the queue has no capacity left
+    /// 3. Pop a batch and verify that the queue has capacity for 1 batch again
+    #[test]
+    fn batch_queue_capacity() {
+        let mut shared = ScabbardShared::new(
+            VecDeque::new(),
+            2,
+            None,
+            HashSet::new(),
+            Box::new(HashVerifier),
+        );
+        assert!(shared.has_queue_capacity(2));
+        assert!(!shared.has_queue_capacity(3));
+
+        for batch in (0..2).map(make_batch) {
+            shared.add_batch_to_queue(batch);
+        }
+        assert_eq!(shared.batch_queue_len(), 2);
+        assert!(!shared.has_queue_capacity(1));
+
+        shared.pop_batches_from_queue(1, std::usize::MAX);
+        assert!(shared.has_queue_capacity(1));
+        assert!(!shared.has_queue_capacity(2));
+    }
+
+    fn signatures(batches: &[BatchPair]) -> Vec<String> {
+        batches
+            .iter()
+            .map(|batch| batch.batch().header_signature().to_string())
+            .collect()
+    }
+}
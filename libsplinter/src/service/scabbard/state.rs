@@ -14,28 +14,26 @@
 
 use std::collections::{HashMap, HashSet, VecDeque};
 use std::convert::TryFrom;
+use std::fmt;
 use std::sync::{
     mpsc::{channel, Receiver, RecvTimeoutError, Sender},
     Arc, RwLock,
 };
-use std::time::{Duration, Instant, SystemTime};
-use std::{fmt, path::Path};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
 use protobuf::Message;
-use sawtooth::store::lmdb::LmdbOrderedStore;
-use sawtooth::store::receipt_store::TransactionReceiptStore;
 use sawtooth_sabre::handler::SabreTransactionHandler;
 use sawtooth_sabre::{ADMINISTRATORS_SETTING_ADDRESS, ADMINISTRATORS_SETTING_KEY};
 use transact::context::manager::sync::ContextManager;
-use transact::database::{
-    lmdb::{LmdbContext, LmdbDatabase},
-    Database,
-};
+use transact::database::Database;
+use transact::handler::TransactionHandler;
 use transact::sawtooth::SawtoothToTransactHandlerAdapter;
 use transact::scheduler::{serial::SerialScheduler, BatchExecutionResult, Scheduler};
+#[cfg(feature = "scabbard-get-state")]
+use transact::state::merkle::StateDatabaseError;
 use transact::state::{
     merkle::{MerkleRadixTree, MerkleState, INDEXES},
-    StateChange as TransactStateChange, Write,
+    Prune, StateChange as TransactStateChange, Write,
 };
 use transact::{
     execution::{adapter::static_adapter::StaticExecutionAdapter, executor::Executor},
@@ -51,12 +49,22 @@ use crate::hex;
 use crate::protos::scabbard::{Setting, Setting_Entry};
 
 use super::error::{ScabbardStateError, StateSubscriberError};
+use super::handler::handler_set_hash;
+use super::prune::{PruneMetrics, PruneResult, PruningPolicy};
+use super::storage::{ReceiptStore, ScabbardStorage};
 
 const EXECUTION_TIMEOUT: u64 = 300; // five minutes
 const CURRENT_STATE_ROOT_INDEX: &str = "current_state_root";
+const BATCH_HISTORY_INDEX: &str = "batch_history";
+const COMMIT_LOG_INDEX: &str = "commit_log";
+const COMMIT_LOG_SIZE: u64 = 1000;
+/// The index in which transact's merkle state records the changes made by each state root
+const MERKLE_CHANGE_LOG_INDEX: &str = "change_log";
 const ITER_CACHE_SIZE: usize = 64;
 const COMPLETED_BATCH_INFO_ITER_RETRY_MILLIS: u64 = 100;
 const DEFAULT_BATCH_HISTORY_SIZE: usize = 100;
+// the error message of batches that were still incomplete when the batch history was reloaded
+const DROPPED_ON_RESTART_MSG: &str = "Batch was dropped when the service restarted";
 
 #[cfg(feature = "scabbard-get-state")]
 pub type StateIter = dyn Iterator<Item = Result<(String, Vec<u8>), ScabbardStateError>>;
@@ -66,27 +74,32 @@ pub struct ScabbardState {
     context_manager: ContextManager,
     executor: Executor,
     current_state_root: String,
-    transaction_receipt_store: Arc<RwLock<TransactionReceiptStore>>,
-    pending_changes: Option<(String, Vec<TransactionReceipt>)>,
+    transaction_receipt_store: Arc<RwLock<Box<dyn ReceiptStore>>>,
+    pending_changes: Option<(Vec<String>, Vec<TransactionReceipt>)>,
     event_subscribers: Vec<Box<dyn StateSubscriber>>,
     batch_history: BatchHistory,
+    handler_set_hash: String,
+    /// The index of the next entry in the commit log
+    next_commit_index: u64,
+    pruning_policy: Option<PruningPolicy>,
+    prune_metrics: PruneMetrics,
 }
 
 impl ScabbardState {
     pub fn new(
-        state_db_path: &Path,
-        state_db_size: usize,
-        receipt_db_path: &Path,
-        receipt_db_size: usize,
+        storage: &ScabbardStorage,
+        // The name of the service's databases within the storage
+        db_name: &str,
         admin_keys: Vec<String>,
+        // Native transaction handlers to run in addition to Sabre
+        transaction_handlers: Vec<Box<dyn TransactionHandler>>,
     ) -> Result<Self, ScabbardStateError> {
         // Initialize the database
         let mut indexes = INDEXES.to_vec();
         indexes.push(CURRENT_STATE_ROOT_INDEX);
-        let db = Box::new(LmdbDatabase::new(
-            LmdbContext::new(state_db_path, indexes.len(), Some(state_db_size))?,
-            &indexes,
-        )?);
+        indexes.push(BATCH_HISTORY_INDEX);
+        indexes.push(COMMIT_LOG_INDEX);
+        let (db, receipt_store) = storage.open(db_name, &indexes)?;
 
         let current_state_root = if let Some(current_state_root) =
             Self::read_current_state_root(&*db)?
@@ -118,12 +131,36 @@ impl ScabbardState {
             )?
         };
 
+        let batch_history = BatchHistory::load(db.clone())?;
+        let next_commit_index = Self::read_next_commit_index(&*db)?;
+
         // Initialize transact
+        let mut handlers: Vec<Box<dyn TransactionHandler>> = vec![Box::new(
+            SawtoothToTransactHandlerAdapter::new(SabreTransactionHandler::new()),
+        )];
+        for handler in transaction_handlers {
+            if handlers
+                .iter()
+                .any(|existing| existing.family_name() == handler.family_name())
+            {
+                return Err(ScabbardStateError(format!(
+                    "multiple transaction handlers provided for family {}",
+                    handler.family_name()
+                )));
+            }
+            handlers.push(handler);
+        }
+        let handler_set_hash = handler_set_hash(
+            &handlers
+                .iter()
+                .map(|handler| &**handler)
+                .collect::<Vec<_>>(),
+        )
+        .map_err(ScabbardStateError)?;
+
         let context_manager = ContextManager::new(Box::new(MerkleState::new(db.clone())));
         let mut executor = Executor::new(vec![Box::new(StaticExecutionAdapter::new_adapter(
-            vec![Box::new(SawtoothToTransactHandlerAdapter::new(
-                SabreTransactionHandler::new(),
-            ))],
+            handlers,
             context_manager.clone(),
         )?)]);
         executor
@@ -135,18 +172,23 @@ impl ScabbardState {
             context_manager,
             executor,
             current_state_root,
-            transaction_receipt_store: Arc::new(RwLock::new(TransactionReceiptStore::new(
-                Box::new(
-                    LmdbOrderedStore::new(receipt_db_path, Some(receipt_db_size))
-                        .map_err(|err| ScabbardStateError(err.to_string()))?,
-                ),
-            ))),
+            transaction_receipt_store: Arc::new(RwLock::new(receipt_store)),
             pending_changes: None,
             event_subscribers: vec![],
-            batch_history: BatchHistory::new(),
+            batch_history,
+            handler_set_hash,
+            next_commit_index,
+            pruning_policy: None,
+            prune_metrics: PruneMetrics::default(),
         })
     }
 
+    /// Returns the digest of the set of transaction handlers that this state executes
+    /// transactions with.
+    pub fn handler_set_hash(&self) -> &str {
+        &self.handler_set_hash
+    }
+
     fn read_current_state_root(db: &dyn Database) -> Result<Option<String>, ScabbardStateError> {
         db.get_reader()
             .and_then(|reader| reader.index_get(CURRENT_STATE_ROOT_INDEX, b"HEAD"))
@@ -180,30 +222,295 @@ impl ScabbardState {
         Ok(())
     }
 
+    /// Returns `true` if the given state root exists in this state's database.
+    #[cfg(feature = "scabbard-get-state")]
+    pub fn has_state_root(&self, state_root: &str) -> Result<bool, ScabbardStateError> {
+        match MerkleRadixTree::new(self.db.clone(), Some(state_root)) {
+            Ok(_) => Ok(true),
+            Err(StateDatabaseError::NotFound(_)) => Ok(false),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// Gets the value at the given address, as of the given state root (or the current state
+    /// root if `None`).
     #[cfg(feature = "scabbard-get-state")]
     pub fn get_state_at_address(
         &self,
         address: &str,
+        state_root: Option<&str>,
     ) -> Result<Option<Vec<u8>>, ScabbardStateError> {
-        Ok(
-            MerkleRadixTree::new(self.db.clone(), Some(&self.current_state_root))?
-                .get_value(address)?,
-        )
+        Ok(MerkleRadixTree::new(
+            self.db.clone(),
+            Some(state_root.unwrap_or(&self.current_state_root)),
+        )?
+        .get_value(address)?)
     }
 
+    /// Gets the entries whose addresses begin with the given prefix (or all entries if `None`),
+    /// as of the given state root (or the current state root if `None`).
     #[cfg(feature = "scabbard-get-state")]
     pub fn get_state_with_prefix(
         &self,
         prefix: Option<&str>,
+        state_root: Option<&str>,
     ) -> Result<Box<StateIter>, ScabbardStateError> {
         Ok(Box::new(
-            MerkleRadixTree::new(self.db.clone(), Some(&self.current_state_root))?
-                .leaves(prefix)?
-                .map(|res| res.map_err(ScabbardStateError::from)),
+            MerkleRadixTree::new(
+                self.db.clone(),
+                Some(state_root.unwrap_or(&self.current_state_root)),
+            )?
+            .leaves(prefix)?
+            .map(|res| res.map_err(ScabbardStateError::from)),
         ))
     }
 
-    pub fn prepare_change(&mut self, batch: BatchPair) -> Result<String, ScabbardStateError> {
+    /// Returns up to `limit` of the most recent entries in the commit log, newest first.
+    #[cfg(feature = "scabbard-get-state")]
+    pub fn get_commit_log(&self, limit: usize) -> Result<Vec<CommitEntry>, ScabbardStateError> {
+        Ok(self
+            .read_commit_log()?
+            .into_iter()
+            .rev()
+            .take(limit)
+            .map(|(_, entry)| entry)
+            .collect())
+    }
+
+    /// Reads every entry in the commit log, oldest first, along with its index.
+    fn read_commit_log(&self) -> Result<Vec<(u64, CommitEntry)>, ScabbardStateError> {
+        let reader = self
+            .db
+            .get_reader()
+            .map_err(|err| ScabbardStateError(format!("Unable to read commit log: {}", err)))?;
+        let cursor = reader
+            .index_cursor(COMMIT_LOG_INDEX)
+            .map_err(|err| ScabbardStateError(format!("Unable to read commit log: {}", err)))?;
+
+        cursor
+            .map(|(key, value)| {
+                let index = parse_commit_index(&key)?;
+                let entry = serde_json::from_slice(&value).map_err(|err| {
+                    ScabbardStateError(format!("Unable to parse commit log entry: {}", err))
+                })?;
+                Ok((index, entry))
+            })
+            .collect()
+    }
+
+    /// Adds an entry for the current state root to the commit log, removing the oldest entry if
+    /// the log has reached its limit.
+    fn append_commit_log(&mut self, batch_ids: Vec<String>) -> Result<(), ScabbardStateError> {
+        let entry = CommitEntry {
+            state_root: self.current_state_root.clone(),
+            batch_ids,
+            timestamp: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|duration| duration.as_millis() as u64)
+                .unwrap_or(0),
+        };
+        let entry_bytes = serde_json::to_vec(&entry).map_err(|err| {
+            ScabbardStateError(format!("Unable to serialize commit log entry: {}", err))
+        })?;
+
+        let mut writer = self.db.get_writer().map_err(|err| {
+            ScabbardStateError(format!(
+                "Unable to start write transaction for commit log: {}",
+                err
+            ))
+        })?;
+        writer
+            .index_put(
+                COMMIT_LOG_INDEX,
+                &self.next_commit_index.to_be_bytes(),
+                &entry_bytes,
+            )
+            .map_err(|err| ScabbardStateError(format!("Unable to write commit log: {}", err)))?;
+        // When pruning is enabled, entries are removed as their state roots are pruned instead.
+        // The state roots of entries that are removed here are still pruned if pruning is enabled
+        // later, since `prune` also prunes the state roots that are not in the commit log.
+        let expired_index = if self.pruning_policy.is_none() {
+            self.next_commit_index.checked_sub(COMMIT_LOG_SIZE)
+        } else {
+            None
+        };
+        if let Some(expired_index) = expired_index {
+            writer
+                .index_delete(COMMIT_LOG_INDEX, &expired_index.to_be_bytes())
+                .map_err(|err| {
+                    ScabbardStateError(format!("Unable to remove commit log entry: {}", err))
+                })?;
+        }
+        writer
+            .commit()
+            .map_err(|err| ScabbardStateError(format!("Unable to commit commit log: {}", err)))?;
+
+        self.next_commit_index += 1;
+
+        Ok(())
+    }
+
+    /// Finds the index of the next entry in the commit log, which follows the last entry
+    /// written.
+    fn read_next_commit_index(db: &dyn Database) -> Result<u64, ScabbardStateError> {
+        let reader = db
+            .get_reader()
+            .map_err(|err| ScabbardStateError(format!("Unable to read commit log: {}", err)))?;
+        let mut cursor = reader
+            .index_cursor(COMMIT_LOG_INDEX)
+            .map_err(|err| ScabbardStateError(format!("Unable to read commit log: {}", err)))?;
+
+        match cursor.seek_last() {
+            Some((key, _)) => Ok(parse_commit_index(&key)? + 1),
+            None => Ok(0),
+        }
+    }
+
+    /// Sets the policy used to prune old state roots; if `None`, state roots are never pruned.
+    pub fn set_pruning_policy(&mut self, pruning_policy: Option<PruningPolicy>) {
+        self.pruning_policy = pruning_policy;
+    }
+
+    pub fn pruning_policy(&self) -> Option<&PruningPolicy> {
+        self.pruning_policy.as_ref()
+    }
+
+    pub fn prune_metrics(&self) -> &PruneMetrics {
+        &self.prune_metrics
+    }
+
+    /// Reads every state root that the merkle state has recorded changes for.
+    fn read_merkle_roots(&self) -> Result<Vec<String>, ScabbardStateError> {
+        let reader = self
+            .db
+            .get_reader()
+            .map_err(|err| ScabbardStateError(format!("Unable to read state roots: {}", err)))?;
+        let cursor = reader
+            .index_cursor(MERKLE_CHANGE_LOG_INDEX)
+            .map_err(|err| ScabbardStateError(format!("Unable to read state roots: {}", err)))?;
+
+        Ok(cursor.map(|(key, _)| hex::to_hex(&key)).collect())
+    }
+
+    /// Prunes the state roots in the commit log that are no longer retained by the pruning
+    /// policy, removing the merkle nodes that are not reachable from any newer state root. The
+    /// current state root is never pruned.
+    ///
+    /// State roots that are not in the commit log, because they were committed before the log
+    /// existed or their entries were removed while pruning was disabled, are older than every
+    /// entry in the log and are always pruned first.
+    pub fn prune(&mut self) -> Result<PruneResult, ScabbardStateError> {
+        let pruning_policy = self
+            .pruning_policy
+            .clone()
+            .ok_or_else(|| ScabbardStateError("state pruning is not enabled".into()))?;
+
+        let commit_log = self.read_commit_log()?;
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_millis() as u64)
+            .unwrap_or(0);
+
+        let retained_count = match pruning_policy {
+            PruningPolicy::KeepRoots(count) => count.max(1),
+            PruningPolicy::MaxAge(max_age) => {
+                let min_timestamp = now.saturating_sub(max_age.as_millis() as u64);
+                commit_log
+                    .iter()
+                    .filter(|(_, entry)| entry.timestamp >= min_timestamp)
+                    .count()
+                    .max(1)
+            }
+        };
+        let prune_count = commit_log.len().saturating_sub(retained_count);
+        let (expired, retained) = commit_log.split_at(prune_count);
+
+        // A state root may be committed more than once, so never prune a root that is retained
+        let retained_roots = retained
+            .iter()
+            .map(|(_, entry)| entry.state_root.as_str())
+            .chain(std::iter::once(self.current_state_root.as_str()))
+            .collect::<HashSet<_>>();
+
+        let logged_roots = commit_log
+            .iter()
+            .map(|(_, entry)| entry.state_root.as_str())
+            .collect::<HashSet<_>>();
+        let unlogged_roots = self
+            .read_merkle_roots()?
+            .into_iter()
+            .filter(|root| {
+                !logged_roots.contains(root.as_str()) && !retained_roots.contains(root.as_str())
+            })
+            .collect::<Vec<_>>();
+
+        let merkle_state = MerkleState::new(self.db.clone());
+        let mut result = PruneResult::default();
+        for state_root in unlogged_roots {
+            let removed = merkle_state
+                .prune(vec![state_root.clone()])
+                .map_err(|err| {
+                    ScabbardStateError(format!(
+                        "Unable to prune state root {}: {}",
+                        state_root, err
+                    ))
+                })?;
+            result.roots_pruned += 1;
+            result.nodes_removed += removed.len() as u64;
+        }
+
+        for (index, entry) in expired {
+            if !retained_roots.contains(entry.state_root.as_str()) {
+                let removed =
+                    merkle_state
+                        .prune(vec![entry.state_root.clone()])
+                        .map_err(|err| {
+                            ScabbardStateError(format!(
+                                "Unable to prune state root {}: {}",
+                                entry.state_root, err
+                            ))
+                        })?;
+                result.roots_pruned += 1;
+                result.nodes_removed += removed.len() as u64;
+            }
+
+            let mut writer = self.db.get_writer().map_err(|err| {
+                ScabbardStateError(format!(
+                    "Unable to start write transaction for commit log: {}",
+                    err
+                ))
+            })?;
+            writer
+                .index_delete(COMMIT_LOG_INDEX, &index.to_be_bytes())
+                .map_err(|err| {
+                    ScabbardStateError(format!("Unable to remove commit log entry: {}", err))
+                })?;
+            writer.commit().map_err(|err| {
+                ScabbardStateError(format!("Unable to commit commit log: {}", err))
+            })?;
+        }
+
+        if result.roots_pruned > 0 {
+            info!(
+                "Pruned {} state root(s), removing {} merkle node(s)",
+                result.roots_pruned, result.nodes_removed
+            );
+        }
+        self.prune_metrics.record(&result, now);
+
+        Ok(result)
+    }
+
+    /// Executes the given batches, in order, against the current state root and saves the
+    /// resulting changes until they are committed or rolled back. Returns the state root that
+    /// will result from committing the changes.
+    ///
+    /// Invalid batches are recorded as such in the batch history and do not contribute any
+    /// changes; an error is returned if none of the batches are valid.
+    pub fn prepare_change(
+        &mut self,
+        batches: Vec<BatchPair>,
+    ) -> Result<String, ScabbardStateError> {
         // Setup the transact scheduler
         let (result_tx, result_rx) = std::sync::mpsc::channel();
         let mut scheduler = SerialScheduler::new(
@@ -216,48 +523,72 @@ impl ScabbardState {
             }
         }))?;
 
-        // Add the batch to, finalize, and execute the scheduler
-        scheduler.add_batch(batch.clone())?;
+        // Add the batches to, finalize, and execute the scheduler
+        let batch_count = batches.len();
+        for batch in batches {
+            scheduler.add_batch(batch)?;
+        }
         scheduler.finalize()?;
         self.executor
             .execute(scheduler.take_task_iterator()?, scheduler.new_notifier()?)?;
 
         // Get the results and shutdown the scheduler
-        let batch_result = result_rx
-            .recv_timeout(Duration::from_secs(EXECUTION_TIMEOUT))
-            .map_err(|_| ScabbardStateError("failed to receive result in reasonable time".into()))?
-            .ok_or_else(|| ScabbardStateError("no result returned from executor".into()))?;
-
-        let batch_status = batch_result.clone().into();
-        let signature = batch.batch().header_signature();
-        self.batch_history
-            .update_batch_status(&signature, batch_status);
-
-        let txn_receipts = batch_result
-            .receipts
-            .into_iter()
-            .map(|receipt| match receipt.transaction_result {
-                TransactionResult::Valid { .. } => Ok(receipt),
-                TransactionResult::Invalid { error_message, .. } => Err(ScabbardStateError(
-                    format!("transaction failed: {:?}", error_message),
-                )),
-            })
-            .collect::<Result<Vec<_>, _>>()?;
+        let mut batch_results = Vec::with_capacity(batch_count);
+        while batch_results.len() < batch_count {
+            match result_rx
+                .recv_timeout(Duration::from_secs(EXECUTION_TIMEOUT))
+                .map_err(|_| {
+                    ScabbardStateError("failed to receive result in reasonable time".into())
+                })? {
+                Some(batch_result) => batch_results.push(batch_result),
+                None => break,
+            }
+        }
 
         scheduler.shutdown();
 
+        if batch_results.len() != batch_count {
+            return Err(ScabbardStateError(format!(
+                "expected {} batch results from executor but got {}",
+                batch_count,
+                batch_results.len()
+            )));
+        }
+
+        let mut signatures = vec![];
+        let mut txn_receipts = vec![];
+        for batch_result in batch_results {
+            let signature = batch_result.batch.batch().header_signature().to_string();
+            let batch_status: BatchStatus = batch_result.clone().into();
+
+            match batch_status {
+                BatchStatus::Valid(_) => {
+                    signatures.push(signature.clone());
+                    txn_receipts.extend(batch_result.receipts);
+                }
+                _ => warn!("Batch {} is invalid and will not be committed", signature),
+            }
+
+            self.batch_history
+                .update_batch_status(&signature, batch_status);
+        }
+
+        if signatures.is_empty() {
+            return Err(ScabbardStateError("no valid batches to prepare".into()));
+        }
+
         // Save the results and compute the resulting state root
         let state_root = MerkleState::new(self.db.clone()).compute_state_id(
             &self.current_state_root,
             &receipts_into_transact_state_changes(&txn_receipts)?,
         )?;
-        self.pending_changes = Some((signature.to_string(), txn_receipts));
+        self.pending_changes = Some((signatures, txn_receipts));
         Ok(state_root)
     }
 
     pub fn commit(&mut self) -> Result<(), ScabbardStateError> {
         match self.pending_changes.take() {
-            Some((signature, txn_receipts)) => {
+            Some((signatures, txn_receipts)) => {
                 let state_changes = receipts_into_transact_state_changes(&txn_receipts)?;
                 self.current_state_root = MerkleState::new(self.db.clone())
                     .commit(&self.current_state_root, &state_changes)?;
@@ -270,42 +601,13 @@ impl ScabbardState {
                     self.current_state_root,
                 );
 
-                let events = txn_receipts
-                    .iter()
-                    .cloned()
-                    .map(StateChangeEvent::try_from)
-                    .collect::<Result<Vec<_>, _>>()?;
-
-                self.transaction_receipt_store
-                    .write()
-                    .map_err(|err| {
-                        ScabbardStateError(format!(
-                            "transaction receipt store lock poisoned: {}",
-                            err
-                        ))
-                    })?
-                    .append(txn_receipts)
-                    .map_err(|err| {
-                        ScabbardStateError(format!(
-                            "failed to add transaction receipts to store: {}",
-                            err
-                        ))
-                    })?;
+                self.store_receipts(txn_receipts)?;
 
-                for event in events {
-                    self.event_subscribers.retain(|subscriber| {
-                        match subscriber.handle_event(event.clone()) {
-                            Ok(()) => true,
-                            Err(StateSubscriberError::Unsubscribe) => false,
-                            Err(err @ StateSubscriberError::UnableToHandleEvent(_)) => {
-                                error!("{}", err);
-                                true
-                            }
-                        }
-                    });
+                for signature in &signatures {
+                    self.batch_history.commit(signature);
                 }
 
-                self.batch_history.commit(&signature);
+                self.append_commit_log(signatures)?;
 
                 Ok(())
             }
@@ -313,6 +615,41 @@ impl ScabbardState {
         }
     }
 
+    /// Adds the committed transaction receipts to the receipt store and notifies subscribers of
+    /// the resulting events.
+    fn store_receipts(
+        &mut self,
+        txn_receipts: Vec<TransactionReceipt>,
+    ) -> Result<(), ScabbardStateError> {
+        let events = txn_receipts
+            .iter()
+            .cloned()
+            .map(StateChangeEvent::try_from)
+            .collect::<Result<Vec<_>, _>>()?;
+
+        self.transaction_receipt_store
+            .write()
+            .map_err(|err| {
+                ScabbardStateError(format!("transaction receipt store lock poisoned: {}", err))
+            })?
+            .append(txn_receipts)?;
+
+        for event in events {
+            self.event_subscribers.retain(|subscriber| {
+                match subscriber.handle_event(event.clone()) {
+                    Ok(()) => true,
+                    Err(StateSubscriberError::Unsubscribe) => false,
+                    Err(err @ StateSubscriberError::UnableToHandleEvent(_)) => {
+                        error!("{}", err);
+                        true
+                    }
+                }
+            });
+        }
+
+        Ok(())
+    }
+
     pub fn rollback(&mut self) -> Result<(), ScabbardStateError> {
         match self.pending_changes.take() {
             Some((_, txn_receipts)) => info!(
@@ -325,6 +662,151 @@ impl ScabbardState {
         Ok(())
     }
 
+    pub fn current_state_root(&self) -> &str {
+        &self.current_state_root
+    }
+
+    /// Returns the ID of the most recently committed transaction receipt, if there is one.
+    pub fn last_receipt_id(&self) -> Result<Option<String>, ScabbardStateError> {
+        let transaction_receipt_store = self.transaction_receipt_store.read().map_err(|err| {
+            ScabbardStateError(format!("transaction receipt store lock poisoned: {}", err))
+        })?;
+
+        let count = transaction_receipt_store.count()?;
+        if count == 0 {
+            return Ok(None);
+        }
+
+        Ok(transaction_receipt_store
+            .get_by_index(count - 1)?
+            .map(|receipt| receipt.transaction_id))
+    }
+
+    /// Gets the data that a lagging service needs in order to reach this state's current state
+    /// root.
+    ///
+    /// If `last_receipt_id` is the ID of a receipt in this state's receipt store, the receipts
+    /// committed after it are returned; otherwise, a snapshot of the full state is returned.
+    pub fn get_state_sync(
+        &self,
+        last_receipt_id: Option<&str>,
+    ) -> Result<StateSync, ScabbardStateError> {
+        if let Some(id) = last_receipt_id {
+            let transaction_receipt_store =
+                self.transaction_receipt_store.read().map_err(|err| {
+                    ScabbardStateError(format!("transaction receipt store lock poisoned: {}", err))
+                })?;
+
+            let has_receipt = transaction_receipt_store.get_by_id(id)?.is_some();
+
+            if has_receipt {
+                let txn_receipts = transaction_receipt_store.iter_since_id(id)?.collect();
+                return Ok(StateSync::Receipts(txn_receipts));
+            }
+        }
+
+        let entries = MerkleRadixTree::new(self.db.clone(), Some(&self.current_state_root))?
+            .leaves(None)?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(StateSync::Snapshot(entries))
+    }
+
+    /// Applies the transaction receipts that a peer committed after this state's last receipt,
+    /// then commits the result if it matches the peer's state root.
+    ///
+    /// The receipts must extend this state's receipts: they must follow `last_receipt_id`, which
+    /// must be this state's last receipt, and none of them may have been committed already.
+    pub fn apply_receipts(
+        &mut self,
+        last_receipt_id: Option<&str>,
+        txn_receipts: Vec<TransactionReceipt>,
+        expected_state_root: &str,
+    ) -> Result<(), ScabbardStateError> {
+        let local_last_receipt_id = self.last_receipt_id()?;
+        if local_last_receipt_id.as_deref() != last_receipt_id {
+            return Err(ScabbardStateError(format!(
+                "receipts follow {:?} but the last receipt is {:?}",
+                last_receipt_id, local_last_receipt_id
+            )));
+        }
+
+        {
+            let transaction_receipt_store =
+                self.transaction_receipt_store.read().map_err(|err| {
+                    ScabbardStateError(format!("transaction receipt store lock poisoned: {}", err))
+                })?;
+            for receipt in &txn_receipts {
+                if transaction_receipt_store
+                    .get_by_id(&receipt.transaction_id)?
+                    .is_some()
+                {
+                    return Err(ScabbardStateError(format!(
+                        "receipt {} has already been committed",
+                        receipt.transaction_id
+                    )));
+                }
+            }
+        }
+
+        let state_changes = receipts_into_transact_state_changes(&txn_receipts)?;
+        let state_root =
+            MerkleState::new(self.db.clone()).commit(&self.current_state_root, &state_changes)?;
+
+        if state_root != expected_state_root {
+            return Err(ScabbardStateError(format!(
+                "applying receipts resulted in state root {} but expected {}",
+                state_root, expected_state_root
+            )));
+        }
+
+        self.set_synced_state_root(state_root)?;
+        self.store_receipts(txn_receipts)
+    }
+
+    /// Replaces this state with a snapshot of a peer's full state, if the snapshot matches the
+    /// peer's state root.
+    ///
+    /// The peer's transaction receipts are not included in a snapshot, so events for the
+    /// changes made before the snapshot will not be available from this service.
+    pub fn apply_snapshot(
+        &mut self,
+        entries: Vec<(String, Vec<u8>)>,
+        expected_state_root: &str,
+    ) -> Result<(), ScabbardStateError> {
+        let empty_state_root = MerkleRadixTree::new(self.db.clone(), None)?.get_merkle_root();
+        let state_changes = entries
+            .into_iter()
+            .map(|(key, value)| TransactStateChange::Set { key, value })
+            .collect::<Vec<_>>();
+        let state_root =
+            MerkleState::new(self.db.clone()).commit(&empty_state_root, &state_changes)?;
+
+        if state_root != expected_state_root {
+            return Err(ScabbardStateError(format!(
+                "snapshot resulted in state root {} but expected {}",
+                state_root, expected_state_root
+            )));
+        }
+
+        self.set_synced_state_root(state_root)
+    }
+
+    fn set_synced_state_root(&mut self, state_root: String) -> Result<(), ScabbardStateError> {
+        if self.pending_changes.take().is_some() {
+            debug!("Discarding pending changes after state sync");
+        }
+
+        info!(
+            "Synced state from {} to {}",
+            self.current_state_root, state_root
+        );
+        self.current_state_root = state_root;
+        self.write_current_state_root()?;
+
+        // The batches that produced a synced state root are not known
+        self.append_commit_log(vec![])
+    }
+
     pub fn batch_history(&mut self) -> &mut BatchHistory {
         &mut self.batch_history
     }
@@ -333,6 +815,41 @@ impl ScabbardState {
         Events::new(self.transaction_receipt_store.clone(), event_id)
     }
 
+    /// Gets the receipt of the committed transaction with the given ID, if there is one.
+    pub fn get_receipt(&self, transaction_id: &str) -> Result<Option<Receipt>, ScabbardStateError> {
+        self.transaction_receipt_store
+            .read()
+            .map_err(|err| {
+                ScabbardStateError(format!("transaction receipt store lock poisoned: {}", err))
+            })?
+            .get_by_id(transaction_id)?
+            .map(Receipt::try_from)
+            .transpose()
+    }
+
+    /// Lists up to `limit` receipts in the order they were committed, starting at `offset`, along
+    /// with the total number of receipts.
+    pub fn list_receipts(
+        &self,
+        offset: usize,
+        limit: usize,
+    ) -> Result<(Vec<Receipt>, usize), ScabbardStateError> {
+        let transaction_receipt_store = self.transaction_receipt_store.read().map_err(|err| {
+            ScabbardStateError(format!("transaction receipt store lock poisoned: {}", err))
+        })?;
+
+        let total = transaction_receipt_store.count()? as usize;
+
+        let receipts = transaction_receipt_store
+            .iter()?
+            .skip(offset)
+            .take(limit)
+            .map(Receipt::try_from)
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok((receipts, total))
+    }
+
     pub fn add_subscriber(&mut self, subscriber: Box<dyn StateSubscriber>) {
         self.event_subscribers.push(subscriber);
     }
@@ -428,6 +945,90 @@ impl From<transact::protocol::receipt::StateChange> for StateChange {
     }
 }
 
+/// The receipt of a committed transaction, which records what the transaction changed
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Receipt {
+    pub transaction_id: String,
+    pub state_changes: Vec<StateChange>,
+    pub events: Vec<ReceiptEvent>,
+    /// Opaque data returned by the transaction handler
+    pub data: Vec<Vec<u8>>,
+}
+
+impl TryFrom<TransactionReceipt> for Receipt {
+    type Error = ScabbardStateError;
+
+    fn try_from(receipt: TransactionReceipt) -> Result<Self, Self::Error> {
+        let TransactionReceipt {
+            transaction_id,
+            transaction_result,
+        } = receipt;
+
+        match transaction_result {
+            TransactionResult::Valid {
+                state_changes,
+                events,
+                data,
+            } => Ok(Receipt {
+                transaction_id,
+                state_changes: state_changes.into_iter().map(StateChange::from).collect(),
+                events: events.into_iter().map(ReceiptEvent::from).collect(),
+                data,
+            }),
+            TransactionResult::Invalid { .. } => Err(ScabbardStateError(format!(
+                "transaction receipt ({}) is for an invalid transaction",
+                transaction_id
+            ))),
+        }
+    }
+}
+
+/// An event emitted by a transaction handler while executing a transaction
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ReceiptEvent {
+    pub event_type: String,
+    pub attributes: Vec<(String, String)>,
+    pub data: Vec<u8>,
+}
+
+impl From<transact::protocol::receipt::Event> for ReceiptEvent {
+    fn from(event: transact::protocol::receipt::Event) -> Self {
+        ReceiptEvent {
+            event_type: event.event_type,
+            attributes: event.attributes,
+            data: event.data,
+        }
+    }
+}
+
+fn parse_commit_index(key: &[u8]) -> Result<u64, ScabbardStateError> {
+    let mut index_bytes = [0; 8];
+    if key.len() != index_bytes.len() {
+        return Err(ScabbardStateError("Invalid commit log index".into()));
+    }
+    index_bytes.copy_from_slice(key);
+    Ok(u64::from_be_bytes(index_bytes))
+}
+
+/// An entry in the commit log, which records each state root committed by scabbard
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct CommitEntry {
+    pub state_root: String,
+    /// The IDs of the batches that were committed to produce the state root; empty if the state
+    /// root was synced from a peer
+    pub batch_ids: Vec<String>,
+    /// Milliseconds since the Unix epoch
+    pub timestamp: u64,
+}
+
+/// The data used to bring a lagging service's state up to date with a peer's
+pub enum StateSync {
+    /// The transaction receipts committed since the lagging service's last receipt
+    Receipts(Vec<TransactionReceipt>),
+    /// Every address and value in the peer's state
+    Snapshot(Vec<(String, Vec<u8>)>),
+}
+
 pub trait StateSubscriber: Send {
     fn handle_event(&self, event: StateChangeEvent) -> Result<(), StateSubscriberError>;
 }
@@ -438,17 +1039,17 @@ enum EventQuery {
     Exhausted,
 }
 
-/// An iterator that wraps the `TransactionReceiptStore` and returns `StateChangeEvent`s using an
+/// An iterator that wraps the `ReceiptStore` and returns `StateChangeEvent`s using an
 /// in-memory cache.
 pub struct Events {
-    transaction_receipt_store: Arc<RwLock<TransactionReceiptStore>>,
+    transaction_receipt_store: Arc<RwLock<Box<dyn ReceiptStore>>>,
     query: EventQuery,
     cache: VecDeque<StateChangeEvent>,
 }
 
 impl Events {
     fn new(
-        transaction_receipt_store: Arc<RwLock<TransactionReceiptStore>>,
+        transaction_receipt_store: Arc<RwLock<Box<dyn ReceiptStore>>>,
         start_id: Option<String>,
     ) -> Result<Self, ScabbardStateError> {
         let mut iter = Events {
@@ -472,16 +1073,10 @@ impl Events {
                     })?;
 
                 self.cache = if let Some(id) = start_id.as_ref() {
-                    transaction_receipt_store.iter_since_id(id.clone())
+                    transaction_receipt_store.iter_since_id(id)
                 } else {
                     transaction_receipt_store.iter()
-                }
-                .map_err(|err| {
-                    ScabbardStateError(format!(
-                        "failed to get transaction receipts from store: {}",
-                        err
-                    ))
-                })?
+                }?
                 .take(ITER_CACHE_SIZE)
                 .map(StateChangeEvent::try_from)
                 .collect::<Result<VecDeque<_>, _>>()?;
@@ -595,11 +1190,24 @@ impl BatchInfo {
     }
 }
 
+/// The form of a `BatchInfo` that is persisted in the batch history database index
+#[derive(Serialize, Deserialize)]
+struct PersistedBatchInfo {
+    status: BatchStatus,
+    /// Milliseconds since the Unix epoch
+    timestamp: u64,
+}
+
 /// BatchHistory keeps track of batches submitted to scabbard
+///
+/// If the history is backed by a database, every change to a batch's info is written through to
+/// the database so it survives restarts. Only the most recently submitted batches are retained,
+/// up to the history's limit.
 pub struct BatchHistory {
     history: HashMap<String, BatchInfo>,
     limit: usize,
     batch_subscribers: Vec<(HashSet<String>, Sender<BatchInfo>)>,
+    db: Option<Box<dyn Database>>,
 }
 
 impl BatchHistory {
@@ -607,6 +1215,95 @@ impl BatchHistory {
         Self::default()
     }
 
+    /// Loads the batch history that was persisted in the given database.
+    ///
+    /// Batches that had not been completed (committed or found invalid) are marked as invalid,
+    /// since the queue of pending batches does not survive a restart and they will never be
+    /// committed; this way, clients waiting on them are told that they failed.
+    fn load(db: Box<dyn Database>) -> Result<Self, ScabbardStateError> {
+        let mut history = HashMap::new();
+        let mut dropped = vec![];
+
+        {
+            let reader = db.get_reader().map_err(|err| {
+                ScabbardStateError(format!("Unable to read batch history: {}", err))
+            })?;
+            let cursor = reader.index_cursor(BATCH_HISTORY_INDEX).map_err(|err| {
+                ScabbardStateError(format!("Unable to read batch history: {}", err))
+            })?;
+
+            for (key, value) in cursor {
+                let id = String::from_utf8(key).map_err(|err| {
+                    ScabbardStateError(format!("Invalid batch ID in batch history: {}", err))
+                })?;
+                let persisted: PersistedBatchInfo =
+                    serde_json::from_slice(&value).map_err(|err| {
+                        ScabbardStateError(format!(
+                            "Unable to parse batch history entry for {}: {}",
+                            id, err
+                        ))
+                    })?;
+
+                let status = match persisted.status {
+                    BatchStatus::Invalid(_) | BatchStatus::Committed(_) => persisted.status,
+                    BatchStatus::Valid(txns) => {
+                        dropped.push(id.clone());
+                        BatchStatus::Invalid(
+                            txns.into_iter()
+                                .map(|txn| {
+                                    InvalidTransaction::new(
+                                        txn.transaction_id,
+                                        DROPPED_ON_RESTART_MSG.into(),
+                                        vec![],
+                                    )
+                                })
+                                .collect(),
+                        )
+                    }
+                    BatchStatus::Pending | BatchStatus::Unknown => {
+                        dropped.push(id.clone());
+                        BatchStatus::Invalid(vec![InvalidTransaction::new(
+                            String::new(),
+                            DROPPED_ON_RESTART_MSG.into(),
+                            vec![],
+                        )])
+                    }
+                };
+
+                let batch_info = BatchInfo {
+                    id: id.clone(),
+                    status,
+                    timestamp: UNIX_EPOCH + Duration::from_millis(persisted.timestamp),
+                };
+                history.insert(id, batch_info);
+            }
+        }
+
+        if !dropped.is_empty() {
+            debug!(
+                "Marking {} incomplete batch(es) in batch history as invalid",
+                dropped.len()
+            );
+        }
+
+        let mut batch_history = Self {
+            history,
+            db: Some(db),
+            ..Self::default()
+        };
+
+        for id in dropped {
+            if let Some(info) = batch_history.history.get(&id) {
+                batch_history.persist_batch(info);
+            }
+        }
+        while batch_history.history.len() > batch_history.limit {
+            batch_history.remove_oldest_batch();
+        }
+
+        Ok(batch_history)
+    }
+
     pub fn add_batch(&mut self, signature: &str) {
         self.upsert_batch(signature.into(), BatchStatus::Pending);
     }
@@ -627,6 +1324,8 @@ impl BatchHistory {
             Some(info) => match info.status.clone() {
                 BatchStatus::Valid(txns) => {
                     info.set_status(BatchStatus::Committed(txns));
+                    let info = info.clone();
+                    self.persist_batch(&info);
                 }
                 _ => {
                     error!(
@@ -648,7 +1347,9 @@ impl BatchHistory {
         match self.history.get_mut(&signature) {
             Some(info) => {
                 info.set_status(status);
-                info.clone()
+                let info = info.clone();
+                self.persist_batch(&info);
+                info
             }
             None => {
                 let batch_info = BatchInfo {
@@ -658,13 +1359,10 @@ impl BatchHistory {
                 };
 
                 self.history.insert(signature, batch_info.clone());
+                self.persist_batch(&batch_info);
 
                 if self.history.len() > self.limit {
-                    self.history
-                        .clone()
-                        .into_iter()
-                        .min_by_key(|(_, v)| v.timestamp)
-                        .and_then(|(k, _)| self.history.remove(&k));
+                    self.remove_oldest_batch();
                 }
 
                 batch_info
@@ -672,6 +1370,73 @@ impl BatchHistory {
         }
     }
 
+    fn remove_oldest_batch(&mut self) {
+        let oldest = self
+            .history
+            .values()
+            .min_by_key(|info| info.timestamp)
+            .map(|info| info.id.clone());
+
+        if let Some(id) = oldest {
+            self.history.remove(&id);
+            self.delete_persisted_batch(&id);
+        }
+    }
+
+    /// Writes the batch info to the database, if this history is backed by one. Failures are
+    /// logged, since the in-memory history remains accurate.
+    fn persist_batch(&self, info: &BatchInfo) {
+        let db = match self.db.as_ref() {
+            Some(db) => db,
+            None => return,
+        };
+
+        let persisted = PersistedBatchInfo {
+            status: info.status.clone(),
+            timestamp: info
+                .timestamp
+                .duration_since(UNIX_EPOCH)
+                .map(|duration| duration.as_millis() as u64)
+                .unwrap_or(0),
+        };
+
+        let result = serde_json::to_vec(&persisted)
+            .map_err(|err| err.to_string())
+            .and_then(|bytes| {
+                let mut writer = db.get_writer().map_err(|err| err.to_string())?;
+                writer
+                    .index_put(BATCH_HISTORY_INDEX, info.id.as_bytes(), &bytes)
+                    .map_err(|err| err.to_string())?;
+                writer.commit().map_err(|err| err.to_string())
+            });
+
+        if let Err(err) = result {
+            error!("Unable to persist batch history for {}: {}", info.id, err);
+        }
+    }
+
+    /// Removes the batch info from the database, if this history is backed by one.
+    fn delete_persisted_batch(&self, id: &str) {
+        let db = match self.db.as_ref() {
+            Some(db) => db,
+            None => return,
+        };
+
+        let result = db
+            .get_writer()
+            .map_err(|err| err.to_string())
+            .and_then(|mut writer| {
+                writer
+                    .index_delete(BATCH_HISTORY_INDEX, id.as_bytes())
+                    .map_err(|err| err.to_string())?;
+                writer.commit().map_err(|err| err.to_string())
+            });
+
+        if let Err(err) = result {
+            error!("Unable to remove batch history for {}: {}", id, err);
+        }
+    }
+
     pub fn get_batch_info(
         &mut self,
         ids: HashSet<String>,
@@ -758,6 +1523,7 @@ impl Default for BatchHistory {
             history: HashMap::new(),
             limit: DEFAULT_BATCH_HISTORY_SIZE,
             batch_subscribers: vec![],
+            db: None,
         }
     }
 }
@@ -823,6 +1589,10 @@ impl Iterator for ChannelBatchInfoIter {
 mod tests {
     use super::*;
 
+    use sawtooth::store::lmdb::LmdbOrderedStore;
+    use sawtooth::store::receipt_store::TransactionReceiptStore;
+    use transact::database::lmdb::{LmdbContext, LmdbDatabase};
+
     const TEMP_DB_SIZE: usize = 1 << 30; // 1024 ** 3
 
     /// Verify that an empty receipt store returns an empty iterator
@@ -831,11 +1601,12 @@ mod tests {
         let temp_db_path = get_temp_db_path();
 
         let test_result = std::panic::catch_unwind(|| {
-            let transaction_receipt_store =
-                Arc::new(RwLock::new(TransactionReceiptStore::new(Box::new(
+            let transaction_receipt_store: Arc<RwLock<Box<dyn ReceiptStore>>> = Arc::new(
+                RwLock::new(Box::new(TransactionReceiptStore::new(Box::new(
                     LmdbOrderedStore::new(&temp_db_path, Some(TEMP_DB_SIZE))
                         .expect("Failed to create LMDB store"),
-                ))));
+                )))),
+            );
 
             // Test without a specified start
             let all_events = Events::new(transaction_receipt_store.clone(), None)
@@ -868,11 +1639,12 @@ mod tests {
                 .map(|receipt| receipt.transaction_id.clone())
                 .collect::<Vec<_>>();
 
-            let transaction_receipt_store =
-                Arc::new(RwLock::new(TransactionReceiptStore::new(Box::new(
+            let transaction_receipt_store: Arc<RwLock<Box<dyn ReceiptStore>>> = Arc::new(
+                RwLock::new(Box::new(TransactionReceiptStore::new(Box::new(
                     LmdbOrderedStore::new(&temp_db_path, Some(TEMP_DB_SIZE))
                         .expect("Failed to create LMDB store"),
-                ))));
+                )))),
+            );
 
             transaction_receipt_store
                 .write()
@@ -903,6 +1675,289 @@ mod tests {
         assert!(test_result.is_ok());
     }
 
+    /// Verify that completed batches in the batch history survive a reload from the database,
+    /// while incomplete batches are marked as invalid.
+    ///
+    /// 1. Create a database-backed batch history
+    /// 2. Add four batches and mark one as valid, one as invalid and one as committed
+    /// 3. Load a new batch history from the same database
+    /// 4. Verify that the invalid and committed batches are unchanged, but the pending and valid
+    ///    batches are now invalid, so waiting on them returns immediately
+    /// 5. Load the batch history again and verify that the batches are still invalid
+    #[test]
+    fn batch_history_persistence() {
+        let temp_db_path = get_temp_db_path();
+
+        let test_result = std::panic::catch_unwind(|| {
+            let db: Box<dyn Database> = Box::new(
+                LmdbDatabase::new(
+                    LmdbContext::new(&temp_db_path, 1, Some(TEMP_DB_SIZE))
+                        .expect("Failed to create LMDB context"),
+                    &[BATCH_HISTORY_INDEX],
+                )
+                .expect("Failed to create LMDB database"),
+            );
+
+            let invalid_status = BatchStatus::Invalid(vec![InvalidTransaction::new(
+                "txn".into(),
+                "error".into(),
+                vec![],
+            )]);
+
+            let mut history = BatchHistory::load(db.clone()).expect("failed to load history");
+            history.add_batch("pending");
+            history.add_batch("valid");
+            history.add_batch("invalid");
+            history.add_batch("committed");
+            history.update_batch_status(
+                "valid",
+                BatchStatus::Valid(vec![ValidTransaction::new("txn".into())]),
+            );
+            history.update_batch_status("invalid", invalid_status.clone());
+            history.update_batch_status("committed", BatchStatus::Valid(vec![]));
+            history.commit("committed");
+
+            let dropped_pending_status = BatchStatus::Invalid(vec![InvalidTransaction::new(
+                String::new(),
+                DROPPED_ON_RESTART_MSG.into(),
+                vec![],
+            )]);
+            let dropped_valid_status = BatchStatus::Invalid(vec![InvalidTransaction::new(
+                "txn".into(),
+                DROPPED_ON_RESTART_MSG.into(),
+                vec![],
+            )]);
+
+            let ids = ["pending", "valid", "invalid", "committed"]
+                .iter()
+                .map(ToString::to_string)
+                .collect::<HashSet<_>>();
+
+            let mut reloaded = BatchHistory::load(db.clone()).expect("failed to reload history");
+            let statuses = reloaded
+                .get_batch_info(ids.clone(), Some(Duration::from_secs(1)))
+                .expect("failed to get batch info")
+                .map(|res| {
+                    let info = res.expect("failed to get batch info");
+                    (info.id, info.status)
+                })
+                .collect::<HashMap<_, _>>();
+
+            assert_eq!(statuses.get("pending"), Some(&dropped_pending_status));
+            assert_eq!(statuses.get("valid"), Some(&dropped_valid_status));
+            assert_eq!(statuses.get("invalid"), Some(&invalid_status));
+            assert_eq!(
+                statuses.get("committed"),
+                Some(&BatchStatus::Committed(vec![]))
+            );
+
+            let mut reloaded = BatchHistory::load(db).expect("failed to reload history");
+            let statuses = reloaded
+                .get_batch_info(ids, None)
+                .expect("failed to get batch info")
+                .map(|res| {
+                    let info = res.expect("failed to get batch info");
+                    (info.id, info.status)
+                })
+                .collect::<HashMap<_, _>>();
+
+            assert_eq!(statuses.get("pending"), Some(&dropped_pending_status));
+            assert_eq!(statuses.get("valid"), Some(&dropped_valid_status));
+        });
+
+        std::fs::remove_file(temp_db_path.as_path()).expect("Failed to remove temp DB file");
+
+        assert!(test_result.is_ok());
+    }
+
+    /// Verify that a state can be synced from another state's snapshot, and that a snapshot is
+    /// rejected if it does not match the expected state root.
+    ///
+    /// 1. Create two states with different admin keys, so they have different state roots
+    /// 2. Get a sync from the first state without a receipt ID and verify it is a snapshot
+    /// 3. Verify that applying the snapshot to the second state with the wrong state root fails
+    ///    and leaves the second state unchanged
+    /// 4. Apply the snapshot with the correct state root and verify that both states now have
+    ///    the same state root
+    #[test]
+    fn state_sync_snapshot() {
+        let source =
+            ScabbardState::new(&ScabbardStorage::Memory, "test", vec!["ab".into()], vec![])
+                .expect("failed to create source state");
+        let mut target = ScabbardState::new(&ScabbardStorage::Memory, "test", vec![], vec![])
+            .expect("failed to create target state");
+        let target_root = target.current_state_root().to_string();
+        assert_ne!(source.current_state_root(), target_root);
+
+        let entries = match source.get_state_sync(None).expect("failed to get sync") {
+            StateSync::Snapshot(entries) => entries,
+            StateSync::Receipts(_) => panic!("expected a snapshot"),
+        };
+
+        assert!(target
+            .apply_snapshot(entries.clone(), &target_root)
+            .is_err());
+        assert_eq!(target.current_state_root(), target_root);
+
+        target
+            .apply_snapshot(entries, source.current_state_root())
+            .expect("failed to apply snapshot");
+        assert_eq!(target.current_state_root(), source.current_state_root());
+    }
+
+    /// Verify that a peer's receipts are only applied if they extend the state's own receipts.
+    ///
+    /// 1. Create a state and store a receipt
+    /// 2. Verify that receipts that do not follow the state's last receipt are rejected
+    /// 3. Verify that receipts that include an already committed receipt are rejected
+    /// 4. Verify that receipts that follow the state's last receipt are applied
+    #[test]
+    fn state_sync_receipts_extend_chain() {
+        let mut state = ScabbardState::new(&ScabbardStorage::Memory, "test", vec![], vec![])
+            .expect("failed to create state");
+        state
+            .store_receipts(vec![mock_transaction_receipt("ab")])
+            .expect("failed to store receipts");
+        let state_root = state.current_state_root().to_string();
+
+        assert!(state
+            .apply_receipts(None, vec![mock_transaction_receipt("cd")], &state_root)
+            .is_err());
+        assert!(state
+            .apply_receipts(
+                Some("ef"),
+                vec![mock_transaction_receipt("cd")],
+                &state_root
+            )
+            .is_err());
+        assert!(state
+            .apply_receipts(
+                Some("ab"),
+                vec![
+                    mock_transaction_receipt("ab"),
+                    mock_transaction_receipt("cd")
+                ],
+                &state_root
+            )
+            .is_err());
+        assert_eq!(
+            state.last_receipt_id().expect("failed to get last receipt"),
+            Some("ab".into())
+        );
+
+        state
+            .apply_receipts(
+                Some("ab"),
+                vec![mock_transaction_receipt("cd")],
+                &state_root,
+            )
+            .expect("failed to apply receipts");
+        assert_eq!(
+            state.last_receipt_id().expect("failed to get last receipt"),
+            Some("cd".into())
+        );
+    }
+
+    /// Verify that pruning removes the state roots that are not retained by the pruning policy,
+    /// and never removes the current state root.
+    ///
+    /// 1. Create two source states with different admin keys, and sync a third state from each
+    ///    of them in turn so that its commit log has two entries
+    /// 2. Verify that pruning fails when no pruning policy is set
+    /// 3. Set a policy that keeps one state root and prune
+    /// 4. Verify that the first synced state root and the initial state root, which is not in
+    ///    the commit log, were pruned, that the current state root is still readable, and that
+    ///    the metrics were updated
+    /// 5. Prune again and verify that no more state roots are pruned
+    #[test]
+    fn prune_state_roots() {
+        let mut target = ScabbardState::new(&ScabbardStorage::Memory, "test", vec![], vec![])
+            .expect("failed to create target state");
+
+        for (i, admin_key) in ["ab", "cd"].iter().enumerate() {
+            let source = ScabbardState::new(
+                &ScabbardStorage::Memory,
+                "test",
+                vec![admin_key.to_string()],
+                vec![],
+            )
+            .expect("failed to create source state");
+            let entries = match source.get_state_sync(None).expect("failed to get sync") {
+                StateSync::Snapshot(entries) => entries,
+                StateSync::Receipts(_) => panic!("expected a snapshot"),
+            };
+            target
+                .apply_snapshot(entries, source.current_state_root())
+                .expect("failed to apply snapshot");
+        }
+
+        let commit_log = target.read_commit_log().expect("failed to read commit log");
+        assert_eq!(commit_log.len(), 2);
+        let current_state_root = target.current_state_root().to_string();
+        assert_eq!(commit_log[1].1.state_root, current_state_root);
+
+        assert!(target.prune().is_err());
+
+        target.set_pruning_policy(Some(PruningPolicy::KeepRoots(1)));
+        let result = target.prune().expect("failed to prune");
+        assert_eq!(result.roots_pruned, 2);
+
+        let commit_log = target.read_commit_log().expect("failed to read commit log");
+        assert_eq!(commit_log.len(), 1);
+        assert_eq!(commit_log[0].1.state_root, current_state_root);
+        assert!(MerkleRadixTree::new(target.db.clone(), Some(&current_state_root)).is_ok());
+
+        let metrics = target.prune_metrics();
+        assert_eq!(metrics.runs, 1);
+        assert_eq!(metrics.roots_pruned, 2);
+        assert_eq!(metrics.nodes_removed, result.nodes_removed);
+        assert!(metrics.last_pruned.is_some());
+
+        let result = target.prune().expect("failed to prune");
+        assert_eq!(result.roots_pruned, 0);
+        assert!(MerkleRadixTree::new(target.db.clone(), Some(&current_state_root)).is_ok());
+    }
+
+    /// Verify that receipts can be fetched by transaction ID and listed in commit order.
+    ///
+    /// 1. Create a state and store three receipts
+    /// 2. Verify that a stored receipt can be fetched and an unknown receipt cannot
+    /// 3. Verify that listing with an offset and limit returns the expected receipts in commit
+    ///    order, along with the total number of receipts
+    #[test]
+    fn receipt_queries() {
+        let mut state = ScabbardState::new(&ScabbardStorage::Memory, "test", vec![], vec![])
+            .expect("failed to create state");
+
+        state
+            .store_receipts(vec![
+                mock_transaction_receipt("ab"),
+                mock_transaction_receipt("cd"),
+                mock_transaction_receipt("ef"),
+            ])
+            .expect("failed to store receipts");
+
+        let receipt = state
+            .get_receipt("cd")
+            .expect("failed to get receipt")
+            .expect("receipt not found");
+        assert_eq!(receipt.transaction_id, "cd");
+        assert!(state
+            .get_receipt("gh")
+            .expect("failed to get receipt")
+            .is_none());
+
+        let (receipts, total) = state.list_receipts(1, 5).expect("failed to list receipts");
+        assert_eq!(total, 3);
+        assert_eq!(
+            receipts
+                .iter()
+                .map(|receipt| receipt.transaction_id.as_str())
+                .collect::<Vec<_>>(),
+            vec!["cd", "ef"]
+        );
+    }
+
     fn get_temp_db_path() -> std::path::PathBuf {
         let mut temp_db_path = std::env::temp_dir();
         let thread_id = std::thread::current().id();
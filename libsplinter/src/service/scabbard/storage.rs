@@ -0,0 +1,510 @@
+// Copyright 2018-2020 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Storage backends for the state and transaction receipts of scabbard services.
+
+use std::path::Path;
+
+use sawtooth::store::lmdb::LmdbOrderedStore;
+use sawtooth::store::receipt_store::TransactionReceiptStore;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+#[cfg(feature = "scabbard-sqlite")]
+use transact::database::sqlite::SqliteDatabase;
+use transact::database::{
+    btree::BTreeDatabase,
+    lmdb::{LmdbContext, LmdbDatabase},
+    Database,
+};
+use transact::protocol::receipt::TransactionReceipt;
+use transact::protos::{FromBytes, IntoBytes};
+
+use crate::storage::{MemStorage, Storage, YamlStorage};
+
+use super::error::ScabbardStateError;
+
+const DEFAULT_STATE_DB_DIR: &str = "/var/lib/splinter";
+const DEFAULT_STATE_DB_SIZE: usize = 1 << 30; // 1024 ** 3
+const DEFAULT_RECEIPT_DB_DIR: &str = "/var/lib/splinter";
+const DEFAULT_RECEIPT_DB_SIZE: usize = 1 << 30; // 1024 ** 3
+
+/// Maps the position of each receipt in commit order to the receipt
+const RECEIPT_INDEX: &str = "receipts";
+/// Maps the transaction ID of each receipt to its position in commit order
+const RECEIPT_ID_INDEX: &str = "receipt_ids";
+
+/// The storage used for the state and transaction receipts of scabbard services. Each service
+/// has its own databases, which are named for the service.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ScabbardStorage {
+    /// LMDB databases, which are memory-mapped with a fixed maximum size
+    Lmdb {
+        /// The directory in which to create the state databases
+        state_db_dir: String,
+        /// The maximum size (in bytes) of each state database
+        state_db_size: usize,
+        /// The directory in which to create the transaction receipt databases
+        receipt_db_dir: String,
+        /// The maximum size (in bytes) of each transaction receipt database
+        receipt_db_size: usize,
+    },
+    /// In-memory databases, which are lost when the service is shut down; useful for tests and
+    /// ephemeral circuits
+    Memory,
+    /// SQLite databases, which hold both state and transaction receipts
+    #[cfg(feature = "scabbard-sqlite")]
+    Sqlite {
+        /// The directory in which to create the databases
+        db_dir: String,
+    },
+}
+
+impl Default for ScabbardStorage {
+    fn default() -> Self {
+        ScabbardStorage::Lmdb {
+            state_db_dir: DEFAULT_STATE_DB_DIR.into(),
+            state_db_size: DEFAULT_STATE_DB_SIZE,
+            receipt_db_dir: DEFAULT_RECEIPT_DB_DIR.into(),
+            receipt_db_size: DEFAULT_RECEIPT_DB_SIZE,
+        }
+    }
+}
+
+impl ScabbardStorage {
+    /// Creates an `Lmdb` storage configuration, using the default value for any argument that is
+    /// `None`.
+    pub fn lmdb(
+        state_db_dir: Option<String>,
+        state_db_size: Option<usize>,
+        receipt_db_dir: Option<String>,
+        receipt_db_size: Option<usize>,
+    ) -> Self {
+        ScabbardStorage::Lmdb {
+            state_db_dir: state_db_dir.unwrap_or_else(|| DEFAULT_STATE_DB_DIR.into()),
+            state_db_size: state_db_size.unwrap_or(DEFAULT_STATE_DB_SIZE),
+            receipt_db_dir: receipt_db_dir.unwrap_or_else(|| DEFAULT_RECEIPT_DB_DIR.into()),
+            receipt_db_size: receipt_db_size.unwrap_or(DEFAULT_RECEIPT_DB_SIZE),
+        }
+    }
+
+    /// Opens (or creates) the state database, with the given indexes, and the transaction receipt
+    /// store with the given name.
+    pub fn open(
+        &self,
+        name: &str,
+        indexes: &[&'static str],
+    ) -> Result<(Box<dyn Database>, Box<dyn ReceiptStore>), ScabbardStateError> {
+        match self {
+            ScabbardStorage::Lmdb {
+                state_db_dir,
+                state_db_size,
+                receipt_db_dir,
+                receipt_db_size,
+            } => {
+                let state_db_path = Path::new(state_db_dir).join(format!("{}-state.lmdb", name));
+                let receipt_db_path =
+                    Path::new(receipt_db_dir).join(format!("{}-receipts.lmdb", name));
+
+                let db = LmdbDatabase::new(
+                    LmdbContext::new(&state_db_path, indexes.len(), Some(*state_db_size))?,
+                    indexes,
+                )?;
+                let receipt_store = TransactionReceiptStore::new(Box::new(
+                    LmdbOrderedStore::new(&receipt_db_path, Some(*receipt_db_size))
+                        .map_err(|err| ScabbardStateError(err.to_string()))?,
+                ));
+
+                Ok((Box::new(db), Box::new(receipt_store)))
+            }
+            ScabbardStorage::Memory => {
+                let db: Box<dyn Database> =
+                    Box::new(BTreeDatabase::new(&with_receipt_indexes(indexes)));
+                let receipt_store = DatabaseReceiptStore::new(db.clone());
+
+                Ok((db, Box::new(receipt_store)))
+            }
+            #[cfg(feature = "scabbard-sqlite")]
+            ScabbardStorage::Sqlite { db_dir } => {
+                let db_path = Path::new(db_dir).join(format!("{}.sqlite", name));
+                let db_path = db_path.to_str().ok_or_else(|| {
+                    ScabbardStateError(format!("invalid database path: {:?}", db_path))
+                })?;
+
+                let db: Box<dyn Database> = Box::new(
+                    SqliteDatabase::new(db_path, &with_receipt_indexes(indexes)).map_err(
+                        |err| ScabbardStateError(format!("failed to open database: {}", err)),
+                    )?,
+                );
+                let receipt_store = DatabaseReceiptStore::new(db.clone());
+
+                Ok((db, Box::new(receipt_store)))
+            }
+        }
+    }
+
+    /// Opens (or creates) the storage with the given name, in which a consensus engine keeps the
+    /// state that it must persist. The state is kept in a YAML file alongside the state database,
+    /// or in memory if the service's databases are in memory.
+    pub fn open_consensus_state<T, F>(
+        &self,
+        name: &str,
+        default: F,
+    ) -> Result<Box<dyn Storage<S = T> + Send>, ScabbardStateError>
+    where
+        T: Serialize + DeserializeOwned + Send + 'static,
+        F: Fn() -> T,
+    {
+        let db_dir = match self {
+            ScabbardStorage::Lmdb { state_db_dir, .. } => state_db_dir,
+            ScabbardStorage::Memory => {
+                return Ok(Box::new(
+                    MemStorage::new(default).map_err(ScabbardStateError)?,
+                ))
+            }
+            #[cfg(feature = "scabbard-sqlite")]
+            ScabbardStorage::Sqlite { db_dir } => db_dir,
+        };
+
+        let path = Path::new(db_dir).join(format!("{}-consensus.yaml", name));
+        let path = path.to_str().ok_or_else(|| {
+            ScabbardStateError(format!("invalid consensus state path: {:?}", path))
+        })?;
+
+        Ok(Box::new(
+            YamlStorage::new(path, default).map_err(ScabbardStateError)?,
+        ))
+    }
+}
+
+fn with_receipt_indexes(indexes: &[&'static str]) -> Vec<&'static str> {
+    let mut indexes = indexes.to_vec();
+    indexes.push(RECEIPT_INDEX);
+    indexes.push(RECEIPT_ID_INDEX);
+    indexes
+}
+
+/// Stores the transaction receipts committed by a scabbard service, in the order they were
+/// committed.
+pub trait ReceiptStore: Send + Sync {
+    /// Gets the receipt of the transaction with the given ID.
+    fn get_by_id(&self, id: &str) -> Result<Option<TransactionReceipt>, ScabbardStateError>;
+
+    /// Gets the receipt at the given position in commit order.
+    fn get_by_index(&self, index: u64) -> Result<Option<TransactionReceipt>, ScabbardStateError>;
+
+    /// Returns the number of receipts in the store.
+    fn count(&self) -> Result<u64, ScabbardStateError>;
+
+    /// Iterates over all receipts in commit order.
+    fn iter(&self) -> Result<Box<dyn Iterator<Item = TransactionReceipt>>, ScabbardStateError>;
+
+    /// Iterates over the receipts that were committed after the receipt with the given ID.
+    fn iter_since_id(
+        &self,
+        id: &str,
+    ) -> Result<Box<dyn Iterator<Item = TransactionReceipt>>, ScabbardStateError>;
+
+    /// Adds the given receipts to the end of the store.
+    fn append(&mut self, receipts: Vec<TransactionReceipt>) -> Result<(), ScabbardStateError>;
+}
+
+impl ReceiptStore for TransactionReceiptStore {
+    fn get_by_id(&self, id: &str) -> Result<Option<TransactionReceipt>, ScabbardStateError> {
+        TransactionReceiptStore::get_by_id(self, id.into()).map_err(|err| {
+            ScabbardStateError(format!("failed to get transaction receipt: {}", err))
+        })
+    }
+
+    fn get_by_index(&self, index: u64) -> Result<Option<TransactionReceipt>, ScabbardStateError> {
+        TransactionReceiptStore::get_by_index(self, index).map_err(|err| {
+            ScabbardStateError(format!("failed to get transaction receipt: {}", err))
+        })
+    }
+
+    fn count(&self) -> Result<u64, ScabbardStateError> {
+        TransactionReceiptStore::count(self).map_err(|err| {
+            ScabbardStateError(format!("failed to count transaction receipts: {}", err))
+        })
+    }
+
+    fn iter(&self) -> Result<Box<dyn Iterator<Item = TransactionReceipt>>, ScabbardStateError> {
+        TransactionReceiptStore::iter(self).map_err(|err| {
+            ScabbardStateError(format!(
+                "failed to get transaction receipts from store: {}",
+                err
+            ))
+        })
+    }
+
+    fn iter_since_id(
+        &self,
+        id: &str,
+    ) -> Result<Box<dyn Iterator<Item = TransactionReceipt>>, ScabbardStateError> {
+        TransactionReceiptStore::iter_since_id(self, id.into()).map_err(|err| {
+            ScabbardStateError(format!(
+                "failed to get transaction receipts from store: {}",
+                err
+            ))
+        })
+    }
+
+    fn append(&mut self, receipts: Vec<TransactionReceipt>) -> Result<(), ScabbardStateError> {
+        TransactionReceiptStore::append(self, receipts).map_err(|err| {
+            ScabbardStateError(format!(
+                "failed to add transaction receipts to store: {}",
+                err
+            ))
+        })
+    }
+}
+
+/// A `ReceiptStore` that keeps receipts in indexes of a transact `Database`, so they can share
+/// a database with state.
+pub struct DatabaseReceiptStore {
+    db: Box<dyn Database>,
+}
+
+impl DatabaseReceiptStore {
+    /// Creates a receipt store backed by the given database, which must have been created with
+    /// the receipt indexes.
+    fn new(db: Box<dyn Database>) -> Self {
+        DatabaseReceiptStore { db }
+    }
+
+    fn get_index_of(&self, id: &str) -> Result<Option<u64>, ScabbardStateError> {
+        self.db
+            .get_reader()
+            .and_then(|reader| reader.index_get(RECEIPT_ID_INDEX, id.as_bytes()))
+            .map_err(|err| {
+                ScabbardStateError(format!("failed to get transaction receipt: {}", err))
+            })?
+            .map(|bytes| parse_receipt_index(&bytes))
+            .transpose()
+    }
+}
+
+impl ReceiptStore for DatabaseReceiptStore {
+    fn get_by_id(&self, id: &str) -> Result<Option<TransactionReceipt>, ScabbardStateError> {
+        match self.get_index_of(id)? {
+            Some(index) => self.get_by_index(index),
+            None => Ok(None),
+        }
+    }
+
+    fn get_by_index(&self, index: u64) -> Result<Option<TransactionReceipt>, ScabbardStateError> {
+        read_receipt(&*self.db, index)
+    }
+
+    fn count(&self) -> Result<u64, ScabbardStateError> {
+        let reader = self.db.get_reader().map_err(|err| {
+            ScabbardStateError(format!("failed to count transaction receipts: {}", err))
+        })?;
+        let mut cursor = reader.index_cursor(RECEIPT_INDEX).map_err(|err| {
+            ScabbardStateError(format!("failed to count transaction receipts: {}", err))
+        })?;
+
+        // Receipts are numbered consecutively, so the count is one more than the last index
+        match cursor.seek_last() {
+            Some((key, _)) => Ok(parse_receipt_index(&key)? + 1),
+            None => Ok(0),
+        }
+    }
+
+    fn iter(&self) -> Result<Box<dyn Iterator<Item = TransactionReceipt>>, ScabbardStateError> {
+        Ok(Box::new(DatabaseReceiptIter {
+            db: self.db.clone(),
+            next_index: 0,
+        }))
+    }
+
+    fn iter_since_id(
+        &self,
+        id: &str,
+    ) -> Result<Box<dyn Iterator<Item = TransactionReceipt>>, ScabbardStateError> {
+        let index = self
+            .get_index_of(id)?
+            .ok_or_else(|| ScabbardStateError(format!("transaction receipt not found: {}", id)))?;
+
+        Ok(Box::new(DatabaseReceiptIter {
+            db: self.db.clone(),
+            next_index: index + 1,
+        }))
+    }
+
+    fn append(&mut self, receipts: Vec<TransactionReceipt>) -> Result<(), ScabbardStateError> {
+        let mut next_index = self.count()?;
+
+        let mut writer = self.db.get_writer().map_err(|err| {
+            ScabbardStateError(format!(
+                "failed to add transaction receipts to store: {}",
+                err
+            ))
+        })?;
+        for receipt in receipts {
+            let id = receipt.transaction_id.clone();
+            let bytes = receipt.into_bytes().map_err(|err| {
+                ScabbardStateError(format!("failed to serialize transaction receipt: {}", err))
+            })?;
+            let index_bytes = next_index.to_be_bytes();
+
+            writer
+                .index_put(RECEIPT_INDEX, &index_bytes, &bytes)
+                .and_then(|_| writer.index_put(RECEIPT_ID_INDEX, id.as_bytes(), &index_bytes))
+                .map_err(|err| {
+                    ScabbardStateError(format!(
+                        "failed to add transaction receipt {} to store: {}",
+                        id, err
+                    ))
+                })?;
+            next_index += 1;
+        }
+        writer.commit().map_err(|err| {
+            ScabbardStateError(format!(
+                "failed to add transaction receipts to store: {}",
+                err
+            ))
+        })
+    }
+}
+
+/// Iterates over the receipts in a `DatabaseReceiptStore`, reading one receipt at a time.
+struct DatabaseReceiptIter {
+    db: Box<dyn Database>,
+    next_index: u64,
+}
+
+impl Iterator for DatabaseReceiptIter {
+    type Item = TransactionReceipt;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match read_receipt(&*self.db, self.next_index) {
+            Ok(Some(receipt)) => {
+                self.next_index += 1;
+                Some(receipt)
+            }
+            Ok(None) => None,
+            Err(err) => {
+                error!("Unable to read transaction receipt: {}", err);
+                None
+            }
+        }
+    }
+}
+
+fn read_receipt(
+    db: &dyn Database,
+    index: u64,
+) -> Result<Option<TransactionReceipt>, ScabbardStateError> {
+    db.get_reader()
+        .and_then(|reader| reader.index_get(RECEIPT_INDEX, &index.to_be_bytes()))
+        .map_err(|err| ScabbardStateError(format!("failed to get transaction receipt: {}", err)))?
+        .map(|bytes| {
+            TransactionReceipt::from_bytes(&bytes).map_err(|err| {
+                ScabbardStateError(format!("failed to parse transaction receipt: {}", err))
+            })
+        })
+        .transpose()
+}
+
+fn parse_receipt_index(bytes: &[u8]) -> Result<u64, ScabbardStateError> {
+    let mut index_bytes = [0; 8];
+    if bytes.len() != index_bytes.len() {
+        return Err(ScabbardStateError(
+            "Invalid transaction receipt index".into(),
+        ));
+    }
+    index_bytes.copy_from_slice(bytes);
+    Ok(u64::from_be_bytes(index_bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use transact::protocol::receipt::TransactionResult;
+
+    /// Verify that a `DatabaseReceiptStore` keeps receipts in commit order and can look them up
+    /// by ID and index.
+    ///
+    /// 1. Open in-memory storage and verify that the receipt store is empty
+    /// 2. Append receipts in two batches and verify the count
+    /// 3. Verify that receipts can be fetched by ID and by index, and that unknown receipts are
+    ///    not found
+    /// 4. Verify that iterating from the start and from a receipt returns receipts in order
+    #[test]
+    fn database_receipt_store() {
+        let (_, mut receipt_store) = ScabbardStorage::Memory
+            .open("test", &[])
+            .expect("failed to open storage");
+        assert_eq!(receipt_store.count().expect("failed to count"), 0);
+        assert_eq!(receipt_store.iter().expect("failed to iter").count(), 0);
+
+        receipt_store
+            .append(vec![mock_receipt("ab"), mock_receipt("cd")])
+            .expect("failed to append receipts");
+        receipt_store
+            .append(vec![mock_receipt("ef")])
+            .expect("failed to append receipts");
+        assert_eq!(receipt_store.count().expect("failed to count"), 3);
+
+        assert_eq!(
+            receipt_store
+                .get_by_id("cd")
+                .expect("failed to get receipt")
+                .map(|receipt| receipt.transaction_id),
+            Some("cd".to_string())
+        );
+        assert!(receipt_store
+            .get_by_id("gh")
+            .expect("failed to get receipt")
+            .is_none());
+        assert_eq!(
+            receipt_store
+                .get_by_index(2)
+                .expect("failed to get receipt")
+                .map(|receipt| receipt.transaction_id),
+            Some("ef".to_string())
+        );
+        assert!(receipt_store
+            .get_by_index(3)
+            .expect("failed to get receipt")
+            .is_none());
+
+        let ids = receipt_store
+            .iter()
+            .expect("failed to iter")
+            .map(|receipt| receipt.transaction_id)
+            .collect::<Vec<_>>();
+        assert_eq!(ids, vec!["ab", "cd", "ef"]);
+
+        let ids = receipt_store
+            .iter_since_id("ab")
+            .expect("failed to iter")
+            .map(|receipt| receipt.transaction_id)
+            .collect::<Vec<_>>();
+        assert_eq!(ids, vec!["cd", "ef"]);
+        assert!(receipt_store.iter_since_id("gh").is_err());
+    }
+
+    fn mock_receipt(id: &str) -> TransactionReceipt {
+        TransactionReceipt {
+            transaction_id: id.into(),
+            transaction_result: TransactionResult::Valid {
+                state_changes: vec![],
+                events: vec![],
+                data: vec![],
+            },
+        }
+    }
+}
@@ -0,0 +1,427 @@
+// Copyright 2018-2020 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! State sync allows a scabbard service that has fallen behind its peers to catch up.
+//!
+//! When a service starts, or when it finds that a proposal does not match its state, it sends a
+//! `StateSyncRequest` with its current state root and the ID of its last transaction receipt to
+//! its peers. A peer responds with the receipts it has committed since that receipt or, if it
+//! does not have that receipt, a snapshot of its full state. The requesting service waits until a
+//! quorum of its peers (more than half) report the same state root, so that a single stale or
+//! faulty peer can not roll its state back, then applies the response that completed the quorum.
+//! It verifies that the result matches the agreed state root and then resumes participating in
+//! consensus. Receipts are only applied if they extend the service's own receipts; if they do
+//! not, or if applying them does not produce the expected state root, a snapshot is requested
+//! instead.
+
+use std::time::Duration;
+
+use protobuf::{Message, RepeatedField};
+use transact::protocol::receipt::TransactionReceipt;
+use transact::protos::{FromBytes, IntoBytes};
+
+use crate::protos::scabbard::{
+    ScabbardMessage, ScabbardMessage_Type, StateEntry, StateSyncRequest, StateSyncResponse,
+    StateSyncResponse_Type,
+};
+
+use super::error::ScabbardError;
+use super::shared::ScabbardShared;
+use super::state::{ScabbardState, StateSync};
+
+/// How long a service waits for its peers to respond to a state sync request before resuming
+/// consensus without syncing
+const STATE_SYNC_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Sends a state sync request to all of the service's peers, and suspends the service's
+/// participation in consensus until a response is applied or the request times out.
+///
+/// If none of the peers can be reached, the service continues without syncing.
+pub fn request_state_sync(
+    service_id: &str,
+    shared: &mut ScabbardShared,
+    state: &ScabbardState,
+    require_snapshot: bool,
+) -> Result<(), ScabbardError> {
+    let mut request = StateSyncRequest::new();
+    request.set_service_id(service_id.into());
+    request.set_current_state_root(state.current_state_root().into());
+    request.set_last_receipt_id(state.last_receipt_id()?.unwrap_or_default());
+    request.set_require_snapshot(require_snapshot);
+
+    let mut msg = ScabbardMessage::new();
+    msg.set_message_type(ScabbardMessage_Type::STATE_SYNC_REQUEST);
+    msg.set_state_sync_request(request);
+    let msg_bytes = msg
+        .write_to_bytes()
+        .map_err(|err| ScabbardError::StateSyncFailed(Box::new(err)))?;
+
+    let sender = shared.network_sender().ok_or(ScabbardError::NotConnected)?;
+
+    let mut sent = 0;
+    for service in shared.peer_services() {
+        match sender.send(service, &msg_bytes) {
+            Ok(()) => sent += 1,
+            Err(err) => debug!("Unable to request state sync from {}: {}", service, err),
+        }
+    }
+
+    if sent > 0 {
+        debug!("Requested state sync from {} service(s)", sent);
+        shared.start_state_sync(STATE_SYNC_TIMEOUT);
+    }
+
+    Ok(())
+}
+
+/// Responds to a peer's state sync request with the data it needs to reach this service's state.
+///
+/// The requester is the service that sent the message; a request that names another service is
+/// ignored. A service that is itself syncing only responds if the requester is already up to date
+/// with it, since its own state may be behind.
+pub fn handle_state_sync_request(
+    service_id: &str,
+    sender: &str,
+    request: &StateSyncRequest,
+    shared: &ScabbardShared,
+    state: &ScabbardState,
+) -> Result<(), ScabbardError> {
+    let requester = sender;
+    if request.get_service_id() != requester {
+        warn!(
+            "Ignoring state sync request from {} on behalf of {}",
+            requester,
+            request.get_service_id()
+        );
+        return Ok(());
+    }
+    if !shared.peer_services().contains(requester) {
+        warn!(
+            "Ignoring state sync request from unknown service {}",
+            requester
+        );
+        return Ok(());
+    }
+
+    let up_to_date = !request.get_require_snapshot()
+        && request.get_current_state_root() == state.current_state_root();
+
+    if !up_to_date && shared.is_syncing() {
+        debug!(
+            "Ignoring state sync request from {} while syncing",
+            requester
+        );
+        return Ok(());
+    }
+
+    let mut response = StateSyncResponse::new();
+    response.set_service_id(service_id.into());
+    response.set_state_root(state.current_state_root().into());
+
+    if up_to_date {
+        response.set_sync_type(StateSyncResponse_Type::UP_TO_DATE);
+    } else {
+        let last_receipt_id = Some(request.get_last_receipt_id())
+            .filter(|id| !id.is_empty() && !request.get_require_snapshot());
+
+        match state.get_state_sync(last_receipt_id)? {
+            StateSync::Receipts(txn_receipts) => {
+                response.set_sync_type(StateSyncResponse_Type::RECEIPTS);
+                response.set_last_receipt_id(request.get_last_receipt_id().into());
+                response.set_receipts(
+                    txn_receipts
+                        .into_iter()
+                        .map(|receipt| receipt.into_bytes())
+                        .collect::<Result<_, _>>()
+                        .map_err(|err| ScabbardError::StateSyncFailed(Box::new(err)))?,
+                );
+            }
+            StateSync::Snapshot(entries) => {
+                response.set_sync_type(StateSyncResponse_Type::SNAPSHOT);
+                response.set_entries(RepeatedField::from_vec(
+                    entries
+                        .into_iter()
+                        .map(|(address, value)| {
+                            let mut entry = StateEntry::new();
+                            entry.set_address(address);
+                            entry.set_value(value);
+                            entry
+                        })
+                        .collect(),
+                ));
+            }
+        }
+    }
+
+    let mut msg = ScabbardMessage::new();
+    msg.set_message_type(ScabbardMessage_Type::STATE_SYNC_RESPONSE);
+    msg.set_state_sync_response(response);
+    let msg_bytes = msg
+        .write_to_bytes()
+        .map_err(|err| ScabbardError::StateSyncFailed(Box::new(err)))?;
+
+    shared
+        .network_sender()
+        .ok_or(ScabbardError::NotConnected)?
+        .send(requester, &msg_bytes)
+        .map_err(|err| ScabbardError::StateSyncFailed(Box::new(err)))
+}
+
+/// Applies a peer's response to this service's state sync request, once a quorum of peers have
+/// reported the same state root as the response. Responses received when the service is not
+/// syncing, such as those that arrive after the sync has completed, are ignored.
+///
+/// The quorum is counted on the services that sent the responses; a response that names another
+/// service than its sender is ignored, so that one peer can not vote on behalf of the others.
+pub fn handle_state_sync_response(
+    service_id: &str,
+    sender: &str,
+    response: &StateSyncResponse,
+    shared: &mut ScabbardShared,
+    state: &mut ScabbardState,
+) -> Result<(), ScabbardError> {
+    let responder = sender;
+    if response.get_service_id() != responder {
+        warn!(
+            "Ignoring state sync response from {} on behalf of {}",
+            responder,
+            response.get_service_id()
+        );
+        return Ok(());
+    }
+    if !shared.peer_services().contains(responder) {
+        warn!(
+            "Ignoring state sync response from unknown service {}",
+            responder
+        );
+        return Ok(());
+    }
+
+    if !shared.is_syncing() {
+        debug!(
+            "Ignoring state sync response from {}; not syncing",
+            responder
+        );
+        return Ok(());
+    }
+
+    if !shared.add_state_sync_root(responder, response.get_state_root()) {
+        debug!(
+            "Waiting for a quorum of peers to report state root {} before syncing",
+            response.get_state_root()
+        );
+        return Ok(());
+    }
+
+    match response.get_sync_type() {
+        StateSyncResponse_Type::UP_TO_DATE => {}
+        StateSyncResponse_Type::RECEIPTS => {
+            let txn_receipts = response
+                .get_receipts()
+                .iter()
+                .map(|bytes| TransactionReceipt::from_bytes(bytes))
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|err| ScabbardError::StateSyncFailed(Box::new(err)))?;
+
+            let last_receipt_id = Some(response.get_last_receipt_id()).filter(|id| !id.is_empty());
+            if let Err(err) =
+                state.apply_receipts(last_receipt_id, txn_receipts, response.get_state_root())
+            {
+                warn!(
+                    "Unable to sync receipts from {}, requesting a snapshot: {}",
+                    responder, err
+                );
+                return request_state_sync(service_id, shared, state, true);
+            }
+        }
+        StateSyncResponse_Type::SNAPSHOT => {
+            let entries = response
+                .get_entries()
+                .iter()
+                .map(|entry| (entry.get_address().to_string(), entry.get_value().to_vec()))
+                .collect();
+
+            state.apply_snapshot(entries, response.get_state_root())?;
+        }
+        StateSyncResponse_Type::UNSET => return Err(ScabbardError::MessageTypeUnset),
+    }
+
+    info!(
+        "State synced with {} at {}",
+        responder,
+        state.current_state_root()
+    );
+    shared.finish_state_sync();
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::collections::{HashSet, VecDeque};
+
+    use crate::signing::hash::HashVerifier;
+
+    use super::super::storage::ScabbardStorage;
+
+    /// Verify that a stale peer's response is not applied, and that the state is only synced
+    /// once a quorum of peers report the same state root.
+    ///
+    /// 1. Create a local state, an up-to-date peer state and a stale peer state, each with a
+    ///    different state root
+    /// 2. Start syncing with three peers, so that two peers are a quorum
+    /// 3. Handle the stale peer's snapshot and verify that the local state is unchanged
+    /// 4. Handle a snapshot from an unknown service with the up-to-date state root and verify
+    ///    that the local state is unchanged
+    /// 5. Handle the first up-to-date peer's snapshot and verify that the local state is
+    ///    unchanged, since there is no quorum yet
+    /// 6. Handle the second up-to-date peer's snapshot and verify that the local state now has
+    ///    the up-to-date state root and is no longer syncing
+    #[test]
+    fn stale_responder() {
+        let mut state = ScabbardState::new(&ScabbardStorage::Memory, "test", vec![], vec![])
+            .expect("failed to create local state");
+        let local_root = state.current_state_root().to_string();
+        let up_to_date =
+            ScabbardState::new(&ScabbardStorage::Memory, "test", vec!["ab".into()], vec![])
+                .expect("failed to create up-to-date state");
+        let stale = ScabbardState::new(&ScabbardStorage::Memory, "test", vec!["cd".into()], vec![])
+            .expect("failed to create stale state");
+
+        let peer_services = vec!["peer_a", "peer_b", "peer_c"]
+            .into_iter()
+            .map(String::from)
+            .collect::<HashSet<_>>();
+        let mut shared = ScabbardShared::new(
+            VecDeque::new(),
+            10,
+            None,
+            peer_services,
+            Box::new(HashVerifier),
+        );
+        shared.start_state_sync(STATE_SYNC_TIMEOUT);
+
+        let responses = vec![
+            ("peer_a", &stale),
+            ("unknown", &up_to_date),
+            ("peer_b", &up_to_date),
+        ];
+        for (service_id, peer_state) in responses {
+            handle_state_sync_response(
+                "local",
+                service_id,
+                &snapshot_response(service_id, peer_state),
+                &mut shared,
+                &mut state,
+            )
+            .expect("failed to handle response");
+            assert_eq!(state.current_state_root(), local_root);
+            assert!(shared.is_syncing());
+        }
+
+        handle_state_sync_response(
+            "local",
+            "peer_c",
+            &snapshot_response("peer_c", &up_to_date),
+            &mut shared,
+            &mut state,
+        )
+        .expect("failed to handle response");
+        assert_eq!(state.current_state_root(), up_to_date.current_state_root());
+        assert!(!shared.is_syncing());
+    }
+
+    /// Verify that a peer can not complete the quorum by sending responses on behalf of other
+    /// peers.
+    ///
+    /// 1. Start syncing with three peers, so that two peers are a quorum
+    /// 2. Handle a snapshot with the up-to-date state root from "peer_a"
+    /// 3. Handle a snapshot with the up-to-date state root that names "peer_b", but was sent by
+    ///    "peer_a", and verify that the local state is unchanged
+    /// 4. Handle a snapshot with the up-to-date state root from "peer_b" and verify that the local
+    ///    state now has the up-to-date state root
+    #[test]
+    fn forged_responder() {
+        let mut state = ScabbardState::new(&ScabbardStorage::Memory, "test", vec![], vec![])
+            .expect("failed to create local state");
+        let local_root = state.current_state_root().to_string();
+        let up_to_date =
+            ScabbardState::new(&ScabbardStorage::Memory, "test", vec!["ab".into()], vec![])
+                .expect("failed to create up-to-date state");
+
+        let peer_services = vec!["peer_a", "peer_b", "peer_c"]
+            .into_iter()
+            .map(String::from)
+            .collect::<HashSet<_>>();
+        let mut shared = ScabbardShared::new(
+            VecDeque::new(),
+            10,
+            None,
+            peer_services,
+            Box::new(HashVerifier),
+        );
+        shared.start_state_sync(STATE_SYNC_TIMEOUT);
+
+        for (sender, service_id) in vec![("peer_a", "peer_a"), ("peer_a", "peer_b")] {
+            handle_state_sync_response(
+                "local",
+                sender,
+                &snapshot_response(service_id, &up_to_date),
+                &mut shared,
+                &mut state,
+            )
+            .expect("failed to handle response");
+            assert_eq!(state.current_state_root(), local_root);
+            assert!(shared.is_syncing());
+        }
+
+        handle_state_sync_response(
+            "local",
+            "peer_b",
+            &snapshot_response("peer_b", &up_to_date),
+            &mut shared,
+            &mut state,
+        )
+        .expect("failed to handle response");
+        assert_eq!(state.current_state_root(), up_to_date.current_state_root());
+        assert!(!shared.is_syncing());
+    }
+
+    /// Creates a snapshot response with the given peer state.
+    fn snapshot_response(service_id: &str, peer_state: &ScabbardState) -> StateSyncResponse {
+        let entries = match peer_state.get_state_sync(None).expect("failed to get sync") {
+            StateSync::Snapshot(entries) => entries,
+            StateSync::Receipts(_) => panic!("expected a snapshot"),
+        };
+
+        let mut response = StateSyncResponse::new();
+        response.set_service_id(service_id.into());
+        response.set_sync_type(StateSyncResponse_Type::SNAPSHOT);
+        response.set_state_root(peer_state.current_state_root().into());
+        response.set_entries(RepeatedField::from_vec(
+            entries
+                .into_iter()
+                .map(|(address, value)| {
+                    let mut entry = StateEntry::new();
+                    entry.set_address(address);
+                    entry.set_value(value);
+                    entry
+                })
+                .collect(),
+        ));
+        response
+    }
+}
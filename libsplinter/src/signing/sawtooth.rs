@@ -69,6 +69,48 @@ impl<'c> Signer for SawtoothSecp256k1RefSigner<'c> {
     }
 }
 
+/// A Sawtooth Secp256k Signer that owns a context.
+///
+/// The SawtoothSecp256k1Signer provides an implementation of the Signer trait, that uses its own
+/// Secp256k1Context.
+pub struct SawtoothSecp256k1Signer {
+    context: secp256k1::Secp256k1Context,
+    private_key: secp256k1::Secp256k1PrivateKey,
+    public_key: Vec<u8>,
+}
+
+impl SawtoothSecp256k1Signer {
+    /// Constructs a signer from a hex-encoded private key.
+    pub fn from_hex(private_key_hex: &str) -> Result<Self, Error> {
+        let context = secp256k1::Secp256k1Context::new();
+        let private_key = secp256k1::Secp256k1PrivateKey::from_hex(private_key_hex)
+            .map_err(|err| Error::SigningError(format!("Unable to read private key: {}", err)))?;
+        let public_key = SawtoothSecp256k1RefSigner::new(&context, private_key.clone())?
+            .public_key()
+            .to_vec();
+        Ok(Self {
+            context,
+            private_key,
+            public_key,
+        })
+    }
+}
+
+impl Signer for SawtoothSecp256k1Signer {
+    fn sign(&self, message: &[u8]) -> Result<Vec<u8>, Error> {
+        SawtoothSecp256k1RefSigner {
+            context: &self.context,
+            private_key: self.private_key.clone(),
+            public_key: self.public_key.clone(),
+        }
+        .sign(message)
+    }
+
+    fn public_key(&self) -> &[u8] {
+        &self.public_key
+    }
+}
+
 /// A Sawtooth Secp256k SignatureVerifier that references a context.
 ///
 /// The SawtoothSecp256k1RefSignatureVerifier provides an implementation of the SignatureVerifier
@@ -153,4 +195,13 @@ mod tests {
 
         test_signer_implementation(&sawtooth_signer, &sawtooth_verifier);
     }
+
+    #[test]
+    fn test_sawtooth_secp256k1_owned() {
+        let sawtooth_signer =
+            SawtoothSecp256k1Signer::from_hex(KEY1_PRIV_HEX).expect("Unable to create signer");
+        let sawtooth_verifier = SawtoothSecp256k1SignatureVerifier::new();
+
+        test_signer_implementation(&sawtooth_signer, &sawtooth_verifier);
+    }
 }
@@ -80,6 +80,15 @@ pub trait Connection: Send {
 
     /// Returns a `mio::event::Evented` for this connection which can be used for polling.
     fn evented(&self) -> &dyn Evented;
+
+    /// Return the identities that the remote peer was authenticated with by the transport.
+    ///
+    /// For TLS-based connection types, these are the common name and the subject alternative
+    /// names of the verified peer certificate. Connection types that do not authenticate the
+    /// remote peer return `None`.
+    fn peer_identities(&self) -> Option<Vec<String>> {
+        None
+    }
 }
 
 pub trait Listener: Send {
@@ -108,6 +108,22 @@ mod tests {
         tests::test_transport(transport, "127.0.0.1:0");
     }
 
+    /// Test MultiTransport using a Unix domain socket transport for the listening endpoint, with
+    /// the standard transport tests.
+    #[cfg(feature = "uds-transport")]
+    #[test]
+    fn test_transport_uds_listener() {
+        let raw_transport = Box::new(socket::TcpTransport::default());
+        let uds_transport = Box::new(socket::UdsTransport::default());
+
+        let transport = MultiTransport::new(vec![raw_transport, uds_transport]);
+        assert!(transport.accepts("uds:///tmp/splinter.sock"));
+
+        let mut path = std::env::temp_dir();
+        path.push(format!("splinter-multi-{}.sock", std::process::id()));
+        tests::test_transport(transport, &format!("uds://{}", path.display()));
+    }
+
     /// Create a transport with tcp and tls transports and attempt to create an unknown protocol.
     /// Expect that a protocol error should be returned.
     #[test]
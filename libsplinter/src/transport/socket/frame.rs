@@ -12,6 +12,7 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use std::borrow::Cow;
 use std::io::{self, Cursor, Read, Write};
 use std::thread;
 use std::time::Duration;
@@ -20,12 +21,31 @@ use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
 
 const HEADER_LENGTH: usize = 6;
 
+/// The length of a version 2 header: the version, the flags, the data length and the data
+/// checksum.
+#[cfg(feature = "frame-v2")]
+const V2_HEADER_LENGTH: usize = 11;
+
+/// Version 2 flag indicating that the data is compressed with LZ4.
+#[cfg(feature = "frame-v2")]
+const FLAG_COMPRESSED: u8 = 0x01;
+/// Version 2 flag indicating that the header contains a CRC32 checksum of the data.
+#[cfg(feature = "frame-v2")]
+const FLAG_CHECKSUM: u8 = 0x02;
+
+/// Version 2 frames with less data than this are not compressed.
+#[cfg(feature = "frame-v2")]
+const COMPRESSION_THRESHOLD: usize = 1024;
+
 /// An error that may be returned during frame-related operations
 #[derive(Debug)]
 pub enum FrameError {
     IoError(io::Error),
     InvalidChecksum,
-    InvalidHeaderLength(usize),
+    InvalidHeaderLength { expected: usize, actual: usize },
+    InvalidDataChecksum,
+    DecompressionError(String),
+    UnsupportedFlags(u8),
     UnsupportedVersion,
 }
 
@@ -34,11 +54,18 @@ impl std::fmt::Display for FrameError {
         match self {
             FrameError::IoError(err) => f.write_str(&err.to_string()),
             FrameError::InvalidChecksum => f.write_str("Invalid checksum in frame header"),
-            FrameError::InvalidHeaderLength(n) => write!(
+            FrameError::InvalidHeaderLength { expected, actual } => write!(
                 f,
                 "Invalid header length expected {} but was {}",
-                HEADER_LENGTH, n
+                expected, actual
             ),
+            FrameError::InvalidDataChecksum => f.write_str("Invalid checksum for frame data"),
+            FrameError::DecompressionError(msg) => {
+                write!(f, "Unable to decompress frame data: {}", msg)
+            }
+            FrameError::UnsupportedFlags(flags) => {
+                write!(f, "Unsupported frame flags {:#04x}", flags)
+            }
             FrameError::UnsupportedVersion => f.write_str("Unsupported frame version"),
         }
     }
@@ -49,7 +76,10 @@ impl std::error::Error for FrameError {
         match self {
             FrameError::IoError(err) => Some(&*err),
             FrameError::InvalidChecksum => None,
-            FrameError::InvalidHeaderLength(_) => None,
+            FrameError::InvalidHeaderLength { .. } => None,
+            FrameError::InvalidDataChecksum => None,
+            FrameError::DecompressionError(_) => None,
+            FrameError::UnsupportedFlags(_) => None,
             FrameError::UnsupportedVersion => None,
         }
     }
@@ -68,6 +98,23 @@ impl From<io::Error> for FrameError {
 #[derive(Debug, PartialEq, Copy, Clone)]
 pub enum FrameVersion {
     V1 = 1,
+    /// Version 2 frames carry flags for compressed data and a checksum of the data.
+    #[cfg(feature = "frame-v2")]
+    V2 = 2,
+}
+
+impl FrameVersion {
+    /// Returns the highest frame version supported.
+    pub fn latest() -> Self {
+        #[cfg(feature = "frame-v2")]
+        {
+            FrameVersion::V2
+        }
+        #[cfg(not(feature = "frame-v2"))]
+        {
+            FrameVersion::V1
+        }
+    }
 }
 
 /// A complete Frame of transmitted data.
@@ -105,35 +152,60 @@ impl Frame {
         };
 
         match frame_header {
-            FrameHeader::V1 { length } => {
-                let mut buffer = vec![0; length as usize];
-                let mut remaining = &mut buffer[..];
-
-                while !remaining.is_empty() {
-                    match reader.read(remaining) {
-                        Ok(0) => break,
-                        Ok(n) => {
-                            let tmp = remaining;
-                            remaining = &mut tmp[n..];
-                        }
-                        Err(ref e) if e.kind() == std::io::ErrorKind::Interrupted => {}
-                        Err(ref e) if e.kind() == std::io::ErrorKind::WouldBlock => {
-                            thread::sleep(Duration::from_millis(100));
-                        }
-                        Err(e) => return Err(FrameError::IoError(e)),
-                    }
+            FrameHeader::V1 { length } => Ok(Self {
+                data: read_data(reader, length)?,
+            }),
+            #[cfg(feature = "frame-v2")]
+            FrameHeader::V2 {
+                flags,
+                length,
+                checksum,
+            } => {
+                let data = read_data(reader, length)?;
+                if flags & FLAG_CHECKSUM != 0 && crc32fast::hash(&data) != checksum {
+                    return Err(FrameError::InvalidDataChecksum);
                 }
-                if !remaining.is_empty() {
-                    Err(FrameError::IoError(io::Error::new(
-                        io::ErrorKind::UnexpectedEof,
-                        "Could not receive complete frame",
-                    )))
+
+                let data = if flags & FLAG_COMPRESSED != 0 {
+                    lz4_flex::decompress_size_prepended(&data)
+                        .map_err(|err| FrameError::DecompressionError(err.to_string()))?
                 } else {
-                    Ok(Self { data: buffer })
-                }
+                    data
+                };
+
+                Ok(Self { data })
+            }
+        }
+    }
+}
+
+/// Read the given number of bytes of frame data from the reader.
+fn read_data<R: Read>(reader: &mut R, length: u32) -> Result<Vec<u8>, FrameError> {
+    let mut buffer = vec![0; length as usize];
+    let mut remaining = &mut buffer[..];
+
+    while !remaining.is_empty() {
+        match reader.read(remaining) {
+            Ok(0) => break,
+            Ok(n) => {
+                let tmp = remaining;
+                remaining = &mut tmp[n..];
+            }
+            Err(ref e) if e.kind() == std::io::ErrorKind::Interrupted => {}
+            Err(ref e) if e.kind() == std::io::ErrorKind::WouldBlock => {
+                thread::sleep(Duration::from_millis(100));
             }
+            Err(e) => return Err(FrameError::IoError(e)),
         }
     }
+    if !remaining.is_empty() {
+        Err(FrameError::IoError(io::Error::new(
+            io::ErrorKind::UnexpectedEof,
+            "Could not receive complete frame",
+        )))
+    } else {
+        Ok(buffer)
+    }
 }
 
 /// A Frame of referenced data to be transmitted using a specified version.
@@ -153,12 +225,35 @@ impl<'a> FrameRef<'a> {
 
     /// Write the frame to the given writer.
     ///
+    /// Version 2 frames include a checksum of the data, and the data is compressed if it is large
+    /// enough and compression reduces its size.
+    ///
     /// # Errors
     ///
     /// Returns a FrameError if an IO error occurs.
     pub fn write<W: Write>(self, writer: &mut W) -> Result<(), FrameError> {
-        let frame_header = match self.version {
-            FrameVersion::V1 => FrameHeader::v1(self.data.len() as u32),
+        let (frame_header, data): (FrameHeader, Cow<[u8]>) = match self.version {
+            FrameVersion::V1 => (
+                FrameHeader::v1(self.data.len() as u32),
+                Cow::Borrowed(self.data),
+            ),
+            #[cfg(feature = "frame-v2")]
+            FrameVersion::V2 => {
+                let mut flags = FLAG_CHECKSUM;
+                let mut data = Cow::Borrowed(self.data);
+                if self.data.len() >= COMPRESSION_THRESHOLD {
+                    let compressed = lz4_flex::compress_prepend_size(self.data);
+                    if compressed.len() < self.data.len() {
+                        flags |= FLAG_COMPRESSED;
+                        data = Cow::Owned(compressed);
+                    }
+                }
+
+                (
+                    FrameHeader::v2(flags, data.len() as u32, crc32fast::hash(&data)),
+                    data,
+                )
+            }
         };
         loop {
             match frame_header.write(writer) {
@@ -171,7 +266,7 @@ impl<'a> FrameRef<'a> {
             }
         }
 
-        let mut buffer = &self.data[..];
+        let mut buffer = &data[..];
         while !buffer.is_empty() {
             match writer.write(buffer) {
                 Ok(0) => {
@@ -198,7 +293,15 @@ impl<'a> FrameRef<'a> {
 /// Each variant corresponds to the implementation for a given version.
 #[derive(Debug, PartialEq)]
 enum FrameHeader {
-    V1 { length: u32 },
+    V1 {
+        length: u32,
+    },
+    #[cfg(feature = "frame-v2")]
+    V2 {
+        flags: u8,
+        length: u32,
+        checksum: u32,
+    },
 }
 
 impl FrameHeader {
@@ -207,6 +310,16 @@ impl FrameHeader {
         FrameHeader::V1 { length }
     }
 
+    /// Construct a version 2 frame header.
+    #[cfg(feature = "frame-v2")]
+    fn v2(flags: u8, length: u32, checksum: u32) -> Self {
+        FrameHeader::V2 {
+            flags,
+            length,
+            checksum,
+        }
+    }
+
     /// Read a FrameHeader from the given reader.
     ///
     /// This function uses the first 2 bytes of the stream to read the version, and constructs the
@@ -219,6 +332,7 @@ impl FrameHeader {
     /// - the version received does not match any of the existing variants
     /// - the frame header is not the proper length
     /// - the frame version fails its checksum
+    /// - the frame header has flags that are not supported
     /// - an IO error occurs
     fn read<R: Read>(reader: &mut R) -> Result<Self, FrameError> {
         let version = reader.read_u16::<BigEndian>()?;
@@ -231,7 +345,10 @@ impl FrameHeader {
 
                 let n = reader.read(&mut cursor.get_mut()[std::mem::size_of::<u16>()..])?;
                 if n != HEADER_LENGTH + 1 - std::mem::size_of::<u16>() {
-                    return Err(FrameError::InvalidHeaderLength(n));
+                    return Err(FrameError::InvalidHeaderLength {
+                        expected: HEADER_LENGTH + 1 - std::mem::size_of::<u16>(),
+                        actual: n,
+                    });
                 }
 
                 let checksum = compute_checksum(&cursor.get_ref()[..HEADER_LENGTH]);
@@ -243,6 +360,37 @@ impl FrameHeader {
                     length: cursor.read_u32::<BigEndian>()?,
                 })
             }
+            #[cfg(feature = "frame-v2")]
+            2 => {
+                // Header length + checksum byte
+                let mut buffer = [0u8; V2_HEADER_LENGTH + 1];
+                let mut cursor = Cursor::new(&mut buffer[..]);
+                cursor.write_u16::<BigEndian>(2u16)?;
+
+                let n = reader.read(&mut cursor.get_mut()[std::mem::size_of::<u16>()..])?;
+                if n != V2_HEADER_LENGTH + 1 - std::mem::size_of::<u16>() {
+                    return Err(FrameError::InvalidHeaderLength {
+                        expected: V2_HEADER_LENGTH + 1 - std::mem::size_of::<u16>(),
+                        actual: n,
+                    });
+                }
+
+                let checksum = compute_checksum(&cursor.get_ref()[..V2_HEADER_LENGTH]);
+                if checksum != cursor.get_ref()[V2_HEADER_LENGTH] {
+                    return Err(FrameError::InvalidChecksum);
+                }
+
+                let flags = cursor.read_u8()?;
+                if flags & !(FLAG_COMPRESSED | FLAG_CHECKSUM) != 0 {
+                    return Err(FrameError::UnsupportedFlags(flags));
+                }
+
+                Ok(FrameHeader::V2 {
+                    flags,
+                    length: cursor.read_u32::<BigEndian>()?,
+                    checksum: cursor.read_u32::<BigEndian>()?,
+                })
+            }
             _ => Err(FrameError::UnsupportedVersion),
         }
     }
@@ -270,6 +418,25 @@ impl FrameHeader {
 
                 writer.write_all(&cursor.into_inner()[..])?;
             }
+            #[cfg(feature = "frame-v2")]
+            FrameHeader::V2 {
+                flags,
+                length,
+                checksum,
+            } => {
+                let mut header_bytes = [0u8; V2_HEADER_LENGTH + 1];
+                let mut cursor = Cursor::new(&mut header_bytes[..]);
+
+                cursor.write_u16::<BigEndian>(2)?;
+                cursor.write_u8(flags)?;
+                cursor.write_u32::<BigEndian>(length)?;
+                cursor.write_u32::<BigEndian>(checksum)?;
+
+                cursor.get_mut()[V2_HEADER_LENGTH] =
+                    compute_checksum(&cursor.get_ref()[..V2_HEADER_LENGTH]);
+
+                writer.write_all(&cursor.into_inner()[..])?;
+            }
         }
 
         Ok(())
@@ -304,8 +471,8 @@ pub enum FrameNegotiation {
         min: FrameVersion,
         max: FrameVersion,
     },
-    /// The Inbound variant transmits receives the min and max and decides if it should send its
-    /// version or `0`, depending on whether or not it falls in the range.
+    /// The Inbound variant receives the min and max and sends the highest version it supports in
+    /// that range, up to its own version, or `0` if it does not support any version in the range.
     Inbound { version: FrameVersion },
 }
 
@@ -315,7 +482,8 @@ impl FrameNegotiation {
         FrameNegotiation::Outbound { min, max }
     }
 
-    /// Construct the inbound side of a negotiation with the given version.
+    /// Construct the inbound side of a negotiation with the given version, which is the highest
+    /// version that this side supports.
     pub fn inbound(version: FrameVersion) -> Self {
         FrameNegotiation::Inbound { version }
     }
@@ -336,21 +504,40 @@ impl FrameNegotiation {
 
                 let frame_version = stream.read_u16::<BigEndian>()?;
 
+                if frame_version < min as u16 || frame_version > max as u16 {
+                    return Err(FrameError::UnsupportedVersion);
+                }
+
                 match frame_version {
                     0 => Err(FrameError::UnsupportedVersion),
                     1 => Ok(FrameVersion::V1),
+                    #[cfg(feature = "frame-v2")]
+                    2 => Ok(FrameVersion::V2),
                     _ => Err(FrameError::UnsupportedVersion),
                 }
             }
             FrameNegotiation::Inbound { version } => {
                 let min = stream.read_u16::<BigEndian>()?;
                 let max = stream.read_u16::<BigEndian>()?;
-                if min > version as u16 || max < version as u16 {
-                    stream.write_u16::<BigEndian>(0)?;
-                    Err(FrameError::UnsupportedVersion)
-                } else {
-                    stream.write_u16::<BigEndian>(version as u16)?;
-                    Ok(version)
+
+                // Every version up to this side's version is supported, so use the highest one
+                // the remote end supports
+                let negotiated = match std::cmp::min(version as u16, max) {
+                    1 if min <= 1 => Some(FrameVersion::V1),
+                    #[cfg(feature = "frame-v2")]
+                    2 if min <= 2 => Some(FrameVersion::V2),
+                    _ => None,
+                };
+
+                match negotiated {
+                    Some(version) => {
+                        stream.write_u16::<BigEndian>(version as u16)?;
+                        Ok(version)
+                    }
+                    None => {
+                        stream.write_u16::<BigEndian>(0)?;
+                        Err(FrameError::UnsupportedVersion)
+                    }
                 }
             }
         }
@@ -618,6 +805,166 @@ mod tests {
         assert_eq!(input.to_vec(), frame.data);
     }
 
+    /// Test that a v2 frame header is written with the correct checksum and read back with the
+    /// same flags, length and data checksum.
+    #[cfg(feature = "frame-v2")]
+    #[test]
+    fn version_2_header_round_trip() {
+        let mut cursor = Cursor::new(vec![0u8; V2_HEADER_LENGTH + 1]);
+        let header = FrameHeader::v2(FLAG_CHECKSUM | FLAG_COMPRESSED, 1024, 0xdead_beef);
+        header.write(&mut cursor).expect("Unable to write header");
+
+        assert_eq!(
+            compute_checksum(&cursor.get_ref()[..V2_HEADER_LENGTH]),
+            cursor.get_ref()[V2_HEADER_LENGTH]
+        );
+
+        cursor.set_position(0);
+
+        assert_eq!(
+            FrameHeader::v2(FLAG_CHECKSUM | FLAG_COMPRESSED, 1024, 0xdead_beef),
+            FrameHeader::read(&mut cursor).expect("Unable to read header")
+        );
+    }
+
+    /// Test that a v2 frame header with flags that are not supported is rejected.
+    #[cfg(feature = "frame-v2")]
+    #[test]
+    fn version_2_unsupported_flags() {
+        let mut cursor = Cursor::new(vec![0u8; V2_HEADER_LENGTH + 1]);
+        FrameHeader::v2(0x80 | FLAG_CHECKSUM, 0, 0)
+            .write(&mut cursor)
+            .expect("Unable to write header");
+
+        cursor.set_position(0);
+
+        match FrameHeader::read(&mut cursor) {
+            Err(FrameError::UnsupportedFlags(0x82)) => (),
+            res => panic!("Unexpected result: {:?}", res),
+        }
+    }
+
+    /// Write v2 frames to a stream and verify that equivalent frames are read back, both for
+    /// data that is too small to be compressed and for data that is compressed.
+    ///
+    /// 1. Write and read back a small frame, verifying that it was not compressed
+    /// 2. Write and read back a large, compressible frame, verifying that it was compressed
+    #[cfg(feature = "frame-v2")]
+    #[test]
+    fn frame_round_trip_v2() {
+        let input = b"hello world";
+        let mut cursor = Cursor::new(vec![]);
+        FrameRef::new(FrameVersion::V2, input)
+            .write(&mut cursor)
+            .expect("Unable to write data");
+
+        assert_eq!(FLAG_CHECKSUM, cursor.get_ref()[2]);
+
+        cursor.set_position(0);
+        let frame = Frame::read(&mut cursor).expect("Unable to read frame");
+        assert_eq!(input.to_vec(), frame.data);
+
+        let input = b"hello world".repeat(1024);
+        let mut cursor = Cursor::new(vec![]);
+        FrameRef::new(FrameVersion::V2, &input)
+            .write(&mut cursor)
+            .expect("Unable to write data");
+
+        assert_eq!(FLAG_CHECKSUM | FLAG_COMPRESSED, cursor.get_ref()[2]);
+        assert!(cursor.get_ref().len() < input.len());
+
+        cursor.set_position(0);
+        let frame = Frame::read(&mut cursor).expect("Unable to read frame");
+        assert_eq!(input, frame.data);
+    }
+
+    /// Test that a v2 frame whose data has been corrupted is rejected.
+    #[cfg(feature = "frame-v2")]
+    #[test]
+    fn frame_v2_corrupted_data() {
+        let mut cursor = Cursor::new(vec![]);
+        FrameRef::new(FrameVersion::V2, b"hello world")
+            .write(&mut cursor)
+            .expect("Unable to write data");
+
+        cursor.get_mut()[V2_HEADER_LENGTH + 1] ^= 0xff;
+        cursor.set_position(0);
+
+        match Frame::read(&mut cursor) {
+            Err(FrameError::InvalidDataChecksum) => (),
+            res => panic!("Unexpected result: {:?}", res),
+        }
+    }
+
+    /// Test that peers negotiate the highest version that both support, so that peers that only
+    /// support v1 can still connect to peers that support v2.
+    #[cfg(feature = "frame-v2")]
+    #[test]
+    fn negotiation_v1_v2() {
+        for &(min, max, inbound, expected) in &[
+            (
+                FrameVersion::V1,
+                FrameVersion::V2,
+                FrameVersion::V2,
+                FrameVersion::V2,
+            ),
+            (
+                FrameVersion::V1,
+                FrameVersion::V2,
+                FrameVersion::V1,
+                FrameVersion::V1,
+            ),
+            (
+                FrameVersion::V1,
+                FrameVersion::V1,
+                FrameVersion::V2,
+                FrameVersion::V1,
+            ),
+            (
+                FrameVersion::V2,
+                FrameVersion::V2,
+                FrameVersion::V2,
+                FrameVersion::V2,
+            ),
+        ] {
+            let (mut tx, mut rx) = stream::byte_stream_pair();
+
+            let join_handle = thread::spawn(move || {
+                FrameNegotiation::inbound(inbound)
+                    .negotiate(&mut rx)
+                    .expect("Should have successfully negotiated")
+            });
+
+            let version = FrameNegotiation::outbound(min, max)
+                .negotiate(&mut tx)
+                .expect("Unable to negotiate a valid version");
+
+            let remote_version = join_handle.join().expect("Unable to join thread");
+
+            assert_eq!(expected, version);
+            assert_eq!(expected, remote_version);
+        }
+    }
+
+    /// Test that an outbound peer that requires v2 is rejected by an inbound peer that only
+    /// supports v1.
+    #[cfg(feature = "frame-v2")]
+    #[test]
+    fn negotiation_v2_required() {
+        let (mut tx, mut rx) = stream::byte_stream_pair();
+
+        let join_handle =
+            thread::spawn(move || FrameNegotiation::inbound(FrameVersion::V1).negotiate(&mut rx));
+
+        let res = FrameNegotiation::outbound(FrameVersion::V2, FrameVersion::V2).negotiate(&mut tx);
+        let remote_res = join_handle.join().expect("Unable to join thread");
+
+        match (res, remote_res) {
+            (Err(FrameError::UnsupportedVersion), Err(FrameError::UnsupportedVersion)) => (),
+            res => panic!("Unexpected result: {:?}", res),
+        }
+    }
+
     #[cfg(not(target_os = "unix"))]
     mod stream {
         use std::io::{Error as IoError, Read, Write};
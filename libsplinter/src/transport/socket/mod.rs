@@ -13,11 +13,19 @@
 // limitations under the License.
 
 mod frame;
+#[cfg(feature = "noise-transport")]
+mod noise;
 mod tcp;
 mod tls;
+#[cfg(feature = "uds-transport")]
+mod uds;
 
+#[cfg(feature = "noise-transport")]
+pub use noise::{NoiseInitError, NoiseTransport};
 pub use tcp::TcpTransport;
-pub use tls::{TlsConnection, TlsInitError, TlsTransport};
+pub use tls::{TlsConnection, TlsInitError, TlsReloader, TlsTransport};
+#[cfg(feature = "uds-transport")]
+pub use uds::UdsTransport;
 
 #[cfg(test)]
 pub mod tests {
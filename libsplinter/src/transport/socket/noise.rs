@@ -0,0 +1,611 @@
+// Copyright 2018-2020 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! A transport that encrypts and mutually authenticates TCP connections with the Noise protocol
+//! framework, using node signing keys instead of certificates.
+//!
+//! Endpoints are prefixed with `noise://`; for example, `noise://127.0.0.1:8044`.
+//!
+//! Connections perform a `Noise_XX_25519_ChaChaPoly_BLAKE2s` handshake, sent in socket frames.
+//! Noise only supports Diffie-Hellman keys, so each transport generates its own static Noise key
+//! and signs it with the node's signing key. The public signing key and the signature are sent
+//! in the encrypted handshake payloads, which binds the peer's Noise key to its signing key. The
+//! hex-encoded public signing key is reported as the connection's peer identity, so it can be
+//! checked against the keys of the node in the node registry.
+
+use std::error::Error;
+use std::fmt;
+use std::io::Cursor;
+use std::net::{Shutdown, TcpListener as StdTcpListener, TcpStream};
+
+use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+use mio::{net::TcpStream as MioTcpStream, Evented};
+use snow::{Builder, HandshakeState, TransportState};
+
+use crate::hex::to_hex;
+use crate::signing::{SignatureVerifier, SignatureVerifierFactory, Signer};
+use crate::transport::{
+    AcceptError, ConnectError, Connection, DisconnectError, ListenError, Listener, RecvError,
+    SendError, Transport,
+};
+
+use super::frame::{Frame, FrameError, FrameNegotiation, FrameRef, FrameVersion};
+
+const PROTOCOL_PREFIX: &str = "noise://";
+
+const NOISE_PARAMS: &str = "Noise_XX_25519_ChaChaPoly_BLAKE2s";
+
+/// The maximum length of a Noise message, including the authentication tag.
+const MAX_NOISE_MESSAGE_LENGTH: usize = 65535;
+/// The length of the authentication tag appended to each encrypted Noise message.
+const NOISE_TAG_LENGTH: usize = 16;
+
+/// The prefix of the message signed with the node's signing key to bind it to the static key.
+const STATIC_KEY_SIGNATURE_PREFIX: &[u8] = b"splinter-noise-static-key:";
+
+pub struct NoiseTransport {
+    static_key: Vec<u8>,
+    identity_payload: Vec<u8>,
+    verifier_factory: Box<dyn SignatureVerifierFactory>,
+}
+
+impl NoiseTransport {
+    /// Constructs a NoiseTransport
+    ///
+    /// A new static Noise key is generated and signed with the given signer, which should use the
+    /// node's signing key. The verifier factory provides the verifiers used to check the
+    /// signatures of peers.
+    pub fn new(
+        signer: &dyn Signer,
+        verifier_factory: Box<dyn SignatureVerifierFactory>,
+    ) -> Result<Self, NoiseInitError> {
+        let keypair = Builder::new(noise_params()?).generate_keypair()?;
+
+        let signature = signer
+            .sign(&static_key_message(&keypair.public))
+            .map_err(|err| NoiseInitError::SigningError(err.to_string()))?;
+
+        Ok(NoiseTransport {
+            static_key: keypair.private,
+            identity_payload: encode_identity(signer.public_key(), &signature)?,
+            verifier_factory,
+        })
+    }
+}
+
+impl Transport for NoiseTransport {
+    fn accepts(&self, address: &str) -> bool {
+        address.starts_with(PROTOCOL_PREFIX)
+    }
+
+    fn connect(&mut self, endpoint: &str) -> Result<Box<dyn Connection>, ConnectError> {
+        if !self.accepts(endpoint) {
+            return Err(ConnectError::ProtocolError(format!(
+                "Invalid protocol \"{}\"",
+                endpoint
+            )));
+        }
+
+        let address = &endpoint[PROTOCOL_PREFIX.len()..];
+        // Connect a std::net::TcpStream to make sure connect() blocks
+        let mut stream = TcpStream::connect(address)?;
+
+        let frame_version = FrameNegotiation::outbound(FrameVersion::V1, FrameVersion::latest())
+            .negotiate(&mut stream)
+            .map_err(|err| match err {
+                FrameError::UnsupportedVersion => ConnectError::ProtocolError(
+                    "Unable to connect; remote version is not with in range".into(),
+                ),
+                FrameError::IoError(err) => ConnectError::from(err),
+                e => ConnectError::ProtocolError(format!("Unexpected protocol error: {}", e)),
+            })?;
+
+        let handshake = Builder::new(noise_params()?)
+            .local_private_key(&self.static_key)
+            .build_initiator()?;
+        let verifier = self.verifier_factory.create_verifier();
+
+        let (noise, remote_identity) = initiate(
+            &mut stream,
+            frame_version,
+            handshake,
+            &self.identity_payload,
+            &*verifier,
+        )
+        .map_err(|err| match err {
+            NoiseHandshakeError::FrameError(FrameError::IoError(err)) => ConnectError::from(err),
+            err => ConnectError::ProtocolError(err.to_string()),
+        })?;
+
+        Ok(Box::new(NoiseConnection {
+            frame_version,
+            stream: MioTcpStream::from_stream(stream)?,
+            noise,
+            remote_identity,
+        }))
+    }
+
+    fn listen(&mut self, bind: &str) -> Result<Box<dyn Listener>, ListenError> {
+        if !self.accepts(bind) {
+            return Err(ListenError::ProtocolError(format!(
+                "Invalid protocol \"{}\"",
+                bind
+            )));
+        }
+
+        let address = &bind[PROTOCOL_PREFIX.len()..];
+
+        Ok(Box::new(NoiseListener {
+            listener: StdTcpListener::bind(address)?,
+            static_key: self.static_key.clone(),
+            identity_payload: self.identity_payload.clone(),
+            verifier: self.verifier_factory.create_verifier(),
+        }))
+    }
+}
+
+struct NoiseListener {
+    listener: StdTcpListener,
+    static_key: Vec<u8>,
+    identity_payload: Vec<u8>,
+    verifier: Box<dyn SignatureVerifier>,
+}
+
+impl Listener for NoiseListener {
+    fn accept(&mut self) -> Result<Box<dyn Connection>, AcceptError> {
+        let (mut stream, _) = self.listener.accept()?;
+
+        let frame_version = FrameNegotiation::inbound(FrameVersion::latest())
+            .negotiate(&mut stream)
+            .map_err(|err| match err {
+                FrameError::UnsupportedVersion => AcceptError::ProtocolError(
+                    "Unable to connect; local version not supported by remote".into(),
+                ),
+                FrameError::IoError(err) => AcceptError::from(err),
+                err => AcceptError::ProtocolError(format!("Unexpected protocol error: {}", err)),
+            })?;
+
+        let handshake = Builder::new(noise_params()?)
+            .local_private_key(&self.static_key)
+            .build_responder()?;
+
+        let (noise, remote_identity) = respond(
+            &mut stream,
+            frame_version,
+            handshake,
+            &self.identity_payload,
+            &*self.verifier,
+        )
+        .map_err(|err| match err {
+            NoiseHandshakeError::FrameError(FrameError::IoError(err)) => AcceptError::from(err),
+            err => AcceptError::ProtocolError(err.to_string()),
+        })?;
+
+        let connection = NoiseConnection {
+            frame_version,
+            stream: MioTcpStream::from_stream(stream)?,
+            noise,
+            remote_identity,
+        };
+        Ok(Box::new(connection))
+    }
+
+    fn endpoint(&self) -> String {
+        format!("{}{}", PROTOCOL_PREFIX, self.listener.local_addr().unwrap())
+    }
+}
+
+struct NoiseConnection {
+    frame_version: FrameVersion,
+    stream: MioTcpStream,
+    noise: TransportState,
+    remote_identity: String,
+}
+
+impl Connection for NoiseConnection {
+    /// Encrypts the message and sends it in a single frame.
+    ///
+    /// Messages that are too long for a single Noise message are split into chunks, which are
+    /// each encrypted and prefixed with their length.
+    fn send(&mut self, message: &[u8]) -> Result<(), SendError> {
+        let mut data = Vec::with_capacity(message.len());
+        let mut buffer = vec![0u8; MAX_NOISE_MESSAGE_LENGTH];
+        for chunk in message.chunks(MAX_NOISE_MESSAGE_LENGTH - NOISE_TAG_LENGTH) {
+            let n = self
+                .noise
+                .write_message(chunk, &mut buffer)
+                .map_err(|err| SendError::ProtocolError(err.to_string()))?;
+            data.write_u16::<BigEndian>(n as u16)?;
+            data.extend_from_slice(&buffer[..n]);
+        }
+
+        match FrameRef::new(self.frame_version, &data).write(&mut self.stream) {
+            Err(FrameError::IoError(e)) => Err(SendError::from(e)),
+            Err(err) => Err(SendError::ProtocolError(err.to_string())),
+            Ok(_) => Ok(()),
+        }
+    }
+
+    fn recv(&mut self) -> Result<Vec<u8>, RecvError> {
+        let data = match Frame::read(&mut self.stream) {
+            Err(FrameError::IoError(e)) => return Err(RecvError::from(e)),
+            Err(err) => return Err(RecvError::ProtocolError(err.to_string())),
+            Ok(frame) => frame.into_inner(),
+        };
+
+        let mut message = Vec::with_capacity(data.len());
+        let mut buffer = vec![0u8; MAX_NOISE_MESSAGE_LENGTH];
+        let mut remaining = &data[..];
+        while !remaining.is_empty() {
+            let len = remaining.read_u16::<BigEndian>()? as usize;
+            if len > remaining.len() {
+                return Err(RecvError::ProtocolError(
+                    "Encrypted message chunk is truncated".into(),
+                ));
+            }
+
+            let n = self
+                .noise
+                .read_message(&remaining[..len], &mut buffer)
+                .map_err(|err| RecvError::ProtocolError(err.to_string()))?;
+            message.extend_from_slice(&buffer[..n]);
+            remaining = &remaining[len..];
+        }
+
+        Ok(message)
+    }
+
+    fn remote_endpoint(&self) -> String {
+        format!("{}{}", PROTOCOL_PREFIX, self.stream.peer_addr().unwrap())
+    }
+
+    fn local_endpoint(&self) -> String {
+        format!("{}{}", PROTOCOL_PREFIX, self.stream.local_addr().unwrap())
+    }
+
+    fn disconnect(&mut self) -> Result<(), DisconnectError> {
+        self.stream
+            .shutdown(Shutdown::Both)
+            .map_err(DisconnectError::from)
+    }
+
+    fn evented(&self) -> &dyn Evented {
+        &self.stream
+    }
+
+    fn peer_identities(&self) -> Option<Vec<String>> {
+        Some(vec![self.remote_identity.clone()])
+    }
+}
+
+/// Performs the initiator's side of the XX handshake, returning the transport state and the
+/// hex-encoded public signing key of the responder.
+fn initiate(
+    stream: &mut TcpStream,
+    frame_version: FrameVersion,
+    mut handshake: HandshakeState,
+    identity_payload: &[u8],
+    verifier: &dyn SignatureVerifier,
+) -> Result<(TransportState, String), NoiseHandshakeError> {
+    // -> e
+    write_handshake_message(stream, frame_version, &mut handshake, &[])?;
+    // <- e, ee, s, es
+    let remote_payload = read_handshake_message(stream, &mut handshake)?;
+    let remote_identity = verify_identity(&handshake, &remote_payload, verifier)?;
+    // -> s, se
+    write_handshake_message(stream, frame_version, &mut handshake, identity_payload)?;
+
+    Ok((handshake.into_transport_mode()?, remote_identity))
+}
+
+/// Performs the responder's side of the XX handshake, returning the transport state and the
+/// hex-encoded public signing key of the initiator.
+fn respond(
+    stream: &mut TcpStream,
+    frame_version: FrameVersion,
+    mut handshake: HandshakeState,
+    identity_payload: &[u8],
+    verifier: &dyn SignatureVerifier,
+) -> Result<(TransportState, String), NoiseHandshakeError> {
+    // -> e
+    read_handshake_message(stream, &mut handshake)?;
+    // <- e, ee, s, es
+    write_handshake_message(stream, frame_version, &mut handshake, identity_payload)?;
+    // -> s, se
+    let remote_payload = read_handshake_message(stream, &mut handshake)?;
+    let remote_identity = verify_identity(&handshake, &remote_payload, verifier)?;
+
+    Ok((handshake.into_transport_mode()?, remote_identity))
+}
+
+fn write_handshake_message(
+    stream: &mut TcpStream,
+    frame_version: FrameVersion,
+    handshake: &mut HandshakeState,
+    payload: &[u8],
+) -> Result<(), NoiseHandshakeError> {
+    let mut buffer = vec![0u8; MAX_NOISE_MESSAGE_LENGTH];
+    let n = handshake.write_message(payload, &mut buffer)?;
+    FrameRef::new(frame_version, &buffer[..n]).write(stream)?;
+    Ok(())
+}
+
+fn read_handshake_message(
+    stream: &mut TcpStream,
+    handshake: &mut HandshakeState,
+) -> Result<Vec<u8>, NoiseHandshakeError> {
+    let frame = Frame::read(stream)?;
+    let mut payload = vec![0u8; MAX_NOISE_MESSAGE_LENGTH];
+    let n = handshake.read_message(&frame.into_inner(), &mut payload)?;
+    payload.truncate(n);
+    Ok(payload)
+}
+
+/// Verifies that the remote static key was signed by the public key in the identity payload,
+/// returning the hex-encoded public key.
+fn verify_identity(
+    handshake: &HandshakeState,
+    payload: &[u8],
+    verifier: &dyn SignatureVerifier,
+) -> Result<String, NoiseHandshakeError> {
+    let remote_static = handshake.get_remote_static().ok_or_else(|| {
+        NoiseHandshakeError::InvalidIdentity("peer did not send a static key".into())
+    })?;
+    let (public_key, signature) = decode_identity(payload)?;
+
+    match verifier.verify(&static_key_message(remote_static), &signature, &public_key) {
+        Ok(true) => Ok(to_hex(&public_key)),
+        Ok(false) => Err(NoiseHandshakeError::InvalidIdentity(
+            "static key signature is not valid".into(),
+        )),
+        Err(err) => Err(NoiseHandshakeError::InvalidIdentity(err.to_string())),
+    }
+}
+
+fn noise_params() -> Result<snow::params::NoiseParams, snow::Error> {
+    NOISE_PARAMS.parse()
+}
+
+fn static_key_message(static_public_key: &[u8]) -> Vec<u8> {
+    let mut message = STATIC_KEY_SIGNATURE_PREFIX.to_vec();
+    message.extend_from_slice(static_public_key);
+    message
+}
+
+/// Encodes the public signing key and the signature of the static key, each prefixed with its
+/// length.
+fn encode_identity(public_key: &[u8], signature: &[u8]) -> Result<Vec<u8>, NoiseInitError> {
+    let mut payload = Vec::with_capacity(public_key.len() + signature.len() + 4);
+    for field in &[public_key, signature] {
+        if field.len() > u16::max_value() as usize {
+            return Err(NoiseInitError::SigningError(
+                "public key or signature is too long".into(),
+            ));
+        }
+        payload
+            .write_u16::<BigEndian>(field.len() as u16)
+            .map_err(|err| NoiseInitError::SigningError(err.to_string()))?;
+        payload.extend_from_slice(field);
+    }
+
+    Ok(payload)
+}
+
+/// Decodes the public signing key and the signature of the static key from a handshake payload.
+fn decode_identity(payload: &[u8]) -> Result<(Vec<u8>, Vec<u8>), NoiseHandshakeError> {
+    let mut cursor = Cursor::new(payload);
+    let mut fields = Vec::with_capacity(2);
+    for _ in 0..2 {
+        let len = cursor.read_u16::<BigEndian>().map_err(|_| {
+            NoiseHandshakeError::InvalidIdentity("identity payload is truncated".into())
+        })? as usize;
+        let start = cursor.position() as usize;
+        let field = payload.get(start..start + len).ok_or_else(|| {
+            NoiseHandshakeError::InvalidIdentity("identity payload is truncated".into())
+        })?;
+        fields.push(field.to_vec());
+        cursor.set_position((start + len) as u64);
+    }
+
+    let signature = fields.pop().unwrap_or_default();
+    let public_key = fields.pop().unwrap_or_default();
+    Ok((public_key, signature))
+}
+
+#[derive(Debug)]
+pub enum NoiseInitError {
+    ProtocolError(String),
+    SigningError(String),
+}
+
+impl Error for NoiseInitError {}
+
+impl fmt::Display for NoiseInitError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            NoiseInitError::ProtocolError(msg) => write!(f, "unable to initialize Noise: {}", msg),
+            NoiseInitError::SigningError(msg) => {
+                write!(f, "unable to sign Noise static key: {}", msg)
+            }
+        }
+    }
+}
+
+impl From<snow::Error> for NoiseInitError {
+    fn from(error: snow::Error) -> Self {
+        NoiseInitError::ProtocolError(format!("Noise Error: {}", error))
+    }
+}
+
+impl From<snow::Error> for ConnectError {
+    fn from(error: snow::Error) -> Self {
+        ConnectError::ProtocolError(format!("Noise Err: {}", error))
+    }
+}
+
+impl From<snow::Error> for AcceptError {
+    fn from(error: snow::Error) -> Self {
+        AcceptError::ProtocolError(format!("Noise Err: {}", error))
+    }
+}
+
+/// An error that may occur during the Noise handshake.
+#[derive(Debug)]
+enum NoiseHandshakeError {
+    FrameError(FrameError),
+    NoiseError(snow::Error),
+    InvalidIdentity(String),
+}
+
+impl fmt::Display for NoiseHandshakeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            NoiseHandshakeError::FrameError(err) => write!(f, "Noise handshake failed: {}", err),
+            NoiseHandshakeError::NoiseError(err) => write!(f, "Noise handshake failed: {}", err),
+            NoiseHandshakeError::InvalidIdentity(msg) => {
+                write!(f, "Noise handshake failed; invalid peer identity: {}", msg)
+            }
+        }
+    }
+}
+
+impl From<FrameError> for NoiseHandshakeError {
+    fn from(error: FrameError) -> Self {
+        NoiseHandshakeError::FrameError(error)
+    }
+}
+
+impl From<snow::Error> for NoiseHandshakeError {
+    fn from(error: snow::Error) -> Self {
+        NoiseHandshakeError::NoiseError(error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::signing::hash::{HashSigner, HashVerifier};
+    use crate::signing::Error as SigningError;
+    use crate::transport::tests;
+
+    use std::thread;
+
+    #[test]
+    fn test_accepts() {
+        let transport = create_transport();
+        assert!(transport.accepts("noise://127.0.0.1:0"));
+        assert!(transport.accepts("noise://somewhere.example.com:4000"));
+
+        assert!(!transport.accepts("127.0.0.1:0"));
+        assert!(!transport.accepts("tcp://127.0.0.1:0"));
+        assert!(!transport.accepts("tcps://somewhere.example.com:4000"));
+    }
+
+    #[test]
+    fn test_transport() {
+        let transport = create_transport();
+
+        tests::test_transport(transport, "noise://127.0.0.1:0");
+    }
+
+    #[test]
+    fn test_poll() {
+        let transport = create_transport();
+        tests::test_poll(transport, "noise://127.0.0.1:0");
+    }
+
+    /// Test that both ends of a connection report the public signing key of the other end as its
+    /// peer identity, and that messages longer than a single Noise message are sent intact.
+    ///
+    /// 1. Connect two transports
+    /// 2. Verify that each connection's peer identity is the hex-encoded HashSigner public key
+    /// 3. Send a message that must be split into several Noise messages and verify that it is
+    ///    received unchanged
+    #[test]
+    fn test_peer_identities_and_large_message() {
+        let mut transport = create_transport();
+        let mut listener = transport
+            .listen("noise://127.0.0.1:0")
+            .expect("Unable to listen");
+        let endpoint = listener.endpoint();
+
+        let message = (0..MAX_NOISE_MESSAGE_LENGTH * 3)
+            .map(|i| i as u8)
+            .collect::<Vec<_>>();
+        let expected = message.clone();
+
+        let handle = thread::spawn(move || {
+            let mut client = transport.connect(&endpoint).expect("Unable to connect");
+            assert_eq!(
+                Some(vec![to_hex(HashSigner.public_key())]),
+                client.peer_identities()
+            );
+            client.send(&message).expect("Unable to send message");
+        });
+
+        let mut server = listener.accept().expect("Unable to accept connection");
+        assert_eq!(
+            Some(vec![to_hex(HashSigner.public_key())]),
+            server.peer_identities()
+        );
+
+        assert_eq!(expected, server.recv().expect("Unable to receive message"));
+
+        handle.join().expect("Unable to join thread");
+    }
+
+    /// Test that a peer whose static key signature can not be verified is rejected.
+    ///
+    /// 1. Create a listener
+    /// 2. Connect to it with a transport that verifies signatures with a verifier that rejects
+    ///    every signature
+    /// 3. Verify that the handshake fails on both ends
+    #[test]
+    fn test_invalid_signature() {
+        let mut transport = create_transport();
+        let mut listener = transport
+            .listen("noise://127.0.0.1:0")
+            .expect("Unable to listen");
+        let endpoint = listener.endpoint();
+
+        let handle = thread::spawn(move || {
+            let mut rejecting_transport =
+                NoiseTransport::new(&HashSigner, Box::new(RejectVerifier))
+                    .expect("Unable to create transport");
+            rejecting_transport.connect(&endpoint).is_err()
+        });
+
+        assert!(listener.accept().is_err());
+        assert!(handle.join().expect("Unable to join thread"));
+    }
+
+    fn create_transport() -> NoiseTransport {
+        NoiseTransport::new(&HashSigner, Box::new(HashVerifier))
+            .expect("Unable to create transport")
+    }
+
+    struct RejectVerifier;
+
+    impl SignatureVerifier for RejectVerifier {
+        fn verify(&self, _: &[u8], _: &[u8], _: &[u8]) -> Result<bool, SigningError> {
+            Ok(false)
+        }
+    }
+
+    impl SignatureVerifierFactory for RejectVerifier {
+        fn create_verifier(&self) -> Box<dyn SignatureVerifier> {
+            Box::new(RejectVerifier)
+        }
+    }
+}
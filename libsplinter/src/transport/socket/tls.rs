@@ -14,18 +14,26 @@
 
 use mio::{unix::EventedFd, Evented, Poll, PollOpt, Ready, Token};
 use openssl::error::ErrorStack;
+use openssl::nid::Nid;
 use openssl::ssl::{
-    Error as OpensslError, HandshakeError, SslAcceptor, SslConnector, SslFiletype, SslMethod,
-    SslStream, SslVerifyMode,
+    Error as OpensslError, HandshakeError, SslAcceptor, SslConnector, SslContextBuilder,
+    SslFiletype, SslMethod, SslStream, SslVerifyMode,
 };
+use openssl::x509::store::X509Lookup;
+use openssl::x509::verify::X509VerifyFlags;
+use openssl::x509::X509VerifyResult;
 use url::{ParseError, Url};
 
 use std::error::Error;
 use std::fmt;
+use std::fs;
 use std::io;
 use std::net::{Ipv4Addr, Ipv6Addr, TcpListener, TcpStream};
 use std::os::unix::io::{AsRawFd, RawFd};
 use std::path::Path;
+use std::sync::{Arc, RwLock};
+use std::thread;
+use std::time::{Duration, SystemTime};
 
 use crate::transport::{
     AcceptError, ConnectError, Connection, DisconnectError, ListenError, Listener, RecvError,
@@ -39,8 +47,8 @@ const DEPRECATED_PROTOCOL_PREFIX: &str = "tls://";
 const PROTOCOL_PREFIX: &str = "tcps://";
 
 pub struct TlsTransport {
-    connector: SslConnector,
-    acceptor: SslAcceptor,
+    files: TlsFiles,
+    context: Arc<RwLock<TlsContext>>,
 }
 
 impl TlsTransport {
@@ -51,10 +59,136 @@ impl TlsTransport {
         server_key: String,
         server_cert: String,
     ) -> Result<Self, TlsInitError> {
-        let client_cert_path = Path::new(&client_cert);
-        let client_key_path = Path::new(&client_key);
-        let server_cert_path = Path::new(&server_cert);
-        let server_key_path = Path::new(&server_key);
+        let files = TlsFiles {
+            ca_cert,
+            client_key,
+            client_cert,
+            server_key,
+            server_cert,
+            crl: None,
+        };
+        let context = TlsContext::build(&files)?;
+
+        Ok(TlsTransport {
+            files,
+            context: Arc::new(RwLock::new(context)),
+        })
+    }
+
+    /// Refuse peer certificates that are revoked by the given certificate revocation list.
+    ///
+    /// The CRL file must be PEM encoded and issued by the CA, so it can only be used when a CA
+    /// certificate was provided.
+    pub fn with_crl_file(mut self, crl_file: String) -> Result<Self, TlsInitError> {
+        if self.files.ca_cert.is_none() {
+            return Err(TlsInitError::ProtocolError(
+                "a CA certificate is required to check certificate revocations".into(),
+            ));
+        }
+
+        self.files.crl = Some(crl_file);
+        *rwlock_write_unwrap!(self.context) = TlsContext::build(&self.files)?;
+        Ok(self)
+    }
+
+    /// Returns a reloader that rebuilds this transport's TLS configuration from its files.
+    pub fn reloader(&self) -> TlsReloader {
+        TlsReloader {
+            files: self.files.clone(),
+            context: self.context.clone(),
+        }
+    }
+}
+
+/// Reloads the certificates, keys, CA certificates and CRL of a `TlsTransport` from their files.
+///
+/// Connections that are already established are not affected; only connections that are made or
+/// accepted after a reload use the new configuration, including those accepted by existing
+/// listeners.
+#[derive(Clone)]
+pub struct TlsReloader {
+    files: TlsFiles,
+    context: Arc<RwLock<TlsContext>>,
+}
+
+impl TlsReloader {
+    /// Reloads the TLS configuration from its files. If any of the files cannot be loaded, the
+    /// previous configuration remains in use.
+    pub fn reload(&self) -> Result<(), TlsInitError> {
+        let context = TlsContext::build(&self.files)?;
+        *rwlock_write_unwrap!(self.context) = context;
+        Ok(())
+    }
+
+    /// Starts a thread that checks the files for modifications at the given interval, and reloads
+    /// the TLS configuration whenever one of them has changed.
+    pub fn watch(self, interval: Duration) -> Result<(), TlsInitError> {
+        let mut last_modified = self.files.last_modified();
+        thread::Builder::new()
+            .name("TlsReloader".into())
+            .spawn(move || loop {
+                thread::sleep(interval);
+
+                let modified = self.files.last_modified();
+                if modified == last_modified {
+                    continue;
+                }
+                last_modified = modified;
+
+                match self.reload() {
+                    Ok(()) => info!("Reloaded TLS certificates"),
+                    Err(err) => error!("Unable to reload TLS certificates: {}", err),
+                }
+            })
+            .map_err(|err| {
+                TlsInitError::ProtocolError(format!("unable to start TLS reloader: {}", err))
+            })?;
+
+        Ok(())
+    }
+}
+
+/// The paths of the files that the TLS configuration is loaded from.
+#[derive(Clone)]
+struct TlsFiles {
+    ca_cert: Option<String>,
+    client_key: String,
+    client_cert: String,
+    server_key: String,
+    server_cert: String,
+    crl: Option<String>,
+}
+
+impl TlsFiles {
+    /// Returns the modification time of each file, or `None` for files that cannot be read.
+    fn last_modified(&self) -> Vec<Option<SystemTime>> {
+        self.ca_cert
+            .iter()
+            .chain(self.crl.iter())
+            .chain(vec![
+                &self.client_key,
+                &self.client_cert,
+                &self.server_key,
+                &self.server_cert,
+            ])
+            .map(|file| fs::metadata(file).and_then(|meta| meta.modified()).ok())
+            .collect()
+    }
+}
+
+/// The connector and acceptor used to establish new connections.
+struct TlsContext {
+    connector: SslConnector,
+    acceptor: SslAcceptor,
+    verify_peer: bool,
+}
+
+impl TlsContext {
+    fn build(files: &TlsFiles) -> Result<Self, TlsInitError> {
+        let client_cert_path = Path::new(&files.client_cert);
+        let client_key_path = Path::new(&files.client_key);
+        let server_cert_path = Path::new(&files.server_cert);
+        let server_key_path = Path::new(&files.server_key);
 
         // Build TLS Connector
         let mut connector = SslConnector::builder(SslMethod::tls())?;
@@ -69,11 +203,19 @@ impl TlsTransport {
         acceptor.check_private_key()?;
 
         // if ca_cert is provided set as accept cert, otherwise set verify to none
+        let verify_peer = files.ca_cert.is_some();
         let (acceptor, connector) = {
-            if let Some(ca_cert) = ca_cert {
-                let ca_cert_path = Path::new(&ca_cert);
+            if let Some(ca_cert) = &files.ca_cert {
+                let ca_cert_path = Path::new(ca_cert);
                 acceptor.set_ca_file(ca_cert_path)?;
+                // request the client's certificate, so that its identity is available to the
+                // node; a client that does not present a certificate is still accepted
+                acceptor.set_verify(SslVerifyMode::PEER);
                 connector.set_ca_file(ca_cert_path)?;
+                if let Some(crl) = &files.crl {
+                    load_crl(&mut acceptor, Path::new(crl))?;
+                    load_crl(&mut connector, Path::new(crl))?;
+                }
                 let connector = connector.build();
                 let acceptor = acceptor.build();
                 (acceptor, connector)
@@ -86,13 +228,24 @@ impl TlsTransport {
             }
         };
 
-        Ok(TlsTransport {
+        Ok(TlsContext {
             connector,
             acceptor,
+            verify_peer,
         })
     }
 }
 
+/// Adds the certificate revocation list to the context's certificate store, and enables
+/// revocation checks for peer certificates.
+fn load_crl(context: &mut SslContextBuilder, crl_path: &Path) -> Result<(), ErrorStack> {
+    let store = context.cert_store_mut();
+    store
+        .add_lookup(X509Lookup::file())?
+        .load_crl_file(crl_path, SslFiletype::PEM)?;
+    store.set_flags(X509VerifyFlags::CRL_CHECK)
+}
+
 fn endpoint_to_dns_name(endpoint: &str) -> Result<String, ParseError> {
     let mut address = String::from("tcp://");
     address.push_str(endpoint);
@@ -131,10 +284,15 @@ impl Transport for TlsTransport {
 
         let dns_name = endpoint_to_dns_name(address)?;
 
+        let (connector, verify_peer) = {
+            let context = rwlock_read_unwrap!(self.context);
+            (context.connector.clone(), context.verify_peer)
+        };
+
         let stream = TcpStream::connect(address)?;
-        let mut tls_stream = self.connector.connect(&dns_name, stream)?;
+        let mut tls_stream = connector.connect(&dns_name, stream)?;
 
-        let frame_version = FrameNegotiation::outbound(FrameVersion::V1, FrameVersion::V1)
+        let frame_version = FrameNegotiation::outbound(FrameVersion::V1, FrameVersion::latest())
             .negotiate(&mut tls_stream)
             .map_err(|err| match err {
                 FrameError::UnsupportedVersion => ConnectError::ProtocolError(
@@ -148,6 +306,7 @@ impl Transport for TlsTransport {
         let connection = TlsConnection {
             frame_version,
             stream: tls_stream,
+            verify_peer,
         };
         Ok(Box::new(connection))
     }
@@ -170,22 +329,27 @@ impl Transport for TlsTransport {
 
         Ok(Box::new(TlsListener {
             listener: TcpListener::bind(address)?,
-            acceptor: self.acceptor.clone(),
+            context: self.context.clone(),
         }))
     }
 }
 
 pub struct TlsListener {
     listener: TcpListener,
-    acceptor: SslAcceptor,
+    context: Arc<RwLock<TlsContext>>,
 }
 
 impl Listener for TlsListener {
     fn accept(&mut self) -> Result<Box<dyn Connection>, AcceptError> {
         let (stream, _) = self.listener.accept()?;
-        let mut tls_stream = self.acceptor.accept(stream)?;
+        // the acceptor is read for each connection, so that reloaded certificates are used
+        let (acceptor, verify_peer) = {
+            let context = rwlock_read_unwrap!(self.context);
+            (context.acceptor.clone(), context.verify_peer)
+        };
+        let mut tls_stream = acceptor.accept(stream)?;
 
-        let frame_version = FrameNegotiation::inbound(FrameVersion::V1)
+        let frame_version = FrameNegotiation::inbound(FrameVersion::latest())
             .negotiate(&mut tls_stream)
             .map_err(|err| match err {
                 FrameError::UnsupportedVersion => AcceptError::ProtocolError(
@@ -199,6 +363,7 @@ impl Listener for TlsListener {
         let connection = TlsConnection {
             frame_version,
             stream: tls_stream,
+            verify_peer,
         };
         Ok(Box::new(connection))
     }
@@ -211,6 +376,7 @@ impl Listener for TlsListener {
 pub struct TlsConnection {
     frame_version: FrameVersion,
     stream: SslStream<TcpStream>,
+    verify_peer: bool,
 }
 
 impl Connection for TlsConnection {
@@ -247,6 +413,32 @@ impl Connection for TlsConnection {
     fn evented(&self) -> &dyn Evented {
         self
     }
+
+    fn peer_identities(&self) -> Option<Vec<String>> {
+        // Without a CA, the peer's certificate is not verified, so it cannot vouch for the peer
+        if !self.verify_peer || self.stream.ssl().verify_result() != X509VerifyResult::OK {
+            return None;
+        }
+
+        let cert = self.stream.ssl().peer_certificate()?;
+        let mut identities = cert
+            .subject_name()
+            .entries_by_nid(Nid::COMMONNAME)
+            .filter_map(|entry| entry.data().as_utf8().ok())
+            .map(|common_name| common_name.to_string())
+            .collect::<Vec<_>>();
+
+        if let Some(alt_names) = cert.subject_alt_names() {
+            identities.extend(
+                alt_names
+                    .iter()
+                    .filter_map(|alt_name| alt_name.dnsname().or_else(|| alt_name.uri()))
+                    .map(String::from),
+            );
+        }
+
+        Some(identities)
+    }
 }
 
 impl TlsConnection {
@@ -259,6 +451,7 @@ impl TlsConnection {
         TlsConnection {
             frame_version: FrameVersion::V1,
             stream,
+            verify_peer: false,
         }
     }
 }
@@ -354,6 +547,7 @@ pub(crate) mod tests {
     use std::fs::File;
     use std::io::Write;
     use std::path::PathBuf;
+    use std::process::Command;
     use tempdir::TempDir;
 
     // Make a certificate and private key for the Certificate Authority
@@ -380,7 +574,7 @@ pub(crate) mod tests {
             .append_extension(BasicConstraints::new().critical().ca().build().unwrap())
             .unwrap();
         cert_builder
-            .append_extension(KeyUsage::new().key_cert_sign().build().unwrap())
+            .append_extension(KeyUsage::new().key_cert_sign().crl_sign().build().unwrap())
             .unwrap();
 
         cert_builder
@@ -538,6 +732,268 @@ pub(crate) mod tests {
         tests::test_transport(transport, "127.0.0.1:0");
     }
 
+    /// Verify that the identities of the peer certificate are available on both sides of a
+    /// connection when certificates are verified against a CA, and are not available otherwise.
+    ///
+    /// 1. Connect two nodes whose certificates are signed by the same CA
+    /// 2. Verify that each side reports the common name of the other side's certificate
+    /// 3. Connect two nodes without a CA
+    /// 4. Verify that neither side reports any identities
+    #[test]
+    fn test_peer_identities() {
+        let (client, server) = connect_pair(create_test_tls_transport(false));
+        assert_eq!(
+            Some(vec!["localhost".to_string()]),
+            client.peer_identities()
+        );
+        assert_eq!(
+            Some(vec!["localhost".to_string()]),
+            server.peer_identities()
+        );
+
+        let (client, server) = connect_pair(create_test_tls_transport(true));
+        assert_eq!(None, client.peer_identities());
+        assert_eq!(None, server.peer_identities());
+    }
+
+    fn connect_pair(mut transport: TlsTransport) -> (Box<dyn Connection>, Box<dyn Connection>) {
+        let mut listener = transport.listen("tcps://127.0.0.1:0").unwrap();
+        let endpoint = listener.endpoint();
+
+        let handle = std::thread::spawn(move || transport.connect(&endpoint).unwrap());
+        let server = listener.accept().unwrap();
+        let client = handle.join().unwrap();
+
+        (client, server)
+    }
+
+    /// Verify that reloading a transport applies its updated files to new connections, including
+    /// the connections accepted by an existing listener.
+    ///
+    /// 1. Connect using certificates signed by the first CA
+    /// 2. Replace the CA certificate with a second CA and reload; verify that connecting fails,
+    ///    since the certificates are still signed by the first CA
+    /// 3. Replace the certificates with ones signed by the second CA and reload; verify that
+    ///    connecting succeeds again
+    #[test]
+    fn test_reload() {
+        let temp_dir = TempDir::new("tls-transport-reload").unwrap();
+        let (ca_key, ca_cert) = make_ca_cert();
+        let files = write_test_files(temp_dir.path(), &ca_cert, &ca_key);
+
+        let mut transport = create_transport_from_files(&files);
+        let reloader = transport.reloader();
+        let mut listener = transport.listen("tcps://127.0.0.1:0").unwrap();
+
+        let (result, returned_listener) = try_connect(&mut transport, listener);
+        listener = returned_listener;
+        assert!(result.is_ok());
+
+        let (new_ca_key, new_ca_cert) = make_ca_cert();
+        write_file(
+            temp_dir.path().to_path_buf(),
+            "ca.cert",
+            &new_ca_cert.to_pem().unwrap(),
+        );
+        reloader.reload().unwrap();
+
+        let (result, returned_listener) = try_connect(&mut transport, listener);
+        listener = returned_listener;
+        assert!(result.is_err());
+
+        write_test_files(temp_dir.path(), &new_ca_cert, &new_ca_key);
+        reloader.reload().unwrap();
+
+        let (result, _) = try_connect(&mut transport, listener);
+        assert!(result.is_ok());
+    }
+
+    /// Verify that a certificate revocation list can only be used with a CA certificate, and that
+    /// a CRL file that cannot be loaded is an error.
+    #[test]
+    fn test_crl_file() {
+        let temp_dir = TempDir::new("tls-transport-crl").unwrap();
+        let (ca_key, ca_cert) = make_ca_cert();
+        let files = write_test_files(temp_dir.path(), &ca_cert, &ca_key);
+
+        let transport = create_transport_from_files(&TlsFiles {
+            ca_cert: None,
+            ..files.clone()
+        });
+        assert!(transport.with_crl_file("crl.pem".into()).is_err());
+
+        let transport = create_transport_from_files(&files);
+        assert!(transport
+            .with_crl_file("/this/crl/does/not/exist.pem".into())
+            .is_err());
+    }
+
+    /// Verify that a peer whose certificate is revoked by the CRL is refused, and that the peer is
+    /// accepted again once the CRL no longer revokes it and the transport has been reloaded.
+    ///
+    /// 1. Write a CRL that revokes the client certificate and add it to the transport
+    /// 2. Connect and verify that the listener refuses the connection
+    /// 3. Replace the CRL with one that does not revoke any certificates and reload
+    /// 4. Connect and verify that the connection is accepted
+    #[test]
+    fn test_crl_revoked_peer() {
+        let temp_dir = TempDir::new("tls-transport-crl-revoked").unwrap();
+        let (ca_key, ca_cert) = make_ca_cert();
+        let files = write_test_files(temp_dir.path(), &ca_cert, &ca_key);
+        let client_cert = X509::from_pem(&std::fs::read(&files.client_cert).unwrap()).unwrap();
+
+        let crl_file = write_crl(temp_dir.path(), &files, &ca_key, &[&client_cert]);
+        let mut transport = create_transport_from_files(&files)
+            .with_crl_file(crl_file)
+            .unwrap();
+        let reloader = transport.reloader();
+        let mut listener = transport.listen("tcps://127.0.0.1:0").unwrap();
+
+        let (accepted, returned_listener) = try_accept(&mut transport, listener);
+        listener = returned_listener;
+        assert!(!accepted);
+
+        write_crl(temp_dir.path(), &files, &ca_key, &[]);
+        reloader.reload().unwrap();
+
+        let (accepted, _) = try_accept(&mut transport, listener);
+        assert!(accepted);
+    }
+
+    // Write a CRL, issued by the CA of the given files, that revokes the given certificates. The
+    // openssl command line tool is used, since the openssl crate is not able to build CRLs.
+    fn write_crl(
+        dir: &std::path::Path,
+        files: &TlsFiles,
+        ca_key: &PKeyRef<Private>,
+        revoked: &[&X509Ref],
+    ) -> String {
+        let ca_key_file = write_file(
+            dir.to_path_buf(),
+            "ca.key",
+            &ca_key.private_key_to_pem_pkcs8().unwrap(),
+        );
+        let index = revoked
+            .iter()
+            .map(|cert| {
+                format!(
+                    "R\t301231235959Z\t200101000000Z\t{}\tunknown\t/CN=localhost\n",
+                    cert.serial_number().to_bn().unwrap().to_hex_str().unwrap()
+                )
+            })
+            .collect::<String>();
+        let index_file = write_file(dir.to_path_buf(), "index.txt", index.as_bytes());
+        let config_file = write_file(
+            dir.to_path_buf(),
+            "ca.cnf",
+            format!(
+                "[ca]\ndefault_ca = ca_default\n[ca_default]\ndatabase = {}\n\
+                 default_md = sha256\ndefault_crl_days = 1\n",
+                index_file
+            )
+            .as_bytes(),
+        );
+        let crl_file = dir.join("crl.pem").to_str().unwrap().to_string();
+
+        let status = Command::new("openssl")
+            .args(&[
+                "ca",
+                "-gencrl",
+                "-config",
+                &config_file,
+                "-keyfile",
+                &ca_key_file,
+            ])
+            .args(&["-cert", files.ca_cert.as_ref().unwrap(), "-out", &crl_file])
+            .status()
+            .expect("failed to run openssl");
+        assert!(status.success());
+
+        crl_file
+    }
+
+    // Write a CA certificate, and client and server certificates and keys signed by it, to the
+    // given directory
+    fn write_test_files(
+        dir: &std::path::Path,
+        ca_cert: &X509Ref,
+        ca_key: &PKeyRef<Private>,
+    ) -> TlsFiles {
+        let (client_key, client_cert) = make_ca_signed_cert(ca_cert, ca_key);
+        let (server_key, server_cert) = make_ca_signed_cert(ca_cert, ca_key);
+
+        TlsFiles {
+            ca_cert: Some(write_file(
+                dir.to_path_buf(),
+                "ca.cert",
+                &ca_cert.to_pem().unwrap(),
+            )),
+            client_key: write_file(
+                dir.to_path_buf(),
+                "client.key",
+                &client_key.private_key_to_pem_pkcs8().unwrap(),
+            ),
+            client_cert: write_file(
+                dir.to_path_buf(),
+                "client.cert",
+                &client_cert.to_pem().unwrap(),
+            ),
+            server_key: write_file(
+                dir.to_path_buf(),
+                "server.key",
+                &server_key.private_key_to_pem_pkcs8().unwrap(),
+            ),
+            server_cert: write_file(
+                dir.to_path_buf(),
+                "server.cert",
+                &server_cert.to_pem().unwrap(),
+            ),
+            crl: None,
+        }
+    }
+
+    fn create_transport_from_files(files: &TlsFiles) -> TlsTransport {
+        TlsTransport::new(
+            files.ca_cert.clone(),
+            files.client_key.clone(),
+            files.client_cert.clone(),
+            files.server_key.clone(),
+            files.server_cert.clone(),
+        )
+        .unwrap()
+    }
+
+    // Connect to the listener, returning the result of the connection and the listener
+    fn try_connect(
+        transport: &mut TlsTransport,
+        mut listener: Box<dyn Listener>,
+    ) -> (Result<(), ConnectError>, Box<dyn Listener>) {
+        let endpoint = listener.endpoint();
+        let handle = std::thread::spawn(move || {
+            let _ = listener.accept();
+            listener
+        });
+        let result = transport.connect(&endpoint).map(|_| ());
+
+        (result, handle.join().unwrap())
+    }
+
+    // Connect to the listener, returning whether the listener accepted the connection and the
+    // listener
+    fn try_accept(
+        transport: &mut TlsTransport,
+        mut listener: Box<dyn Listener>,
+    ) -> (bool, Box<dyn Listener>) {
+        let endpoint = listener.endpoint();
+        let handle = std::thread::spawn(move || {
+            let accepted = listener.accept().is_ok();
+            (accepted, listener)
+        });
+        let _ = transport.connect(&endpoint);
+
+        handle.join().unwrap()
+    }
+
     #[cfg(not(unix))]
     #[test]
     fn test_poll_no_verify() {
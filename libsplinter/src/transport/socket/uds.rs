@@ -0,0 +1,290 @@
+// Copyright 2018-2020 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! A transport for connections between processes on the same host, using Unix domain sockets.
+//!
+//! Endpoints are the path of the socket file, prefixed with `uds://`; for example,
+//! `uds:///var/run/splinter/service.sock`.
+
+use std::fs;
+use std::io::ErrorKind;
+use std::net::Shutdown;
+use std::os::unix::fs::FileTypeExt;
+use std::os::unix::net::{UnixListener as StdUnixListener, UnixStream};
+use std::path::{Path, PathBuf};
+
+use mio::Evented;
+use mio_uds::UnixStream as MioUnixStream;
+
+use crate::transport::{
+    AcceptError, ConnectError, Connection, DisconnectError, ListenError, Listener, RecvError,
+    SendError, Transport,
+};
+
+use super::frame::{Frame, FrameError, FrameNegotiation, FrameRef, FrameVersion};
+
+const PROTOCOL_PREFIX: &str = "uds://";
+
+#[derive(Default)]
+pub struct UdsTransport {}
+
+impl Transport for UdsTransport {
+    fn accepts(&self, address: &str) -> bool {
+        address.starts_with(PROTOCOL_PREFIX)
+    }
+
+    fn connect(&mut self, endpoint: &str) -> Result<Box<dyn Connection>, ConnectError> {
+        if !self.accepts(endpoint) {
+            return Err(ConnectError::ProtocolError(format!(
+                "Invalid protocol \"{}\"",
+                endpoint
+            )));
+        }
+
+        let path = PathBuf::from(&endpoint[PROTOCOL_PREFIX.len()..]);
+        // Connect a std::os::unix::net::UnixStream to make sure connect() blocks
+        let mut stream = UnixStream::connect(&path)?;
+
+        let frame_version = FrameNegotiation::outbound(FrameVersion::V1, FrameVersion::latest())
+            .negotiate(&mut stream)
+            .map_err(|err| match err {
+                FrameError::UnsupportedVersion => ConnectError::ProtocolError(
+                    "Unable to connect; remote version is not with in range".into(),
+                ),
+                FrameError::IoError(err) => ConnectError::from(err),
+                e => ConnectError::ProtocolError(format!("Unexpected protocol error: {}", e)),
+            })?;
+
+        Ok(Box::new(UdsConnection {
+            frame_version,
+            stream: MioUnixStream::from_stream(stream)?,
+            path,
+        }))
+    }
+
+    fn listen(&mut self, bind: &str) -> Result<Box<dyn Listener>, ListenError> {
+        if !self.accepts(bind) {
+            return Err(ListenError::ProtocolError(format!(
+                "Invalid protocol \"{}\"",
+                bind
+            )));
+        }
+
+        let path = PathBuf::from(&bind[PROTOCOL_PREFIX.len()..]);
+        remove_stale_socket(&path)?;
+
+        Ok(Box::new(UdsListener {
+            listener: StdUnixListener::bind(&path)?,
+            path,
+        }))
+    }
+}
+
+/// Removes the socket file at the given path if it was left behind by a listener that is no
+/// longer running. A socket that is still accepting connections is left in place, so binding to
+/// it will fail. Any other file at the path is never removed; an error is returned instead.
+fn remove_stale_socket(path: &Path) -> Result<(), ListenError> {
+    let metadata = match fs::symlink_metadata(path) {
+        Ok(metadata) => metadata,
+        Err(ref err) if err.kind() == ErrorKind::NotFound => return Ok(()),
+        Err(err) => return Err(ListenError::from(err)),
+    };
+
+    if !metadata.file_type().is_socket() {
+        return Err(ListenError::ProtocolError(format!(
+            "Unable to listen on {}: file exists and is not a socket",
+            path.display()
+        )));
+    }
+
+    match UnixStream::connect(path) {
+        Err(ref err) if err.kind() == ErrorKind::ConnectionRefused => {
+            debug!("Removing stale socket file {}", path.display());
+            fs::remove_file(path).map_err(ListenError::from)
+        }
+        _ => Ok(()),
+    }
+}
+
+struct UdsListener {
+    listener: StdUnixListener,
+    path: PathBuf,
+}
+
+impl Listener for UdsListener {
+    fn accept(&mut self) -> Result<Box<dyn Connection>, AcceptError> {
+        let (mut stream, _) = self.listener.accept()?;
+
+        let frame_version = FrameNegotiation::inbound(FrameVersion::latest())
+            .negotiate(&mut stream)
+            .map_err(|err| match err {
+                FrameError::UnsupportedVersion => AcceptError::ProtocolError(
+                    "Unable to connect; local version not supported by remote".into(),
+                ),
+                FrameError::IoError(err) => AcceptError::from(err),
+                err => AcceptError::ProtocolError(format!("Unexpected protocol error: {}", err)),
+            })?;
+
+        let connection = UdsConnection {
+            frame_version,
+            stream: MioUnixStream::from_stream(stream)?,
+            path: self.path.clone(),
+        };
+        Ok(Box::new(connection))
+    }
+
+    fn endpoint(&self) -> String {
+        format!("{}{}", PROTOCOL_PREFIX, self.path.display())
+    }
+}
+
+impl Drop for UdsListener {
+    fn drop(&mut self) {
+        if let Err(err) = fs::remove_file(&self.path) {
+            debug!(
+                "Unable to remove socket file {}: {}",
+                self.path.display(),
+                err
+            );
+        }
+    }
+}
+
+/// A connection over a Unix domain socket. The connecting side of a Unix domain socket is
+/// usually unnamed, so both sides of the connection use the path of the listener's socket as
+/// their local and remote endpoints.
+struct UdsConnection {
+    frame_version: FrameVersion,
+    stream: MioUnixStream,
+    path: PathBuf,
+}
+
+impl Connection for UdsConnection {
+    fn send(&mut self, message: &[u8]) -> Result<(), SendError> {
+        match FrameRef::new(self.frame_version, message).write(&mut self.stream) {
+            Err(FrameError::IoError(e)) => Err(SendError::from(e)),
+            Err(err) => Err(SendError::ProtocolError(err.to_string())),
+            Ok(_) => Ok(()),
+        }
+    }
+
+    fn recv(&mut self) -> Result<Vec<u8>, RecvError> {
+        match Frame::read(&mut self.stream) {
+            Err(FrameError::IoError(e)) => Err(RecvError::from(e)),
+            Err(err) => Err(RecvError::ProtocolError(err.to_string())),
+            Ok(frame) => Ok(frame.into_inner()),
+        }
+    }
+
+    fn remote_endpoint(&self) -> String {
+        format!("{}{}", PROTOCOL_PREFIX, self.path.display())
+    }
+
+    fn local_endpoint(&self) -> String {
+        format!("{}{}", PROTOCOL_PREFIX, self.path.display())
+    }
+
+    fn disconnect(&mut self) -> Result<(), DisconnectError> {
+        self.stream
+            .shutdown(Shutdown::Both)
+            .map_err(DisconnectError::from)
+    }
+
+    fn evented(&self) -> &dyn Evented {
+        &self.stream
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transport::tests;
+
+    #[test]
+    fn test_accepts() {
+        let transport = UdsTransport::default();
+        assert!(transport.accepts("uds:///tmp/splinter.sock"));
+
+        assert!(!transport.accepts("/tmp/splinter.sock"));
+        assert!(!transport.accepts("127.0.0.1:0"));
+        assert!(!transport.accepts("tcp://127.0.0.1:0"));
+    }
+
+    #[test]
+    fn test_transport() {
+        let transport = UdsTransport::default();
+
+        tests::test_transport(transport, &temp_socket_endpoint("transport"));
+    }
+
+    #[test]
+    fn test_poll() {
+        let transport = UdsTransport::default();
+        tests::test_poll(transport, &temp_socket_endpoint("poll"));
+    }
+
+    /// Verify that a socket file left behind by a listener that is no longer running is replaced,
+    /// but a socket that is still in use is not, and that the socket file is removed when the
+    /// listener is dropped.
+    ///
+    /// 1. Bind a std listener and drop it, leaving its socket file behind
+    /// 2. Verify that the transport can listen on the same path
+    /// 3. Verify that listening on the path again fails while the first listener is running
+    /// 4. Drop the listener and verify that the socket file was removed
+    #[test]
+    fn test_listen_replaces_stale_socket() {
+        let endpoint = temp_socket_endpoint("stale");
+        let path = PathBuf::from(&endpoint[PROTOCOL_PREFIX.len()..]);
+
+        drop(StdUnixListener::bind(&path).expect("failed to bind std listener"));
+        assert!(path.exists());
+
+        let mut transport = UdsTransport::default();
+        let listener = transport.listen(&endpoint).expect("failed to listen");
+        assert_eq!(listener.endpoint(), endpoint);
+
+        assert!(transport.listen(&endpoint).is_err());
+
+        drop(listener);
+        assert!(!path.exists());
+    }
+
+    /// Verify that a file at the socket path that is not a socket is never removed.
+    ///
+    /// 1. Create a regular file at the socket path
+    /// 2. Verify that listening on the path fails
+    /// 3. Verify that the file was left in place
+    #[test]
+    fn test_listen_keeps_non_socket_file() {
+        let endpoint = temp_socket_endpoint("regular");
+        let path = PathBuf::from(&endpoint[PROTOCOL_PREFIX.len()..]);
+
+        fs::write(&path, b"not a socket").expect("failed to create file");
+
+        let mut transport = UdsTransport::default();
+        assert!(transport.listen(&endpoint).is_err());
+
+        assert_eq!(
+            b"not a socket".to_vec(),
+            fs::read(&path).expect("failed to read file")
+        );
+        fs::remove_file(&path).expect("failed to remove file");
+    }
+
+    fn temp_socket_endpoint(name: &str) -> String {
+        let mut path = std::env::temp_dir();
+        path.push(format!("splinter-uds-{}-{}.sock", name, std::process::id()));
+        format!("{}{}", PROTOCOL_PREFIX, path.display())
+    }
+}
@@ -802,7 +802,11 @@ fn run() -> Result<(), CliError> {
                     .expect("default not set for --format");
 
                 let registries = client
-                    .get_state_with_prefix(&service_id, Some(CONTRACT_REGISTRY_ADDRESS_PREFIX))?
+                    .get_state_with_prefix(
+                        &service_id,
+                        Some(CONTRACT_REGISTRY_ADDRESS_PREFIX),
+                        None,
+                    )?
                     .iter()
                     .map(|entry| ContractRegistryList::from_bytes(entry.value()))
                     .collect::<Result<Vec<_>, _>>()?;
@@ -862,7 +866,7 @@ fn run() -> Result<(), CliError> {
 
                 let address = compute_contract_address(name, version)?;
                 let contract_bytes = client
-                    .get_state_at_address(&service_id, &to_hex(&address))?
+                    .get_state_at_address(&service_id, &to_hex(&address), None)?
                     .ok_or_else(|| {
                         CliError::action_error(&format!("contract '{}' not found", contract))
                     })?;
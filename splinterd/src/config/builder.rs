@@ -297,6 +297,60 @@ impl ConfigBuilder {
                     None => None,
                 })
                 .ok_or_else(|| ConfigError::MissingValue("no tls".to_string()))?,
+            node_key_file: self
+                .partial_configs
+                .iter()
+                .find_map(|p| match p.node_key_file() {
+                    Some(v) => Some((v, p.source())),
+                    None => None,
+                }),
+            key_permissions: self
+                .partial_configs
+                .iter()
+                .find_map(|p| match p.key_permissions() {
+                    Some(v) => Some((v, p.source())),
+                    None => None,
+                })
+                .ok_or_else(|| ConfigError::MissingValue("key permissions".to_string()))?,
+            allow_key_role_changes: self
+                .partial_configs
+                .iter()
+                .find_map(|p| match p.allow_key_role_changes() {
+                    Some(v) => Some((v, p.source())),
+                    None => None,
+                })
+                .ok_or_else(|| ConfigError::MissingValue("allow key role changes".to_string()))?,
+            scabbard_storage: self
+                .partial_configs
+                .iter()
+                .find_map(|p| match p.scabbard_storage() {
+                    Some(v) => Some((v, p.source())),
+                    None => None,
+                })
+                .ok_or_else(|| ConfigError::MissingValue("scabbard storage".to_string()))?,
+            verify_peer_identity: self
+                .partial_configs
+                .iter()
+                .find_map(|p| match p.verify_peer_identity() {
+                    Some(v) => Some((v, p.source())),
+                    None => None,
+                })
+                .ok_or_else(|| ConfigError::MissingValue("tls verify peer identity".to_string()))?,
+            tls_crl_file: self
+                .partial_configs
+                .iter()
+                .find_map(|p| match p.tls_crl_file() {
+                    Some(v) => Some((v, p.source())),
+                    None => None,
+                }),
+            tls_reload_interval: self
+                .partial_configs
+                .iter()
+                .find_map(|p| match p.tls_reload_interval() {
+                    Some(v) => Some((v, p.source())),
+                    None => None,
+                })
+                .ok_or_else(|| ConfigError::MissingValue("tls reload interval".to_string()))?,
             #[cfg(feature = "biome")]
             biome_enabled: self
                 .partial_configs
@@ -90,7 +90,22 @@ impl<'a> PartialConfigBuilder for ClapPartialConfigBuilder<'_> {
                 Some(true)
             } else {
                 None
-            });
+            })
+            .with_node_key_file(self.matches.value_of("node_key_file").map(String::from))
+            .with_key_permissions(self.matches.value_of("key_permissions").map(String::from))
+            .with_allow_key_role_changes(if self.matches.is_present("allow_key_role_changes") {
+                Some(true)
+            } else {
+                None
+            })
+            .with_scabbard_storage(self.matches.value_of("scabbard_storage").map(String::from))
+            .with_verify_peer_identity(if self.matches.is_present("verify_peer_identity") {
+                Some(true)
+            } else {
+                None
+            })
+            .with_tls_crl_file(self.matches.value_of("tls_crl_file").map(String::from))
+            .with_tls_reload_interval(parse_value(&self.matches, "tls_reload_interval")?);
 
         #[cfg(feature = "biome")]
         {
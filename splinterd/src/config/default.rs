@@ -25,6 +25,7 @@ const CA_PEM: &str = "ca.pem";
 const REGISTRY_AUTO_REFRESH_DEFAULT: u64 = 600; // 600 seconds = 10 minutes
 const REGISTRY_FORCED_REFRESH_DEFAULT: u64 = 10; // 10 seconds
 const HEARTBEAT_DEFAULT: u64 = 30;
+const TLS_RELOAD_INTERVAL_DEFAULT: u64 = 0;
 const DEFAULT_ADMIN_SERVICE_COORDINATOR_TIMEOUT: u64 = 30; // 30 seconds
 
 /// Holds the default configuration values.
@@ -59,7 +60,12 @@ impl PartialConfigBuilder for DefaultPartialConfigBuilder {
             .with_admin_service_coordinator_timeout(Some(DEFAULT_ADMIN_SERVICE_COORDINATOR_TIMEOUT))
             .with_state_dir(Some(String::from(DEFAULT_STATE_DIR)))
             .with_tls_insecure(Some(false))
-            .with_no_tls(Some(false));
+            .with_no_tls(Some(false))
+            .with_key_permissions(Some(String::from("allow-all")))
+            .with_allow_key_role_changes(Some(false))
+            .with_scabbard_storage(Some(String::from("lmdb")))
+            .with_verify_peer_identity(Some(false))
+            .with_tls_reload_interval(Some(TLS_RELOAD_INTERVAL_DEFAULT));
 
         #[cfg(feature = "biome")]
         {
@@ -123,6 +129,15 @@ mod tests {
         assert_eq!(config.state_dir(), Some(String::from(DEFAULT_STATE_DIR)));
         assert_eq!(config.tls_insecure(), Some(false));
         assert_eq!(config.no_tls(), Some(false));
+        assert_eq!(config.key_permissions(), Some(String::from("allow-all")));
+        assert_eq!(config.allow_key_role_changes(), Some(false));
+        assert_eq!(config.scabbard_storage(), Some(String::from("lmdb")));
+        assert_eq!(config.verify_peer_identity(), Some(false));
+        assert_eq!(config.tls_crl_file(), None);
+        assert_eq!(
+            config.tls_reload_interval(),
+            Some(TLS_RELOAD_INTERVAL_DEFAULT)
+        );
         #[cfg(feature = "biome")]
         assert_eq!(config.biome_enabled(), Some(false));
         // Assert the source is correctly identified for this PartialConfig object.
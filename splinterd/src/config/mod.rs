@@ -66,6 +66,13 @@ pub struct Config {
     state_dir: (String, ConfigSource),
     tls_insecure: (bool, ConfigSource),
     no_tls: (bool, ConfigSource),
+    node_key_file: Option<(String, ConfigSource)>,
+    key_permissions: (String, ConfigSource),
+    allow_key_role_changes: (bool, ConfigSource),
+    scabbard_storage: (String, ConfigSource),
+    verify_peer_identity: (bool, ConfigSource),
+    tls_crl_file: Option<(String, ConfigSource)>,
+    tls_reload_interval: (u64, ConfigSource),
     #[cfg(feature = "biome")]
     biome_enabled: (bool, ConfigSource),
     #[cfg(feature = "rest-api-cors")]
@@ -166,6 +173,42 @@ impl Config {
         self.no_tls.0
     }
 
+    pub fn node_key_file(&self) -> Option<&str> {
+        if let Some((node_key_file, _)) = &self.node_key_file {
+            Some(node_key_file)
+        } else {
+            None
+        }
+    }
+
+    pub fn key_permissions(&self) -> &str {
+        &self.key_permissions.0
+    }
+
+    pub fn allow_key_role_changes(&self) -> bool {
+        self.allow_key_role_changes.0
+    }
+
+    pub fn scabbard_storage(&self) -> &str {
+        &self.scabbard_storage.0
+    }
+
+    pub fn verify_peer_identity(&self) -> bool {
+        self.verify_peer_identity.0
+    }
+
+    pub fn tls_crl_file(&self) -> Option<&str> {
+        if let Some((tls_crl_file, _)) = &self.tls_crl_file {
+            Some(tls_crl_file)
+        } else {
+            None
+        }
+    }
+
+    pub fn tls_reload_interval(&self) -> u64 {
+        self.tls_reload_interval.0
+    }
+
     #[cfg(feature = "biome")]
     pub fn biome_enabled(&self) -> bool {
         self.biome_enabled.0
@@ -273,6 +316,42 @@ impl Config {
         &self.no_tls.1
     }
 
+    fn node_key_file_source(&self) -> Option<&ConfigSource> {
+        if let Some((_, source)) = &self.node_key_file {
+            Some(source)
+        } else {
+            None
+        }
+    }
+
+    fn key_permissions_source(&self) -> &ConfigSource {
+        &self.key_permissions.1
+    }
+
+    fn allow_key_role_changes_source(&self) -> &ConfigSource {
+        &self.allow_key_role_changes.1
+    }
+
+    fn scabbard_storage_source(&self) -> &ConfigSource {
+        &self.scabbard_storage.1
+    }
+
+    fn verify_peer_identity_source(&self) -> &ConfigSource {
+        &self.verify_peer_identity.1
+    }
+
+    fn tls_crl_file_source(&self) -> Option<&ConfigSource> {
+        if let Some((_, source)) = &self.tls_crl_file {
+            Some(source)
+        } else {
+            None
+        }
+    }
+
+    fn tls_reload_interval_source(&self) -> &ConfigSource {
+        &self.tls_reload_interval.1
+    }
+
     #[cfg(feature = "biome")]
     fn biome_enabled_source(&self) -> &ConfigSource {
         &self.biome_enabled.1
@@ -405,6 +484,45 @@ impl Config {
             self.no_tls(),
             self.no_tls_source()
         );
+        if let Some(node_key_file) = self.node_key_file() {
+            debug!(
+                "Config: node_key_file: {} (source: {:?})",
+                node_key_file,
+                self.node_key_file_source()
+            );
+        }
+        debug!(
+            "Config: key_permissions: {} (source: {:?})",
+            self.key_permissions(),
+            self.key_permissions_source()
+        );
+        debug!(
+            "Config: allow_key_role_changes: {:?} (source: {:?})",
+            self.allow_key_role_changes(),
+            self.allow_key_role_changes_source()
+        );
+        debug!(
+            "Config: scabbard_storage: {} (source: {:?})",
+            self.scabbard_storage(),
+            self.scabbard_storage_source()
+        );
+        debug!(
+            "Config: verify_peer_identity: {:?} (source: {:?})",
+            self.verify_peer_identity(),
+            self.verify_peer_identity_source()
+        );
+        if let Some(tls_crl_file) = self.tls_crl_file() {
+            debug!(
+                "Config: tls_crl_file: {} (source: {:?})",
+                tls_crl_file,
+                self.tls_crl_file_source()
+            );
+        }
+        debug!(
+            "Config: tls_reload_interval: {} (source: {:?})",
+            self.tls_reload_interval(),
+            self.tls_reload_interval_source()
+        );
         #[cfg(feature = "biome")]
         debug!(
             "Config: biome_enabled: {:?} (source: {:?})",
@@ -736,6 +854,56 @@ mod tests {
             (true, &ConfigSource::CommandLine)
         );
 
+        // Only the DefaultPartialConfigBuilder had a value for `key_permissions` (source should
+        // be Default).
+        assert_eq!(
+            (
+                final_config.key_permissions(),
+                final_config.key_permissions_source()
+            ),
+            ("allow-all", &ConfigSource::Default)
+        );
+
+        // Only the DefaultPartialConfigBuilder had a value for `allow_key_role_changes` (source
+        // should be Default).
+        assert_eq!(
+            (
+                final_config.allow_key_role_changes(),
+                final_config.allow_key_role_changes_source()
+            ),
+            (false, &ConfigSource::Default)
+        );
+
+        // Only the DefaultPartialConfigBuilder had a value for `scabbard_storage` (source should
+        // be Default).
+        assert_eq!(
+            (
+                final_config.scabbard_storage(),
+                final_config.scabbard_storage_source()
+            ),
+            ("lmdb", &ConfigSource::Default)
+        );
+
+        // Only the DefaultPartialConfigBuilder had a value for `verify_peer_identity` (source
+        // should be Default).
+        assert_eq!(
+            (
+                final_config.verify_peer_identity(),
+                final_config.verify_peer_identity_source()
+            ),
+            (false, &ConfigSource::Default)
+        );
+
+        // Only the DefaultPartialConfigBuilder had a value for `tls_reload_interval` (source
+        // should be Default).
+        assert_eq!(
+            (
+                final_config.tls_reload_interval(),
+                final_config.tls_reload_interval_source()
+            ),
+            (0, &ConfigSource::Default)
+        );
+
         // The DefaultPartialConfigBuilder and EnvPartialConfigBuilder had values for
         // `tls_cert_dir`, but the EnvPartialConfigBuilder value should have precedence (source
         // should be Environment).
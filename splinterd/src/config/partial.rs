@@ -54,6 +54,13 @@ pub struct PartialConfig {
     state_dir: Option<String>,
     tls_insecure: Option<bool>,
     no_tls: Option<bool>,
+    node_key_file: Option<String>,
+    key_permissions: Option<String>,
+    allow_key_role_changes: Option<bool>,
+    scabbard_storage: Option<String>,
+    verify_peer_identity: Option<bool>,
+    tls_crl_file: Option<String>,
+    tls_reload_interval: Option<u64>,
     #[cfg(feature = "biome")]
     biome_enabled: Option<bool>,
     #[cfg(feature = "rest-api-cors")]
@@ -89,6 +96,13 @@ impl PartialConfig {
             state_dir: None,
             tls_insecure: None,
             no_tls: None,
+            node_key_file: None,
+            key_permissions: None,
+            allow_key_role_changes: None,
+            scabbard_storage: None,
+            verify_peer_identity: None,
+            tls_crl_file: None,
+            tls_reload_interval: None,
             #[cfg(feature = "biome")]
             biome_enabled: None,
             #[cfg(feature = "rest-api-cors")]
@@ -193,6 +207,34 @@ impl PartialConfig {
         self.no_tls
     }
 
+    pub fn node_key_file(&self) -> Option<String> {
+        self.node_key_file.clone()
+    }
+
+    pub fn key_permissions(&self) -> Option<String> {
+        self.key_permissions.clone()
+    }
+
+    pub fn allow_key_role_changes(&self) -> Option<bool> {
+        self.allow_key_role_changes
+    }
+
+    pub fn scabbard_storage(&self) -> Option<String> {
+        self.scabbard_storage.clone()
+    }
+
+    pub fn verify_peer_identity(&self) -> Option<bool> {
+        self.verify_peer_identity
+    }
+
+    pub fn tls_crl_file(&self) -> Option<String> {
+        self.tls_crl_file.clone()
+    }
+
+    pub fn tls_reload_interval(&self) -> Option<u64> {
+        self.tls_reload_interval
+    }
+
     #[cfg(feature = "biome")]
     pub fn biome_enabled(&self) -> Option<bool> {
         self.biome_enabled
@@ -492,6 +534,91 @@ impl PartialConfig {
         self
     }
 
+    /// Adds a `node_key_file` value to the PartialConfig object.
+    ///
+    /// # Arguments
+    ///
+    /// * `node_key_file` - File path to the private key the node uses to prove its identity to
+    ///   its peers.
+    ///
+    pub fn with_node_key_file(mut self, node_key_file: Option<String>) -> Self {
+        self.node_key_file = node_key_file;
+        self
+    }
+
+    /// Adds a `key_permissions` value to the PartialConfig object.
+    ///
+    /// # Arguments
+    ///
+    /// * `key_permissions` - The type of key permission manager used to check whether a public
+    ///   key may propose or vote on circuits.
+    ///
+    pub fn with_key_permissions(mut self, key_permissions: Option<String>) -> Self {
+        self.key_permissions = key_permissions;
+        self
+    }
+
+    /// Adds an `allow_key_role_changes` value to the PartialConfig object.
+    ///
+    /// # Arguments
+    ///
+    /// * `allow_key_role_changes` - Allow key roles to be granted and revoked through the
+    ///   unauthenticated REST API.
+    ///
+    pub fn with_allow_key_role_changes(mut self, allow_key_role_changes: Option<bool>) -> Self {
+        self.allow_key_role_changes = allow_key_role_changes;
+        self
+    }
+
+    /// Adds a `scabbard_storage` value to the PartialConfig object.
+    ///
+    /// # Arguments
+    ///
+    /// * `scabbard_storage` - The type of storage used for the state and transaction receipts of
+    ///   scabbard services.
+    ///
+    pub fn with_scabbard_storage(mut self, scabbard_storage: Option<String>) -> Self {
+        self.scabbard_storage = scabbard_storage;
+        self
+    }
+
+    /// Adds a `verify_peer_identity` value to the PartialConfig object.
+    ///
+    /// # Arguments
+    ///
+    /// * `verify_peer_identity` - Require peers to claim a node ID that is bound to the
+    ///   identity that their connection authenticated, such as their TLS certificate or the key
+    ///   of their Noise connection.
+    ///
+    pub fn with_verify_peer_identity(mut self, verify_peer_identity: Option<bool>) -> Self {
+        self.verify_peer_identity = verify_peer_identity;
+        self
+    }
+
+    /// Adds a `tls_crl_file` value to the PartialConfig object.
+    ///
+    /// # Arguments
+    ///
+    /// * `tls_crl_file` - File path to the certificate revocation list used to refuse revoked
+    ///   peer certificates.
+    ///
+    pub fn with_tls_crl_file(mut self, tls_crl_file: Option<String>) -> Self {
+        self.tls_crl_file = tls_crl_file;
+        self
+    }
+
+    /// Adds a `tls_reload_interval` value to the PartialConfig object.
+    ///
+    /// # Arguments
+    ///
+    /// * `tls_reload_interval` - How often the TLS certificate, key and CRL files are checked for
+    ///   changes, in seconds.
+    ///
+    pub fn with_tls_reload_interval(mut self, tls_reload_interval: Option<u64>) -> Self {
+        self.tls_reload_interval = tls_reload_interval;
+        self
+    }
+
     #[cfg(feature = "biome")]
     /// Adds a `biome_enabled` value to the PartialConfig object.
     ///
@@ -45,6 +45,13 @@ struct TomlConfig {
     registry_forced_refresh_interval: Option<u64>,
     heartbeat_interval: Option<u64>,
     admin_service_coordinator_timeout: Option<u64>,
+    node_key_file: Option<String>,
+    key_permissions: Option<String>,
+    allow_key_role_changes: Option<bool>,
+    scabbard_storage: Option<String>,
+    verify_peer_identity: Option<bool>,
+    tls_crl_file: Option<String>,
+    tls_reload_interval: Option<u64>,
     version: Option<String>,
     #[cfg(feature = "rest-api-cors")]
     whitelist: Option<Vec<String>>,
@@ -122,7 +129,14 @@ impl PartialConfigBuilder for TomlPartialConfigBuilder {
             .with_heartbeat_interval(self.toml_config.heartbeat_interval)
             .with_admin_service_coordinator_timeout(
                 self.toml_config.admin_service_coordinator_timeout,
-            );
+            )
+            .with_node_key_file(self.toml_config.node_key_file)
+            .with_key_permissions(self.toml_config.key_permissions)
+            .with_allow_key_role_changes(self.toml_config.allow_key_role_changes)
+            .with_scabbard_storage(self.toml_config.scabbard_storage)
+            .with_verify_peer_identity(self.toml_config.verify_peer_identity)
+            .with_tls_crl_file(self.toml_config.tls_crl_file)
+            .with_tls_reload_interval(self.toml_config.tls_reload_interval);
 
         #[cfg(feature = "database")]
         {
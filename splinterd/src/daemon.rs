@@ -16,6 +16,7 @@
 use std::collections::HashMap;
 use std::error::Error;
 use std::fmt;
+use std::fs;
 use std::path::Path;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
@@ -25,6 +26,8 @@ use std::time::Duration;
 #[cfg(feature = "health")]
 use health::HealthService;
 use splinter::admin::rest_api::CircuitResourceProvider;
+#[cfg(feature = "database")]
+use splinter::admin::service::DieselProposalStore;
 use splinter::admin::service::{admin_service_id, AdminService};
 #[cfg(feature = "biome")]
 use splinter::biome::rest_api::{BiomeRestResourceManager, BiomeRestResourceManagerBuilder};
@@ -39,18 +42,25 @@ use splinter::circuit::handlers::{
     AdminDirectMessageHandler, CircuitDirectMessageHandler, CircuitErrorHandler,
     CircuitMessageHandler, ServiceConnectRequestHandler, ServiceDisconnectRequestHandler,
 };
+#[cfg(feature = "database")]
+use splinter::circuit::store::diesel::DieselCircuitStore;
 use splinter::circuit::{SplinterState, SplinterStateError};
-#[cfg(feature = "biome")]
+#[cfg(feature = "database")]
 use splinter::database::{self, ConnectionPool};
 use splinter::keys::{
-    insecure::AllowAllKeyPermissionManager, rest_api::KeyRegistryManager,
+    insecure::AllowAllKeyPermissionManager,
+    rest_api::{KeyRegistryManager, KeyRolesManager},
+    roles::RoleBasedKeyPermissionManager,
     storage::StorageKeyRegistry,
+    KeyPermissionManager,
 };
 use splinter::mesh::Mesh;
 use splinter::network::auth::handlers::{
     create_authorization_dispatcher, AuthorizationMessageHandler, NetworkAuthGuardHandler,
 };
-use splinter::network::auth::AuthorizationManager;
+use splinter::network::auth::{
+    AuthorizationManager, CertificateIdentityBinding, ChallengeAuthorization,
+};
 use splinter::network::dispatch::{DispatchLoopBuilder, DispatchMessageSender, Dispatcher};
 use splinter::network::handlers::{NetworkEchoHandler, NetworkHeartbeatHandler};
 use splinter::network::peer::PeerConnector;
@@ -70,11 +80,11 @@ use splinter::rest_api::{
 };
 #[cfg(feature = "service-arg-validation")]
 use splinter::service::scabbard::ScabbardArgValidator;
-use splinter::service::scabbard::ScabbardFactory;
+use splinter::service::scabbard::{ScabbardFactory, ScabbardStorage};
 #[cfg(feature = "service-arg-validation")]
 use splinter::service::validation::ServiceArgValidator;
 use splinter::service::{self, ServiceProcessor, ShutdownHandle};
-use splinter::signing::sawtooth::SawtoothSecp256k1SignatureVerifier;
+use splinter::signing::sawtooth::{SawtoothSecp256k1SignatureVerifier, SawtoothSecp256k1Signer};
 use splinter::storage::get_storage;
 use splinter::transport::{
     multi::MultiTransport, AcceptError, ConnectError, Connection, Incoming, ListenError, Listener,
@@ -125,6 +135,11 @@ pub struct SplinterDaemon {
     registry_forced_refresh_interval: u64,
     storage_type: String,
     admin_service_coordinator_timeout: Duration,
+    node_key_file: Option<String>,
+    key_roles_location: Option<String>,
+    allow_key_role_changes: bool,
+    scabbard_storage: ScabbardStorage,
+    verify_peer_identity: bool,
     #[cfg(feature = "rest-api-cors")]
     whitelist: Option<Vec<String>>,
 }
@@ -134,13 +149,36 @@ impl SplinterDaemon {
         // Setup up ctrlc handling
         let running = Arc::new(AtomicBool::new(true));
 
-        // Load initial state from the configured storage location and create the new
-        // SplinterState from the retrieved circuit directory
-        let storage = get_storage(&self.storage_location, CircuitDirectory::new)
-            .map_err(StartError::StorageError)?;
+        // When using database storage, circuits and proposals are loaded from and persisted to
+        // the Splinter database
+        #[cfg(feature = "database")]
+        let storage_connection_pool = if self.storage_type == "database" {
+            let db_url = self.db_url.as_ref().ok_or_else(|| {
+                StartError::StorageError(
+                    "database storage was selected but the builder failed to require the db URL"
+                        .into(),
+                )
+            })?;
+            Some(ConnectionPool::new_pg(db_url).map_err(|err| {
+                StartError::StorageError(format!(
+                    "Unable to connect to the Splinter database: {}",
+                    err
+                ))
+            })?)
+        } else {
+            None
+        };
 
-        let circuit_directory = storage.read().clone();
-        let state = SplinterState::new(self.storage_location.to_string(), circuit_directory);
+        #[cfg(feature = "database")]
+        let state = match &storage_connection_pool {
+            Some(connection_pool) => {
+                SplinterState::from_circuit_store(DieselCircuitStore::new(connection_pool.clone()))
+                    .map_err(|err| StartError::StorageError(err.to_string()))?
+            }
+            None => load_splinter_state(&self.storage_location)?,
+        };
+        #[cfg(not(feature = "database"))]
+        let state = load_splinter_state(&self.storage_location)?;
 
         // set up the listeners on the transport. This will set up listeners for different
         // transports based on the protocol prefix of the endpoint.
@@ -202,8 +240,26 @@ impl SplinterDaemon {
             ))
         })?;
 
+        let (node_registry, registry_shutdown) = create_node_registry(
+            &self.node_registry_directory,
+            &self.registries,
+            self.registry_auto_refresh_interval,
+            self.registry_forced_refresh_interval,
+        )?;
+
         let peer_connector = PeerConnector::new(self.network.clone(), Box::new(transport));
-        let auth_manager = AuthorizationManager::new(self.network.clone(), self.node_id.clone());
+        let mut auth_manager = create_authorization_manager(
+            self.network.clone(),
+            &self.node_id,
+            self.node_key_file.as_ref().map(String::as_str),
+            Box::new(node_registry.clone()),
+        )?;
+        if self.verify_peer_identity {
+            info!("Peers must claim the node ID bound to their authenticated connection");
+            auth_manager = auth_manager.with_certificate_identity_binding(
+                CertificateIdentityBinding::new(Box::new(node_registry.clone())),
+            );
+        }
 
         info!("Starting SpinterNode with ID {}", self.node_id);
 
@@ -379,13 +435,16 @@ impl SplinterDaemon {
             .map_err(|_| StartError::ThreadError("Unable to spawn main loop".into()))?;
 
         let orchestrator = ServiceOrchestrator::new(
-            vec![Box::new(ScabbardFactory::new(
-                None,
-                None,
-                None,
-                None,
-                Box::new(SawtoothSecp256k1SignatureVerifier::new()),
-            ))],
+            vec![Box::new(
+                ScabbardFactory::new(
+                    None,
+                    None,
+                    None,
+                    None,
+                    Box::new(SawtoothSecp256k1SignatureVerifier::new()),
+                )
+                .with_storage(self.scabbard_storage.clone()),
+            )],
             orchestrator_connection,
             ORCHESTRATOR_INCOMING_CAPACITY,
             ORCHESTRATOR_OUTGOING_CAPACITY,
@@ -400,6 +459,28 @@ impl SplinterDaemon {
                 .map_err(|err| StartError::StorageError(format!("{}", err)))?,
         );
 
+        // Keys are only checked against their granted roles if a key roles location has been
+        // configured; otherwise, every registered key may propose and vote on circuits.
+        let role_based_permission_manager = match &self.key_roles_location {
+            Some(key_roles_location) => Some(
+                RoleBasedKeyPermissionManager::new(key_roles_location.clone())
+                    .map_err(|err| StartError::StorageError(format!("{}", err)))?,
+            ),
+            None => None,
+        };
+        let key_permission_manager: Box<dyn KeyPermissionManager> =
+            match &role_based_permission_manager {
+                Some(permission_manager) => Box::new(permission_manager.clone()),
+                None => Box::new(AllowAllKeyPermissionManager),
+            };
+
+        // Proposals in database storage are loaded from the proposal store, so the admin
+        // service itself starts with in-memory proposals
+        let admin_storage_type = match self.storage_type.as_str() {
+            "database" => "memory",
+            storage_type => storage_type,
+        };
+
         let admin_service = AdminService::new(
             &self.node_id,
             orchestrator,
@@ -415,21 +496,27 @@ impl SplinterDaemon {
             state.clone(),
             Box::new(signature_verifier),
             key_registry.clone(),
-            Box::new(AllowAllKeyPermissionManager),
-            &self.storage_type,
+            key_permission_manager,
+            admin_storage_type,
             Some(self.admin_service_coordinator_timeout),
         )
         .map_err(|err| {
             StartError::AdminServiceError(format!("unable to create admin service: {}", err))
         })?;
-        let key_registry_manager = KeyRegistryManager::new(key_registry);
 
-        let (node_registry, registry_shutdown) = create_node_registry(
-            &self.node_registry_directory,
-            &self.registries,
-            self.registry_auto_refresh_interval,
-            self.registry_forced_refresh_interval,
-        )?;
+        #[cfg(feature = "database")]
+        let admin_service = match storage_connection_pool {
+            Some(connection_pool) => admin_service
+                .with_proposal_store(DieselProposalStore::new(connection_pool))
+                .map_err(|err| {
+                    StartError::AdminServiceError(format!(
+                        "unable to load proposals from the database: {}",
+                        err
+                    ))
+                })?,
+            None => admin_service,
+        };
+        let key_registry_manager = KeyRegistryManager::new(key_registry);
 
         let node_id = self.node_id.clone();
         let display_name = self.display_name.clone();
@@ -465,6 +552,12 @@ impl SplinterDaemon {
             .add_resources(orchestrator_resources)
             .add_resources(circuit_resource_provider.resources());
 
+        if let Some(permission_manager) = role_based_permission_manager {
+            rest_api_builder = rest_api_builder.add_resources(
+                KeyRolesManager::new(permission_manager, self.allow_key_role_changes).resources(),
+            );
+        }
+
         #[cfg(feature = "rest-api-cors")]
         {
             if let Some(list) = &self.whitelist {
@@ -680,6 +773,19 @@ fn start_health_service(
     })?
 }
 
+fn load_splinter_state(storage_location: &str) -> Result<SplinterState, StartError> {
+    // Load initial state from the configured storage location and create the new
+    // SplinterState from the retrieved circuit directory
+    let storage =
+        get_storage(storage_location, CircuitDirectory::new).map_err(StartError::StorageError)?;
+
+    let circuit_directory = storage.read().clone();
+    Ok(SplinterState::new(
+        storage_location.to_string(),
+        circuit_directory,
+    ))
+}
+
 #[cfg(feature = "biome")]
 fn build_biome_routes(db_url: &str) -> Result<BiomeRestResourceManager, StartError> {
     info!("Adding biome routes");
@@ -734,6 +840,11 @@ pub struct SplinterDaemonBuilder {
     storage_type: Option<String>,
     heartbeat_interval: Option<u64>,
     admin_service_coordinator_timeout: Duration,
+    node_key_file: Option<String>,
+    key_roles_location: Option<String>,
+    allow_key_role_changes: bool,
+    scabbard_storage: Option<ScabbardStorage>,
+    verify_peer_identity: bool,
     #[cfg(feature = "rest-api-cors")]
     whitelist: Option<Vec<String>>,
 }
@@ -835,6 +946,31 @@ impl SplinterDaemonBuilder {
         self
     }
 
+    pub fn with_node_key_file(mut self, value: Option<String>) -> Self {
+        self.node_key_file = value;
+        self
+    }
+
+    pub fn with_key_roles_location(mut self, value: Option<String>) -> Self {
+        self.key_roles_location = value;
+        self
+    }
+
+    pub fn with_allow_key_role_changes(mut self, value: bool) -> Self {
+        self.allow_key_role_changes = value;
+        self
+    }
+
+    pub fn with_scabbard_storage(mut self, value: ScabbardStorage) -> Self {
+        self.scabbard_storage = Some(value);
+        self
+    }
+
+    pub fn with_verify_peer_identity(mut self, value: bool) -> Self {
+        self.verify_peer_identity = value;
+        self
+    }
+
     #[cfg(feature = "rest-api-cors")]
     pub fn with_whitelist(mut self, value: Option<Vec<String>>) -> Self {
         self.whitelist = value;
@@ -920,6 +1056,15 @@ impl SplinterDaemonBuilder {
             CreateError::MissingRequiredField("Missing field: storage_type".to_string())
         })?;
 
+        #[cfg(feature = "database")]
+        {
+            if storage_type == "database" && db_url.is_none() {
+                return Err(CreateError::MissingRequiredField(
+                    "db_url is required to use database storage.".to_string(),
+                ));
+            }
+        }
+
         Ok(SplinterDaemon {
             storage_location,
             service_endpoint,
@@ -941,6 +1086,11 @@ impl SplinterDaemonBuilder {
             node_registry_directory,
             storage_type,
             admin_service_coordinator_timeout: self.admin_service_coordinator_timeout,
+            node_key_file: self.node_key_file,
+            key_roles_location: self.key_roles_location,
+            allow_key_role_changes: self.allow_key_role_changes,
+            scabbard_storage: self.scabbard_storage.unwrap_or_default(),
+            verify_peer_identity: self.verify_peer_identity,
             #[cfg(feature = "rest-api-cors")]
             whitelist: self.whitelist,
         })
@@ -1007,6 +1157,46 @@ fn set_up_circuit_dispatcher(
     dispatcher
 }
 
+/// Creates the authorization manager for the node's peer connections.
+///
+/// If a node key file is provided, peers must use challenge authorization; otherwise, trust
+/// authorization is used.
+fn create_authorization_manager(
+    network: Network,
+    node_id: &str,
+    node_key_file: Option<&str>,
+    node_registry: Box<dyn NodeRegistryReader>,
+) -> Result<AuthorizationManager, StartError> {
+    let node_key_file = match node_key_file {
+        Some(node_key_file) => node_key_file,
+        None => return Ok(AuthorizationManager::new(network, node_id.to_string())),
+    };
+
+    let private_key = fs::read_to_string(node_key_file).map_err(|err| {
+        StartError::NetworkError(format!(
+            "unable to read node key file {}: {}",
+            node_key_file, err
+        ))
+    })?;
+    let signer = SawtoothSecp256k1Signer::from_hex(private_key.trim()).map_err(|err| {
+        StartError::NetworkError(format!(
+            "unable to load node key from {}: {}",
+            node_key_file, err
+        ))
+    })?;
+
+    info!("Peers must use challenge authorization to connect");
+    Ok(AuthorizationManager::new_with_challenge(
+        network,
+        node_id.to_string(),
+        ChallengeAuthorization::new(
+            Box::new(signer),
+            Box::new(SawtoothSecp256k1SignatureVerifier::new()),
+            node_registry,
+        ),
+    ))
+}
+
 fn create_node_registry(
     node_registry_directory: &str,
     registries: &[String],
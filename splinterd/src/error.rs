@@ -16,6 +16,8 @@ use std::error::Error;
 use std::fmt;
 use std::io;
 
+#[cfg(feature = "noise-transport")]
+use splinter::transport::socket::NoiseInitError;
 use splinter::transport::socket::TlsInitError;
 
 use crate::config::ConfigError;
@@ -111,6 +113,8 @@ impl From<ConfigError> for UserError {
 pub enum GetTransportError {
     CertError(String),
     TlsTransportError(TlsInitError),
+    #[cfg(feature = "noise-transport")]
+    NoiseTransportError(NoiseInitError),
     IoError(io::Error),
 }
 
@@ -119,6 +123,8 @@ impl Error for GetTransportError {
         match self {
             GetTransportError::CertError(_) => None,
             GetTransportError::TlsTransportError(err) => Some(err),
+            #[cfg(feature = "noise-transport")]
+            GetTransportError::NoiseTransportError(err) => Some(err),
             GetTransportError::IoError(err) => Some(err),
         }
     }
@@ -133,6 +139,10 @@ impl fmt::Display for GetTransportError {
             GetTransportError::TlsTransportError(err) => {
                 write!(f, "unable to create TLS transport: {}", err)
             }
+            #[cfg(feature = "noise-transport")]
+            GetTransportError::NoiseTransportError(err) => {
+                write!(f, "unable to create Noise transport: {}", err)
+            }
             GetTransportError::IoError(err) => {
                 write!(f, "unable to get transport due to IoError: {}", err)
             }
@@ -146,6 +156,13 @@ impl From<TlsInitError> for GetTransportError {
     }
 }
 
+#[cfg(feature = "noise-transport")]
+impl From<NoiseInitError> for GetTransportError {
+    fn from(noise_error: NoiseInitError) -> Self {
+        GetTransportError::NoiseTransportError(noise_error)
+    }
+}
+
 impl From<io::Error> for GetTransportError {
     fn from(io_error: io::Error) -> Self {
         GetTransportError::IoError(io_error)
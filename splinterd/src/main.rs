@@ -43,6 +43,7 @@ use crate::config::{Config, ConfigBuilder, ConfigError};
 use crate::daemon::SplinterDaemonBuilder;
 use clap::{clap_app, crate_version};
 use clap::{Arg, ArgMatches};
+use splinter::service::scabbard::ScabbardStorage;
 
 use std::env;
 use std::fs;
@@ -123,13 +124,13 @@ fn main() {
         (@arg display_name: --("display-name") +takes_value
           "Human-readable name for the node")
         (@arg storage: --("storage") +takes_value
-          "Storage type used for the node; defaults to yaml")
+          "Storage type used for the node (yaml, memory or database); defaults to yaml")
         (@arg network_endpoints: -n --("network-endpoint") +takes_value +multiple
           "Endpoints to connect to the network, protocol-prefix://ip:port")
         (@arg advertised_endpoints: -a --("advertised-endpoint") +takes_value +multiple
           "Publicly-visible network endpoints")
         (@arg service_endpoint: --("service-endpoint") +takes_value
-          "Endpoint that service will connect to, tcp://ip:port")
+          "Endpoint that service will connect to, tcp://ip:port or uds://path")
         (@arg peers: --peer +takes_value +multiple
           "Endpoint that service will connect to, ip:port")
         (@arg no_tls:  --("no-tls") "Turn off tls configuration")
@@ -205,6 +206,77 @@ fn main() {
                 .long("tls-insecure")
                 .help("If set to tls, should accept all peer certificates")
                 .alias("insecure"),
+        )
+        .arg(
+            Arg::with_name("node_key_file")
+                .long("node-key-file")
+                .long_help(
+                    "File path to the private key the node uses to prove its identity to peers; \
+                     if set, peers must prove their identity with a key registered in the node \
+                     registry",
+                )
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("key_permissions")
+                .long("key-permissions")
+                .long_help(
+                    "Key permission manager used to check whether a public key may propose or \
+                     vote on circuits; 'roles' only permits the roles granted to a key, \
+                     'allow-all' permits every registered key",
+                )
+                .takes_value(true)
+                .possible_values(&["allow-all", "roles"]),
+        )
+        .arg(
+            Arg::with_name("allow_key_role_changes")
+                .long("allow-key-role-changes")
+                .long_help(
+                    "Allow key roles to be granted and revoked through the REST API; these \
+                     requests are not authenticated, so only use this when the REST API is not \
+                     reachable by untrusted clients",
+                ),
+        )
+        .arg(
+            Arg::with_name("scabbard_storage")
+                .long("scabbard-storage")
+                .long_help(
+                    "Storage used for the state and transaction receipts of scabbard services; \
+                     'lmdb' (the default), 'memory', or 'sqlite' (requires the scabbard-sqlite \
+                     feature)",
+                )
+                .takes_value(true)
+                .possible_values(&["lmdb", "memory", "sqlite"]),
+        )
+        .arg(
+            Arg::with_name("verify_peer_identity")
+                .long("verify-peer-identity")
+                .long_help(
+                    "Require peers to claim a node ID that is bound to the identity their \
+                     connection authenticated: the common name or a subject alternative name of \
+                     their TLS certificate, or the certificate_identity metadata of the node in \
+                     the node registry, or, for Noise connections, one of the node's keys in the \
+                     node registry",
+                ),
+        )
+        .arg(
+            Arg::with_name("tls_crl_file")
+                .long("tls-crl-file")
+                .long_help(
+                    "File path to a PEM-encoded certificate revocation list issued by the CA; \
+                     peer certificates that it revokes are refused",
+                )
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("tls_reload_interval")
+                .long("tls-reload-interval")
+                .long_help(
+                    "How often the TLS certificate, key and CRL files are checked for changes, in \
+                     seconds; changed files are used for new connections. Defaults to 0, which \
+                     means off",
+                )
+                .takes_value(true),
         );
 
     #[cfg(feature = "database")]
@@ -272,6 +344,28 @@ fn start_daemon(matches: ArgMatches) -> Result<(), UserError> {
 
     let config = create_config(config_file_path, matches.clone())?;
 
+    if config.verify_peer_identity() {
+        // Peer identities are verified against TLS certificates that are verified by a CA, or
+        // against the node keys of Noise connections
+        if !config.no_tls() && config.tls_insecure() {
+            return Err(UserError::InvalidArgument(
+                "peer identities can only be verified with TLS certificates that are verified \
+                 by a CA; 'verify_peer_identity' cannot be used with 'tls_insecure'"
+                    .into(),
+            ));
+        }
+
+        let noise_enabled = cfg!(feature = "noise-transport") && config.node_key_file().is_some();
+        if config.no_tls() && !noise_enabled {
+            return Err(UserError::InvalidArgument(
+                "peer identities can only be verified with TLS certificates or Noise connections; \
+                 'verify_peer_identity' cannot be used with 'no_tls' unless the Noise transport \
+                 is enabled by 'node_key_file'"
+                    .into(),
+            ));
+        }
+    }
+
     let transport = build_transport(&config)?;
 
     let state_dir = Path::new(config.state_dir());
@@ -285,6 +379,8 @@ fn start_daemon(matches: ArgMatches) -> Result<(), UserError> {
             })?
             .to_string(),
         "memory" => "memory".to_string(),
+        #[cfg(feature = "database")]
+        "database" => "database".to_string(),
         _ => {
             return Err(UserError::InvalidArgument(format!(
                 "storage type is not supported: {}",
@@ -293,8 +389,10 @@ fn start_daemon(matches: ArgMatches) -> Result<(), UserError> {
         }
     };
 
+    // Keys and key roles are not stored in the database, so database storage keeps them in
+    // YAML files in the state directory
     let key_registry_location = match &config.storage() as &str {
-        "yaml" => state_dir
+        "yaml" | "database" => state_dir
             .join("keys.yaml")
             .to_str()
             .ok_or_else(|| {
@@ -310,6 +408,61 @@ fn start_daemon(matches: ArgMatches) -> Result<(), UserError> {
         }
     };
 
+    let key_roles_location = match config.key_permissions() {
+        "allow-all" => None,
+        "roles" => match &config.storage() as &str {
+            "yaml" | "database" => Some(
+                state_dir
+                    .join("key_roles.yaml")
+                    .to_str()
+                    .ok_or_else(|| {
+                        UserError::InvalidArgument("'state_dir' is not a valid UTF-8 string".into())
+                    })?
+                    .to_string(),
+            ),
+            "memory" => Some("memory".to_string()),
+            _ => {
+                return Err(UserError::InvalidArgument(format!(
+                    "storage type is not supported: {}",
+                    config.storage()
+                )))
+            }
+        },
+        _ => {
+            return Err(UserError::InvalidArgument(format!(
+                "key permissions type is not supported: {}",
+                config.key_permissions()
+            )))
+        }
+    };
+
+    if config.allow_key_role_changes() && key_roles_location.is_none() {
+        return Err(UserError::InvalidArgument(
+            "'allow_key_role_changes' can only be used with 'key_permissions' set to 'roles'"
+                .into(),
+        ));
+    }
+
+    let scabbard_storage = match config.scabbard_storage() {
+        "lmdb" => ScabbardStorage::default(),
+        "memory" => ScabbardStorage::Memory,
+        #[cfg(feature = "scabbard-sqlite")]
+        "sqlite" => ScabbardStorage::Sqlite {
+            db_dir: state_dir
+                .to_str()
+                .ok_or_else(|| {
+                    UserError::InvalidArgument("'state_dir' is not a valid UTF-8 string".into())
+                })?
+                .to_string(),
+        },
+        _ => {
+            return Err(UserError::InvalidArgument(format!(
+                "scabbard storage type is not supported: {}",
+                config.scabbard_storage()
+            )))
+        }
+    };
+
     let node_registry_directory = state_dir
         .to_str()
         .ok_or_else(|| {
@@ -344,7 +497,12 @@ fn start_daemon(matches: ArgMatches) -> Result<(), UserError> {
         .with_registry_auto_refresh_interval(config.registry_auto_refresh_interval())
         .with_registry_forced_refresh_interval(config.registry_forced_refresh_interval())
         .with_heartbeat_interval(config.heartbeat_interval())
-        .with_admin_service_coordinator_timeout(admin_service_coordinator_timeout);
+        .with_admin_service_coordinator_timeout(admin_service_coordinator_timeout)
+        .with_node_key_file(config.node_key_file().map(String::from))
+        .with_key_roles_location(key_roles_location)
+        .with_allow_key_role_changes(config.allow_key_role_changes())
+        .with_scabbard_storage(scabbard_storage)
+        .with_verify_peer_identity(config.verify_peer_identity());
 
     #[cfg(feature = "database")]
     {
@@ -14,11 +14,18 @@
 
 use std::fs;
 use std::path::Path;
+use std::time::Duration;
 
+#[cfg(feature = "noise-transport")]
+use splinter::signing::sawtooth::{SawtoothSecp256k1SignatureVerifier, SawtoothSecp256k1Signer};
 use splinter::transport::inproc::InprocTransport;
 use splinter::transport::multi::MultiTransport;
 use splinter::transport::socket::TcpTransport;
 use splinter::transport::socket::TlsTransport;
+#[cfg(feature = "uds-transport")]
+use splinter::transport::socket::UdsTransport;
+#[cfg(feature = "noise-transport")]
+use splinter::transport::socket::{NoiseInitError, NoiseTransport};
 #[cfg(feature = "ws-transport")]
 use splinter::transport::ws::WsTransport;
 use splinter::transport::Transport;
@@ -38,6 +45,10 @@ pub fn build_transport(config: &Config) -> Result<MultiTransport, GetTransportEr
     // add inproc transpoort
     transports.push(Box::new(InprocTransport::default()));
 
+    // add unix domain socket transport
+    #[cfg(feature = "uds-transport")]
+    transports.push(Box::new(UdsTransport::default()));
+
     // add web socket transport
     #[cfg(feature = "ws-transport")]
     transports.push(Box::new(WsTransport::default()));
@@ -47,6 +58,14 @@ pub fn build_transport(config: &Config) -> Result<MultiTransport, GetTransportEr
         transports.push(build_tls_transport(config)?)
     }
 
+    // add noise transport, which is keyed by the node key
+    #[cfg(feature = "noise-transport")]
+    {
+        if let Some(node_key_file) = config.node_key_file() {
+            transports.push(build_noise_transport(node_key_file)?)
+        }
+    }
+
     Ok(MultiTransport::new(transports))
 }
 
@@ -128,11 +147,55 @@ fn build_tls_transport(config: &Config) -> Result<Box<dyn Transport + Send>, Get
         }
     };
 
-    Ok(Box::new(TlsTransport::new(
+    let mut transport = TlsTransport::new(
         ca_file.map(String::from),
         String::from(client_key_file),
         String::from(client_cert),
         String::from(server_key_file),
         String::from(server_cert),
-    )?))
+    )?;
+
+    if let Some(crl_file) = config.tls_crl_file() {
+        if !Path::new(crl_file).is_file() {
+            return Err(GetTransportError::CertError(format!(
+                "Must provide a valid certificate revocation list: {}",
+                crl_file
+            )));
+        }
+        debug!(
+            "Using certificate revocation list file: {:?}",
+            fs::canonicalize(crl_file)?
+        );
+        transport = transport.with_crl_file(String::from(crl_file))?;
+    }
+
+    let reload_interval = config.tls_reload_interval();
+    if reload_interval != 0 {
+        debug!(
+            "Checking TLS certificate files for changes every {} seconds",
+            reload_interval
+        );
+        transport
+            .reloader()
+            .watch(Duration::from_secs(reload_interval))?;
+    }
+
+    Ok(Box::new(transport))
+}
+
+#[cfg(feature = "noise-transport")]
+fn build_noise_transport(node_key_file: &str) -> Result<SendableTransport, GetTransportError> {
+    let private_key = fs::read_to_string(node_key_file)?;
+    let signer = SawtoothSecp256k1Signer::from_hex(private_key.trim()).map_err(|err| {
+        NoiseInitError::SigningError(format!(
+            "unable to load node key from {}: {}",
+            node_key_file, err
+        ))
+    })?;
+
+    debug!("Using node key file for Noise transport: {}", node_key_file);
+    let transport =
+        NoiseTransport::new(&signer, Box::new(SawtoothSecp256k1SignatureVerifier::new()))?;
+
+    Ok(Box::new(transport))
 }